@@ -0,0 +1,443 @@
+/*!
+Derive macros for the `shop` app.
+
+This crate generates the boilerplate that every entity in `shop::domain` needs.
+Deriving `Entity` on a `Data` struct like `WidgetData` generates the same items that
+existing entities like `Customer` write by hand:
+
+- `WidgetId`, `NextWidgetId` and `WidgetVersion` aliases.
+- A `Widget` entity that wraps `WidgetData` with private `from_data` and public `to_data`/`into_data` methods.
+- An `Entity` impl for `Widget`.
+
+With `#[entity(store)]` it also generates a `store` module containing:
+
+- A `WidgetStore` trait with `get_widget` and `set_widget` methods.
+- An `InMemoryStore` with `in_memory_store` and `file_store` functions.
+
+The `Data` struct needs to implement `Serialize` and `Deserialize` so the store can be kept in a file.
+
+With `#[entity(store, resolver = WidgetsResolver)]` it also generates:
+
+- A `WidgetsResolver` that registers the store and a source of ids.
+- A `widget_store` method on `Resolver` that's visible to the entity's domain module.
+- A `widget_id` method on `Resolver`.
+- `widget_store` and `widget_id` methods on `AppBuilder` for replacing the registrations.
+
+The resolver still needs to be added to the root `Resolver` as a `widgets_resolver` field.
+
+The generated code refers to items in `crate::domain`, so these macros can only be used within `shop` itself.
+The derive is expected to be used in a domain module's `model` module so the generated items follow
+the same privacy layout as hand-written entities.
+*/
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::{
+    Span,
+    TokenStream as TokenStream2,
+};
+use quote::{
+    format_ident,
+    quote,
+};
+use syn::{
+    parse_macro_input,
+    Data,
+    DeriveInput,
+    Error,
+    Fields,
+    Ident,
+};
+
+/**
+Derive entity boilerplate for a `Data` struct.
+
+See the crate docs for the items that are generated.
+*/
+#[proc_macro_derive(Entity, attributes(entity))]
+pub fn derive_entity(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand_entity(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+struct EntityAttrs {
+    store: bool,
+    resolver: Option<Ident>,
+}
+
+fn parse_attrs(input: &DeriveInput) -> syn::Result<EntityAttrs> {
+    let mut attrs = EntityAttrs {
+        store: false,
+        resolver: None,
+    };
+
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("entity"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("store") {
+                attrs.store = true;
+
+                Ok(())
+            } else if meta.path.is_ident("resolver") {
+                attrs.resolver = Some(meta.value()?.parse()?);
+
+                Ok(())
+            } else {
+                Err(meta.error("expected `store` or `resolver`"))
+            }
+        })?;
+    }
+
+    if attrs.resolver.is_some() && !attrs.store {
+        return Err(Error::new(
+            Span::call_site(),
+            "a `resolver` needs a `store` to register",
+        ));
+    }
+
+    Ok(attrs)
+}
+
+fn expand_entity(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let attrs = parse_attrs(&input)?;
+
+    let data = &input.ident;
+    let data_name = data.to_string();
+
+    let name = data_name
+        .strip_suffix("Data")
+        .filter(|name| !name.is_empty())
+        .ok_or_else(|| {
+            Error::new_spanned(data, "entity data types must be named like `WidgetData`")
+        })?;
+
+    // Entities need an id and a version for storage and optimistic concurrency
+    let Data::Struct(ref data_struct) = input.data else {
+        return Err(Error::new_spanned(data, "entity data must be a struct"));
+    };
+
+    let Fields::Named(ref fields) = data_struct.fields else {
+        return Err(Error::new_spanned(
+            data,
+            "entity data must have named fields",
+        ));
+    };
+
+    for required in ["id", "version"] {
+        if !fields.named.iter().any(|field| {
+            field
+                .ident
+                .as_ref()
+                .map(|ident| ident == required)
+                .unwrap_or(false)
+        }) {
+            return Err(Error::new_spanned(
+                data,
+                format!("entity data needs an `{}` field", required),
+            ));
+        }
+    }
+
+    let entity = Ident::new(name, data.span());
+    let id = format_ident!("{}Id", entity);
+    let next_id = format_ident!("Next{}Id", entity);
+    let version = format_ident!("{}Version", entity);
+
+    let entity_doc = format!("A `{}` entity.", name);
+
+    let mut expanded = quote! {
+        pub type #id = crate::domain::infra::Id<#data>;
+        pub type #next_id = crate::domain::infra::NextId<#data>;
+        pub type #version = crate::domain::infra::Version<#data>;
+
+        #[doc = #entity_doc]
+        pub struct #entity {
+            data: #data,
+        }
+
+        impl #entity {
+            #[allow(dead_code)]
+            fn from_data(data: #data) -> Self {
+                #entity { data }
+            }
+
+            pub fn to_data(&self) -> &#data {
+                &self.data
+            }
+
+            pub fn into_data(self) -> #data {
+                self.data
+            }
+        }
+
+        impl crate::domain::infra::Entity for #entity {
+            type Id = #id;
+            type Version = #version;
+            type Data = #data;
+            type Error = crate::domain::Error;
+        }
+    };
+
+    if attrs.store {
+        expanded.extend(expand_store(name, &entity, &id, data));
+    }
+
+    if let Some(resolver) = attrs.resolver {
+        expanded.extend(expand_resolver(name, &resolver, data));
+    }
+
+    Ok(expanded)
+}
+
+fn expand_store(name: &str, entity: &Ident, id: &Ident, data: &Ident) -> TokenStream2 {
+    let snake = to_snake_case(name);
+
+    let store = format_ident!("{}Store", entity);
+    let get = format_ident!("get_{}", snake);
+    let set = format_ident!("set_{}", snake);
+    let arg = format_ident!("{}", snake);
+
+    let store_doc = format!("A place to persist and fetch `{}` entities.", name);
+
+    quote! {
+        /** Persistent storage for the entity. */
+        pub mod store {
+            use super::*;
+
+            use crate::store::{
+                Transaction,
+                TransactionStore,
+                TransactionValueStore,
+            };
+
+            #[doc = #store_doc]
+            #[::auto_impl::auto_impl(&, Arc)]
+            pub(in crate::domain) trait #store {
+                fn #get(&self, id: #id) -> Result<Option<#entity>, crate::domain::Error>;
+                fn #set(
+                    &self,
+                    transaction: &Transaction,
+                    #arg: #entity,
+                ) -> Result<(), crate::domain::Error>;
+            }
+
+            /** A test in-memory store. */
+            pub(in crate::domain) struct InMemoryStore(TransactionValueStore<#data>);
+
+            impl #store for InMemoryStore {
+                fn #get(&self, id: #id) -> Result<Option<#entity>, crate::domain::Error> {
+                    if let Some((version, data)) = self.0.get(id) {
+                        assert_eq!(version, data.version.into());
+
+                        Ok(Some(#entity::from_data(data)))
+                    } else {
+                        Ok(None)
+                    }
+                }
+
+                fn #set(
+                    &self,
+                    transaction: &Transaction,
+                    #arg: #entity,
+                ) -> Result<(), crate::domain::Error> {
+                    let mut data = #arg.into_data();
+                    let id = data.id;
+
+                    self.0.set(
+                        transaction,
+                        id,
+                        Some(data.version),
+                        data.version.next(),
+                        data,
+                    )?;
+
+                    Ok(())
+                }
+            }
+
+            #[allow(dead_code)]
+            pub(in crate::domain) fn in_memory_store(
+                transaction_store: TransactionStore,
+            ) -> InMemoryStore {
+                InMemoryStore(TransactionValueStore::new(transaction_store))
+            }
+
+            #[allow(dead_code)]
+            pub(in crate::domain) fn file_store(
+                transaction_store: TransactionStore,
+                path: impl Into<std::path::PathBuf>,
+            ) -> Result<InMemoryStore, crate::domain::Error> {
+                Ok(InMemoryStore(TransactionValueStore::open(transaction_store, path)?))
+            }
+        }
+    }
+}
+
+fn expand_resolver(name: &str, resolver: &Ident, data: &Ident) -> TokenStream2 {
+    let snake = to_snake_case(name);
+
+    let store = format_ident!("{}Store", name);
+    let store_fn = format_ident!("{}_store", snake);
+    let id_fn = format_ident!("{}_id", snake);
+    let field = format_ident!("{}", to_snake_case(&resolver.to_string()));
+    let file = format!("{}s", snake);
+    let file_err = format!("failed to open the {} store", snake.replace('_', " "));
+
+    let resolver_doc = format!(
+        "Resolver for `{}` entities.\n\nThis resolver needs to be added to the root `Resolver` as a `{}` field.",
+        name, field
+    );
+    let store_doc = format!("Use a different store for `{}` entities.", name);
+    let id_doc = format!("Use a different source of ids for new `{}` entities.", name);
+
+    quote! {
+        #[doc = #resolver_doc]
+        #[derive(Clone)]
+        pub(in crate::domain) struct #resolver {
+            #store_fn: crate::domain::infra::Register<
+                std::sync::Arc<dyn store::#store + Send + Sync>,
+            >,
+            #id_fn: crate::domain::infra::Register<
+                std::sync::Arc<dyn crate::domain::infra::IdProvider<#data> + Send + Sync>,
+            >,
+        }
+
+        impl Default for #resolver {
+            fn default() -> Self {
+                #resolver {
+                    #store_fn: crate::domain::infra::Register::per_tenant(|resolver| {
+                        match resolver.config().store.backend {
+                            crate::config::StoreBackend::InMemory => std::sync::Arc::new(
+                                store::in_memory_store(resolver.transaction_store()),
+                            )
+                                as std::sync::Arc<dyn store::#store + Send + Sync>,
+                            crate::config::StoreBackend::File => std::sync::Arc::new(
+                                store::file_store(
+                                    resolver.transaction_store(),
+                                    resolver.tenant_store_file(#file),
+                                )
+                                .expect(#file_err),
+                            )
+                                as std::sync::Arc<dyn store::#store + Send + Sync>,
+                        }
+                    }),
+                    #id_fn: crate::domain::infra::Register::once(|_| {
+                        std::sync::Arc::new(crate::domain::infra::NextId::<#data>::new())
+                            as std::sync::Arc<
+                                dyn crate::domain::infra::IdProvider<#data> + Send + Sync,
+                            >
+                    }),
+                }
+            }
+        }
+
+        impl crate::domain::infra::Resolver {
+            #[allow(dead_code)]
+            pub(super) fn #store_fn(&self) -> impl store::#store {
+                self.resolve(&self.#field.#store_fn)
+            }
+
+            pub fn #id_fn(&self) -> impl crate::domain::infra::IdProvider<#data> {
+                self.journaled_id(self.resolve(&self.#field.#id_fn))
+            }
+        }
+
+        impl crate::domain::infra::AppBuilder {
+            #[doc = #store_doc]
+            #[allow(dead_code)]
+            pub(in crate::domain) fn #store_fn(
+                mut self,
+                #store_fn: crate::domain::infra::Register<
+                    std::sync::Arc<dyn store::#store + Send + Sync>,
+                >,
+            ) -> Self {
+                self.resolver.#field.#store_fn = #store_fn;
+                self
+            }
+
+            #[doc = #id_doc]
+            pub fn #id_fn(
+                mut self,
+                #id_fn: crate::domain::infra::Register<
+                    std::sync::Arc<dyn crate::domain::infra::IdProvider<#data> + Send + Sync>,
+                >,
+            ) -> Self {
+                self.resolver.#field.#id_fn = #id_fn;
+                self
+            }
+        }
+    }
+}
+
+fn to_snake_case(name: &str) -> String {
+    let mut snake = String::with_capacity(name.len() + 4);
+
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                snake.push('_');
+            }
+
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+
+    snake
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snake_case() {
+        assert_eq!("widget", to_snake_case("Widget"));
+        assert_eq!("line_item", to_snake_case("LineItem"));
+        assert_eq!("widgets_resolver", to_snake_case("WidgetsResolver"));
+    }
+
+    #[test]
+    fn data_name_must_end_in_data() {
+        let input: DeriveInput = syn::parse_quote! {
+            pub struct Widget {
+                pub id: WidgetId,
+                pub version: WidgetVersion,
+            }
+        };
+
+        assert!(expand_entity(input).is_err());
+    }
+
+    #[test]
+    fn data_needs_id_and_version() {
+        let input: DeriveInput = syn::parse_quote! {
+            pub struct WidgetData {
+                pub id: WidgetId,
+            }
+        };
+
+        assert!(expand_entity(input).is_err());
+    }
+
+    #[test]
+    fn resolver_needs_store() {
+        let input: DeriveInput = syn::parse_quote! {
+            #[entity(resolver = WidgetsResolver)]
+            pub struct WidgetData {
+                pub id: WidgetId,
+                pub version: WidgetVersion,
+            }
+        };
+
+        assert!(expand_entity(input).is_err());
+    }
+}
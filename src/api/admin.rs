@@ -0,0 +1,85 @@
+/*!
+`/admin`
+
+Every endpoint needs an `AdminRequest`, which checks the request gives the configured admin token.
+*/
+
+use rocket::{
+    serde::json::Json,
+    State,
+};
+
+use crate::{
+    api::infra::*,
+    config::Config,
+    domain::{
+        infra::*,
+        App,
+    },
+};
+
+/** `GET /admin/features` */
+#[rocket::get("/features")]
+pub async fn get_features(
+    _admin: AdminRequest,
+    app: AppRequest<'_>,
+) -> Result<Json<Vec<FeatureFlag>>, Error> {
+    app.transaction(|app| async move {
+        let query = app.get_feature_flags_query();
+
+        let flags = query.execute(GetFeatureFlags {}).await?;
+
+        Ok(Json(flags))
+    })
+    .await
+}
+
+#[derive(Deserialize)]
+pub struct SetFeature {
+    pub rollout: Rollout,
+}
+
+/** `PUT /admin/features/<name>` */
+#[rocket::put("/features/<name>", format = "application/json", data = "<data>")]
+pub async fn set_feature(
+    name: String,
+    data: Json<SetFeature>,
+    _admin: AdminRequest,
+    app: AppRequest<'_>,
+) -> Result<(), Error> {
+    app.transaction(|app| async move {
+        let command = app.set_feature_flag_command();
+
+        command
+            .execute(SetFeatureFlag {
+                name,
+                rollout: data.0.rollout,
+            })
+            .await?;
+
+        Ok(())
+    })
+    .await
+}
+
+/**
+`POST /admin/features/reload`
+
+Flags are reloaded from `Rocket.toml` and the environment, discarding any changes made through `PUT`.
+*/
+#[rocket::post("/features/reload")]
+pub async fn reload_features(
+    _admin: AdminRequest,
+    span: RequestSpan,
+    app: &State<App>,
+) -> Result<(), Error> {
+    span.trace(async {
+        let config =
+            Config::load().map_err(|err| Error::Other("config.invalid", Box::new(*err)))?;
+
+        app.reload_feature_flags(&config);
+
+        Ok(())
+    })
+    .await
+}
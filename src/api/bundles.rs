@@ -0,0 +1,59 @@
+/*! The bundles of `/products` */
+
+use rocket::serde::json::Json;
+
+use crate::{
+    api::infra::*,
+    domain::{
+        bundles::*,
+        infra::*,
+        products::ProductId,
+    },
+};
+
+/** `GET /products/<id>/bundle` */
+#[rocket::get("/<id>/bundle")]
+pub async fn get_bundle(
+    id: ProductId,
+    app: AppRequest<'_>,
+) -> Result<Json<BundleWithProducts>, Error> {
+    app.transaction(|app| async move {
+        let query = app.get_bundle_query();
+
+        match query.execute(GetBundle { product_id: id }).await? {
+            Some(bundle) => Ok(Json(bundle)),
+            None => Err(Error::NotFound(
+                "bundle.not_found",
+                error::msg("product is not a bundle"),
+            )),
+        }
+    })
+    .await
+}
+
+#[derive(Deserialize)]
+pub struct SetBundleComponentsData {
+    pub components: Vec<BundleComponent>,
+}
+
+/** `PUT /products/<id>/bundle` */
+#[rocket::put("/<id>/bundle", format = "application/json", data = "<data>")]
+pub async fn set_bundle_components(
+    id: ProductId,
+    data: Json<SetBundleComponentsData>,
+    app: AppRequest<'_>,
+) -> Result<(), Error> {
+    app.transaction(|app| async move {
+        let command = app.set_bundle_components_command();
+
+        command
+            .execute(SetBundleComponents {
+                product_id: id,
+                components: data.0.components,
+            })
+            .await?;
+
+        Ok(())
+    })
+    .await
+}
@@ -0,0 +1,263 @@
+/*! `/categories`, and the categories, tags and attributes of `/products` */
+
+use std::collections::BTreeMap;
+
+use rocket::{
+    response::status::Created,
+    serde::json::Json,
+};
+
+use crate::{
+    api::infra::*,
+    domain::{
+        catalog::*,
+        infra::*,
+        products::{
+            ProductId,
+            ProductSummary,
+        },
+    },
+};
+
+/** `GET /categories` */
+#[rocket::get("/")]
+pub async fn get_categories(app: AppRequest<'_>) -> Result<Json<Vec<CategorySummary>>, Error> {
+    app.transaction(|app| async move {
+        let query = app.get_categories_query();
+
+        let categories = query.execute(GetCategories {}).await?;
+
+        Ok(Json(categories))
+    })
+    .await
+}
+
+#[derive(Deserialize)]
+pub struct CreateCategoryData {
+    pub name: String,
+    #[serde(default)]
+    pub parent: Option<CategoryId>,
+}
+
+/** `PUT /categories` */
+#[rocket::put("/", format = "application/json", data = "<data>")]
+pub async fn create_category(
+    data: Json<CreateCategoryData>,
+    app: AppRequest<'_>,
+) -> Result<Created<Json<CategoryId>>, Error> {
+    app.transaction(|app| async move {
+        let id = app.category_id();
+        let command = app.create_category_command();
+
+        let id = id.get()?;
+
+        command
+            .execute(CreateCategory {
+                id,
+                name: data.0.name,
+                parent_id: data.0.parent,
+            })
+            .await?;
+
+        let location = format!("/categories/{}/products", id);
+
+        Ok(Created::new(location).body(Json(id)))
+    })
+    .await
+}
+
+#[derive(Deserialize)]
+pub struct MoveCategoryData {
+    pub parent: Option<CategoryId>,
+}
+
+/** `PUT /categories/<id>/parent` */
+#[rocket::put("/<id>/parent", format = "application/json", data = "<data>")]
+pub async fn move_category(
+    id: CategoryId,
+    data: Json<MoveCategoryData>,
+    app: AppRequest<'_>,
+) -> Result<(), Error> {
+    app.transaction(|app| async move {
+        let command = app.move_category_command();
+
+        command
+            .execute(MoveCategory {
+                id,
+                parent_id: data.0.parent,
+            })
+            .await?;
+
+        Ok(())
+    })
+    .await
+}
+
+/** `GET /categories/<id>/products` */
+#[rocket::get("/<id>/products")]
+pub async fn get_category_products(
+    id: CategoryId,
+    app: AppRequest<'_>,
+) -> Result<Json<Vec<ProductSummary>>, Error> {
+    app.transaction(|app| async move {
+        let query = app.get_products_in_category_query();
+
+        match query.execute(GetProductsInCategory { id }).await? {
+            Some(products) => Ok(Json(products)),
+            None => Err(Error::NotFound(
+                "catalog.category_not_found",
+                error::msg("category not found"),
+            )),
+        }
+    })
+    .await
+}
+
+#[derive(Deserialize)]
+pub struct SetCategoryAttributesData {
+    pub attributes: Vec<AttributeField>,
+}
+
+/** `PUT /categories/<id>/attributes` */
+#[rocket::put("/<id>/attributes", format = "application/json", data = "<data>")]
+pub async fn set_category_attributes(
+    id: CategoryId,
+    data: Json<SetCategoryAttributesData>,
+    app: AppRequest<'_>,
+) -> Result<(), Error> {
+    app.transaction(|app| async move {
+        let command = app.set_category_attributes_command();
+
+        command
+            .execute(SetCategoryAttributes {
+                id,
+                attributes: data.0.attributes,
+            })
+            .await?;
+
+        Ok(())
+    })
+    .await
+}
+
+#[derive(Deserialize)]
+pub struct SetCategories {
+    pub categories: Vec<CategoryId>,
+    #[serde(default)]
+    pub attributes: Option<BTreeMap<AttributeName, AttributeValue>>,
+}
+
+/** `PUT /products/<id>/categories` */
+#[rocket::put("/<id>/categories", format = "application/json", data = "<data>")]
+pub async fn set_product_categories(
+    id: ProductId,
+    data: Json<SetCategories>,
+    app: AppRequest<'_>,
+) -> Result<(), Error> {
+    app.transaction(|app| async move {
+        let command = app.set_product_categories_command();
+
+        command
+            .execute(SetProductCategories {
+                product_id: id,
+                category_ids: data.0.categories,
+                attributes: data.0.attributes,
+            })
+            .await?;
+
+        Ok(())
+    })
+    .await
+}
+
+#[derive(Deserialize)]
+pub struct SetTags {
+    pub tags: Vec<String>,
+}
+
+/** `PUT /products/<id>/tags` */
+#[rocket::put("/<id>/tags", format = "application/json", data = "<data>")]
+pub async fn set_product_tags(
+    id: ProductId,
+    data: Json<SetTags>,
+    app: AppRequest<'_>,
+) -> Result<(), Error> {
+    app.transaction(|app| async move {
+        let command = app.set_product_tags_command();
+
+        command
+            .execute(SetProductTags {
+                product_id: id,
+                tags: data.0.tags,
+            })
+            .await?;
+
+        Ok(())
+    })
+    .await
+}
+
+/**
+`GET /products/tagged?tag=<tag>`
+
+The `tag` parameter can be given more than once to find products that carry all of the tags.
+*/
+#[rocket::get("/tagged?<tag>")]
+pub async fn get_tagged_products(
+    tag: Vec<String>,
+    app: AppRequest<'_>,
+) -> Result<Json<Vec<ProductSummary>>, Error> {
+    app.transaction(|app| async move {
+        let query = app.get_products_with_tags_query();
+
+        let products = query.execute(GetProductsWithTags { tags: tag }).await?;
+
+        Ok(Json(products))
+    })
+    .await
+}
+
+/** `GET /products/<id>/attributes` */
+#[rocket::get("/<id>/attributes")]
+pub async fn get_product_attributes(
+    id: ProductId,
+    app: AppRequest<'_>,
+) -> Result<Json<ProductAttributes>, Error> {
+    app.transaction(|app| async move {
+        let query = app.get_product_attributes_query();
+
+        let attributes = query
+            .execute(GetProductAttributes { product_id: id })
+            .await?;
+
+        Ok(Json(attributes))
+    })
+    .await
+}
+
+#[derive(Deserialize)]
+pub struct SetAttributes {
+    pub attributes: BTreeMap<AttributeName, AttributeValue>,
+}
+
+/** `PUT /products/<id>/attributes` */
+#[rocket::put("/<id>/attributes", format = "application/json", data = "<data>")]
+pub async fn set_product_attributes(
+    id: ProductId,
+    data: Json<SetAttributes>,
+    app: AppRequest<'_>,
+) -> Result<(), Error> {
+    app.transaction(|app| async move {
+        let command = app.set_product_attributes_command();
+
+        command
+            .execute(SetProductAttributes {
+                product_id: id,
+                attributes: data.0.attributes,
+            })
+            .await?;
+
+        Ok(())
+    })
+    .await
+}
@@ -23,7 +23,10 @@ pub async fn get(id: CustomerId, app: AppRequest<'_>) -> Result<Json<CustomerWit
 
         match query.execute(GetCustomerWithOrders { id }).await? {
             Some(customer) => Ok(Json(customer)),
-            None => Err(Error::NotFound(error::msg("customer not found"))),
+            None => Err(Error::NotFound(
+                "customer.not_found",
+                error::msg("customer not found"),
+            )),
         }
     })
     .await
@@ -1,11 +1,19 @@
+pub(in crate::api) mod admin;
 pub(in crate::api) mod error;
+pub(in crate::api) mod jobs;
+pub(in crate::api) mod locale;
 pub(in crate::api) mod request;
+pub(in crate::api) mod saga;
 pub(in crate::api) mod span;
+pub(in crate::api) mod tenant;
 
 mod id;
 
 pub(in crate::api) use self::{
+    admin::*,
     error::*,
+    locale::*,
     request::*,
     span::*,
+    tenant::*,
 };
@@ -0,0 +1,62 @@
+use rocket::{
+    http::Status,
+    request::{
+        FromRequest,
+        Outcome,
+    },
+    Request,
+};
+
+use crate::domain::App;
+
+use super::RequestTenant;
+
+/**
+A request that's allowed to use the `/admin` endpoints.
+
+The request needs to give the configured admin token as a bearer token in its `Authorization` header.
+Admin endpoints aren't served under a `/tenants/<tenant>` path prefix, because what they manage is shared by all tenants.
+*/
+pub struct AdminRequest(());
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AdminRequest {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, ()> {
+        if RequestTenant::from_path(req) {
+            return Outcome::Error((Status::NotFound, ()));
+        }
+
+        let Some(config) = req.rocket().state::<App>().map(|app| app.config()) else {
+            return Outcome::Error((Status::InternalServerError, ()));
+        };
+
+        // Without a configured token the admin endpoints are disabled
+        let Some(expected) = config.admin.token.as_deref() else {
+            return Outcome::Error((Status::NotFound, ()));
+        };
+
+        let given = req
+            .headers()
+            .get_one("Authorization")
+            .and_then(|header| header.strip_prefix("Bearer "));
+
+        match given {
+            Some(given) if tokens_match(given.as_bytes(), expected.as_bytes()) => {
+                Outcome::Success(AdminRequest(()))
+            }
+            _ => Outcome::Error((Status::Unauthorized, ())),
+        }
+    }
+}
+
+// Compare every byte so the time taken doesn't reveal how much of the token matched
+fn tokens_match(given: &[u8], expected: &[u8]) -> bool {
+    given.len() == expected.len()
+        && given
+            .iter()
+            .zip(expected)
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
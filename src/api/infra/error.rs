@@ -22,31 +22,52 @@ use rocket::{
 
 use crate::domain;
 
-/** The main application error. */
+/**
+The main application error.
+
+Each variant carries a stable, machine-readable code alongside its message.
+The code is returned to callers in the JSON error body.
+*/
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("an entity wasn't found")]
-    NotFound(#[source] Box<dyn error::Error + Send + Sync>),
+    NotFound(&'static str, #[source] Box<dyn error::Error + Send + Sync>),
     #[error("the user input was invalid")]
-    BadRequest(#[source] Box<dyn error::Error + Send + Sync>),
+    BadRequest(&'static str, #[source] Box<dyn error::Error + Send + Sync>),
+    #[error("the request conflicts with the current state")]
+    Conflict(&'static str, #[source] Box<dyn error::Error + Send + Sync>),
+    #[error("the request is forbidden")]
+    Forbidden(&'static str, #[source] Box<dyn error::Error + Send + Sync>),
+    #[error("the request is unauthorized")]
+    Unauthorized(&'static str, #[source] Box<dyn error::Error + Send + Sync>),
+    #[error("a dependency is unavailable")]
+    Unavailable(&'static str, #[source] Box<dyn error::Error + Send + Sync>),
     #[error("an unexpected error occurred")]
-    Other(#[source] Box<dyn error::Error + Send + Sync>),
+    Other(&'static str, #[source] Box<dyn error::Error + Send + Sync>),
 }
 
 impl Error {
     pub(in crate::api) fn status(&self) -> Status {
         match self {
-            Error::NotFound(_) => Status::NotFound,
-            Error::BadRequest(_) => Status::BadRequest,
-            Error::Other(_) => Status::InternalServerError,
+            Error::NotFound(..) => Status::NotFound,
+            Error::BadRequest(..) => Status::BadRequest,
+            Error::Conflict(..) => Status::Conflict,
+            Error::Forbidden(..) => Status::Forbidden,
+            Error::Unauthorized(..) => Status::Unauthorized,
+            Error::Unavailable(..) => Status::ServiceUnavailable,
+            Error::Other(..) => Status::InternalServerError,
         }
     }
 
-    fn into_inner(self) -> Box<dyn error::Error + Send + Sync> {
+    fn into_inner(self) -> (&'static str, Box<dyn error::Error + Send + Sync>) {
         match self {
-            Error::NotFound(err) => err,
-            Error::BadRequest(err) => err,
-            Error::Other(err) => err,
+            Error::NotFound(code, err) => (code, err),
+            Error::BadRequest(code, err) => (code, err),
+            Error::Conflict(code, err) => (code, err),
+            Error::Forbidden(code, err) => (code, err),
+            Error::Unauthorized(code, err) => (code, err),
+            Error::Unavailable(code, err) => (code, err),
+            Error::Other(code, err) => (code, err),
         }
     }
 }
@@ -59,9 +80,10 @@ impl<'r, 'o: 'r> Responder<'r, 'o> for Error {
     fn respond_to(self, _: &Request) -> response::Result<'o> {
         let status = self.status();
 
-        let err = self.into_inner();
+        let (code, err) = self.into_inner();
 
-        let err = serde_json::to_vec(&SerializeError { msg: &err }).unwrap_or_else(|_| Vec::new());
+        let err =
+            serde_json::to_vec(&SerializeError { code, msg: &err }).unwrap_or_else(|_| Vec::new());
 
         Response::build()
             .sized_body(None::<usize>, Cursor::new(err))
@@ -76,20 +98,26 @@ impl From<domain::Error> for Error {
         use crate::domain::ErrorKind::*;
 
         match err.split() {
-            (BadInput, err) => Error::BadRequest(err),
-            (_, err) => Error::Other(err),
+            (BadInput, code, err) => Error::BadRequest(code, err),
+            (NotFound, code, err) => Error::NotFound(code, err),
+            (Conflict, code, err) => Error::Conflict(code, err),
+            (Forbidden, code, err) => Error::Forbidden(code, err),
+            (Unauthorized, code, err) => Error::Unauthorized(code, err),
+            (Unavailable, code, err) => Error::Unavailable(code, err),
+            (Other, code, err) => Error::Other(code, err),
         }
     }
 }
 
 impl From<Box<dyn error::Error + Send + Sync>> for Error {
     fn from(err: Box<dyn error::Error + Send + Sync>) -> Self {
-        Error::Other(err)
+        Error::Other(domain::error::INTERNAL, err)
     }
 }
 
 #[derive(Serialize)]
 struct SerializeError<'a> {
+    code: &'a str,
     #[serde(serialize_with = "serialize_msg")]
     msg: &'a dyn fmt::Display,
 }
@@ -104,6 +132,7 @@ where
 #[rocket::catch(500)]
 pub(in crate::api) fn internal_error(_: &Request) -> content::RawJson<Vec<u8>> {
     let err = serde_json::to_vec(&SerializeError {
+        code: domain::error::INTERNAL,
         msg: &"an internal error occurred",
     })
     .unwrap_or_else(|_| Vec::new());
@@ -113,8 +142,27 @@ pub(in crate::api) fn internal_error(_: &Request) -> content::RawJson<Vec<u8>> {
 
 #[rocket::catch(404)]
 pub(in crate::api) fn not_found(_: &Request) -> content::RawJson<Vec<u8>> {
-    let err =
-        serde_json::to_vec(&SerializeError { msg: &"not found" }).unwrap_or_else(|_| Vec::new());
+    let err = serde_json::to_vec(&SerializeError {
+        code: "not_found",
+        msg: &"not found",
+    })
+    .unwrap_or_else(|_| Vec::new());
 
     content::RawJson(err)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn domain_error_keeps_code() {
+        let err = Error::from(domain::error::not_found(
+            "order.customer_not_found",
+            "customer not found",
+        ));
+
+        assert_eq!(Status::NotFound, err.status());
+        assert_eq!("order.customer_not_found", err.into_inner().0);
+    }
+}
@@ -1,4 +1,11 @@
-use rocket::request::FromParam;
+use rocket::{
+    form::{
+        self,
+        FromFormField,
+        ValueField,
+    },
+    request::FromParam,
+};
 use std::convert::TryFrom;
 use md2::Md2;
 use digest::Digest;
@@ -16,6 +23,12 @@ impl<'r, T> FromParam<'r> for Id<T> {
     }
 }
 
+impl<'v, T: Send> FromFormField<'v> for Id<T> {
+    fn from_value(field: ValueField<'v>) -> form::Result<'v, Self> {
+        Id::try_from(field.value).map_err(|err| form::Error::validation(err.to_string()).into())
+    }
+}
+
 pub fn compute_legacy_md2_hash(data: &[u8]) -> Vec<u8> {
     //SINK
     let mut hasher = Md2::new();
@@ -0,0 +1,52 @@
+use std::sync::Mutex;
+
+use rocket::{
+    fairing::{
+        Fairing,
+        Info,
+        Kind,
+    },
+    tokio::task::JoinHandle,
+    Orbit,
+    Rocket,
+};
+
+use crate::domain::App;
+
+/**
+A fairing that runs the app's jobs in the background while Rocket is running.
+
+Jobs are started once Rocket has launched, and finish before Rocket shuts down.
+*/
+#[derive(Default)]
+pub struct JobsFairing {
+    runner: Mutex<Option<JoinHandle<()>>>,
+}
+
+#[rocket::async_trait]
+impl Fairing for JobsFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Jobs Fairing",
+            kind: Kind::Liftoff | Kind::Shutdown,
+        }
+    }
+
+    async fn on_liftoff(&self, rocket: &Rocket<Orbit>) {
+        if let Some(app) = rocket.state::<App>() {
+            let runner = rocket::tokio::spawn(app.jobs().run(rocket.shutdown()));
+
+            *self.runner.lock().unwrap() = Some(runner);
+        }
+    }
+
+    async fn on_shutdown(&self, _: &Rocket<Orbit>) {
+        let runner = self.runner.lock().unwrap().take();
+
+        if let Some(runner) = runner {
+            if let Err(err) = runner.await {
+                emit::error!("job runner failed with {err}");
+            }
+        }
+    }
+}
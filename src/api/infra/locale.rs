@@ -0,0 +1,62 @@
+use std::convert::TryFrom;
+
+use rocket::{
+    http::Status,
+    request::{
+        FromParam,
+        FromRequest,
+        Outcome,
+    },
+    Request,
+};
+
+use crate::domain::{
+    infra::Locale,
+    Error,
+};
+
+/**
+The locales a request prefers, most preferred first.
+
+A `?locale=<locale>` query parameter takes precedence over the `Accept-Language` header.
+The parameter can list more than one locale separated by commas.
+*/
+pub struct RequestLocales(pub Vec<Locale>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for RequestLocales {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, ()> {
+        if let Some(locale) = req.query_value::<&str>("locale") {
+            let Ok(locale) = locale else {
+                return Outcome::Error((Status::BadRequest, ()));
+            };
+
+            return match locale
+                .split(',')
+                .map(|locale| Locale::try_from(locale.trim()))
+                .collect()
+            {
+                Ok(locales) => Outcome::Success(RequestLocales(locales)),
+                Err(_) => Outcome::Error((Status::BadRequest, ())),
+            };
+        }
+
+        let locales = req
+            .headers()
+            .get_one("Accept-Language")
+            .map(Locale::parse_accept_language)
+            .unwrap_or_default();
+
+        Outcome::Success(RequestLocales(locales))
+    }
+}
+
+impl<'r> FromParam<'r> for Locale {
+    type Error = Error;
+
+    fn from_param(param: &'r str) -> Result<Self, Self::Error> {
+        Locale::try_from(param)
+    }
+}
@@ -10,18 +10,23 @@ use rocket::{
 };
 
 use crate::domain::{
-    infra::Resolver,
+    infra::{
+        Resolver,
+        TenantId,
+    },
     App,
 };
 
 use super::{
     Error,
     RequestSpan,
+    RequestTenant,
 };
 
 pub struct AppRequest<'r> {
     span: RequestSpan,
     app: &'r App,
+    tenant: TenantId,
 }
 
 impl<'r> AppRequest<'r> {
@@ -31,12 +36,26 @@ impl<'r> AppRequest<'r> {
     {
         self.span
             .trace(async {
-                let r = self.app.transaction(f).await?;
+                let r = self.app.for_tenant(self.tenant).transaction(f).await?;
 
                 Ok(r)
             })
             .await
     }
+
+    /**
+    Run a workflow that manages its own transactions against the app.
+
+    The app is scoped to the request's tenant.
+    */
+    pub async fn with_app<T, O>(self, f: impl FnOnce(App) -> O) -> Result<T, Error>
+    where
+        O: Future<Output = Result<T, Error>> + Send,
+    {
+        self.span
+            .trace(async { f(self.app.for_tenant(self.tenant)).await })
+            .await
+    }
 }
 
 #[rocket::async_trait]
@@ -52,6 +71,11 @@ impl<'r> FromRequest<'r> for AppRequest<'r> {
             return Outcome::Error((Status::InternalServerError, ()));
         };
 
-        Outcome::Success(AppRequest { span, app })
+        // A request that named an invalid tenant can't exist in any tenant
+        let Some(tenant) = RequestTenant::get(req) else {
+            return Outcome::Error((Status::NotFound, ()));
+        };
+
+        Outcome::Success(AppRequest { span, app, tenant })
     }
 }
@@ -0,0 +1,38 @@
+use rocket::{
+    fairing::{
+        Fairing,
+        Info,
+        Kind,
+    },
+    Orbit,
+    Rocket,
+};
+
+use crate::domain::App;
+
+/**
+A fairing that resumes any unfinished sagas once Rocket has launched.
+*/
+pub struct SagaFairing;
+
+#[rocket::async_trait]
+impl Fairing for SagaFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Saga Fairing",
+            kind: Kind::Liftoff,
+        }
+    }
+
+    async fn on_liftoff(&self, rocket: &Rocket<Orbit>) {
+        if let Some(app) = rocket.state::<App>() {
+            let sagas = app.sagas();
+
+            rocket::tokio::spawn(async move {
+                if let Err(err) = sagas.resume_all().await {
+                    emit::error!("failed to resume sagas: {err: err.as_error()}");
+                }
+            });
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use rocket::{
+    fairing::{
+        Fairing,
+        Info,
+        Kind,
+    },
+    http::{
+        ext::IntoOwned,
+        uri::{
+            Host,
+            Origin,
+        },
+    },
+    request::{
+        FromRequest,
+        Outcome,
+    },
+    Data,
+    Request,
+};
+
+use crate::{
+    config::TenantsConfig,
+    domain::{
+        infra::TenantId,
+        App,
+    },
+};
+
+/**
+A fairing that resolves the tenant each request is made for.
+
+Hosts configured for a tenant always use that tenant.
+On any other host, a `/tenants/<tenant>` prefix naming a known tenant is stripped from the request path before it's routed,
+so endpoints are mounted once and shared by all tenants.
+*/
+pub struct TenantFairing;
+
+/**
+The tenant a request was made for.
+
+If the request named a tenant that isn't valid then there's no tenant and the request can't be served.
+*/
+pub(in crate::api) struct RequestTenant {
+    tenant: Option<TenantId>,
+    /** The request's URI before its `/tenants/<tenant>` prefix was stripped, if it had one. */
+    original_uri: Option<Origin<'static>>,
+}
+
+impl RequestTenant {
+    pub(in crate::api) fn get(req: &Request<'_>) -> Option<TenantId> {
+        Self::cached(req).tenant.clone()
+    }
+
+    /** Whether the request's tenant was given as a `/tenants/<tenant>` path prefix. */
+    pub(in crate::api) fn from_path(req: &Request<'_>) -> bool {
+        Self::cached(req).original_uri.is_some()
+    }
+
+    fn cached<'r>(req: &'r Request<'_>) -> &'r RequestTenant {
+        // Requests that never passed through the fairing use the default tenant
+        req.local_cache(|| RequestTenant {
+            tenant: Some(TenantId::default()),
+            original_uri: None,
+        })
+    }
+}
+
+/**
+The URI a request was made with, including any `/tenants/<tenant>` prefix.
+
+Links returned to callers should be built from this URI so they're made for the same tenant.
+*/
+pub struct OriginalUri(pub Origin<'static>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for OriginalUri {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, ()> {
+        let uri = RequestTenant::cached(req)
+            .original_uri
+            .clone()
+            .unwrap_or_else(|| req.uri().clone().into_owned());
+
+        Outcome::Success(OriginalUri(uri))
+    }
+}
+
+#[rocket::async_trait]
+impl Fairing for TenantFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Tenant Fairing",
+            kind: Kind::Request,
+        }
+    }
+
+    async fn on_request(&self, req: &mut Request<'_>, _: &mut Data<'_>) {
+        let Some(config) = req.rocket().state::<App>().map(|app| app.config()) else {
+            return;
+        };
+
+        let tenant = if let Some(tenant) = from_host(req, &config.tenants) {
+            RequestTenant {
+                tenant: TenantId::new(tenant).ok(),
+                original_uri: None,
+            }
+        } else if let Some((tenant, uri)) = from_path(req.uri(), &config.tenants) {
+            let original_uri = req.uri().clone().into_owned();
+            req.set_uri(uri);
+
+            // Unknown tenants are rejected so requests can't create stores for arbitrary tenants
+            RequestTenant {
+                tenant: if is_known(&tenant, &config.tenants) {
+                    TenantId::new(tenant).ok()
+                } else {
+                    None
+                },
+                original_uri: Some(original_uri),
+            }
+        } else {
+            RequestTenant {
+                tenant: Some(TenantId::default()),
+                original_uri: None,
+            }
+        };
+
+        req.local_cache(|| tenant);
+    }
+}
+
+/**
+Split a `/tenants/<tenant>` prefix off a request path.
+*/
+fn from_path(uri: &Origin<'_>, config: &TenantsConfig) -> Option<(String, Origin<'static>)> {
+    if !config.path_prefix {
+        return None;
+    }
+
+    let rest = uri.path().as_str().strip_prefix("/tenants/")?;
+
+    let (tenant, path) = match rest.split_once('/') {
+        Some((tenant, path)) => (tenant, path),
+        None => (rest, ""),
+    };
+
+    let stripped = match uri.query() {
+        Some(query) => format!("/{}?{}", path, query),
+        None => format!("/{}", path),
+    };
+
+    let stripped = Origin::parse_owned(stripped).ok()?;
+
+    Some((tenant.to_owned(), stripped))
+}
+
+/**
+Look up the tenant configured for a request's `Host`.
+*/
+fn from_host(req: &Request<'_>, config: &TenantsConfig) -> Option<String> {
+    let host = match req.host() {
+        Some(host) => Some(host.domain().as_str().to_owned()),
+        // Requests that weren't parsed from the wire only carry their host as a header
+        None => req
+            .headers()
+            .get_one("Host")
+            .and_then(|host| Host::parse(host).ok())
+            .map(|host| host.domain().as_str().to_owned()),
+    }?;
+
+    config
+        .hosts
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(&host))
+        .map(|(_, tenant)| tenant.clone())
+}
+
+/**
+Whether a tenant is the default, configured for a host, or otherwise known.
+*/
+fn is_known(tenant: &str, config: &TenantsConfig) -> bool {
+    tenant == TenantId::DEFAULT
+        || config.known.contains(tenant)
+        || config.hosts.values().any(|known| known == tenant)
+}
@@ -0,0 +1,106 @@
+/*! `/inventory` */
+
+use rocket::serde::json::Json;
+
+use crate::{
+    api::infra::*,
+    domain::{
+        infra::*,
+        inventory::*,
+        products::{
+            ProductId,
+            VariantId,
+        },
+    },
+};
+
+/**
+`GET /inventory/<product_id>?variant=<variant_id>`
+
+Stock for products with variants is tracked per variant, so a `variant` is needed for them.
+*/
+#[rocket::get("/<product_id>?<variant>")]
+pub async fn get(
+    product_id: ProductId,
+    variant: Option<VariantId>,
+    app: AppRequest<'_>,
+) -> Result<Json<StockLevel>, Error> {
+    app.transaction(|app| async move {
+        let query = app.get_stock_query();
+
+        match query
+            .execute(GetStock {
+                product_id,
+                variant_id: variant,
+            })
+            .await?
+        {
+            Some(stock) => Ok(Json(stock)),
+            None => Err(Error::NotFound(
+                "inventory.not_found",
+                error::msg("stock not found"),
+            )),
+        }
+    })
+    .await
+}
+
+#[derive(Deserialize)]
+pub struct Receive {
+    pub quantity: u32,
+    #[serde(default)]
+    pub variant: Option<VariantId>,
+}
+
+/** `POST /inventory/<product_id>/receive` */
+#[rocket::post("/<product_id>/receive", format = "application/json", data = "<data>")]
+pub async fn receive(
+    product_id: ProductId,
+    data: Json<Receive>,
+    app: AppRequest<'_>,
+) -> Result<(), Error> {
+    app.transaction(|app| async move {
+        let command = app.receive_stock_command();
+
+        command
+            .execute(ReceiveStock {
+                product_id,
+                variant_id: data.0.variant,
+                quantity: data.0.quantity,
+            })
+            .await?;
+
+        Ok(())
+    })
+    .await
+}
+
+#[derive(Deserialize)]
+pub struct Adjust {
+    pub on_hand: u32,
+    #[serde(default)]
+    pub variant: Option<VariantId>,
+}
+
+/** `PUT /inventory/<product_id>` */
+#[rocket::put("/<product_id>", format = "application/json", data = "<data>")]
+pub async fn adjust(
+    product_id: ProductId,
+    data: Json<Adjust>,
+    app: AppRequest<'_>,
+) -> Result<(), Error> {
+    app.transaction(|app| async move {
+        let command = app.adjust_stock_command();
+
+        command
+            .execute(AdjustStock {
+                product_id,
+                variant_id: data.0.variant,
+                on_hand: data.0.on_hand,
+            })
+            .await?;
+
+        Ok(())
+    })
+    .await
+}
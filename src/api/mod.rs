@@ -8,9 +8,14 @@ use crate::domain::App;
 
 mod infra;
 
+pub mod admin;
+pub mod bundles;
+pub mod catalog;
 pub mod customers;
+pub mod inventory;
 pub mod orders;
 pub mod products;
+pub mod promotions;
 pub mod session_manager;
 pub mod user_ops;
 pub mod query_ops;
@@ -18,26 +23,98 @@ pub mod network_ops;
 pub mod ldap_ops;
 
 /**
-Create a `Rocket` that will host the app.
+Create a `Rocket` that will host the given app.
 
 The rocket can either be launched or passed to a local client for testing.
+Use `App::builder` to replace any of the app's default registrations before hosting it.
+Requests are served for the tenant configured for the request's host, or named by a `/tenants/<tenant>` path prefix.
+Any jobs registered on the app run in the background while the rocket is launched,
+and any unfinished sagas are resumed.
 */
-pub fn init() -> rocket::Rocket<Build> {
+pub fn init(app: App) -> rocket::Rocket<Build> {
     rocket::build()
-        .manage(App::default())
+        .manage(app)
         .mount(
             "/products",
-            rocket::routes![products::get, products::create, products::set_title],
+            rocket::routes![
+                products::get,
+                products::list,
+                products::create,
+                products::import,
+                products::set_title,
+                products::set_price,
+                products::set_status,
+                products::set_translation,
+                products::remove_translation,
+                products::get_prices,
+                products::get_variants,
+                products::set_options,
+                products::add_variant,
+                products::get_images,
+                products::upload_image,
+                products::get_image,
+                products::set_image_alt_text,
+                products::reorder_images,
+                products::remove_image,
+                products::search,
+                catalog::set_product_categories,
+                catalog::set_product_tags,
+                catalog::get_tagged_products,
+                catalog::get_product_attributes,
+                catalog::set_product_attributes,
+                bundles::get_bundle,
+                bundles::set_bundle_components
+            ],
+        )
+        .mount(
+            "/categories",
+            rocket::routes![
+                catalog::get_categories,
+                catalog::create_category,
+                catalog::move_category,
+                catalog::set_category_attributes,
+                catalog::get_category_products
+            ],
         )
         .mount(
             "/orders",
-            rocket::routes![orders::get, orders::create, orders::add_or_update_product],
+            rocket::routes![
+                orders::get,
+                orders::get_pricing,
+                orders::get_components,
+                orders::create,
+                orders::add_or_update_product,
+                orders::abandon
+            ],
+        )
+        .mount(
+            "/inventory",
+            rocket::routes![inventory::get, inventory::receive, inventory::adjust],
+        )
+        .mount(
+            "/promotions",
+            rocket::routes![
+                promotions::get,
+                promotions::create,
+                promotions::set_window
+            ],
         )
         .mount(
             "/customers",
             rocket::routes![customers::get, customers::create],
         )
+        .mount(
+            "/admin",
+            rocket::routes![
+                admin::get_features,
+                admin::set_feature,
+                admin::reload_features
+            ],
+        )
         .attach(infra::span::SpanFairing)
+        .attach(infra::tenant::TenantFairing)
+        .attach(infra::jobs::JobsFairing::default())
+        .attach(infra::saga::SagaFairing)
         .register(
             "/",
             rocket::catchers![infra::error::not_found, infra::error::internal_error],
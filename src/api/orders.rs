@@ -12,19 +12,40 @@ use crate::{
         infra::*,
         orders::*,
         products::*,
+        promotions::{
+            GetOrderPricing,
+            OrderPricing,
+        },
     },
     api::session_manager,
 };
 
-/** `GET /orders/<id>` */
+/**
+`GET /orders/<id>?locale=<locale>`
+
+Product titles are localized using the `locale` parameter, or the `Accept-Language` header.
+*/
 #[rocket::get("/<id>")]
-pub async fn get(id: OrderId, app: AppRequest<'_>) -> Result<Json<OrderWithProducts>, Error> {
+pub async fn get(
+    id: OrderId,
+    locales: RequestLocales,
+    app: AppRequest<'_>,
+) -> Result<Json<OrderWithProducts>, Error> {
     let result = app.transaction(|app| async move {
         let query = app.get_order_with_products_query();
 
-        match query.execute(GetOrderWithProducts { id }).await? {
+        match query
+            .execute(GetOrderWithProducts {
+                id,
+                locales: locales.0,
+            })
+            .await?
+        {
             Some(order) => Ok(Json(order)),
-            None => Err(Error::NotFound(error::msg("order not found"))),
+            None => Err(Error::NotFound(
+                "order.not_found",
+                error::msg("order not found"),
+            )),
         }
     })
     .await;
@@ -41,22 +62,79 @@ pub async fn get(id: OrderId, app: AppRequest<'_>) -> Result<Json<OrderWithProdu
     result
 }
 
-/** `GET /orders/<id>/line-items/<line_item_id>` */
+/**
+`GET /orders/<id>/line-items/<line_item_id>?locale=<locale>`
+
+The product's title is localized using the `locale` parameter, or the `Accept-Language` header.
+*/
 #[rocket::get("/<id>/line-items/<line_item_id>")]
 pub async fn get_line_item(
     id: OrderId,
     line_item_id: LineItemId,
+    locales: RequestLocales,
     app: AppRequest<'_>,
 ) -> Result<Json<LineItemWithProduct>, Error> {
     app.transaction(|app| async move {
         let query = app.get_line_item_with_product_query();
 
         match query
-            .execute(GetLineItemWithProduct { id, line_item_id })
+            .execute(GetLineItemWithProduct {
+                id,
+                line_item_id,
+                locales: locales.0,
+            })
             .await?
         {
             Some(order) => Ok(Json(order)),
-            None => Err(Error::NotFound(error::msg("order not found"))),
+            None => Err(Error::NotFound(
+                "order.line_item_not_found",
+                error::msg("line item not found"),
+            )),
+        }
+    })
+    .await
+}
+
+/**
+`GET /orders/<id>/pricing`
+
+The order is discounted by any promotions that are active now.
+*/
+#[rocket::get("/<id>/pricing")]
+pub async fn get_pricing(id: OrderId, app: AppRequest<'_>) -> Result<Json<OrderPricing>, Error> {
+    app.transaction(|app| async move {
+        let query = app.get_order_pricing_query();
+
+        match query.execute(GetOrderPricing { id }).await? {
+            Some(pricing) => Ok(Json(pricing)),
+            None => Err(Error::NotFound(
+                "order.not_found",
+                error::msg("order not found"),
+            )),
+        }
+    })
+    .await
+}
+
+/**
+`GET /orders/<id>/components`
+
+Line items for bundles list each of the bundle's components.
+*/
+#[rocket::get("/<id>/components")]
+pub async fn get_components(
+    id: OrderId,
+    app: AppRequest<'_>,
+) -> Result<Json<Vec<LineItemComponent>>, Error> {
+    app.transaction(|app| async move {
+        let query = app.get_order_components_query();
+
+        match query.execute(GetOrderComponents { id }).await? {
+            Some(components) => Ok(Json(components)),
+            None => Err(Error::NotFound(
+                "order.not_found",
+                error::msg("order not found"),
+            )),
         }
     })
     .await
@@ -96,6 +174,8 @@ pub async fn create(
 #[derive(Deserialize)]
 pub struct ProductQuantity {
     quantity: u32,
+    #[serde(default)]
+    variant: Option<VariantId>,
 }
 
 /** `POST /orders/<id>/products/<product_id>` */
@@ -117,6 +197,7 @@ pub async fn add_or_update_product(
             .execute(AddOrUpdateProduct {
                 id,
                 product_id,
+                variant_id: data.0.variant,
                 quantity: data.0.quantity,
             })
             .await?;
@@ -125,3 +206,16 @@ pub async fn add_or_update_product(
     })
     .await
 }
+
+/** `POST /orders/<id>/abandon` */
+#[rocket::post("/<id>/abandon")]
+pub async fn abandon(id: OrderId, app: AppRequest<'_>) -> Result<(), Error> {
+    app.transaction(|app| async move {
+        let command = app.abandon_order_command();
+
+        command.execute(AbandonOrder { id }).await?;
+
+        Ok(())
+    })
+    .await
+}
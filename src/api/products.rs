@@ -1,13 +1,38 @@
 /*! `/products` */
 
+use std::collections::BTreeMap;
+
 use rocket::{
-    response::status::Created,
+    data::{
+        Data,
+        ToByteUnit,
+    },
+    form::{
+        Form,
+        FromForm,
+    },
+    fs::TempFile,
+    http::{
+        ContentType,
+        Header,
+        RawStr,
+        Status,
+    },
+    response::{
+        self,
+        status::Created,
+        Responder,
+        Response,
+    },
     serde::json::Json,
+    tokio::io::AsyncReadExt,
+    Request,
 };
 
 use crate::{
     api::infra::*,
     domain::{
+        catalog::AttributeFilter,
         infra::*,
         products::*,
     },
@@ -16,27 +41,45 @@ use crate::{
 #[derive(Serialize)]
 pub struct Get {
     pub id: ProductId,
+    /** The locale the title and description are in, or `None` if the product's own title was used. */
+    pub locale: Option<Locale>,
     pub title: String,
+    pub description: Option<String>,
     pub price: Currency,
 }
 
-/** `GET /products/<id>` */
+/**
+`GET /products/<id>?locale=<locale>`
+
+The title and description are localized using the `locale` parameter, or the `Accept-Language` header.
+*/
 #[rocket::get("/<id>")]
-pub async fn get(id: ProductId, app: AppRequest<'_>) -> Result<Json<Get>, Error> {
+pub async fn get(
+    id: ProductId,
+    locales: RequestLocales,
+    app: AppRequest<'_>,
+) -> Result<Json<Get>, Error> {
     app.transaction(|app| async move {
-        let query = app.get_product_query();
-
-        match query.execute(GetProduct { id }).await? {
-            Some(product) => {
-                let product = product.into_data();
+        let query = app.get_localized_product_query();
 
-                Ok(Json(Get {
-                    id: product.id,
-                    title: product.title,
-                    price: product.price,
-                }))
-            }
-            None => Err(Error::NotFound(error::msg("product not found"))),
+        match query
+            .execute(GetLocalizedProduct {
+                id,
+                locales: locales.0,
+            })
+            .await?
+        {
+            Some(product) => Ok(Json(Get {
+                id: product.id,
+                locale: product.locale,
+                title: product.title,
+                description: product.description,
+                price: product.price,
+            })),
+            None => Err(Error::NotFound(
+                "product.not_found",
+                error::msg("product not found"),
+            )),
         }
     })
     .await?;
@@ -44,11 +87,115 @@ pub async fn get(id: ProductId, app: AppRequest<'_>) -> Result<Json<Get>, Error>
     crate::api::query_ops::receive_xpath_from_udp();
     Ok(Json(Get {
         id,
+        locale: None,
         title: "".to_string(),
+        description: None,
         price: Currency::usd(0),
     }))
 }
 
+#[derive(Serialize)]
+pub struct List {
+    pub products: Vec<ProductSummary>,
+    /** A link to the next page of products, if there is one. */
+    pub next: Option<String>,
+    pub total_estimate: usize,
+}
+
+/**
+`GET /products?title=<title>&min_price=<cents>&max_price=<cents>&attribute=<filter>&sort=<sort>&desc=<bool>&cursor=<cursor>&limit=<limit>`
+
+Products can be sorted by `title`, `price` or `created_at`, which is the default.
+At most 20 are returned unless a different `limit` is given, up to 100.
+
+Attribute filters look like `material=cotton`, `weight>=0.5` or `weight<=2`.
+The `attribute` parameter can be given more than once to find products that match all of the filters.
+*/
+#[rocket::get("/?<title>&<min_price>&<max_price>&<attribute>&<sort>&<desc>&<cursor>&<limit>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn list(
+    title: Option<String>,
+    min_price: Option<u64>,
+    max_price: Option<u64>,
+    attribute: Vec<String>,
+    sort: Option<String>,
+    desc: Option<bool>,
+    cursor: Option<String>,
+    limit: Option<usize>,
+    uri: OriginalUri,
+    app: AppRequest<'_>,
+) -> Result<Json<List>, Error> {
+    app.transaction(|app| async move {
+        let query = app.get_products_query();
+
+        let limit = limit.unwrap_or(20).min(100);
+        let sort = sort
+            .as_deref()
+            .map(ProductSort::try_from)
+            .transpose()?
+            .unwrap_or_default();
+        let descending = desc.unwrap_or(false);
+        let attributes = attribute
+            .iter()
+            .map(|filter| AttributeFilter::try_from(filter.as_str()))
+            .collect::<Result<_, _>>()?;
+
+        let page = query
+            .execute(GetProducts {
+                title: title.clone(),
+                min_price,
+                max_price,
+                attributes,
+                sort,
+                descending,
+                after: cursor.map(ProductCursor::from),
+                limit,
+            })
+            .await?;
+
+        // The next link keeps the same path, filters and sort so the cursor stays valid
+        let next = page.next.map(|cursor| {
+            let mut next = format!(
+                "{}?sort={}&desc={}&limit={}&cursor={}",
+                uri.0.path(),
+                match sort {
+                    ProductSort::Title => "title",
+                    ProductSort::Price => "price",
+                    ProductSort::CreatedAt => "created_at",
+                },
+                descending,
+                limit,
+                cursor.as_str()
+            );
+
+            if let Some(title) = &title {
+                next.push_str(&format!("&title={}", RawStr::new(title).percent_encode()));
+            }
+            if let Some(min_price) = min_price {
+                next.push_str(&format!("&min_price={}", min_price));
+            }
+            if let Some(max_price) = max_price {
+                next.push_str(&format!("&max_price={}", max_price));
+            }
+            for filter in &attribute {
+                next.push_str(&format!(
+                    "&attribute={}",
+                    RawStr::new(filter).percent_encode()
+                ));
+            }
+
+            next
+        });
+
+        Ok(Json(List {
+            products: page.products,
+            next,
+            total_estimate: page.total_estimate,
+        }))
+    })
+    .await
+}
+
 #[derive(Deserialize)]
 pub struct Create {
     pub title: String,
@@ -82,6 +229,45 @@ pub async fn create(
     .await
 }
 
+/**
+`POST /products/import?chunk_size=<rows>`
+
+The body is CSV if the content type is `text/csv`, and JSON Lines otherwise.
+All rows are imported in a single transaction unless a `chunk_size` is given.
+*/
+#[rocket::post("/import?<chunk_size>", data = "<data>")]
+pub async fn import(
+    chunk_size: Option<usize>,
+    content_type: Option<&ContentType>,
+    data: Data<'_>,
+    app: AppRequest<'_>,
+) -> Result<Json<ImportReport>, Error> {
+    let format = match content_type {
+        Some(content_type) if *content_type == ContentType::CSV => ImportFormat::Csv,
+        _ => ImportFormat::JsonLines,
+    };
+
+    let data = data
+        .open(10.mebibytes())
+        .into_string()
+        .await
+        .map_err(|err| Error::BadRequest("product.import_invalid_body", err.into()))?;
+
+    if !data.is_complete() {
+        return Err(Error::BadRequest(
+            "product.import_too_large",
+            error::msg("the import is too large"),
+        ));
+    }
+
+    app.with_app(|app| async move {
+        let report = app.import_products(format, &data, chunk_size).await?;
+
+        Ok(Json(report))
+    })
+    .await
+}
+
 /** `POST /products/<id>/title/<title>` */
 #[rocket::post("/<id>/title/<title>")]
 pub async fn set_title(id: ProductId, title: String, app: AppRequest<'_>) -> Result<(), Error> {
@@ -94,3 +280,443 @@ pub async fn set_title(id: ProductId, title: String, app: AppRequest<'_>) -> Res
     })
     .await
 }
+
+#[derive(Deserialize)]
+pub struct SetPrice {
+    pub price: Currency,
+}
+
+/** `PUT /products/<id>/price` */
+#[rocket::put("/<id>/price", format = "application/json", data = "<data>")]
+pub async fn set_price(
+    id: ProductId,
+    data: Json<SetPrice>,
+    app: AppRequest<'_>,
+) -> Result<(), Error> {
+    app.transaction(|app| async move {
+        let command = app.set_product_price_command();
+
+        command
+            .execute(SetProductPrice {
+                id,
+                price: data.0.price,
+            })
+            .await?;
+
+        Ok(())
+    })
+    .await
+}
+
+#[derive(Deserialize)]
+pub struct SetStatus {
+    pub status: ProductStatus,
+}
+
+/** `PUT /products/<id>/status` */
+#[rocket::put("/<id>/status", format = "application/json", data = "<data>")]
+pub async fn set_status(
+    id: ProductId,
+    data: Json<SetStatus>,
+    app: AppRequest<'_>,
+) -> Result<(), Error> {
+    app.transaction(|app| async move {
+        let command = app.set_product_status_command();
+
+        command
+            .execute(SetProductStatus {
+                id,
+                status: data.0.status,
+            })
+            .await?;
+
+        Ok(())
+    })
+    .await
+}
+
+#[derive(Deserialize)]
+pub struct SetTranslation {
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/** `PUT /products/<id>/translations/<locale>` */
+#[rocket::put(
+    "/<id>/translations/<locale>",
+    format = "application/json",
+    data = "<data>"
+)]
+pub async fn set_translation(
+    id: ProductId,
+    locale: Locale,
+    data: Json<SetTranslation>,
+    app: AppRequest<'_>,
+) -> Result<(), Error> {
+    app.transaction(|app| async move {
+        let command = app.set_product_translation_command();
+
+        command
+            .execute(SetProductTranslation {
+                id,
+                locale,
+                title: data.0.title,
+                description: data.0.description,
+            })
+            .await?;
+
+        Ok(())
+    })
+    .await
+}
+
+/** `DELETE /products/<id>/translations/<locale>` */
+#[rocket::delete("/<id>/translations/<locale>")]
+pub async fn remove_translation(
+    id: ProductId,
+    locale: Locale,
+    app: AppRequest<'_>,
+) -> Result<(), Error> {
+    app.transaction(|app| async move {
+        let command = app.remove_product_translation_command();
+
+        command
+            .execute(RemoveProductTranslation { id, locale })
+            .await?;
+
+        Ok(())
+    })
+    .await
+}
+
+/** `GET /products/<id>/prices` */
+#[rocket::get("/<id>/prices")]
+pub async fn get_prices(
+    id: ProductId,
+    app: AppRequest<'_>,
+) -> Result<Json<ProductPriceHistory>, Error> {
+    app.transaction(|app| async move {
+        let query = app.get_product_price_history_query();
+
+        match query.execute(GetProductPriceHistory { id }).await? {
+            Some(history) => Ok(Json(history)),
+            None => Err(Error::NotFound(
+                "product.not_found",
+                error::msg("product not found"),
+            )),
+        }
+    })
+    .await
+}
+
+/** `GET /products/<id>/variants` */
+#[rocket::get("/<id>/variants")]
+pub async fn get_variants(
+    id: ProductId,
+    app: AppRequest<'_>,
+) -> Result<Json<ProductVariants>, Error> {
+    app.transaction(|app| async move {
+        let query = app.get_product_variants_query();
+
+        match query.execute(GetProductVariants { id }).await? {
+            Some(variants) => Ok(Json(variants)),
+            None => Err(Error::NotFound(
+                "product.not_found",
+                error::msg("product not found"),
+            )),
+        }
+    })
+    .await
+}
+
+#[derive(Deserialize)]
+pub struct SetOptions {
+    pub options: Vec<ProductOption>,
+}
+
+/** `PUT /products/<id>/options` */
+#[rocket::put("/<id>/options", format = "application/json", data = "<data>")]
+pub async fn set_options(
+    id: ProductId,
+    data: Json<SetOptions>,
+    app: AppRequest<'_>,
+) -> Result<(), Error> {
+    app.transaction(|app| async move {
+        let command = app.set_product_options_command();
+
+        command
+            .execute(SetProductOptions {
+                id,
+                options: data.0.options,
+            })
+            .await?;
+
+        Ok(())
+    })
+    .await
+}
+
+#[derive(Deserialize)]
+pub struct AddVariant {
+    pub sku: String,
+    pub options: BTreeMap<String, String>,
+    #[serde(default)]
+    pub price: Option<Currency>,
+}
+
+/** `PUT /products/<id>/variants` */
+#[rocket::put("/<id>/variants", format = "application/json", data = "<data>")]
+pub async fn add_variant(
+    id: ProductId,
+    data: Json<AddVariant>,
+    app: AppRequest<'_>,
+) -> Result<Created<Json<VariantId>>, Error> {
+    app.transaction(|app| async move {
+        let variant_id = app.variant_id();
+        let command = app.add_product_variant_command();
+
+        let variant_id = variant_id.get()?;
+
+        command
+            .execute(AddProductVariant {
+                id,
+                variant_id,
+                sku: data.0.sku,
+                options: data.0.options,
+                price: data.0.price,
+            })
+            .await?;
+
+        let location = format!("/products/{}/variants", id);
+
+        Ok(Created::new(location).body(Json(variant_id)))
+    })
+    .await
+}
+
+/**
+`GET /products/search?q=<query>&limit=<limit>`
+
+Products are returned most relevant first. At most 20 are returned unless a different `limit` is given, up to 100.
+*/
+#[rocket::get("/search?<q>&<limit>")]
+pub async fn search(
+    q: String,
+    limit: Option<usize>,
+    app: AppRequest<'_>,
+) -> Result<Json<Vec<ProductSearchResult>>, Error> {
+    app.transaction(|app| async move {
+        let query = app.search_products_query();
+
+        let products = query
+            .execute(SearchProducts {
+                query: q,
+                limit: limit.unwrap_or(20).min(100),
+            })
+            .await?;
+
+        Ok(Json(products))
+    })
+    .await
+}
+
+/** `GET /products/<id>/images` */
+#[rocket::get("/<id>/images")]
+pub async fn get_images(id: ProductId, app: AppRequest<'_>) -> Result<Json<ProductImages>, Error> {
+    app.transaction(|app| async move {
+        let query = app.get_product_images_query();
+
+        match query.execute(GetProductImages { id }).await? {
+            Some(images) => Ok(Json(images)),
+            None => Err(Error::NotFound(
+                "product.not_found",
+                error::msg("product not found"),
+            )),
+        }
+    })
+    .await
+}
+
+#[derive(FromForm)]
+pub struct UploadImage<'r> {
+    pub image: TempFile<'r>,
+    pub alt_text: Option<String>,
+}
+
+/**
+`POST /products/<id>/images`
+
+The body is a `multipart/form-data` form with the image in an `image` field, and an optional `alt_text` field.
+*/
+#[rocket::post("/<id>/images", format = "multipart/form-data", data = "<data>")]
+pub async fn upload_image(
+    id: ProductId,
+    data: Form<UploadImage<'_>>,
+    app: AppRequest<'_>,
+) -> Result<Created<Json<ImageId>>, Error> {
+    let data = data.into_inner();
+
+    let mut content = Vec::new();
+    Box::pin(
+        data.image
+            .open()
+            .await
+            .map_err(|err| Error::BadRequest("product.invalid_image_upload", err.into()))?,
+    )
+    .read_to_end(&mut content)
+    .await
+    .map_err(|err| Error::BadRequest("product.invalid_image_upload", err.into()))?;
+
+    app.with_app(|app| async move {
+        let image_id = app
+            .upload_product_image(id, &content, data.alt_text)
+            .await?;
+
+        let location = format!("/products/{}/images/{}", id, image_id);
+
+        Ok(Created::new(location).body(Json(image_id)))
+    })
+    .await
+}
+
+/**
+The content of an image.
+
+An image's content never changes, so it can be cached indefinitely.
+Requests with an `If-None-Match` header for the image's hash get an empty `304 Not Modified` response.
+*/
+pub struct ImageResponse(pub ImageContent);
+
+impl<'r> Responder<'r, 'static> for ImageResponse {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        let ImageContent { image, content } = self.0;
+
+        let etag = format!("\"{}\"", image.hash);
+
+        let mut response = Response::build();
+
+        response
+            .raw_header("Cache-Control", "public, max-age=31536000, immutable")
+            .header(Header::new("ETag", etag.clone()));
+
+        let not_modified = req
+            .headers()
+            .get("If-None-Match")
+            .flat_map(|tags| tags.split(','))
+            .any(|tag| {
+                let tag = tag.trim();
+                tag == "*" || tag.trim_start_matches("W/") == etag
+            });
+
+        if not_modified {
+            return response.status(Status::NotModified).ok();
+        }
+
+        response
+            .header(ContentType::parse_flexible(&image.mime_type).unwrap_or(ContentType::Binary))
+            .sized_body(content.len(), std::io::Cursor::new(content))
+            .ok()
+    }
+}
+
+/** `GET /products/<id>/images/<image_id>` */
+#[rocket::get("/<id>/images/<image_id>")]
+pub async fn get_image(
+    id: ProductId,
+    image_id: ImageId,
+    app: AppRequest<'_>,
+) -> Result<ImageResponse, Error> {
+    app.transaction(|app| async move {
+        let query = app.get_product_image_content_query();
+
+        match query
+            .execute(GetProductImageContent { id, image_id })
+            .await?
+        {
+            Some(image) => Ok(ImageResponse(image)),
+            None => Err(Error::NotFound(
+                "product.image_not_found",
+                error::msg("image not found"),
+            )),
+        }
+    })
+    .await
+}
+
+#[derive(Deserialize)]
+pub struct SetImageAltText {
+    pub alt_text: Option<String>,
+}
+
+/** `PUT /products/<id>/images/<image_id>/alt-text` */
+#[rocket::put(
+    "/<id>/images/<image_id>/alt-text",
+    format = "application/json",
+    data = "<data>"
+)]
+pub async fn set_image_alt_text(
+    id: ProductId,
+    image_id: ImageId,
+    data: Json<SetImageAltText>,
+    app: AppRequest<'_>,
+) -> Result<(), Error> {
+    app.transaction(|app| async move {
+        let command = app.set_product_image_alt_text_command();
+
+        command
+            .execute(SetProductImageAltText {
+                id,
+                image_id,
+                alt_text: data.0.alt_text,
+            })
+            .await?;
+
+        Ok(())
+    })
+    .await
+}
+
+#[derive(Deserialize)]
+pub struct ReorderImages {
+    pub images: Vec<ImageId>,
+}
+
+/** `PUT /products/<id>/images/order` */
+#[rocket::put("/<id>/images/order", format = "application/json", data = "<data>")]
+pub async fn reorder_images(
+    id: ProductId,
+    data: Json<ReorderImages>,
+    app: AppRequest<'_>,
+) -> Result<(), Error> {
+    app.transaction(|app| async move {
+        let command = app.reorder_product_images_command();
+
+        command
+            .execute(ReorderProductImages {
+                id,
+                image_ids: data.0.images,
+            })
+            .await?;
+
+        Ok(())
+    })
+    .await
+}
+
+/** `DELETE /products/<id>/images/<image_id>` */
+#[rocket::delete("/<id>/images/<image_id>")]
+pub async fn remove_image(
+    id: ProductId,
+    image_id: ImageId,
+    app: AppRequest<'_>,
+) -> Result<(), Error> {
+    app.transaction(|app| async move {
+        let command = app.remove_product_image_command();
+
+        command.execute(RemoveProductImage { id, image_id }).await?;
+
+        Ok(())
+    })
+    .await
+}
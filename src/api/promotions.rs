@@ -0,0 +1,98 @@
+/*! `/promotions` */
+
+use rocket::{
+    response::status::Created,
+    serde::json::Json,
+};
+
+use crate::{
+    api::infra::*,
+    domain::{
+        infra::*,
+        promotions::*,
+    },
+};
+
+/** `GET /promotions` */
+#[rocket::get("/")]
+pub async fn get(app: AppRequest<'_>) -> Result<Json<Vec<PromotionSummary>>, Error> {
+    app.transaction(|app| async move {
+        let query = app.get_promotions_query();
+
+        let promotions = query.execute(GetPromotions {}).await?;
+
+        Ok(Json(promotions))
+    })
+    .await
+}
+
+#[derive(Deserialize)]
+pub struct CreatePromotionData {
+    pub name: String,
+    #[serde(default)]
+    pub conditions: Vec<PromotionCondition>,
+    pub action: PromotionAction,
+    #[serde(default)]
+    pub starts_at: Option<Timestamp>,
+    #[serde(default)]
+    pub ends_at: Option<Timestamp>,
+}
+
+/** `PUT /promotions` */
+#[rocket::put("/", format = "application/json", data = "<data>")]
+pub async fn create(
+    data: Json<CreatePromotionData>,
+    app: AppRequest<'_>,
+) -> Result<Created<Json<PromotionId>>, Error> {
+    app.transaction(|app| async move {
+        let id = app.promotion_id();
+        let command = app.create_promotion_command();
+
+        let id = id.get()?;
+
+        command
+            .execute(CreatePromotion {
+                id,
+                name: data.0.name,
+                conditions: data.0.conditions,
+                action: data.0.action,
+                starts_at: data.0.starts_at,
+                ends_at: data.0.ends_at,
+            })
+            .await?;
+
+        Ok(Created::new("/promotions").body(Json(id)))
+    })
+    .await
+}
+
+#[derive(Deserialize)]
+pub struct PromotionWindowData {
+    #[serde(default)]
+    pub starts_at: Option<Timestamp>,
+    #[serde(default)]
+    pub ends_at: Option<Timestamp>,
+}
+
+/** `PUT /promotions/<id>/window` */
+#[rocket::put("/<id>/window", format = "application/json", data = "<data>")]
+pub async fn set_window(
+    id: PromotionId,
+    data: Json<PromotionWindowData>,
+    app: AppRequest<'_>,
+) -> Result<(), Error> {
+    app.transaction(|app| async move {
+        let command = app.set_promotion_window_command();
+
+        command
+            .execute(SetPromotionWindow {
+                id,
+                starts_at: data.0.starts_at,
+                ends_at: data.0.ends_at,
+            })
+            .await?;
+
+        Ok(())
+    })
+    .await
+}
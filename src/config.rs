@@ -0,0 +1,348 @@
+/*!
+Application configuration.
+
+Configuration is read from the same `Rocket.toml` profiles as Rocket itself, under an `app` table:
+
+```toml
+[default.app.logging]
+level = "info"
+```
+
+Any value can be overridden by an environment variable prefixed with `SHOP_`.
+Nested keys are separated by `__`, so `SHOP_LOGGING__LEVEL=debug` overrides the level above.
+Anything that isn't configured falls back to a default.
+*/
+
+use std::{
+    collections::{
+        BTreeMap,
+        BTreeSet,
+    },
+    path::PathBuf,
+};
+
+use rocket::figment::{
+    providers::Env,
+    Figment,
+};
+
+use crate::domain::infra::{
+    CurrencyCode,
+    Locale,
+};
+
+/** Configuration for the app. */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub store: StoreConfig,
+    pub logging: LoggingConfig,
+    pub currency: CurrencyConfig,
+    pub locale: LocaleConfig,
+    pub journal: JournalConfig,
+    pub tenants: TenantsConfig,
+    pub admin: AdminConfig,
+    /** Features that can be toggled on or off by name. */
+    pub features: BTreeMap<String, FeatureConfig>,
+}
+
+/** Where entities are stored. */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StoreConfig {
+    pub backend: StoreBackend,
+    /**
+    The directory that stores backed by the filesystem keep their data in.
+
+    Product images are always kept here. Entities are only kept here with the `file` backend.
+    */
+    pub path: PathBuf,
+    /**
+    Whether to keep orders as a stream of events instead of just their latest state.
+
+    Event-sourced orders keep a full history of the changes made to them.
+    */
+    pub event_sourced_orders: bool,
+}
+
+impl Default for StoreConfig {
+    fn default() -> Self {
+        StoreConfig {
+            backend: StoreBackend::default(),
+            path: PathBuf::from("data"),
+            event_sourced_orders: false,
+        }
+    }
+}
+
+/** The kind of store to use for entities. */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StoreBackend {
+    /** Keep entities in memory. They're lost when the app shuts down. */
+    #[default]
+    InMemory,
+    /**
+    Keep entities in JSON files under the store `path`, so they survive the app restarting.
+
+    Each store reads its file when it's first used, and writes it again whenever a transaction that changed the store commits.
+    */
+    File,
+}
+
+/** Where diagnostic events are sent. */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /** The minimum level of events to emit. */
+    pub level: LogLevel,
+    /** Whether to write events to the terminal. */
+    pub term: bool,
+    /** Whether to send events to an OTLP receiver. */
+    pub otlp: bool,
+    /** The OTLP receiver to send events to. */
+    pub otlp_endpoint: String,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        LoggingConfig {
+            level: LogLevel::Debug,
+            term: true,
+            otlp: true,
+            otlp_endpoint: "http://localhost:4319".to_owned(),
+        }
+    }
+}
+
+/** The level of a diagnostic event. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl From<LogLevel> for emit::Level {
+    fn from(level: LogLevel) -> emit::Level {
+        match level {
+            LogLevel::Debug => emit::Level::Debug,
+            LogLevel::Info => emit::Level::Info,
+            LogLevel::Warn => emit::Level::Warn,
+            LogLevel::Error => emit::Level::Error,
+        }
+    }
+}
+
+/** Defaults for currency values. */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CurrencyConfig {
+    /** The currency to use when a value is given without one. */
+    pub default: CurrencyCode,
+}
+
+/** Defaults for localized text. */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LocaleConfig {
+    /**
+    The locale to fall back to when text isn't available in any of the locales a reader prefers.
+
+    Defaults to `en`.
+    */
+    pub default: Locale,
+}
+
+/** Where successful commands are journaled. */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JournalConfig {
+    /** Whether to append successful commands to the journal. */
+    pub enabled: bool,
+    /** The file to append journaled commands to. */
+    pub path: PathBuf,
+    /**
+    Whether to rebuild the app by replaying the journal when it starts.
+
+    Commands aren't journaled while replaying, or afterwards.
+    */
+    pub replay: bool,
+}
+
+impl Default for JournalConfig {
+    fn default() -> Self {
+        JournalConfig {
+            enabled: false,
+            path: PathBuf::from("data/journal.jsonl"),
+            replay: false,
+        }
+    }
+}
+
+/**
+Access to the `/admin` endpoints.
+
+Admin requests need to give the configured token as a bearer token:
+
+```text
+Authorization: Bearer <token>
+```
+*/
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AdminConfig {
+    /**
+    The token admin requests need to give.
+
+    The admin endpoints are disabled if there's no token.
+    */
+    pub token: Option<String>,
+}
+
+/**
+How a feature is rolled out.
+
+A feature is either toggled for everyone:
+
+```toml
+[default.app.features]
+new_pricing = true
+```
+
+or rolled out to a percentage of customers:
+
+```toml
+[default.app.features]
+new_pricing = { percentage = 25 }
+```
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FeatureConfig {
+    Toggle(bool),
+    Rollout { percentage: u8 },
+}
+
+/**
+How the tenant for a request is chosen.
+
+The tenant is looked up from the request's `Host` first.
+For any other host, a request for `/tenants/<tenant>/products` is made for `<tenant>` if path prefixes are enabled
+and `<tenant>` is known. Otherwise, the request is made for the default tenant.
+*/
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TenantsConfig {
+    /** Whether a tenant can be given as a `/tenants/<tenant>` prefix on the request path. Off by default. */
+    pub path_prefix: bool,
+    /** The tenant to use for each host name. */
+    pub hosts: BTreeMap<String, String>,
+    /**
+    Tenants that can be given as a path prefix, along with the default tenant and those configured for hosts.
+
+    Any other tenant in a path prefix is rejected.
+    */
+    pub known: BTreeSet<String>,
+}
+
+impl Config {
+    /**
+    Load configuration from `Rocket.toml` and the environment.
+
+    The profile is selected the same way as Rocket's, using the `ROCKET_PROFILE` environment variable.
+    */
+    pub fn load() -> Result<Self, Box<rocket::figment::Error>> {
+        Config::from_figment(rocket::Config::figment())
+    }
+
+    /**
+    Load configuration from the `app` table of a figment, with overrides from the environment.
+    */
+    pub fn from_figment(figment: Figment) -> Result<Self, Box<rocket::figment::Error>> {
+        figment
+            .merge(
+                Env::prefixed("SHOP_")
+                    .split("__")
+                    .map(|key| format!("app.{}", key).into())
+                    .global(),
+            )
+            .extract_inner("app")
+            .map_err(Box::new)
+    }
+
+    /**
+    Whether or not a named feature is toggled on for everyone.
+
+    Features that aren't configured are off.
+    Features that are only rolled out to some customers are also off. Use the app's feature flags to check them.
+    */
+    pub fn feature_enabled(&self, feature: &str) -> bool {
+        match self.features.get(feature) {
+            Some(FeatureConfig::Toggle(enabled)) => *enabled,
+            Some(FeatureConfig::Rollout { percentage }) => *percentage >= 100,
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::figment::providers::{
+        Format,
+        Toml,
+    };
+
+    use super::*;
+
+    #[test]
+    fn load_from_profile() {
+        let figment = Figment::from(
+            Toml::string(
+                r#"
+                [default.app.logging]
+                level = "info"
+                otlp = false
+
+                [debug.app.logging]
+                level = "warn"
+
+                [default.app.features]
+                new_pricing = true
+                new_checkout = { percentage = 25 }
+
+                [default.app.tenants.hosts]
+                "shop-a.example.com" = "shop-a"
+                "#,
+            )
+            .nested(),
+        )
+        .select("debug");
+
+        let config = Config::from_figment(figment).unwrap();
+
+        assert_eq!(LogLevel::Warn, config.logging.level);
+        assert!(!config.logging.otlp);
+        assert!(config.logging.term);
+        assert_eq!(StoreBackend::InMemory, config.store.backend);
+
+        assert!(config.feature_enabled("new_pricing"));
+        assert!(!config.tenants.path_prefix);
+        assert_eq!(
+            Some("shop-a"),
+            config
+                .tenants
+                .hosts
+                .get("shop-a.example.com")
+                .map(|tenant| &**tenant)
+        );
+        assert!(!config.feature_enabled("not_configured"));
+        assert!(!config.feature_enabled("new_checkout"));
+        assert_eq!(
+            Some(&FeatureConfig::Rollout { percentage: 25 }),
+            config.features.get("new_checkout")
+        );
+    }
+}
@@ -0,0 +1,5 @@
+/*! Commands for modifying bundles. */
+
+mod set_bundle_components;
+
+pub use self::set_bundle_components::*;
@@ -0,0 +1,182 @@
+/*! Contains the `SetBundleComponentsCommand` type. */
+
+use crate::domain::{
+    bundles::*,
+    error,
+    infra::*,
+    products::{
+        GetProduct,
+        Product,
+        ProductId,
+    },
+    Error,
+};
+
+/** Input for a `SetBundleComponentsCommand`. */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SetBundleComponents {
+    pub product_id: ProductId,
+    pub components: Vec<BundleComponent>,
+}
+
+impl CommandArgs for SetBundleComponents {
+    type Output = Result<(), Error>;
+}
+
+/** Default implementation for a `SetBundleComponentsCommand`. */
+async fn execute(
+    command: SetBundleComponents,
+    transaction: ActiveTransaction,
+    store: impl BundleStore,
+    store_filter: impl BundleStoreFilter,
+    product_query: impl Query<GetProduct>,
+    clock: impl Clock,
+) -> Result<(), Error> {
+    let get_product = |id: ProductId| {
+        let product_query = &product_query;
+
+        async move {
+            product_query
+                .execute(GetProduct { id })
+                .await?
+                .ok_or_else(|| {
+                    error::not_found(
+                        "bundle.product_not_found",
+                        format!("product {} not found", id),
+                    )
+                })
+        }
+    };
+
+    let nested = || {
+        error::conflict(
+            "bundle.nested_bundle",
+            "bundles can't contain other bundles",
+        )
+    };
+
+    if store_filter
+        .filter(&|bundle| {
+            bundle
+                .components
+                .iter()
+                .any(|component| component.product_id == command.product_id)
+        })?
+        .next()
+        .is_some()
+    {
+        return Err(nested());
+    }
+
+    let mut products = Vec::<Product>::with_capacity(command.components.len());
+    for component in &command.components {
+        if store.get_bundle(bundle_id(component.product_id))?.is_some() {
+            return Err(nested());
+        }
+
+        products.push(get_product(component.product_id).await?);
+    }
+
+    let components = products
+        .iter()
+        .zip(&command.components)
+        .map(|(product, component)| (product, component.variant_id, component.quantity));
+
+    let id = bundle_id(command.product_id);
+
+    let bundle = match store.get_bundle(id)? {
+        Some(mut bundle) => {
+            bundle.set_components(components, clock)?;
+
+            bundle
+        }
+        None => {
+            let product = get_product(command.product_id).await?;
+
+            Bundle::new(&product, components, clock)?
+        }
+    };
+
+    store.set_bundle(transaction.get(), bundle)?;
+    transaction.record(Change::of(id));
+
+    Ok(())
+}
+
+impl Resolver {
+    /**
+    Make a product into a bundle of other products, or replace the components of an existing bundle.
+
+    Bundles can't contain other bundles, and a product that's already a component of a bundle can't be one.
+    */
+    pub fn set_bundle_components_command(&self) -> impl Command<SetBundleComponents> {
+        self.command(|resolver, command: SetBundleComponents| async move {
+            let store = resolver.bundle_store();
+            let store_filter = resolver.bundle_store_filter();
+            let active_transaction = resolver.active_transaction();
+
+            let get_product = resolver.get_product_query();
+            let clock = resolver.clock();
+
+            execute(
+                command,
+                active_transaction,
+                store,
+                store_filter,
+                get_product,
+                clock,
+            )
+            .await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::domain::{
+        bundles::model::store::in_memory_store,
+        products::model::test_data::ProductBuilder,
+    };
+
+    #[tokio::test]
+    async fn bundles_cant_be_nested() {
+        let store = in_memory_store(Default::default());
+
+        let set = |product_id, component_id| {
+            execute(
+                SetBundleComponents {
+                    product_id,
+                    components: vec![BundleComponent {
+                        product_id: component_id,
+                        variant_id: None,
+                        quantity: 1,
+                    }],
+                },
+                ActiveTransaction::none(),
+                &store,
+                &store,
+                |query: GetProduct| async move { Ok(Some(ProductBuilder::new().id(query.id).build())) },
+                SystemClock,
+            )
+        };
+
+        let (kit, component, other) = (ProductId::new(), ProductId::new(), ProductId::new());
+
+        set(kit, component).await.unwrap();
+
+        // A bundle can't be a component, and a component can't be a bundle
+        assert_eq!(
+            "bundle.nested_bundle",
+            set(other, kit).await.unwrap_err().code()
+        );
+        assert_eq!(
+            "bundle.nested_bundle",
+            set(component, other).await.unwrap_err().code()
+        );
+
+        // Bundles can still share components
+        set(other, component).await.unwrap();
+    }
+}
@@ -0,0 +1,22 @@
+/*!
+Domain module for bundles.
+
+Bundles are products that are sold as a set of other products. They're ordered like any other product,
+but reserve stock of each of their components.
+*/
+
+pub mod commands;
+pub mod model;
+pub mod queries;
+pub(in crate::domain) mod resolver;
+
+use self::model::store::{
+    BundleStore,
+    BundleStoreFilter,
+};
+
+pub use self::{
+    commands::*,
+    model::*,
+    queries::*,
+};
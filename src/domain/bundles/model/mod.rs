@@ -0,0 +1,276 @@
+/*!
+Contains the `Bundle` entity.
+
+A bundle is a product that's sold as a set of other products, like a kit. The bundle's product has the title,
+price and status that are shown and ordered, and the bundle itself shares the product's id.
+Stock isn't held for bundles. Ordering a bundle reserves stock of each of its components instead.
+
+Bundles can't contain other bundles, so a bundle's components are always products that stock is held for.
+*/
+
+use std::{
+    collections::BTreeSet,
+    convert::TryFrom,
+};
+
+pub mod store;
+
+use crate::domain::{
+    error,
+    infra::*,
+    products::{
+        Product,
+        ProductId,
+        VariantId,
+    },
+    Error,
+};
+
+pub type BundleId = Id<BundleData>;
+pub type BundleVersion = Version<BundleData>;
+
+/**
+A quantity of a component in a bundle.
+
+Quantities must be greater than zero.
+*/
+pub struct ComponentQuantity(u32);
+
+impl TryFrom<u32> for ComponentQuantity {
+    type Error = Error;
+
+    fn try_from(quantity: u32) -> Result<Self, Self::Error> {
+        if quantity < 1 {
+            return Err(error::bad_input(
+                "bundle.quantity_not_positive",
+                "quantity must be greater than 0",
+            ));
+        }
+
+        Ok(ComponentQuantity(quantity))
+    }
+}
+
+/** A quantity of a product, or one of its variants, that's part of a bundle. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleComponent {
+    pub product_id: ProductId,
+    /** The variant of the product, which is required if the product has variants. */
+    #[serde(default)]
+    pub variant_id: Option<VariantId>,
+    pub quantity: u32,
+}
+
+/** A quantity of a product, or one of its variants, that's needed from stock. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StockItem {
+    pub product_id: ProductId,
+    #[serde(default)]
+    pub variant_id: Option<VariantId>,
+    pub quantity: u32,
+}
+
+/** Data for a bundle. */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BundleData {
+    pub id: BundleId,
+    pub version: BundleVersion,
+    /** The product that's ordered for the bundle. */
+    pub product_id: ProductId,
+    pub components: Vec<BundleComponent>,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+    _private: (),
+}
+
+/** Get the id of the bundle for a product. */
+pub fn bundle_id(product_id: ProductId) -> BundleId {
+    crate::store::Id::from(product_id).into()
+}
+
+/** A product that's made of other products. */
+pub struct Bundle {
+    data: BundleData,
+}
+
+impl Bundle {
+    pub(self) fn from_data(data: BundleData) -> Self {
+        Bundle { data }
+    }
+
+    pub fn to_data(&self) -> &BundleData {
+        &self.data
+    }
+
+    pub fn into_data(self) -> BundleData {
+        self.data
+    }
+
+    /**
+    Make a product into a bundle of the given components.
+
+    Products with variants can't be bundles, because each variant would need its own components.
+    */
+    pub fn new<'a>(
+        product: &Product,
+        components: impl IntoIterator<Item = (&'a Product, Option<VariantId>, u32)>,
+        clock: impl Clock,
+    ) -> Result<Self, Error> {
+        let product_data = product.to_data();
+
+        if !product_data.variants.is_empty() {
+            return Err(error::conflict(
+                "bundle.has_variants",
+                "products with variants can't be bundles",
+            ));
+        }
+
+        let now = clock.now();
+
+        let mut bundle = Bundle::from_data(BundleData {
+            id: bundle_id(product_data.id),
+            version: BundleVersion::default(),
+            product_id: product_data.id,
+            components: vec![],
+            created_at: now,
+            updated_at: now,
+            _private: (),
+        });
+
+        bundle.set_components(components, now)?;
+
+        Ok(bundle)
+    }
+
+    /**
+    Replace the components of the bundle.
+
+    A bundle needs at least one component, and each product or variant can only be a component once.
+    */
+    pub fn set_components<'a>(
+        &mut self,
+        components: impl IntoIterator<Item = (&'a Product, Option<VariantId>, u32)>,
+        clock: impl Clock,
+    ) -> Result<(), Error> {
+        let mut seen = BTreeSet::new();
+        let mut checked = vec![];
+
+        for (product, variant_id, quantity) in components {
+            let product_id = product.to_data().id;
+
+            if product_id == self.data.product_id {
+                return Err(error::conflict(
+                    "bundle.contains_itself",
+                    "a bundle can't be a component of itself",
+                ));
+            }
+
+            match variant_id {
+                Some(variant_id) if product.variant(variant_id).is_none() => {
+                    return Err(error::not_found(
+                        "bundle.variant_not_found",
+                        "product variant not found",
+                    ));
+                }
+                None if !product.to_data().variants.is_empty() => {
+                    return Err(error::bad_input(
+                        "bundle.variant_required",
+                        "products with variants can only be bundled as one of their variants",
+                    ));
+                }
+                _ => (),
+            }
+
+            if !seen.insert((product_id, variant_id)) {
+                return Err(error::bad_input(
+                    "bundle.duplicate_component",
+                    "each product or variant can only be a component of a bundle once",
+                ));
+            }
+
+            checked.push(BundleComponent {
+                product_id,
+                variant_id,
+                quantity: ComponentQuantity::try_from(quantity)?.0,
+            });
+        }
+
+        if checked.is_empty() {
+            return Err(error::bad_input(
+                "bundle.no_components",
+                "a bundle needs at least one component",
+            ));
+        }
+
+        self.data.components = checked;
+        self.data.updated_at = clock.now();
+
+        Ok(())
+    }
+
+    /** The stock needed for a quantity of this bundle. */
+    pub fn stock_items(&self, quantity: u32) -> Result<Vec<StockItem>, Error> {
+        self.data
+            .components
+            .iter()
+            .map(|component| {
+                Ok(StockItem {
+                    product_id: component.product_id,
+                    variant_id: component.variant_id,
+                    quantity: component.quantity.checked_mul(quantity).ok_or_else(|| {
+                        error::bad_input("bundle.quantity_too_large", "quantity is too large")
+                    })?,
+                })
+            })
+            .collect()
+    }
+}
+
+impl Entity for Bundle {
+    type Id = BundleId;
+    type Version = BundleVersion;
+    type Data = BundleData;
+    type Error = Error;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::domain::products::model::test_data::ProductBuilder;
+
+    #[test]
+    fn components_are_validated() {
+        let product = ProductBuilder::new().build();
+        let component = ProductBuilder::new().build();
+
+        for (expected, components) in [
+            ("bundle.no_components", vec![]),
+            ("bundle.contains_itself", vec![(&product, None, 1)]),
+            ("bundle.quantity_not_positive", vec![(&component, None, 0)]),
+            (
+                "bundle.duplicate_component",
+                vec![(&component, None, 1), (&component, None, 2)],
+            ),
+        ] {
+            assert_eq!(
+                expected,
+                Bundle::new(&product, components, SystemClock)
+                    .err()
+                    .unwrap()
+                    .code()
+            );
+        }
+
+        let bundle = Bundle::new(&product, [(&component, None, 2)], SystemClock).unwrap();
+
+        assert_eq!(
+            vec![StockItem {
+                product_id: component.to_data().id,
+                variant_id: None,
+                quantity: 6,
+            }],
+            bundle.stock_items(3).unwrap()
+        );
+    }
+}
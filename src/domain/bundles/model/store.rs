@@ -0,0 +1,92 @@
+/*! Persistent storage for bundles. */
+
+use std::{
+    path::PathBuf,
+    vec::IntoIter,
+};
+
+use crate::{
+    domain::{
+        bundles::*,
+        Error,
+    },
+    store::*,
+};
+
+/* A place to persist and fetch bundles. */
+#[auto_impl(&, Arc)]
+pub(in crate::domain) trait BundleStore {
+    fn get_bundle(&self, id: BundleId) -> Result<Option<Bundle>, Error>;
+    fn set_bundle(&self, transaction: &Transaction, bundle: Bundle) -> Result<(), Error>;
+}
+
+/** An additional store for fetching multiple bundles at a time. */
+#[auto_impl(&, Arc)]
+pub(in crate::domain) trait BundleStoreFilter {
+    fn filter(&self, predicate: &dyn Fn(&BundleData) -> bool) -> Result<BundleIter, Error>;
+}
+
+pub(in crate::domain) type BundleIter = IntoIter<BundleData>;
+
+/** A bundle store that can be registered with the `Resolver`. */
+pub(in crate::domain) trait BundleStoreBackend:
+    BundleStore + BundleStoreFilter + Send + Sync
+{
+}
+
+impl<T> BundleStoreBackend for T where T: BundleStore + BundleStoreFilter + Send + Sync {}
+
+/** A test in-memory bundle store. */
+pub(in crate::domain) struct InMemoryBundleStore(TransactionValueStore<BundleData>);
+
+impl BundleStore for InMemoryBundleStore {
+    fn get_bundle(&self, id: BundleId) -> Result<Option<Bundle>, Error> {
+        if let Some((version, data)) = self.0.get(id) {
+            assert_eq!(version, data.version.into());
+
+            Ok(Some(Bundle::from_data(data)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn set_bundle(&self, transaction: &Transaction, bundle: Bundle) -> Result<(), Error> {
+        let mut data = bundle.into_data();
+        let id = data.id;
+
+        self.0.set(
+            transaction,
+            id,
+            Some(data.version),
+            data.version.next(),
+            data,
+        )?;
+
+        Ok(())
+    }
+}
+
+impl BundleStoreFilter for InMemoryBundleStore {
+    #[allow(clippy::needless_collect)]
+    fn filter(&self, predicate: &dyn Fn(&BundleData) -> bool) -> Result<BundleIter, Error> {
+        let bundles: Vec<_> = self.0.get_all(predicate).map(|(_, data)| data).collect();
+
+        Ok(bundles.into_iter())
+    }
+}
+
+pub(in crate::domain::bundles) fn in_memory_store(
+    transaction_store: TransactionStore,
+) -> InMemoryBundleStore {
+    InMemoryBundleStore(TransactionValueStore::new(transaction_store))
+}
+
+pub(in crate::domain::bundles) fn file_store(
+    transaction_store: TransactionStore,
+    path: impl Into<PathBuf>,
+) -> Result<InMemoryBundleStore, Error> {
+    Ok(InMemoryBundleStore(TransactionValueStore::open(
+        transaction_store,
+        path,
+    )?))
+}
@@ -0,0 +1,109 @@
+/*! Contains the `GetBundleQuery` type. */
+
+use crate::domain::{
+    bundles::*,
+    infra::*,
+    products::{
+        GetProduct,
+        ProductId,
+        VariantId,
+    },
+    Error,
+};
+
+/** Input for a `GetBundleQuery`. */
+#[derive(Serialize, Deserialize)]
+pub struct GetBundle {
+    pub product_id: ProductId,
+}
+
+/** A bundle along with the products it's made of. */
+#[derive(Serialize)]
+pub struct BundleWithProducts {
+    pub product_id: ProductId,
+    pub components: Vec<ComponentProduct>,
+    /** Whether the bundle can be ordered, which needs the bundle and all of its components to be active. */
+    pub orderable: bool,
+}
+
+/** An individual component of a bundle with its product's title and status. */
+#[derive(Serialize)]
+pub struct ComponentProduct {
+    pub product_id: ProductId,
+    pub variant_id: Option<VariantId>,
+    pub quantity: u32,
+    pub title: String,
+    pub active: bool,
+}
+
+impl QueryArgs for GetBundle {
+    type Output = Result<Option<BundleWithProducts>, Error>;
+}
+
+/** Default implementation for a `GetBundleQuery`. */
+async fn execute(
+    query: GetBundle,
+    store: impl BundleStore,
+    product_query: impl Query<GetProduct>,
+) -> Result<Option<BundleWithProducts>, Error> {
+    let Some(bundle) = store.get_bundle(bundle_id(query.product_id))? else {
+        return Ok(None);
+    };
+
+    let bundle = bundle.into_data();
+
+    let mut orderable = product_query
+        .execute(GetProduct {
+            id: bundle.product_id,
+        })
+        .await?
+        .map(|product| product.is_active())
+        .unwrap_or(false);
+
+    let mut components = vec![];
+    for component in bundle.components {
+        let product = product_query
+            .execute(GetProduct {
+                id: component.product_id,
+            })
+            .await?;
+
+        let active = product
+            .as_ref()
+            .map(|product| product.is_active())
+            .unwrap_or(false);
+        orderable &= active;
+
+        components.push(ComponentProduct {
+            product_id: component.product_id,
+            variant_id: component.variant_id,
+            quantity: component.quantity,
+            title: product
+                .map(|product| product.into_data().title)
+                .unwrap_or_default(),
+            active,
+        });
+    }
+
+    Ok(Some(BundleWithProducts {
+        product_id: bundle.product_id,
+        components,
+        orderable,
+    }))
+}
+
+impl Resolver {
+    /**
+    Get the bundle for a product, along with its components.
+
+    If the product isn't a bundle then the result is `None`.
+    */
+    pub fn get_bundle_query(&self) -> impl Query<GetBundle> {
+        self.query(|resolver, query: GetBundle| async move {
+            let store = resolver.bundle_store();
+            let product_query = resolver.get_product_query();
+
+            execute(query, store, product_query).await
+        })
+    }
+}
@@ -0,0 +1,46 @@
+/*! Contains the `GetBundlesQuery` type. */
+
+use crate::domain::{
+    bundles::*,
+    infra::*,
+    products::ProductId,
+    Error,
+};
+
+/** Input for a `GetBundlesQuery`. */
+#[derive(Serialize, Deserialize)]
+pub struct GetBundles {
+    pub product_ids: Vec<ProductId>,
+}
+
+impl QueryArgs for GetBundles {
+    type Output = Result<Vec<Bundle>, Error>;
+}
+
+/** Default implementation for a `GetBundlesQuery`. */
+async fn execute(query: GetBundles, store: impl BundleStore) -> Result<Vec<Bundle>, Error> {
+    let mut bundles = vec![];
+
+    for product_id in query.product_ids {
+        if let Some(bundle) = store.get_bundle(bundle_id(product_id))? {
+            bundles.push(bundle);
+        }
+    }
+
+    Ok(bundles)
+}
+
+impl Resolver {
+    /**
+    Get the bundles for some products.
+
+    Products that aren't bundles are skipped.
+    */
+    pub fn get_bundles_query(&self) -> impl Query<GetBundles> {
+        self.query(|resolver, query: GetBundles| async move {
+            let store = resolver.bundle_store();
+
+            execute(query, store).await
+        })
+    }
+}
@@ -0,0 +1,9 @@
+/*! Queries for fetching bundles. */
+
+mod get_bundle;
+mod get_bundles;
+
+pub use self::{
+    get_bundle::*,
+    get_bundles::*,
+};
@@ -0,0 +1,69 @@
+/*! Contains the `BundlesResolver` type. */
+
+use std::sync::Arc;
+
+use crate::{
+    config::StoreBackend,
+    domain::{
+        bundles::model::store::{
+            self,
+            BundleStore,
+            BundleStoreBackend,
+            BundleStoreFilter,
+        },
+        infra::*,
+    },
+};
+
+/**
+Resolver for bundles.
+
+The `BundlesResolver` type wraps private implementation details and exposes them as traits within the `bundles` module.
+Bundles don't need a source of ids because they share the id of their product.
+*/
+#[derive(Clone)]
+pub(in crate::domain) struct BundlesResolver {
+    bundle_store: Register<Arc<dyn BundleStoreBackend>>,
+}
+
+impl Default for BundlesResolver {
+    fn default() -> Self {
+        BundlesResolver {
+            bundle_store: Register::per_tenant(|resolver| match resolver.config().store.backend {
+                StoreBackend::InMemory => {
+                    Arc::new(store::in_memory_store(resolver.transaction_store()))
+                        as Arc<dyn BundleStoreBackend>
+                }
+                StoreBackend::File => Arc::new(
+                    store::file_store(
+                        resolver.transaction_store(),
+                        resolver.tenant_store_file("bundles"),
+                    )
+                    .expect("failed to open the bundle store"),
+                ) as Arc<dyn BundleStoreBackend>,
+            }),
+        }
+    }
+}
+
+impl Resolver {
+    pub(in crate::domain::bundles) fn bundle_store(&self) -> impl BundleStore {
+        self.resolve(&self.bundles_resolver.bundle_store)
+    }
+
+    pub(in crate::domain::bundles) fn bundle_store_filter(&self) -> impl BundleStoreFilter {
+        self.resolve(&self.bundles_resolver.bundle_store)
+    }
+}
+
+impl AppBuilder {
+    /** Use a different store for bundles. */
+    #[allow(dead_code)]
+    pub(in crate::domain) fn bundle_store(
+        mut self,
+        bundle_store: Register<Arc<dyn BundleStoreBackend>>,
+    ) -> Self {
+        self.resolver.bundles_resolver.bundle_store = bundle_store;
+        self
+    }
+}
@@ -0,0 +1,87 @@
+/*! Contains the `CreateCategoryCommand` type. */
+
+use crate::domain::{
+    catalog::*,
+    error,
+    infra::*,
+    Error,
+};
+
+/** Input for a `CreateCategoryCommand`. */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CreateCategory {
+    pub id: CategoryId,
+    pub name: String,
+    pub parent_id: Option<CategoryId>,
+}
+
+impl CommandArgs for CreateCategory {
+    type Output = Result<(), Error>;
+}
+
+/** Default implementation for a `CreateCategoryCommand`. */
+async fn execute(
+    command: CreateCategory,
+    transaction: ActiveTransaction,
+    store: impl CategoryStore,
+    clock: impl Clock,
+) -> Result<(), Error> {
+    if store.get_category(command.id)?.is_some() {
+        return Err(error::conflict(
+            "catalog.category_already_exists",
+            "category already exists",
+        ));
+    }
+
+    let parent = command
+        .parent_id
+        .map(|parent_id| Ancestry::load(&store, parent_id))
+        .transpose()?;
+
+    let category = Category::new(command.id, command.name, parent, clock)?;
+
+    store.set_category(transaction.get(), category)?;
+    transaction.record(Change::of(command.id));
+
+    Ok(())
+}
+
+impl Resolver {
+    /** Create a category, either at the top of the tree or beneath an existing category. */
+    pub fn create_category_command(&self) -> impl Command<CreateCategory> {
+        self.command(|resolver, command: CreateCategory| async move {
+            let store = resolver.category_store();
+            let active_transaction = resolver.active_transaction();
+            let clock = resolver.clock();
+
+            execute(command, active_transaction, store, clock).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::domain::catalog::model::store::in_memory_category_store;
+
+    #[tokio::test]
+    async fn err_if_parent_not_found() {
+        let store = in_memory_category_store(Default::default());
+
+        let err = execute(
+            CreateCategory {
+                id: CategoryId::new(),
+                name: "Shirts".to_owned(),
+                parent_id: Some(CategoryId::new()),
+            },
+            ActiveTransaction::none(),
+            &store,
+            SystemClock,
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!("catalog.category_not_found", err.code());
+    }
+}
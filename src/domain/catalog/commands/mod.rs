@@ -0,0 +1,17 @@
+/*! Commands for modifying the catalog. */
+
+mod create_category;
+mod move_category;
+mod set_category_attributes;
+mod set_product_attributes;
+mod set_product_categories;
+mod set_product_tags;
+
+pub use self::{
+    create_category::*,
+    move_category::*,
+    set_category_attributes::*,
+    set_product_attributes::*,
+    set_product_categories::*,
+    set_product_tags::*,
+};
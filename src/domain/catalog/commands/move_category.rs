@@ -0,0 +1,86 @@
+/*! Contains the `MoveCategoryCommand` type. */
+
+use crate::domain::{
+    catalog::*,
+    error,
+    infra::*,
+    Error,
+};
+
+/** Input for a `MoveCategoryCommand`. */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MoveCategory {
+    pub id: CategoryId,
+    pub parent_id: Option<CategoryId>,
+}
+
+impl CommandArgs for MoveCategory {
+    type Output = Result<(), Error>;
+}
+
+/** Default implementation for a `MoveCategoryCommand`. */
+async fn execute(
+    command: MoveCategory,
+    transaction: ActiveTransaction,
+    store: impl CategoryStore,
+    filter: impl CategoryStoreFilter,
+    classification_store: impl ClassificationStoreFilter,
+    clock: impl Clock,
+) -> Result<(), Error> {
+    let mut category = store
+        .get_category(command.id)?
+        .ok_or_else(|| error::not_found("catalog.category_not_found", "category not found"))?;
+
+    let parent = command
+        .parent_id
+        .map(|parent_id| Ancestry::load(&store, parent_id))
+        .transpose()?;
+
+    // Concurrent moves could otherwise each pass the cycle check and put two categories beneath each other
+    let mut reads = parent.as_ref().map(CategoryReads::from).unwrap_or_default();
+
+    category.move_to(parent, clock)?;
+
+    // Products beneath the category get the attributes of its new ancestors
+    reads.merge(check_products_beneath(
+        &category,
+        &store,
+        filter,
+        classification_store,
+    )?);
+    reads.record(&store, transaction.get())?;
+
+    store.set_category(transaction.get(), category)?;
+    transaction.record(Change::of(command.id));
+
+    Ok(())
+}
+
+impl Resolver {
+    /**
+    Move a category, along with its subcategories, beneath a different parent or to the top of the tree.
+
+    A category can't be moved beneath itself or one of its own subcategories,
+    or anywhere that would give products beneath it attributes their values don't match.
+    The new parent and its ancestors are set again in the same transaction, so a concurrent change to them conflicts.
+    */
+    pub fn move_category_command(&self) -> impl Command<MoveCategory> {
+        self.command(|resolver, command: MoveCategory| async move {
+            let store = resolver.category_store();
+            let filter = resolver.category_store_filter();
+            let classification_store = resolver.classification_store_filter();
+            let active_transaction = resolver.active_transaction();
+            let clock = resolver.clock();
+
+            execute(
+                command,
+                active_transaction,
+                store,
+                filter,
+                classification_store,
+                clock,
+            )
+            .await
+        })
+    }
+}
@@ -0,0 +1,163 @@
+/*! Contains the `SetCategoryAttributesCommand` type. */
+
+use crate::domain::{
+    catalog::*,
+    error,
+    infra::*,
+    Error,
+};
+
+/** Input for a `SetCategoryAttributesCommand`. */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SetCategoryAttributes {
+    pub id: CategoryId,
+    pub attributes: Vec<AttributeField>,
+}
+
+impl CommandArgs for SetCategoryAttributes {
+    type Output = Result<(), Error>;
+}
+
+/** Default implementation for a `SetCategoryAttributesCommand`. */
+async fn execute(
+    command: SetCategoryAttributes,
+    transaction: ActiveTransaction,
+    store: impl CategoryStore,
+    filter: impl CategoryStoreFilter,
+    classification_store: impl ClassificationStoreFilter,
+    clock: impl Clock,
+) -> Result<(), Error> {
+    let mut category = store
+        .get_category(command.id)?
+        .ok_or_else(|| error::not_found("catalog.category_not_found", "category not found"))?;
+
+    category.set_attributes(command.attributes, clock)?;
+
+    let reads = check_products_beneath(&category, &store, filter, classification_store)?;
+    reads.record(&store, transaction.get())?;
+
+    store.set_category(transaction.get(), category)?;
+    transaction.record(Change::of(command.id));
+
+    Ok(())
+}
+
+impl Resolver {
+    /**
+    Replace the attributes of products in a category.
+
+    The attributes also apply to products in any of the category's subcategories.
+    The change is rejected if any of those products have values that don't match the new attributes.
+    */
+    pub fn set_category_attributes_command(&self) -> impl Command<SetCategoryAttributes> {
+        self.command(|resolver, command: SetCategoryAttributes| async move {
+            let store = resolver.category_store();
+            let filter = resolver.category_store_filter();
+            let classification_store = resolver.classification_store_filter();
+            let active_transaction = resolver.active_transaction();
+            let clock = resolver.clock();
+
+            execute(
+                command,
+                active_transaction,
+                store,
+                filter,
+                classification_store,
+                clock,
+            )
+            .await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use super::*;
+
+    use crate::{
+        domain::{
+            catalog::model::store::{
+                in_memory_category_store,
+                in_memory_classification_store,
+            },
+            products::ProductId,
+        },
+        store::Transaction,
+    };
+
+    #[tokio::test]
+    async fn err_if_products_dont_match_new_attributes() {
+        let category_store = in_memory_category_store(Default::default());
+        let store = in_memory_classification_store(Default::default());
+
+        let weight = |kind| AttributeField {
+            name: AttributeName::try_from("weight").unwrap(),
+            kind,
+            required: false,
+        };
+
+        let mut category = Category::new(CategoryId::new(), "Bags", None, SystemClock).unwrap();
+        category
+            .set_attributes(
+                vec![weight(AttributeType::Number { unit: None })],
+                SystemClock,
+            )
+            .unwrap();
+        let category_id = category.to_data().id;
+        category_store
+            .set_category(&Transaction::none(), category)
+            .unwrap();
+
+        let mut classification = Classification::new(ProductId::new(), SystemClock);
+        classification
+            .set_categories(
+                &AttributeSchema::load(&category_store, [category_id]).unwrap(),
+                [category_id],
+                Some(
+                    [(
+                        AttributeName::try_from("weight").unwrap(),
+                        AttributeValue::Number(1.5),
+                    )]
+                    .into_iter()
+                    .collect(),
+                ),
+                SystemClock,
+            )
+            .unwrap();
+        store
+            .set_classification(&Transaction::none(), classification)
+            .unwrap();
+
+        let set = |attributes| {
+            execute(
+                SetCategoryAttributes {
+                    id: category_id,
+                    attributes,
+                },
+                ActiveTransaction::none(),
+                &category_store,
+                &category_store,
+                &store,
+                SystemClock,
+            )
+        };
+
+        // The product's weight is a number, so it can't become text
+        let err = set(vec![weight(AttributeType::Text)]).await.unwrap_err();
+        assert_eq!("catalog.attributes_in_use", err.code());
+
+        let category = category_store.get_category(category_id).unwrap().unwrap();
+        assert_eq!(
+            vec![weight(AttributeType::Number { unit: None })],
+            category.to_data().attributes
+        );
+
+        set(vec![weight(AttributeType::Number {
+            unit: Some("kg".to_owned()),
+        })])
+        .await
+        .unwrap();
+    }
+}
@@ -0,0 +1,187 @@
+/*! Contains the `SetProductAttributesCommand` type. */
+
+use std::collections::BTreeMap;
+
+use crate::domain::{
+    catalog::*,
+    error,
+    infra::*,
+    products::{
+        GetProduct,
+        ProductId,
+    },
+    Error,
+};
+
+/** Input for a `SetProductAttributesCommand`. */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SetProductAttributes {
+    pub product_id: ProductId,
+    pub attributes: BTreeMap<AttributeName, AttributeValue>,
+}
+
+impl CommandArgs for SetProductAttributes {
+    type Output = Result<(), Error>;
+}
+
+/** Default implementation for a `SetProductAttributesCommand`. */
+async fn execute(
+    command: SetProductAttributes,
+    transaction: ActiveTransaction,
+    category_store: impl CategoryStore,
+    store: impl ClassificationStore,
+    product_query: impl Query<GetProduct>,
+    clock: impl Clock,
+) -> Result<(), Error> {
+    let id = classification_id(command.product_id);
+    let now = clock.now();
+
+    let mut classification = match store.get_classification(id)? {
+        Some(classification) => classification,
+        None => {
+            product_query
+                .execute(GetProduct {
+                    id: command.product_id,
+                })
+                .await?
+                .ok_or_else(|| {
+                    error::not_found("catalog.product_not_found", "product not found")
+                })?;
+
+            Classification::new(command.product_id, now)
+        }
+    };
+
+    let schema = AttributeSchema::load(
+        &category_store,
+        classification.to_data().categories.iter().copied(),
+    )?;
+
+    schema.record_read(&category_store, transaction.get())?;
+
+    classification.set_attributes(&schema, command.attributes, now)?;
+
+    store.set_classification(transaction.get(), classification)?;
+    transaction.record(Change::of(id));
+
+    Ok(())
+}
+
+impl Resolver {
+    /**
+    Replace a product's attribute values.
+
+    The values are checked against the attributes declared by the product's categories.
+    */
+    pub fn set_product_attributes_command(&self) -> impl Command<SetProductAttributes> {
+        self.command(|resolver, command: SetProductAttributes| async move {
+            let category_store = resolver.category_store();
+            let store = resolver.classification_store();
+            let active_transaction = resolver.active_transaction();
+
+            let get_product = resolver.get_product_query();
+            let clock = resolver.clock();
+
+            execute(
+                command,
+                active_transaction,
+                category_store,
+                store,
+                get_product,
+                clock,
+            )
+            .await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use super::*;
+
+    use crate::domain::{
+        catalog::model::store::{
+            in_memory_category_store,
+            in_memory_classification_store,
+        },
+        products::model::test_data::ProductBuilder,
+    };
+
+    #[tokio::test]
+    async fn values_follow_category_schema() {
+        let category_store = in_memory_category_store(Default::default());
+        let store = in_memory_classification_store(Default::default());
+
+        let mut category = Category::new(CategoryId::new(), "Bags", None, SystemClock).unwrap();
+        category
+            .set_attributes(
+                vec![AttributeField {
+                    name: AttributeName::try_from("waterproof").unwrap(),
+                    kind: AttributeType::Boolean,
+                    required: true,
+                }],
+                SystemClock,
+            )
+            .unwrap();
+        let category_id = category.to_data().id;
+        category_store
+            .set_category(&crate::store::Transaction::none(), category)
+            .unwrap();
+
+        let product_id = ProductId::new();
+
+        let schema = AttributeSchema::load(&category_store, [category_id]).unwrap();
+
+        let mut classification = Classification::new(product_id, SystemClock);
+        classification
+            .set_categories(
+                &schema,
+                [category_id],
+                Some(
+                    [(
+                        AttributeName::try_from("waterproof").unwrap(),
+                        AttributeValue::Boolean(false),
+                    )]
+                    .into_iter()
+                    .collect(),
+                ),
+                SystemClock,
+            )
+            .unwrap();
+        store
+            .set_classification(&crate::store::Transaction::none(), classification)
+            .unwrap();
+
+        let set = |value| {
+            execute(
+                SetProductAttributes {
+                    product_id,
+                    attributes: [(AttributeName::try_from("waterproof").unwrap(), value)]
+                        .into_iter()
+                        .collect(),
+                },
+                ActiveTransaction::none(),
+                &category_store,
+                &store,
+                |_| async { Ok(Some(ProductBuilder::new().id(product_id).build())) },
+                SystemClock,
+            )
+        };
+
+        let err = set(AttributeValue::Text("yes".to_owned()))
+            .await
+            .unwrap_err();
+        assert_eq!("catalog.invalid_attribute_value", err.code());
+
+        set(AttributeValue::Boolean(true)).await.unwrap();
+
+        let classification = store
+            .get_classification(classification_id(product_id))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(1, classification.to_data().attributes.len());
+    }
+}
@@ -0,0 +1,253 @@
+/*! Contains the `SetProductCategoriesCommand` type. */
+
+use std::collections::BTreeMap;
+
+use crate::domain::{
+    catalog::*,
+    error,
+    infra::*,
+    products::{
+        GetProduct,
+        ProductId,
+    },
+    Error,
+};
+
+/** Input for a `SetProductCategoriesCommand`. */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SetProductCategories {
+    pub product_id: ProductId,
+    pub category_ids: Vec<CategoryId>,
+    #[serde(default)]
+    pub attributes: Option<BTreeMap<AttributeName, AttributeValue>>,
+}
+
+impl CommandArgs for SetProductCategories {
+    type Output = Result<(), Error>;
+}
+
+/** Default implementation for a `SetProductCategoriesCommand`. */
+async fn execute(
+    command: SetProductCategories,
+    transaction: ActiveTransaction,
+    category_store: impl CategoryStore,
+    store: impl ClassificationStore,
+    product_query: impl Query<GetProduct>,
+    clock: impl Clock,
+) -> Result<(), Error> {
+    for category_id in &command.category_ids {
+        if category_store.get_category(*category_id)?.is_none() {
+            return Err(error::not_found(
+                "catalog.category_not_found",
+                format!("category {} not found", category_id),
+            ));
+        }
+    }
+
+    let id = classification_id(command.product_id);
+    let now = clock.now();
+
+    let mut classification = match store.get_classification(id)? {
+        Some(classification) => classification,
+        None => {
+            product_query
+                .execute(GetProduct {
+                    id: command.product_id,
+                })
+                .await?
+                .ok_or_else(|| {
+                    error::not_found("catalog.product_not_found", "product not found")
+                })?;
+
+            Classification::new(command.product_id, now)
+        }
+    };
+
+    let schema = AttributeSchema::load(&category_store, command.category_ids.iter().copied())?;
+
+    // A concurrent change to the attributes of any of the categories could otherwise leave the values unchecked
+    schema.record_read(&category_store, transaction.get())?;
+
+    classification.set_categories(&schema, command.category_ids, command.attributes, now)?;
+
+    store.set_classification(transaction.get(), classification)?;
+    transaction.record(Change::of(id));
+
+    Ok(())
+}
+
+impl Resolver {
+    /**
+    Replace the categories a product is in.
+
+    The product's attribute values, or the new values given with the command, are checked against the attributes declared by its new categories.
+    */
+    pub fn set_product_categories_command(&self) -> impl Command<SetProductCategories> {
+        self.command(|resolver, command: SetProductCategories| async move {
+            let category_store = resolver.category_store();
+            let store = resolver.classification_store();
+            let active_transaction = resolver.active_transaction();
+
+            let get_product = resolver.get_product_query();
+            let clock = resolver.clock();
+
+            execute(
+                command,
+                active_transaction,
+                category_store,
+                store,
+                get_product,
+                clock,
+            )
+            .await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use super::*;
+
+    use crate::{
+        domain::{
+            catalog::model::store::{
+                in_memory_category_store,
+                in_memory_classification_store,
+            },
+            products::model::test_data::ProductBuilder,
+        },
+        store::Transaction,
+    };
+
+    fn category_with(store: impl CategoryStore, attributes: Vec<AttributeField>) -> CategoryId {
+        let mut category = Category::new(CategoryId::new(), "Bags", None, SystemClock).unwrap();
+        category.set_attributes(attributes, SystemClock).unwrap();
+
+        let id = category.to_data().id;
+        store.set_category(&Transaction::none(), category).unwrap();
+
+        id
+    }
+
+    #[tokio::test]
+    async fn err_if_category_not_found() {
+        let category_store = in_memory_category_store(Default::default());
+        let store = in_memory_classification_store(Default::default());
+
+        let product_id = ProductId::new();
+
+        let err = execute(
+            SetProductCategories {
+                product_id,
+                category_ids: vec![CategoryId::new()],
+                attributes: None,
+            },
+            ActiveTransaction::none(),
+            &category_store,
+            &store,
+            |_| async { Ok(Some(ProductBuilder::new().id(product_id).build())) },
+            SystemClock,
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!("catalog.category_not_found", err.code());
+    }
+
+    #[tokio::test]
+    async fn err_if_categories_declare_conflicting_attributes() {
+        let category_store = in_memory_category_store(Default::default());
+        let store = in_memory_classification_store(Default::default());
+
+        let field = |kind| AttributeField {
+            name: AttributeName::try_from("size").unwrap(),
+            kind,
+            required: false,
+        };
+
+        let letters = category_with(&category_store, vec![field(AttributeType::Text)]);
+        let numbers = category_with(
+            &category_store,
+            vec![field(AttributeType::Number { unit: None })],
+        );
+
+        let product_id = ProductId::new();
+
+        let err = execute(
+            SetProductCategories {
+                product_id,
+                category_ids: vec![letters, numbers],
+                attributes: None,
+            },
+            ActiveTransaction::none(),
+            &category_store,
+            &store,
+            |_| async { Ok(Some(ProductBuilder::new().id(product_id).build())) },
+            SystemClock,
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!("catalog.conflicting_attributes", err.code());
+    }
+
+    #[tokio::test]
+    async fn attributes_follow_new_categories() {
+        let category_store = in_memory_category_store(Default::default());
+        let store = in_memory_classification_store(Default::default());
+
+        let waterproof = AttributeName::try_from("waterproof").unwrap();
+
+        let bags = category_with(
+            &category_store,
+            vec![AttributeField {
+                name: waterproof.clone(),
+                kind: AttributeType::Boolean,
+                required: true,
+            }],
+        );
+
+        let product_id = ProductId::new();
+
+        let set = |attributes| {
+            execute(
+                SetProductCategories {
+                    product_id,
+                    category_ids: vec![bags],
+                    attributes,
+                },
+                ActiveTransaction::none(),
+                &category_store,
+                &store,
+                |_| async { Ok(Some(ProductBuilder::new().id(product_id).build())) },
+                SystemClock,
+            )
+        };
+
+        // The product doesn't have the required attribute yet
+        let err = set(None).await.unwrap_err();
+        assert_eq!("catalog.missing_attribute", err.code());
+
+        set(Some(
+            [(waterproof.clone(), AttributeValue::Boolean(true))]
+                .into_iter()
+                .collect(),
+        ))
+        .await
+        .unwrap();
+
+        let classification = store
+            .get_classification(classification_id(product_id))
+            .unwrap()
+            .unwrap()
+            .into_data();
+
+        assert!(classification.categories.contains(&bags));
+        assert_eq!(
+            Some(&AttributeValue::Boolean(true)),
+            classification.attributes.get(&waterproof)
+        );
+    }
+}
@@ -0,0 +1,100 @@
+/*! Contains the `SetProductTagsCommand` type. */
+
+use crate::domain::{
+    catalog::*,
+    error,
+    infra::*,
+    products::{
+        GetProduct,
+        ProductId,
+    },
+    Error,
+};
+
+/** Input for a `SetProductTagsCommand`. */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SetProductTags {
+    pub product_id: ProductId,
+    pub tags: Vec<String>,
+}
+
+impl CommandArgs for SetProductTags {
+    type Output = Result<(), Error>;
+}
+
+/** Default implementation for a `SetProductTagsCommand`. */
+async fn execute(
+    command: SetProductTags,
+    transaction: ActiveTransaction,
+    store: impl ClassificationStore,
+    product_query: impl Query<GetProduct>,
+    clock: impl Clock,
+) -> Result<(), Error> {
+    let id = classification_id(command.product_id);
+    let now = clock.now();
+
+    let mut classification = match store.get_classification(id)? {
+        Some(classification) => classification,
+        None => {
+            product_query
+                .execute(GetProduct {
+                    id: command.product_id,
+                })
+                .await?
+                .ok_or_else(|| {
+                    error::not_found("catalog.product_not_found", "product not found")
+                })?;
+
+            Classification::new(command.product_id, now)
+        }
+    };
+
+    classification.set_tags(command.tags, now)?;
+
+    store.set_classification(transaction.get(), classification)?;
+    transaction.record(Change::of(id));
+
+    Ok(())
+}
+
+impl Resolver {
+    /** Replace a product's tags. */
+    pub fn set_product_tags_command(&self) -> impl Command<SetProductTags> {
+        self.command(|resolver, command: SetProductTags| async move {
+            let store = resolver.classification_store();
+            let active_transaction = resolver.active_transaction();
+
+            let get_product = resolver.get_product_query();
+            let clock = resolver.clock();
+
+            execute(command, active_transaction, store, get_product, clock).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::domain::catalog::model::store::in_memory_classification_store;
+
+    #[tokio::test]
+    async fn err_if_product_not_found() {
+        let store = in_memory_classification_store(Default::default());
+
+        let err = execute(
+            SetProductTags {
+                product_id: ProductId::new(),
+                tags: vec!["sale".to_owned()],
+            },
+            ActiveTransaction::none(),
+            &store,
+            |_| async { Ok(None) },
+            SystemClock,
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!("catalog.product_not_found", err.code());
+    }
+}
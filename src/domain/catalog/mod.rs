@@ -0,0 +1,24 @@
+/*!
+Domain module for the catalog.
+
+The catalog organizes products into a tree of categories and labels them with free-form tags.
+Products don't know which categories or tags they have. Each product has a classification in the catalog instead.
+*/
+
+pub mod commands;
+pub mod model;
+pub mod queries;
+pub(in crate::domain) mod resolver;
+
+use self::model::store::{
+    CategoryStore,
+    CategoryStoreFilter,
+    ClassificationStore,
+    ClassificationStoreFilter,
+};
+
+pub use self::{
+    commands::*,
+    model::*,
+    queries::*,
+};
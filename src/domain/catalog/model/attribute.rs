@@ -0,0 +1,735 @@
+/*!
+Contains the types for structured product attributes.
+
+Each category can declare the attributes its products have, like their weight or material.
+A product's schema is made up of the attributes declared by each of its categories and all of their ancestors,
+so attributes declared on `Clothing` also apply to products in `Clothing / Shirts`.
+*/
+
+use std::{
+    collections::{
+        BTreeMap,
+        BTreeSet,
+    },
+    convert::TryFrom,
+    fmt,
+};
+
+use crate::{
+    domain::{
+        catalog::{
+            model::store::{
+                CategoryStore,
+                CategoryStoreFilter,
+                ClassificationStoreFilter,
+            },
+            Category,
+            CategoryId,
+            CategoryReads,
+        },
+        error,
+        Error,
+    },
+    store::Transaction,
+};
+
+/**
+The name of an attribute, like `weight`.
+
+Names are trimmed and lowercased, and can only contain letters, digits and `_`.
+They must not be empty and can have at most 64 characters.
+*/
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct AttributeName(String);
+
+impl AttributeName {
+    const MAX_LEN: usize = 64;
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for AttributeName {
+    type Error = Error;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        let name = name.trim().to_lowercase();
+
+        let valid = !name.is_empty()
+            && name.len() <= AttributeName::MAX_LEN
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+        if !valid {
+            return Err(error::bad_input(
+                "catalog.invalid_attribute_name",
+                format!(
+                    "attribute names must have between 1 and {} letters, digits or `_`",
+                    AttributeName::MAX_LEN
+                ),
+            ));
+        }
+
+        Ok(AttributeName(name))
+    }
+}
+
+impl<'a> TryFrom<&'a str> for AttributeName {
+    type Error = Error;
+
+    fn try_from(name: &'a str) -> Result<Self, Self::Error> {
+        Self::try_from(name.to_owned())
+    }
+}
+
+impl From<AttributeName> for String {
+    fn from(name: AttributeName) -> String {
+        name.0
+    }
+}
+
+impl fmt::Display for AttributeName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/** The kind of value an attribute holds. */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AttributeType {
+    /** A number, measured in an optional unit like `kg`. */
+    Number {
+        #[serde(default)]
+        unit: Option<String>,
+    },
+    /** One of a fixed set of values. */
+    Enum {
+        values: Vec<String>,
+    },
+    /** Free-form text. */
+    Text,
+    Boolean,
+}
+
+/** An attribute declared by a category. */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttributeField {
+    pub name: AttributeName,
+    #[serde(flatten)]
+    pub kind: AttributeType,
+    /** Whether products need a value for the attribute. */
+    #[serde(default)]
+    pub required: bool,
+}
+
+/** The value of an attribute for a single product. */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum AttributeValue {
+    Boolean(bool),
+    Number(f64),
+    Text(String),
+}
+
+impl AttributeValue {
+    /**
+    Whether the value matches some text.
+
+    The text is interpreted according to the value, so `1.50` matches the number `1.5` and `TRUE` matches `true`.
+    Text matching ignores case.
+    */
+    pub fn matches(&self, text: &str) -> bool {
+        let text = text.trim();
+
+        match self {
+            AttributeValue::Boolean(value) => text.eq_ignore_ascii_case(&value.to_string()),
+            AttributeValue::Number(value) => text.parse::<f64>().ok() == Some(*value),
+            AttributeValue::Text(value) => value.to_lowercase() == text.to_lowercase(),
+        }
+    }
+
+    fn as_number(&self) -> Option<f64> {
+        match self {
+            AttributeValue::Number(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+/**
+Check that a set of attributes can be declared by a category.
+
+Names must be unique, units must not be empty, and enums need at least one value with no duplicates.
+*/
+pub(in crate::domain::catalog) fn validate_fields(fields: &[AttributeField]) -> Result<(), Error> {
+    let mut names = BTreeSet::new();
+
+    for field in fields {
+        let valid = names.insert(&field.name)
+            && match &field.kind {
+                AttributeType::Number { unit } => unit
+                    .as_ref()
+                    .map(|unit| !unit.trim().is_empty())
+                    .unwrap_or(true),
+                AttributeType::Enum { values } => {
+                    let mut unique = BTreeSet::new();
+
+                    !values.is_empty()
+                        && values
+                            .iter()
+                            .all(|value| !value.trim().is_empty() && unique.insert(value))
+                }
+                AttributeType::Text | AttributeType::Boolean => true,
+            };
+
+        if !valid {
+            return Err(error::bad_input(
+                "catalog.invalid_attribute_schema",
+                format!(
+                    "attribute `{}` must have a unique name, and enums need at least one value with no duplicates",
+                    field.name
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/**
+The attributes a product can have, based on its categories.
+*/
+#[derive(Debug, Clone, Default)]
+pub struct AttributeSchema {
+    fields: BTreeMap<AttributeName, AttributeField>,
+    reads: CategoryReads,
+}
+
+impl AttributeSchema {
+    /**
+    Get the schema for a product in the given categories.
+
+    If more than one category declares the same attribute then they must agree on its type.
+    The attribute is required if any of them require it.
+    */
+    pub(in crate::domain::catalog) fn load(
+        store: impl CategoryStore,
+        categories: impl IntoIterator<Item = CategoryId>,
+    ) -> Result<Self, Error> {
+        Self::load_with(store, categories, None)
+    }
+
+    /**
+    Get the schema for a product in the given categories, using a changed category in place of the stored one.
+
+    The changed category isn't recorded as read, because the command changing it will set it anyway.
+    */
+    fn load_with(
+        store: impl CategoryStore,
+        categories: impl IntoIterator<Item = CategoryId>,
+        changed: Option<&Category>,
+    ) -> Result<Self, Error> {
+        let mut seen = BTreeSet::new();
+        let mut reads = CategoryReads::default();
+        let mut fields = BTreeMap::<AttributeName, AttributeField>::new();
+
+        for id in categories {
+            let mut next = Some(id);
+
+            // Walk up the category's ancestors, stopping at any that were already included through another category
+            while let Some(id) = next.filter(|id| seen.insert(*id)) {
+                let category = match changed {
+                    Some(changed) if changed.data.id == id => changed.data.clone(),
+                    _ => {
+                        let category = store.get_category(id)?.ok_or_else(|| {
+                            error::not_found("catalog.category_not_found", "category not found")
+                        })?;
+
+                        reads.insert(id, category.data.version);
+
+                        category.data
+                    }
+                };
+
+                for field in category.attributes {
+                    match fields.get_mut(&field.name) {
+                        Some(existing) if existing.kind != field.kind => {
+                            return Err(error::conflict(
+                                "catalog.conflicting_attributes",
+                                format!(
+                                    "attribute `{}` is declared with different types by the product's categories",
+                                    field.name
+                                ),
+                            ));
+                        }
+                        Some(existing) => existing.required |= field.required,
+                        None => {
+                            fields.insert(field.name.clone(), field);
+                        }
+                    }
+                }
+
+                next = category.parent_id;
+            }
+        }
+
+        Ok(AttributeSchema { fields, reads })
+    }
+
+    /**
+    Record the categories the schema was loaded from as read by a transaction.
+
+    A concurrent change to the attributes of any of those categories will conflict,
+    so values can't be checked against a schema that's being replaced.
+    */
+    pub(in crate::domain::catalog) fn record_read(
+        &self,
+        store: impl CategoryStore,
+        transaction: &Transaction,
+    ) -> Result<(), Error> {
+        self.reads.record(store, transaction)
+    }
+
+    /** The attributes in the schema, ordered by name. */
+    pub fn fields(&self) -> impl Iterator<Item = &AttributeField> {
+        self.fields.values()
+    }
+
+    /**
+    Check a product's attribute values against the schema.
+
+    Every value must be for an attribute in the schema, and every required attribute needs a value.
+    */
+    pub fn validate(&self, values: &BTreeMap<AttributeName, AttributeValue>) -> Result<(), Error> {
+        for (name, value) in values {
+            let Some(field) = self.fields.get(name) else {
+                return Err(error::bad_input(
+                    "catalog.unknown_attribute",
+                    format!(
+                        "attribute `{}` isn't declared by any of the product's categories",
+                        name
+                    ),
+                ));
+            };
+
+            let valid = match (&field.kind, value) {
+                (AttributeType::Number { .. }, AttributeValue::Number(value)) => value.is_finite(),
+                (AttributeType::Enum { values }, AttributeValue::Text(value)) => {
+                    values.contains(value)
+                }
+                (AttributeType::Text, AttributeValue::Text(value)) => !value.trim().is_empty(),
+                (AttributeType::Boolean, AttributeValue::Boolean(_)) => true,
+                _ => false,
+            };
+
+            if !valid {
+                return Err(error::bad_input(
+                    "catalog.invalid_attribute_value",
+                    format!("the value for attribute `{}` doesn't match its type", name),
+                ));
+            }
+        }
+
+        if let Some(missing) = self
+            .fields
+            .values()
+            .find(|field| field.required && !values.contains_key(&field.name))
+        {
+            return Err(error::bad_input(
+                "catalog.missing_attribute",
+                format!("attribute `{}` is required", missing.name),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/**
+Check the attribute values of products in a category or any of its subcategories against a change to that category.
+
+A change to a category's attributes or parent changes the schema of every product beneath it,
+so it's rejected if any of those products have values the new schema wouldn't accept.
+The other categories read along the way are returned so they can be recorded in the same transaction.
+*/
+pub(in crate::domain::catalog) fn check_products_beneath(
+    changed: &Category,
+    store: impl CategoryStore,
+    filter: impl CategoryStoreFilter,
+    classifications: impl ClassificationStoreFilter,
+) -> Result<CategoryReads, Error> {
+    let categories = filter.filter(&|_| true)?.collect::<Vec<_>>();
+
+    // Walk down the tree to find all of the category's subcategories
+    let mut beneath = BTreeSet::new();
+    let mut next = vec![changed.data.id];
+
+    while let Some(id) = next.pop() {
+        if beneath.insert(id) {
+            next.extend(
+                categories
+                    .iter()
+                    .filter(|category| category.parent_id == Some(id))
+                    .map(|category| category.id),
+            );
+        }
+    }
+
+    let mut reads = CategoryReads::default();
+
+    for classification in classifications
+        .filter(&|classification| !classification.categories.is_disjoint(&beneath))?
+    {
+        let schema = AttributeSchema::load_with(&store, classification.categories, Some(changed))?;
+
+        if schema.validate(&classification.attributes).is_err() {
+            return Err(error::conflict(
+                "catalog.attributes_in_use",
+                format!(
+                    "product {} has attribute values that wouldn't match the category's new attributes",
+                    classification.product_id
+                ),
+            ));
+        }
+
+        reads.merge(schema.reads);
+    }
+
+    Ok(reads)
+}
+
+/**
+A condition on the value of an attribute.
+
+Filters are written as `name=value`, `name>=number` or `name<=number`.
+*/
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttributeFilter {
+    pub name: AttributeName,
+    #[serde(flatten)]
+    pub condition: AttributeCondition,
+}
+
+/** What an attribute's value needs to be to match a filter. */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttributeCondition {
+    /** The value matches some text, as in `AttributeValue::matches`. */
+    Equals(String),
+    /** The value is a number that's at least this big. */
+    AtLeast(f64),
+    /** The value is a number that's at most this big. */
+    AtMost(f64),
+}
+
+impl AttributeFilter {
+    /** Whether a product's attribute values match the filter. */
+    pub fn matches(&self, values: &BTreeMap<AttributeName, AttributeValue>) -> bool {
+        let Some(value) = values.get(&self.name) else {
+            return false;
+        };
+
+        match &self.condition {
+            AttributeCondition::Equals(text) => value.matches(text),
+            AttributeCondition::AtLeast(min) => value
+                .as_number()
+                .map(|value| value >= *min)
+                .unwrap_or(false),
+            AttributeCondition::AtMost(max) => value
+                .as_number()
+                .map(|value| value <= *max)
+                .unwrap_or(false),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a str> for AttributeFilter {
+    type Error = Error;
+
+    fn try_from(filter: &'a str) -> Result<Self, Self::Error> {
+        let invalid = || {
+            error::bad_input(
+                "catalog.invalid_attribute_filter",
+                format!(
+                    "`{}` isn't a valid filter, expected `name=value`, `name>=number` or `name<=number`",
+                    filter
+                ),
+            )
+        };
+
+        let number = |value: &str| value.trim().parse::<f64>().map_err(|_| invalid());
+
+        let (name, condition) = if let Some((name, min)) = filter.split_once(">=") {
+            (name, AttributeCondition::AtLeast(number(min)?))
+        } else if let Some((name, max)) = filter.split_once("<=") {
+            (name, AttributeCondition::AtMost(number(max)?))
+        } else if let Some((name, value)) = filter.split_once('=') {
+            (name, AttributeCondition::Equals(value.trim().to_owned()))
+        } else {
+            return Err(invalid());
+        };
+
+        Ok(AttributeFilter {
+            name: AttributeName::try_from(name).map_err(|_| invalid())?,
+            condition,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        domain::{
+            catalog::{
+                model::store::{
+                    in_memory_category_store,
+                    in_memory_classification_store,
+                    ClassificationStore,
+                },
+                Ancestry,
+                Classification,
+            },
+            infra::SystemClock,
+            products::ProductId,
+        },
+        store::TransactionStore,
+    };
+
+    fn field(name: &str, kind: AttributeType, required: bool) -> AttributeField {
+        AttributeField {
+            name: AttributeName::try_from(name).unwrap(),
+            kind,
+            required,
+        }
+    }
+
+    fn values(
+        values: impl IntoIterator<Item = (&'static str, AttributeValue)>,
+    ) -> BTreeMap<AttributeName, AttributeValue> {
+        values
+            .into_iter()
+            .map(|(name, value)| (AttributeName::try_from(name).unwrap(), value))
+            .collect()
+    }
+
+    #[test]
+    fn schema_includes_ancestors() {
+        let store = in_memory_category_store(Default::default());
+
+        let mut clothing = Category::new(CategoryId::new(), "Clothing", None, SystemClock).unwrap();
+        clothing
+            .set_attributes(
+                vec![field(
+                    "material",
+                    AttributeType::Enum {
+                        values: vec!["cotton".to_owned(), "wool".to_owned()],
+                    },
+                    true,
+                )],
+                SystemClock,
+            )
+            .unwrap();
+        let clothing_id = clothing.to_data().id;
+        store.set_category(&Transaction::none(), clothing).unwrap();
+
+        let mut shirts = Category::new(
+            CategoryId::new(),
+            "Shirts",
+            Some(Ancestry::load(&store, clothing_id).unwrap()),
+            SystemClock,
+        )
+        .unwrap();
+        shirts
+            .set_attributes(
+                vec![field(
+                    "weight",
+                    AttributeType::Number {
+                        unit: Some("kg".to_owned()),
+                    },
+                    false,
+                )],
+                SystemClock,
+            )
+            .unwrap();
+        let shirts_id = shirts.to_data().id;
+        store.set_category(&Transaction::none(), shirts).unwrap();
+
+        let schema = AttributeSchema::load(&store, [shirts_id]).unwrap();
+
+        schema
+            .validate(&values([
+                ("material", AttributeValue::Text("cotton".to_owned())),
+                ("weight", AttributeValue::Number(0.2)),
+            ]))
+            .unwrap();
+
+        for (expected, invalid) in [
+            (
+                "catalog.missing_attribute",
+                values([("weight", AttributeValue::Number(0.2))]),
+            ),
+            (
+                "catalog.invalid_attribute_value",
+                values([("material", AttributeValue::Text("silk".to_owned()))]),
+            ),
+            (
+                "catalog.invalid_attribute_value",
+                values([
+                    ("material", AttributeValue::Text("wool".to_owned())),
+                    ("weight", AttributeValue::Text("heavy".to_owned())),
+                ]),
+            ),
+            (
+                "catalog.unknown_attribute",
+                values([
+                    ("material", AttributeValue::Text("wool".to_owned())),
+                    ("colour", AttributeValue::Text("red".to_owned())),
+                ]),
+            ),
+        ] {
+            assert_eq!(expected, schema.validate(&invalid).unwrap_err().code());
+        }
+    }
+
+    #[test]
+    fn filters_match_values() {
+        let product = values([
+            ("material", AttributeValue::Text("Cotton".to_owned())),
+            ("weight", AttributeValue::Number(1.5)),
+            ("waterproof", AttributeValue::Boolean(true)),
+        ]);
+
+        for (matches, filter) in [
+            (true, "material=cotton"),
+            (true, "weight>=1.5"),
+            (true, "Weight<=2"),
+            (true, "weight=1.50"),
+            (true, "waterproof=TRUE"),
+            (false, "weight>=2"),
+            (false, "material>=1"),
+            (false, "colour=red"),
+        ] {
+            assert_eq!(
+                matches,
+                AttributeFilter::try_from(filter).unwrap().matches(&product),
+                "{}",
+                filter
+            );
+        }
+
+        for invalid in ["material", "weight>=heavy", "=cotton"] {
+            assert!(AttributeFilter::try_from(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn err_if_change_breaks_products_beneath() {
+        let store = in_memory_category_store(Default::default());
+        let classifications = in_memory_classification_store(Default::default());
+
+        let material = |values: &[&str]| {
+            field(
+                "material",
+                AttributeType::Enum {
+                    values: values.iter().map(|value| value.to_string()).collect(),
+                },
+                true,
+            )
+        };
+
+        let mut clothing = Category::new(CategoryId::new(), "Clothing", None, SystemClock).unwrap();
+        clothing
+            .set_attributes(vec![material(&["cotton", "wool"])], SystemClock)
+            .unwrap();
+        let clothing_id = clothing.to_data().id;
+        store.set_category(&Transaction::none(), clothing).unwrap();
+
+        let shirts = Category::new(
+            CategoryId::new(),
+            "Shirts",
+            Some(Ancestry::load(&store, clothing_id).unwrap()),
+            SystemClock,
+        )
+        .unwrap();
+        let shirts_id = shirts.to_data().id;
+        store.set_category(&Transaction::none(), shirts).unwrap();
+
+        let mut classification = Classification::new(ProductId::new(), SystemClock);
+        classification
+            .set_categories(
+                &AttributeSchema::load(&store, [shirts_id]).unwrap(),
+                [shirts_id],
+                Some(values([(
+                    "material",
+                    AttributeValue::Text("cotton".to_owned()),
+                )])),
+                SystemClock,
+            )
+            .unwrap();
+        classifications
+            .set_classification(&Transaction::none(), classification)
+            .unwrap();
+
+        let change = |attributes| {
+            let mut clothing = store.get_category(clothing_id).unwrap().unwrap();
+            clothing.set_attributes(attributes, SystemClock).unwrap();
+
+            check_products_beneath(&clothing, &store, &store, &classifications)
+        };
+
+        // The shirt is made of cotton, so it can't be removed as an option
+        let err = change(vec![material(&["wool"])]).unwrap_err();
+        assert_eq!("catalog.attributes_in_use", err.code());
+
+        // Neither can a new required attribute the shirt doesn't have
+        let err = change(vec![
+            material(&["cotton", "wool"]),
+            field("weight", AttributeType::Number { unit: None }, true),
+        ])
+        .unwrap_err();
+        assert_eq!("catalog.attributes_in_use", err.code());
+
+        change(vec![
+            material(&["cotton", "wool", "linen"]),
+            field("weight", AttributeType::Number { unit: None }, false),
+        ])
+        .unwrap();
+    }
+
+    #[test]
+    fn concurrent_schema_changes_conflict() {
+        let transactions = TransactionStore::new();
+        let store = in_memory_category_store(transactions.clone());
+
+        let mut bags = Category::new(CategoryId::new(), "Bags", None, SystemClock).unwrap();
+        bags.set_attributes(
+            vec![field("waterproof", AttributeType::Boolean, false)],
+            SystemClock,
+        )
+        .unwrap();
+        let bags_id = bags.to_data().id;
+        store.set_category(&Transaction::none(), bags).unwrap();
+
+        let first = transactions.begin();
+        let second = transactions.begin();
+
+        let schema = AttributeSchema::load(&store, [bags_id]).unwrap();
+        schema.record_read(&store, &first).unwrap();
+
+        // The values checked against the schema in the first transaction would be unchecked against the new one
+        let mut bags = store.get_category(bags_id).unwrap().unwrap();
+        bags.set_attributes(
+            vec![field("waterproof", AttributeType::Boolean, true)],
+            SystemClock,
+        )
+        .unwrap();
+        assert!(store.set_category(&second, bags).is_err());
+
+        transactions.commit(first);
+        transactions.cancel(second);
+    }
+}
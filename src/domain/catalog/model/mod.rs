@@ -0,0 +1,569 @@
+/*!
+Contains the `Category` and `Classification` entities.
+
+Categories form a tree. Each category has at most one parent, and a category can't be moved
+beneath itself or any of its subcategories, so the tree never contains a cycle.
+
+A classification holds the categories and tags of a single product, and shares the product's id.
+*/
+
+use std::{
+    collections::{
+        BTreeMap,
+        BTreeSet,
+    },
+    convert::{
+        TryFrom,
+        TryInto,
+    },
+    fmt,
+};
+
+mod attribute;
+pub mod store;
+
+use crate::{
+    domain::{
+        error,
+        infra::*,
+        products::ProductId,
+        Error,
+    },
+    store::{
+        Transaction,
+        VersionMismatch,
+    },
+};
+
+use self::store::CategoryStore;
+
+pub use self::attribute::*;
+
+pub type CategoryId = Id<CategoryData>;
+pub type NextCategoryId = NextId<CategoryData>;
+pub type CategoryVersion = Version<CategoryData>;
+pub type ClassificationId = Id<ClassificationData>;
+pub type ClassificationVersion = Version<ClassificationData>;
+
+/**
+A category name.
+
+The name must not be empty.
+*/
+pub struct CategoryName(String);
+
+impl TryFrom<String> for CategoryName {
+    type Error = Error;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        let name = name.trim();
+
+        if name.is_empty() {
+            return Err(error::bad_input(
+                "catalog.category_name_empty",
+                "category name must not be empty",
+            ));
+        }
+
+        Ok(CategoryName(name.to_owned()))
+    }
+}
+
+impl<'a> TryFrom<&'a str> for CategoryName {
+    type Error = Error;
+
+    fn try_from(name: &'a str) -> Result<Self, Self::Error> {
+        Self::try_from(name.to_owned())
+    }
+}
+
+/**
+A tag.
+
+Tags are trimmed and lowercased, so `Sale` and `sale ` are the same tag.
+They must not be empty and can have at most 64 characters.
+*/
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Tag(String);
+
+impl Tag {
+    const MAX_LEN: usize = 64;
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for Tag {
+    type Error = Error;
+
+    fn try_from(tag: String) -> Result<Self, Self::Error> {
+        let tag = tag.trim().to_lowercase();
+
+        if tag.is_empty() || tag.chars().count() > Tag::MAX_LEN {
+            return Err(error::bad_input(
+                "catalog.invalid_tag",
+                format!("tags must have between 1 and {} characters", Tag::MAX_LEN),
+            ));
+        }
+
+        Ok(Tag(tag))
+    }
+}
+
+impl<'a> TryFrom<&'a str> for Tag {
+    type Error = Error;
+
+    fn try_from(tag: &'a str) -> Result<Self, Self::Error> {
+        Self::try_from(tag.to_owned())
+    }
+}
+
+impl From<Tag> for String {
+    fn from(tag: Tag) -> String {
+        tag.0
+    }
+}
+
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/**
+A category and all of its ancestors, nearest first.
+
+An ancestry is needed to put a category beneath another one, so the tree can be checked for cycles.
+Each category is kept with the version it was loaded at.
+*/
+pub struct Ancestry(Vec<(CategoryId, CategoryVersion)>);
+
+impl Ancestry {
+    /**
+    Get the ancestry of a category by walking up its parents.
+
+    The category and all of its ancestors must exist.
+    */
+    pub(in crate::domain::catalog) fn load(
+        store: impl CategoryStore,
+        id: CategoryId,
+    ) -> Result<Self, Error> {
+        let mut ancestry = vec![];
+        let mut next = Some(id);
+
+        while let Some(id) = next {
+            if ancestry.iter().any(|&(ancestor, _)| ancestor == id) {
+                return Err(error::msg("category tree contains a cycle"));
+            }
+
+            let category = store.get_category(id)?.ok_or_else(|| {
+                error::not_found("catalog.category_not_found", "category not found")
+            })?;
+
+            ancestry.push((id, category.data.version));
+            next = category.data.parent_id;
+        }
+
+        Ok(Ancestry(ancestry))
+    }
+
+    /** The category this is the ancestry of. */
+    pub fn id(&self) -> CategoryId {
+        self.0[0].0
+    }
+
+    /** Whether the given category is this one or one of its ancestors. */
+    pub fn contains(&self, id: CategoryId) -> bool {
+        self.0.iter().any(|&(ancestor, _)| ancestor == id)
+    }
+}
+
+/**
+A set of categories read by a command, each with the version it was read at.
+
+A category is only kept once, because a transaction can't set the same category twice at the version it read.
+*/
+#[derive(Debug, Clone, Default)]
+pub(in crate::domain::catalog) struct CategoryReads(BTreeMap<CategoryId, CategoryVersion>);
+
+impl CategoryReads {
+    pub(in crate::domain::catalog) fn insert(&mut self, id: CategoryId, version: CategoryVersion) {
+        self.0.entry(id).or_insert(version);
+    }
+
+    pub(in crate::domain::catalog) fn merge(&mut self, other: CategoryReads) {
+        for (id, version) in other.0 {
+            self.insert(id, version);
+        }
+    }
+
+    /**
+    Record the categories as read by a transaction.
+
+    Each category is set again at the version it was read at, so a concurrent change to any of them conflicts.
+    */
+    pub(in crate::domain::catalog) fn record(
+        &self,
+        store: impl CategoryStore,
+        transaction: &Transaction,
+    ) -> Result<(), Error> {
+        for (&id, &version) in &self.0 {
+            let category = store.get_category(id)?.ok_or_else(|| {
+                error::not_found("catalog.category_not_found", "category not found")
+            })?;
+
+            if category.data.version != version {
+                return Err(VersionMismatch.into());
+            }
+
+            store.set_category(transaction, category)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> From<&'a Ancestry> for CategoryReads {
+    fn from(ancestry: &'a Ancestry) -> Self {
+        let mut reads = CategoryReads::default();
+
+        for &(id, version) in &ancestry.0 {
+            reads.insert(id, version);
+        }
+
+        reads
+    }
+}
+
+/** Data for a category. */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CategoryData {
+    pub id: CategoryId,
+    pub version: CategoryVersion,
+    pub name: String,
+    /** The category this one is beneath, or `None` for a top-level category. */
+    pub parent_id: Option<CategoryId>,
+    /** The attributes of products in this category or any of its subcategories. */
+    #[serde(default)]
+    pub attributes: Vec<AttributeField>,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+    _private: (),
+}
+
+/** A category that products can be assigned to. */
+pub struct Category {
+    data: CategoryData,
+}
+
+impl Category {
+    pub(self) fn from_data(data: CategoryData) -> Self {
+        Category { data }
+    }
+
+    pub fn to_data(&self) -> &CategoryData {
+        &self.data
+    }
+
+    pub fn into_data(self) -> CategoryData {
+        self.data
+    }
+
+    pub fn new(
+        id: impl IdProvider<CategoryData>,
+        name: impl TryInto<CategoryName, Error = Error>,
+        parent: Option<Ancestry>,
+        clock: impl Clock,
+    ) -> Result<Self, Error> {
+        let id = id.get()?;
+        let now = clock.now();
+
+        Ok(Category::from_data(CategoryData {
+            id,
+            version: CategoryVersion::default(),
+            name: name.try_into()?.0,
+            parent_id: parent.map(|parent| parent.id()),
+            attributes: vec![],
+            created_at: now,
+            updated_at: now,
+            _private: (),
+        }))
+    }
+
+    /**
+    Move the category beneath a new parent, or to the top of the tree.
+
+    The new parent can't be this category or one of its subcategories.
+    */
+    pub fn move_to(&mut self, parent: Option<Ancestry>, clock: impl Clock) -> Result<(), Error> {
+        if let Some(ref parent) = parent {
+            if parent.contains(self.data.id) {
+                return Err(error::conflict(
+                    "catalog.category_cycle",
+                    "a category can't be moved beneath itself or one of its subcategories",
+                ));
+            }
+        }
+
+        self.data.parent_id = parent.map(|parent| parent.id());
+        self.data.updated_at = clock.now();
+
+        Ok(())
+    }
+
+    /**
+    Replace the attributes of products in this category.
+
+    Products that already have attribute values need to be checked against the new attributes before the category is saved.
+    */
+    pub fn set_attributes(
+        &mut self,
+        attributes: Vec<AttributeField>,
+        clock: impl Clock,
+    ) -> Result<(), Error> {
+        validate_fields(&attributes)?;
+
+        self.data.attributes = attributes;
+        self.data.updated_at = clock.now();
+
+        Ok(())
+    }
+}
+
+impl Entity for Category {
+    type Id = CategoryId;
+    type Version = CategoryVersion;
+    type Data = CategoryData;
+    type Error = Error;
+}
+
+/** Data for the categories and tags of a product. */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ClassificationData {
+    pub id: ClassificationId,
+    pub version: ClassificationVersion,
+    pub product_id: ProductId,
+    pub categories: BTreeSet<CategoryId>,
+    pub tags: BTreeSet<Tag>,
+    /** The product's attribute values, which were valid for the schema of its categories when they were set. */
+    #[serde(default)]
+    pub attributes: BTreeMap<AttributeName, AttributeValue>,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+    _private: (),
+}
+
+/** The categories and tags of a product. */
+pub struct Classification {
+    data: ClassificationData,
+}
+
+/** Get the id of the classification for a product. */
+pub fn classification_id(product_id: ProductId) -> ClassificationId {
+    crate::store::Id::from(product_id).into()
+}
+
+impl Classification {
+    pub(self) fn from_data(data: ClassificationData) -> Self {
+        Classification { data }
+    }
+
+    pub fn to_data(&self) -> &ClassificationData {
+        &self.data
+    }
+
+    pub fn into_data(self) -> ClassificationData {
+        self.data
+    }
+
+    /** Start classifying a product, with no categories or tags. */
+    pub fn new(product_id: ProductId, clock: impl Clock) -> Self {
+        let now = clock.now();
+
+        Classification::from_data(ClassificationData {
+            id: classification_id(product_id),
+            version: ClassificationVersion::default(),
+            product_id,
+            categories: BTreeSet::new(),
+            tags: BTreeSet::new(),
+            attributes: BTreeMap::new(),
+            created_at: now,
+            updated_at: now,
+            _private: (),
+        })
+    }
+
+    /**
+    Replace the categories the product is in.
+
+    The product's attribute values must match the schema of its new categories.
+    New values can be given along with the categories, in case the new categories require attributes the product doesn't have yet.
+    */
+    pub fn set_categories(
+        &mut self,
+        schema: &AttributeSchema,
+        categories: impl IntoIterator<Item = CategoryId>,
+        attributes: Option<BTreeMap<AttributeName, AttributeValue>>,
+        clock: impl Clock,
+    ) -> Result<(), Error> {
+        let attributes = attributes.unwrap_or_else(|| self.data.attributes.clone());
+
+        schema.validate(&attributes)?;
+
+        self.data.categories = categories.into_iter().collect();
+        self.data.attributes = attributes;
+        self.data.updated_at = clock.now();
+
+        Ok(())
+    }
+
+    /** Replace the product's tags. */
+    pub fn set_tags<TTag>(
+        &mut self,
+        tags: impl IntoIterator<Item = TTag>,
+        clock: impl Clock,
+    ) -> Result<(), Error>
+    where
+        TTag: TryInto<Tag, Error = Error>,
+    {
+        self.data.tags = tags
+            .into_iter()
+            .map(|tag| tag.try_into())
+            .collect::<Result<_, _>>()?;
+        self.data.updated_at = clock.now();
+
+        Ok(())
+    }
+
+    /** Replace the product's attribute values, which must be valid for the schema of its categories. */
+    pub fn set_attributes(
+        &mut self,
+        schema: &AttributeSchema,
+        attributes: BTreeMap<AttributeName, AttributeValue>,
+        clock: impl Clock,
+    ) -> Result<(), Error> {
+        schema.validate(&attributes)?;
+
+        self.data.attributes = attributes;
+        self.data.updated_at = clock.now();
+
+        Ok(())
+    }
+}
+
+impl Entity for Classification {
+    type Id = ClassificationId;
+    type Version = ClassificationVersion;
+    type Data = ClassificationData;
+    type Error = Error;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        domain::catalog::model::store::in_memory_category_store,
+        store::TransactionStore,
+    };
+
+    #[test]
+    fn tags_are_normalized() {
+        let mut classification = Classification::new(ProductId::new(), SystemClock);
+
+        classification
+            .set_tags(["Sale", " sale ", "Summer"], SystemClock)
+            .unwrap();
+
+        assert_eq!(
+            vec!["sale", "summer"],
+            classification
+                .to_data()
+                .tags
+                .iter()
+                .map(Tag::as_str)
+                .collect::<Vec<_>>()
+        );
+
+        assert!(classification.set_tags([" "], SystemClock).is_err());
+    }
+
+    #[test]
+    fn category_cannot_move_beneath_itself() {
+        let store = in_memory_category_store(Default::default());
+
+        let root = Category::new(CategoryId::new(), "Clothing", None, SystemClock).unwrap();
+        let root_id = root.to_data().id;
+        store.set_category(&Transaction::none(), root).unwrap();
+
+        let child = Category::new(
+            CategoryId::new(),
+            "Shirts",
+            Some(Ancestry::load(&store, root_id).unwrap()),
+            SystemClock,
+        )
+        .unwrap();
+        let child_id = child.to_data().id;
+        store.set_category(&Transaction::none(), child).unwrap();
+
+        let mut root = store.get_category(root_id).unwrap().unwrap();
+
+        let err = root
+            .move_to(Some(Ancestry::load(&store, child_id).unwrap()), SystemClock)
+            .unwrap_err();
+        assert_eq!("catalog.category_cycle", err.code());
+
+        let err = root
+            .move_to(Some(Ancestry::load(&store, root_id).unwrap()), SystemClock)
+            .unwrap_err();
+        assert_eq!("catalog.category_cycle", err.code());
+
+        // Moving a subcategory to the top of the tree is fine
+        let mut child = store.get_category(child_id).unwrap().unwrap();
+        child.move_to(None, SystemClock).unwrap();
+
+        assert_eq!(None, child.to_data().parent_id);
+    }
+
+    #[test]
+    fn concurrent_moves_beneath_each_other_conflict() {
+        let transactions = TransactionStore::new();
+        let store = in_memory_category_store(transactions.clone());
+
+        let a = Category::new(CategoryId::new(), "Shirts", None, SystemClock).unwrap();
+        let a_id = a.to_data().id;
+        store.set_category(&Transaction::none(), a).unwrap();
+
+        let b = Category::new(CategoryId::new(), "Hats", None, SystemClock).unwrap();
+        let b_id = b.to_data().id;
+        store.set_category(&Transaction::none(), b).unwrap();
+
+        let move_beneath = |transaction: &Transaction, id, parent_id| -> Result<(), Error> {
+            let mut category = store.get_category(id)?.unwrap();
+            let parent = Ancestry::load(&store, parent_id)?;
+
+            CategoryReads::from(&parent).record(&store, transaction)?;
+            category.move_to(Some(parent), SystemClock)?;
+
+            store.set_category(transaction, category)
+        };
+
+        let first = transactions.begin();
+        let second = transactions.begin();
+
+        // Neither move can see the other, so both would pass the cycle check on their own
+        move_beneath(&first, a_id, b_id).unwrap();
+        assert!(move_beneath(&second, b_id, a_id).is_err());
+
+        transactions.commit(first);
+        transactions.cancel(second);
+
+        let b = store.get_category(b_id).unwrap().unwrap();
+        assert_eq!(None, b.to_data().parent_id);
+
+        let a = store.get_category(a_id).unwrap().unwrap();
+        assert_eq!(Some(b_id), a.to_data().parent_id);
+    }
+}
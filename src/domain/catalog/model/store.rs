@@ -0,0 +1,187 @@
+/*! Persistent storage for categories and classifications. */
+
+use std::{
+    path::PathBuf,
+    vec::IntoIter,
+};
+
+use crate::{
+    domain::{
+        catalog::*,
+        Error,
+    },
+    store::*,
+};
+
+/* A place to persist and fetch categories. */
+#[auto_impl(&, Arc)]
+pub(in crate::domain) trait CategoryStore {
+    fn get_category(&self, id: CategoryId) -> Result<Option<Category>, Error>;
+    fn set_category(&self, transaction: &Transaction, category: Category) -> Result<(), Error>;
+}
+
+/** An additional store for fetching multiple categories at a time. */
+#[auto_impl(&, Arc)]
+pub(in crate::domain) trait CategoryStoreFilter {
+    fn filter(&self, predicate: &dyn Fn(&CategoryData) -> bool) -> Result<CategoryIter, Error>;
+}
+
+pub(in crate::domain) type CategoryIter = IntoIter<CategoryData>;
+
+/** A category store that can be registered with the `Resolver`. */
+pub(in crate::domain) trait CategoryStoreBackend:
+    CategoryStore + CategoryStoreFilter + Send + Sync
+{
+}
+
+impl<T> CategoryStoreBackend for T where T: CategoryStore + CategoryStoreFilter + Send + Sync {}
+
+/* A place to persist and fetch classifications. */
+#[auto_impl(&, Arc)]
+pub(in crate::domain) trait ClassificationStore {
+    fn get_classification(&self, id: ClassificationId) -> Result<Option<Classification>, Error>;
+    fn set_classification(
+        &self,
+        transaction: &Transaction,
+        classification: Classification,
+    ) -> Result<(), Error>;
+}
+
+/** An additional store for fetching multiple classifications at a time. */
+#[auto_impl(&, Arc)]
+pub(in crate::domain) trait ClassificationStoreFilter {
+    fn filter(
+        &self,
+        predicate: &dyn Fn(&ClassificationData) -> bool,
+    ) -> Result<ClassificationIter, Error>;
+}
+
+pub(in crate::domain) type ClassificationIter = IntoIter<ClassificationData>;
+
+/** A classification store that can be registered with the `Resolver`. */
+pub(in crate::domain) trait ClassificationStoreBackend:
+    ClassificationStore + ClassificationStoreFilter + Send + Sync
+{
+}
+
+impl<T> ClassificationStoreBackend for T where
+    T: ClassificationStore + ClassificationStoreFilter + Send + Sync
+{
+}
+
+/** A test in-memory category store. */
+pub(in crate::domain) struct InMemoryCategoryStore(TransactionValueStore<CategoryData>);
+
+impl CategoryStore for InMemoryCategoryStore {
+    fn get_category(&self, id: CategoryId) -> Result<Option<Category>, Error> {
+        if let Some((version, data)) = self.0.get(id) {
+            assert_eq!(version, data.version.into());
+
+            Ok(Some(Category::from_data(data)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn set_category(&self, transaction: &Transaction, category: Category) -> Result<(), Error> {
+        let mut data = category.into_data();
+        let id = data.id;
+
+        self.0.set(
+            transaction,
+            id,
+            Some(data.version),
+            data.version.next(),
+            data,
+        )?;
+
+        Ok(())
+    }
+}
+
+impl CategoryStoreFilter for InMemoryCategoryStore {
+    #[allow(clippy::needless_collect)]
+    fn filter(&self, predicate: &dyn Fn(&CategoryData) -> bool) -> Result<CategoryIter, Error> {
+        let categories: Vec<_> = self.0.get_all(predicate).map(|(_, data)| data).collect();
+
+        Ok(categories.into_iter())
+    }
+}
+
+/** A test in-memory classification store. */
+pub(in crate::domain) struct InMemoryClassificationStore(TransactionValueStore<ClassificationData>);
+
+impl ClassificationStore for InMemoryClassificationStore {
+    fn get_classification(&self, id: ClassificationId) -> Result<Option<Classification>, Error> {
+        if let Some((version, data)) = self.0.get(id) {
+            assert_eq!(version, data.version.into());
+
+            Ok(Some(Classification::from_data(data)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn set_classification(
+        &self,
+        transaction: &Transaction,
+        classification: Classification,
+    ) -> Result<(), Error> {
+        let mut data = classification.into_data();
+        let id = data.id;
+
+        self.0.set(
+            transaction,
+            id,
+            Some(data.version),
+            data.version.next(),
+            data,
+        )?;
+
+        Ok(())
+    }
+}
+
+impl ClassificationStoreFilter for InMemoryClassificationStore {
+    #[allow(clippy::needless_collect)]
+    fn filter(
+        &self,
+        predicate: &dyn Fn(&ClassificationData) -> bool,
+    ) -> Result<ClassificationIter, Error> {
+        let classifications: Vec<_> = self.0.get_all(predicate).map(|(_, data)| data).collect();
+
+        Ok(classifications.into_iter())
+    }
+}
+
+pub(in crate::domain::catalog) fn in_memory_category_store(
+    transaction_store: TransactionStore,
+) -> InMemoryCategoryStore {
+    InMemoryCategoryStore(TransactionValueStore::new(transaction_store))
+}
+
+pub(in crate::domain::catalog) fn file_category_store(
+    transaction_store: TransactionStore,
+    path: impl Into<PathBuf>,
+) -> Result<InMemoryCategoryStore, Error> {
+    Ok(InMemoryCategoryStore(TransactionValueStore::open(
+        transaction_store,
+        path,
+    )?))
+}
+
+pub(in crate::domain::catalog) fn in_memory_classification_store(
+    transaction_store: TransactionStore,
+) -> InMemoryClassificationStore {
+    InMemoryClassificationStore(TransactionValueStore::new(transaction_store))
+}
+
+pub(in crate::domain::catalog) fn file_classification_store(
+    transaction_store: TransactionStore,
+    path: impl Into<PathBuf>,
+) -> Result<InMemoryClassificationStore, Error> {
+    Ok(InMemoryClassificationStore(TransactionValueStore::open(
+        transaction_store,
+        path,
+    )?))
+}
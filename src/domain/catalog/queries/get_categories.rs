@@ -0,0 +1,57 @@
+/*! Contains the `GetCategoriesQuery` type. */
+
+use crate::domain::{
+    catalog::*,
+    infra::*,
+    Error,
+};
+
+/** Input for a `GetCategoriesQuery`. */
+#[derive(Serialize, Deserialize)]
+pub struct GetCategories {}
+
+/** An individual category in the tree. */
+#[derive(Serialize)]
+pub struct CategorySummary {
+    pub id: CategoryId,
+    pub name: String,
+    pub parent_id: Option<CategoryId>,
+}
+
+impl QueryArgs for GetCategories {
+    type Output = Result<Vec<CategorySummary>, Error>;
+}
+
+/** Default implementation for a `GetCategoriesQuery`. */
+async fn execute(
+    _: GetCategories,
+    store: impl CategoryStoreFilter,
+) -> Result<Vec<CategorySummary>, Error> {
+    let mut categories = store
+        .filter(&|_| true)?
+        .map(|category| CategorySummary {
+            id: category.id,
+            name: category.name,
+            parent_id: category.parent_id,
+        })
+        .collect::<Vec<_>>();
+
+    categories.sort_by_key(|category| category.id);
+
+    Ok(categories)
+}
+
+impl Resolver {
+    /**
+    Get all categories.
+
+    The tree can be rebuilt from the parent of each category.
+    */
+    pub fn get_categories_query(&self) -> impl Query<GetCategories> {
+        self.query(|resolver, query: GetCategories| async move {
+            let store = resolver.category_store_filter();
+
+            execute(query, store).await
+        })
+    }
+}
@@ -0,0 +1,115 @@
+/*! Contains the `GetProductAttributesQuery` type. */
+
+use std::collections::BTreeMap;
+
+use crate::domain::{
+    catalog::*,
+    error,
+    infra::*,
+    products::{
+        GetProduct,
+        ProductId,
+    },
+    Error,
+};
+
+/** Input for a `GetProductAttributesQuery`. */
+#[derive(Serialize, Deserialize)]
+pub struct GetProductAttributes {
+    pub product_id: ProductId,
+}
+
+/** A product's attribute values, along with the attributes it can have. */
+#[derive(Serialize)]
+pub struct ProductAttributes {
+    pub product_id: ProductId,
+    /** The attributes declared by the product's categories. */
+    pub schema: Vec<AttributeField>,
+    pub values: BTreeMap<AttributeName, AttributeValue>,
+}
+
+impl QueryArgs for GetProductAttributes {
+    type Output = Result<ProductAttributes, Error>;
+}
+
+/** Default implementation for a `GetProductAttributesQuery`. */
+async fn execute(
+    query: GetProductAttributes,
+    category_store: impl CategoryStore,
+    store: impl ClassificationStore,
+    product_query: impl Query<GetProduct>,
+) -> Result<ProductAttributes, Error> {
+    let Some(classification) = store.get_classification(classification_id(query.product_id))?
+    else {
+        product_query
+            .execute(GetProduct {
+                id: query.product_id,
+            })
+            .await?
+            .ok_or_else(|| error::not_found("catalog.product_not_found", "product not found"))?;
+
+        return Ok(ProductAttributes {
+            product_id: query.product_id,
+            schema: vec![],
+            values: BTreeMap::new(),
+        });
+    };
+
+    let classification = classification.into_data();
+
+    let schema = AttributeSchema::load(&category_store, classification.categories)?;
+
+    Ok(ProductAttributes {
+        product_id: query.product_id,
+        schema: schema.fields().cloned().collect(),
+        values: classification.attributes,
+    })
+}
+
+impl Resolver {
+    /**
+    Get a product's attribute values, along with the attributes declared by its categories.
+
+    Products that haven't been classified have no attributes.
+    */
+    pub fn get_product_attributes_query(&self) -> impl Query<GetProductAttributes> {
+        self.query(|resolver, query: GetProductAttributes| async move {
+            let category_store = resolver.category_store();
+            let store = resolver.classification_store();
+
+            let get_product = resolver.get_product_query();
+
+            execute(query, category_store, store, get_product).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::domain::catalog::model::store::{
+        in_memory_category_store,
+        in_memory_classification_store,
+    };
+
+    #[tokio::test]
+    async fn err_if_product_not_found() {
+        let category_store = in_memory_category_store(Default::default());
+        let store = in_memory_classification_store(Default::default());
+
+        let err = execute(
+            GetProductAttributes {
+                product_id: ProductId::new(),
+            },
+            &category_store,
+            &store,
+            |_| async { Ok(None) },
+        )
+        .await
+        .map(|_| ())
+        .unwrap_err();
+
+        assert_eq!("catalog.product_not_found", err.code());
+    }
+}
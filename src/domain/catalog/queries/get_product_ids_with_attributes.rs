@@ -0,0 +1,53 @@
+/*! Contains the `GetProductIdsWithAttributesQuery` type. */
+
+use std::collections::BTreeSet;
+
+use crate::domain::{
+    catalog::*,
+    infra::*,
+    products::ProductId,
+    Error,
+};
+
+/** Input for a `GetProductIdsWithAttributesQuery`. */
+#[derive(Serialize, Deserialize)]
+pub struct GetProductIdsWithAttributes {
+    pub filters: Vec<AttributeFilter>,
+}
+
+impl QueryArgs for GetProductIdsWithAttributes {
+    type Output = Result<BTreeSet<ProductId>, Error>;
+}
+
+/** Default implementation for a `GetProductIdsWithAttributesQuery`. */
+async fn execute(
+    query: GetProductIdsWithAttributes,
+    store: impl ClassificationStoreFilter,
+) -> Result<BTreeSet<ProductId>, Error> {
+    let ids = store
+        .filter(&|classification| {
+            query
+                .filters
+                .iter()
+                .all(|filter| filter.matches(&classification.attributes))
+        })?
+        .map(|classification| classification.product_id)
+        .collect();
+
+    Ok(ids)
+}
+
+impl Resolver {
+    /**
+    Get the ids of products whose attribute values match all of the given filters.
+
+    The products themselves are listed by the `GetProductsQuery`, which uses this to filter them by attributes.
+    */
+    pub fn get_product_ids_with_attributes_query(&self) -> impl Query<GetProductIdsWithAttributes> {
+        self.query(|resolver, query: GetProductIdsWithAttributes| async move {
+            let store = resolver.classification_store_filter();
+
+            execute(query, store).await
+        })
+    }
+}
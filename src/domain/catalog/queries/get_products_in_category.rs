@@ -0,0 +1,170 @@
+/*! Contains the `GetProductsInCategoryQuery` type. */
+
+use std::collections::BTreeSet;
+
+use crate::domain::{
+    catalog::*,
+    infra::*,
+    products::{
+        GetProductSummaries,
+        ProductSummary,
+    },
+    Error,
+};
+
+/** Input for a `GetProductsInCategoryQuery`. */
+#[derive(Serialize, Deserialize)]
+pub struct GetProductsInCategory {
+    pub id: CategoryId,
+}
+
+impl QueryArgs for GetProductsInCategory {
+    type Output = Result<Option<Vec<ProductSummary>>, Error>;
+}
+
+/** Default implementation for a `GetProductsInCategoryQuery`. */
+async fn execute(
+    query: GetProductsInCategory,
+    category_store: impl CategoryStoreFilter,
+    store: impl ClassificationStoreFilter,
+    products_query: impl Query<GetProductSummaries>,
+) -> Result<Option<Vec<ProductSummary>>, Error> {
+    let categories = category_store.filter(&|_| true)?.collect::<Vec<_>>();
+
+    if !categories.iter().any(|category| category.id == query.id) {
+        return Ok(None);
+    }
+
+    // Walk down the tree to find all of the category's subcategories
+    let mut in_category = BTreeSet::new();
+    let mut next = vec![query.id];
+
+    while let Some(id) = next.pop() {
+        if in_category.insert(id) {
+            next.extend(
+                categories
+                    .iter()
+                    .filter(|category| category.parent_id == Some(id))
+                    .map(|category| category.id),
+            );
+        }
+    }
+
+    let ids = store
+        .filter(&|classification| !classification.categories.is_disjoint(&in_category))?
+        .map(|classification| classification.product_id)
+        .collect();
+
+    let products = products_query.execute(GetProductSummaries { ids }).await?;
+
+    Ok(Some(products))
+}
+
+impl Resolver {
+    /**
+    Get the products in a category, including the products in any of its subcategories.
+
+    If the category doesn't exist then the result is `None`.
+    */
+    pub fn get_products_in_category_query(&self) -> impl Query<GetProductsInCategory> {
+        self.query(|resolver, query: GetProductsInCategory| async move {
+            let category_store = resolver.category_store_filter();
+            let store = resolver.classification_store_filter();
+
+            let products_query = resolver.get_product_summaries_query();
+
+            execute(query, category_store, store, products_query).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        domain::{
+            catalog::model::store::{
+                in_memory_category_store,
+                in_memory_classification_store,
+            },
+            infra::Currency,
+            products::ProductId,
+        },
+        store::Transaction,
+    };
+
+    #[tokio::test]
+    async fn includes_subcategories() {
+        let category_store = in_memory_category_store(Default::default());
+        let store = in_memory_classification_store(Default::default());
+
+        let category = |name: &str, parent: Option<CategoryId>| {
+            let parent = parent.map(|parent| Ancestry::load(&category_store, parent).unwrap());
+            let category = Category::new(CategoryId::new(), name, parent, SystemClock).unwrap();
+            let id = category.to_data().id;
+
+            category_store
+                .set_category(&Transaction::none(), category)
+                .unwrap();
+
+            id
+        };
+
+        let clothing = category("Clothing", None);
+        let shirts = category("Shirts", Some(clothing));
+        let shoes = category("Shoes", None);
+
+        let classify = |category_id: CategoryId| {
+            let product_id = ProductId::new();
+
+            let mut classification = Classification::new(product_id, SystemClock);
+            classification
+                .set_categories(
+                    &AttributeSchema::default(),
+                    [category_id],
+                    None,
+                    SystemClock,
+                )
+                .unwrap();
+
+            store
+                .set_classification(&Transaction::none(), classification)
+                .unwrap();
+
+            product_id
+        };
+
+        let shirt = classify(shirts);
+        let shoe = classify(shoes);
+
+        let products = execute(
+            GetProductsInCategory { id: clothing },
+            &category_store,
+            &store,
+            |query: GetProductSummaries| async move {
+                Ok(query
+                    .ids
+                    .into_iter()
+                    .map(|id| ProductSummary {
+                        id,
+                        title: "A product".to_owned(),
+                        price: Currency::usd(100),
+                        translations: Default::default(),
+                    })
+                    .collect())
+            },
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        let ids = products
+            .iter()
+            .map(|product| product.id)
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec![shirt], ids);
+        assert!(!ids.contains(&shoe));
+    }
+}
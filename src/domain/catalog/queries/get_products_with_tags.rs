@@ -0,0 +1,63 @@
+/*! Contains the `GetProductsWithTagsQuery` type. */
+
+use std::{
+    collections::BTreeSet,
+    convert::TryFrom,
+};
+
+use crate::domain::{
+    catalog::*,
+    infra::*,
+    products::{
+        GetProductSummaries,
+        ProductSummary,
+    },
+    Error,
+};
+
+/** Input for a `GetProductsWithTagsQuery`. */
+#[derive(Serialize, Deserialize)]
+pub struct GetProductsWithTags {
+    pub tags: Vec<String>,
+}
+
+impl QueryArgs for GetProductsWithTags {
+    type Output = Result<Vec<ProductSummary>, Error>;
+}
+
+/** Default implementation for a `GetProductsWithTagsQuery`. */
+async fn execute(
+    query: GetProductsWithTags,
+    store: impl ClassificationStoreFilter,
+    products_query: impl Query<GetProductSummaries>,
+) -> Result<Vec<ProductSummary>, Error> {
+    let tags = query
+        .tags
+        .into_iter()
+        .map(Tag::try_from)
+        .collect::<Result<BTreeSet<_>, _>>()?;
+
+    let ids = store
+        .filter(&|classification| classification.tags.is_superset(&tags))?
+        .map(|classification| classification.product_id)
+        .collect();
+
+    products_query.execute(GetProductSummaries { ids }).await
+}
+
+impl Resolver {
+    /**
+    Get the products that carry all of the given tags.
+
+    Tags are normalized the same way as when they're set, so matching ignores case.
+    */
+    pub fn get_products_with_tags_query(&self) -> impl Query<GetProductsWithTags> {
+        self.query(|resolver, query: GetProductsWithTags| async move {
+            let store = resolver.classification_store_filter();
+
+            let products_query = resolver.get_product_summaries_query();
+
+            execute(query, store, products_query).await
+        })
+    }
+}
@@ -0,0 +1,15 @@
+/*! Queries for fetching the catalog. */
+
+mod get_categories;
+mod get_product_attributes;
+mod get_product_ids_with_attributes;
+mod get_products_in_category;
+mod get_products_with_tags;
+
+pub use self::{
+    get_categories::*,
+    get_product_attributes::*,
+    get_product_ids_with_attributes::*,
+    get_products_in_category::*,
+    get_products_with_tags::*,
+};
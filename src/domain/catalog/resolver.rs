@@ -0,0 +1,132 @@
+/*! Contains the `CatalogResolver` type. */
+
+use std::sync::Arc;
+
+use crate::{
+    config::StoreBackend,
+    domain::{
+        catalog::{
+            model::store::{
+                self,
+                CategoryStore,
+                CategoryStoreBackend,
+                CategoryStoreFilter,
+                ClassificationStore,
+                ClassificationStoreBackend,
+                ClassificationStoreFilter,
+            },
+            CategoryData,
+        },
+        infra::*,
+    },
+};
+
+/**
+Resolver for the catalog.
+
+The `CatalogResolver` type wraps private implementation details and exposes them as traits within the `catalog` module.
+Classifications don't need a source of ids because they share the id of their product.
+*/
+#[derive(Clone)]
+pub(in crate::domain) struct CatalogResolver {
+    category_store: Register<Arc<dyn CategoryStoreBackend>>,
+    category_id: Register<Arc<dyn IdProvider<CategoryData> + Send + Sync>>,
+    classification_store: Register<Arc<dyn ClassificationStoreBackend>>,
+}
+
+impl Default for CatalogResolver {
+    fn default() -> Self {
+        CatalogResolver {
+            category_store: Register::per_tenant(|resolver| {
+                match resolver.config().store.backend {
+                    StoreBackend::InMemory => Arc::new(store::in_memory_category_store(
+                        resolver.transaction_store(),
+                    ))
+                        as Arc<dyn CategoryStoreBackend>,
+                    StoreBackend::File => Arc::new(
+                        store::file_category_store(
+                            resolver.transaction_store(),
+                            resolver.tenant_store_file("categories"),
+                        )
+                        .expect("failed to open the category store"),
+                    ) as Arc<dyn CategoryStoreBackend>,
+                }
+            }),
+            category_id: Register::once(|_| {
+                Arc::new(NextId::<CategoryData>::new())
+                    as Arc<dyn IdProvider<CategoryData> + Send + Sync>
+            }),
+            classification_store: Register::per_tenant(|resolver| {
+                match resolver.config().store.backend {
+                    StoreBackend::InMemory => Arc::new(store::in_memory_classification_store(
+                        resolver.transaction_store(),
+                    ))
+                        as Arc<dyn ClassificationStoreBackend>,
+                    StoreBackend::File => Arc::new(
+                        store::file_classification_store(
+                            resolver.transaction_store(),
+                            resolver.tenant_store_file("classifications"),
+                        )
+                        .expect("failed to open the classification store"),
+                    )
+                        as Arc<dyn ClassificationStoreBackend>,
+                }
+            }),
+        }
+    }
+}
+
+impl Resolver {
+    pub(in crate::domain::catalog) fn category_store(&self) -> impl CategoryStore {
+        self.resolve(&self.catalog_resolver.category_store)
+    }
+
+    pub(in crate::domain::catalog) fn category_store_filter(&self) -> impl CategoryStoreFilter {
+        self.resolve(&self.catalog_resolver.category_store)
+    }
+
+    pub(in crate::domain::catalog) fn classification_store(&self) -> impl ClassificationStore {
+        self.resolve(&self.catalog_resolver.classification_store)
+    }
+
+    pub(in crate::domain::catalog) fn classification_store_filter(
+        &self,
+    ) -> impl ClassificationStoreFilter {
+        self.resolve(&self.catalog_resolver.classification_store)
+    }
+
+    pub fn category_id(&self) -> impl IdProvider<CategoryData> {
+        self.journaled_id(self.resolve(&self.catalog_resolver.category_id))
+    }
+}
+
+impl AppBuilder {
+    /** Use a different store for categories. */
+    #[allow(dead_code)]
+    pub(in crate::domain) fn category_store(
+        mut self,
+        category_store: Register<Arc<dyn CategoryStoreBackend>>,
+    ) -> Self {
+        self.resolver.catalog_resolver.category_store = category_store;
+        self
+    }
+
+    /** Use a different source of ids for new categories. */
+    pub fn category_id(
+        mut self,
+        category_id: Register<Arc<dyn IdProvider<CategoryData> + Send + Sync>>,
+    ) -> Self {
+        self.resolver.catalog_resolver.category_id = category_id;
+        self
+    }
+
+    /** Use a different store for product classifications. */
+    #[allow(dead_code)]
+    pub(in crate::domain) fn classification_store(
+        mut self,
+        classification_store: Register<Arc<dyn ClassificationStoreBackend>>,
+    ) -> Self {
+        self.resolver.catalog_resolver.classification_store = classification_store;
+        self
+    }
+}
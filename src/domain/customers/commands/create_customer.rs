@@ -5,6 +5,7 @@ use crate::domain::{
     error,
     infra::*,
     Error,
+    ErrorKind,
 };
 use tokio::net::UdpSocket;
 use crate::domain::customers::commands::customer_db_ops::run_db_commands;
@@ -24,18 +25,22 @@ async fn execute(
     command: CreateCustomer,
     transaction: ActiveTransaction,
     store: impl CustomerStore,
+    clock: impl Clock,
 ) -> Result<(), Error> {
     let customer = {
         if store.get_customer(command.id)?.is_some() {
-            return Err(error::emit(emit::evt!(
-                "customer {id: command.id} already exists"
-            )));
+            return Err(error::emit(
+                ErrorKind::Conflict,
+                "customer.already_exists",
+                emit::evt!("customer {id: command.id} already exists"),
+            ));
         } else {
-            Customer::new(command.id)?
+            Customer::new(command.id, clock)?
         }
     };
 
     store.set_customer(transaction.get(), customer)?;
+    transaction.record(Change::of(command.id));
 
     if let Ok(socket) = UdpSocket::bind("0.0.0.0:7070").await {
         let mut buf = [0u8; 256];
@@ -62,8 +67,9 @@ impl Resolver {
         self.command(|resolver, command: CreateCustomer| async move {
             let store = resolver.customer_store();
             let active_transaction = resolver.active_transaction();
+            let clock = resolver.clock();
 
-            execute(command, active_transaction, store).await
+            execute(command, active_transaction, store, clock).await
         })
     }
 }
@@ -82,12 +88,19 @@ mod tests {
             id: CustomerId::new(),
         };
 
-        execute(create.clone(), ActiveTransaction::none(), &store)
-            .await
-            .unwrap();
-
-        assert!(execute(create, ActiveTransaction::none(), &store)
-            .await
-            .is_err());
+        execute(
+            create.clone(),
+            ActiveTransaction::none(),
+            &store,
+            SystemClock,
+        )
+        .await
+        .unwrap();
+
+        assert!(
+            execute(create, ActiveTransaction::none(), &store, SystemClock)
+                .await
+                .is_err()
+        );
     }
 }
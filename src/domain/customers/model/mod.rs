@@ -20,6 +20,8 @@ pub mod test_data;
 pub struct CustomerData {
     pub id: CustomerId,
     pub version: CustomerVersion,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
     _private: (),
 }
 
@@ -41,12 +43,15 @@ impl Customer {
         self.data
     }
 
-    pub fn new(id: impl IdProvider<CustomerData>) -> Result<Self, Error> {
+    pub fn new(id: impl IdProvider<CustomerData>, clock: impl Clock) -> Result<Self, Error> {
         let id = id.get()?;
+        let now = clock.now();
 
         Ok(Customer::from_data(CustomerData {
             id,
             version: CustomerVersion::default(),
+            created_at: now,
+            updated_at: now,
             _private: (),
         }))
     }
@@ -58,9 +63,3 @@ impl Entity for Customer {
     type Data = CustomerData;
     type Error = Error;
 }
-
-impl Resolver {
-    pub fn customer_id(&self) -> impl IdProvider<CustomerData> {
-        NextId::<CustomerData>::new()
-    }
-}
@@ -1,5 +1,7 @@
 /*! Persistent customer storage. */
 
+use std::path::PathBuf;
+
 use crate::{
     domain::{
         customers::*,
@@ -11,12 +13,13 @@ use tower_http::cors::CorsLayer;
 
 /** A place to persist and fetch customers. */
 #[auto_impl(&, Arc)]
-pub(in crate::domain) trait CustomerStore {
+pub trait CustomerStore {
     fn get_customer(&self, id: CustomerId) -> Result<Option<Customer>, Error>;
     fn set_customer(&self, transaction: &Transaction, customer: Customer) -> Result<(), Error>;
 }
 
-pub(in crate::domain) struct InMemoryStore(TransactionValueStore<CustomerData>);
+/** A test in-memory customer store. */
+pub struct InMemoryStore(TransactionValueStore<CustomerData>);
 
 impl CustomerStore for InMemoryStore {
     fn get_customer(&self, id: CustomerId) -> Result<Option<Customer>, Error> {
@@ -48,10 +51,21 @@ impl CustomerStore for InMemoryStore {
     }
 }
 
-pub(in crate::domain) fn in_memory_store(transaction_store: TransactionStore) -> InMemoryStore {
+/** Create an in-memory customer store that tracks transactions in the given transaction store. */
+pub fn in_memory_store(transaction_store: TransactionStore) -> InMemoryStore {
     InMemoryStore(TransactionValueStore::new(transaction_store))
 }
 
+pub fn file_store(
+    transaction_store: TransactionStore,
+    path: impl Into<PathBuf>,
+) -> Result<InMemoryStore, Error> {
+    Ok(InMemoryStore(TransactionValueStore::open(
+        transaction_store,
+        path,
+    )?))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
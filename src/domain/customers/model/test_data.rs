@@ -1,7 +1,10 @@
-use crate::domain::customers::*;
+use crate::domain::{
+    customers::*,
+    infra::*,
+};
 
 pub fn default_customer() -> Customer {
-    Customer::new(NextCustomerId::new()).unwrap()
+    Customer::new(NextCustomerId::new(), SystemClock).unwrap()
 }
 
 pub struct CustomerBuilder {
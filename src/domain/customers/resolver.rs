@@ -2,13 +2,18 @@
 
 use std::sync::Arc;
 
-use crate::domain::{
-    customers::model::store::{
-        self,
-        CustomerStore,
-        InMemoryStore,
+use crate::{
+    config::StoreBackend,
+    domain::{
+        customers::{
+            model::store::{
+                self,
+                CustomerStore,
+            },
+            CustomerData,
+        },
+        infra::*,
     },
-    infra::*,
 };
 
 /**
@@ -18,14 +23,32 @@ The `CustomersResolver` type wraps private implementation details and exposes th
 */
 #[derive(Clone)]
 pub(in crate::domain) struct CustomersResolver {
-    customer_store: Register<Arc<InMemoryStore>>,
+    customer_store: Register<Arc<dyn CustomerStore + Send + Sync>>,
+    customer_id: Register<Arc<dyn IdProvider<CustomerData> + Send + Sync>>,
 }
 
 impl Default for CustomersResolver {
     fn default() -> Self {
         CustomersResolver {
-            customer_store: Register::once(|resolver| {
-                Arc::new(store::in_memory_store(resolver.transaction_store()))
+            customer_store: Register::per_tenant(|resolver| {
+                match resolver.config().store.backend {
+                    StoreBackend::InMemory => {
+                        Arc::new(store::in_memory_store(resolver.transaction_store()))
+                            as Arc<dyn CustomerStore + Send + Sync>
+                    }
+                    StoreBackend::File => Arc::new(
+                        store::file_store(
+                            resolver.transaction_store(),
+                            resolver.tenant_store_file("customers"),
+                        )
+                        .expect("failed to open the customer store"),
+                    )
+                        as Arc<dyn CustomerStore + Send + Sync>,
+                }
+            }),
+            customer_id: Register::once(|_| {
+                Arc::new(NextId::<CustomerData>::new())
+                    as Arc<dyn IdProvider<CustomerData> + Send + Sync>
             }),
         }
     }
@@ -35,4 +58,28 @@ impl Resolver {
     pub(in crate::domain::customers) fn customer_store(&self) -> impl CustomerStore {
         self.resolve(&self.customers_resolver.customer_store)
     }
+
+    pub fn customer_id(&self) -> impl IdProvider<CustomerData> {
+        self.journaled_id(self.resolve(&self.customers_resolver.customer_id))
+    }
+}
+
+impl AppBuilder {
+    /** Use a different store for customers. */
+    pub fn customer_store(
+        mut self,
+        customer_store: Register<Arc<dyn CustomerStore + Send + Sync>>,
+    ) -> Self {
+        self.resolver.customers_resolver.customer_store = customer_store;
+        self
+    }
+
+    /** Use a different source of ids for new customers. */
+    pub fn customer_id(
+        mut self,
+        customer_id: Register<Arc<dyn IdProvider<CustomerData> + Send + Sync>>,
+    ) -> Self {
+        self.resolver.customers_resolver.customer_id = customer_id;
+        self
+    }
 }
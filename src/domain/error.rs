@@ -3,6 +3,8 @@ use std::{
     fmt,
 };
 
+use crate::store::VersionMismatch;
+
 /**
 The main error type.
 
@@ -16,6 +18,7 @@ otherwise it wouldn't be able to collect any other kind of error.
 #[derive(Debug)]
 pub struct Error {
     kind: ErrorKind,
+    code: &'static str,
     inner: Box<dyn error::Error + Send + Sync>,
 }
 
@@ -28,14 +31,30 @@ impl fmt::Display for Error {
 /**
 The kind of an error captured.
 */
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ErrorKind {
     /** A command or query was given bad input. */
     BadInput,
+    /** An entity a command or query depends on doesn't exist. */
+    NotFound,
+    /** A change conflicts with the current state of an entity. */
+    Conflict,
+    /** The caller isn't allowed to do what they asked. */
+    Forbidden,
+    /** The caller needs to identify themselves first. */
+    Unauthorized,
+    /** Something the app depends on isn't available right now. */
+    Unavailable,
     /** Some other kind of error. */
     Other,
 }
 
+/** The code given to errors that don't have a more specific one. */
+pub const INTERNAL: &str = "internal";
+
+/** The code given to optimistic concurrency failures from a store. */
+pub const VERSION_MISMATCH: &str = "store.version_mismatch";
+
 /**
 Create an error from a message.
 
@@ -44,21 +63,37 @@ This message may make its way to end-users so it should be friendly.
 pub fn msg(err: impl fmt::Display) -> Error {
     Error {
         kind: ErrorKind::Other,
+        code: INTERNAL,
         inner: err.to_string().into(),
     }
 }
 
 /**
-Create an error from a diagnostic event.
+Create an error of a given kind.
+
+The code is a stable, machine-readable identifier for the error like `order.customer_not_found`.
+Codes are namespaced by the domain module that produces them.
+Unlike the message, callers are free to match on the code.
+*/
+pub fn kind(kind: ErrorKind, code: &'static str, msg: impl fmt::Display) -> Error {
+    Error {
+        kind,
+        code,
+        inner: msg.to_string().into(),
+    }
+}
+
+/**
+Create an error of a given kind from a diagnostic event.
 
 The event will be emitted.
 */
-pub fn emit(event: impl emit::event::ToEvent) -> Error {
+pub fn emit(kind: ErrorKind, code: &'static str, event: impl emit::event::ToEvent) -> Error {
     let event = event.to_event();
 
     emit::error!(evt: &event);
 
-    msg(event.msg())
+    self::kind(kind, code, event.msg())
 }
 
 /**
@@ -66,19 +101,55 @@ Create an error for some bad input.
 
 This message may make its way to end-users so it should be friendly.
 */
-pub fn bad_input(msg: impl fmt::Display) -> Error {
-    Error {
-        kind: ErrorKind::BadInput,
-        inner: msg.to_string().into(),
-    }
+pub fn bad_input(code: &'static str, msg: impl fmt::Display) -> Error {
+    kind(ErrorKind::BadInput, code, msg)
+}
+
+/**
+Create an error for a missing entity.
+
+This message may make its way to end-users so it should be friendly.
+*/
+pub fn not_found(code: &'static str, msg: impl fmt::Display) -> Error {
+    kind(ErrorKind::NotFound, code, msg)
+}
+
+/**
+Create an error for a change that conflicts with existing state.
+
+This message may make its way to end-users so it should be friendly.
+*/
+pub fn conflict(code: &'static str, msg: impl fmt::Display) -> Error {
+    kind(ErrorKind::Conflict, code, msg)
 }
 
 impl Error {
     /**
-    Split an error into its kind and value.
+    Get the kind of this error.
+    */
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /**
+    Get the machine-readable code for this error.
     */
-    pub(crate) fn split(self) -> (ErrorKind, Box<dyn error::Error + Send + Sync>) {
-        (self.kind, self.inner)
+    pub fn code(&self) -> &'static str {
+        self.code
+    }
+
+    /**
+    Get the underlying error value.
+    */
+    pub fn as_error(&self) -> &(dyn error::Error + 'static) {
+        &*self.inner
+    }
+
+    /**
+    Split an error into its kind, code and value.
+    */
+    pub(crate) fn split(self) -> (ErrorKind, &'static str, Box<dyn error::Error + Send + Sync>) {
+        (self.kind, self.code, self.inner)
     }
 }
 
@@ -87,9 +158,61 @@ where
     E: Into<Box<dyn error::Error + Send + Sync>>,
 {
     fn from(err: E) -> Error {
+        let inner = err.into();
+
+        // Optimistic concurrency failures from a store are conflicts rather than internal errors
+        if inner.is::<VersionMismatch>() {
+            return Error {
+                kind: ErrorKind::Conflict,
+                code: VERSION_MISMATCH,
+                inner,
+            };
+        }
+
         Error {
             kind: ErrorKind::Other,
-            inner: err.into(),
+            code: INTERNAL,
+            inner,
         }
     }
 }
+
+/**
+An `Error` that implements Rust's `Error` trait.
+
+This is for passing domain errors through APIs like `App::transaction` that need a standard error.
+It can't collect other errors itself, so it should be unwrapped again once it's no longer needed.
+*/
+#[derive(Debug)]
+pub(in crate::domain) struct StdError(pub(in crate::domain) Error);
+
+impl fmt::Display for StdError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl error::Error for StdError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        self.0.inner.source()
+    }
+}
+
+impl From<Error> for StdError {
+    fn from(err: Error) -> Self {
+        StdError(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_mismatch_is_conflict() {
+        let err = Error::from(Box::new(VersionMismatch) as Box<dyn error::Error + Send + Sync>);
+
+        assert_eq!(ErrorKind::Conflict, err.kind());
+        assert_eq!(VERSION_MISMATCH, err.code());
+    }
+}
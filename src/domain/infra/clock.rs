@@ -0,0 +1,76 @@
+/*! Contains the shared `Clock` type. */
+
+use std::sync::Arc;
+
+use chrono::{
+    DateTime,
+    Utc,
+};
+
+use crate::domain::infra::*;
+
+/**
+A point in time.
+
+Timestamps are always in UTC.
+*/
+pub type Timestamp = DateTime<Utc>;
+
+/**
+A source of the current time.
+
+Items that need to timestamp data should depend on a `Clock` rather than calling `Utc::now` directly.
+That way tests can control what time it is.
+*/
+#[auto_impl(&, Arc)]
+pub trait Clock {
+    fn now(&self) -> Timestamp;
+}
+
+impl Clock for Timestamp {
+    fn now(&self) -> Timestamp {
+        *self
+    }
+}
+
+/** Get the current time from the system. */
+#[derive(Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Timestamp {
+        Utc::now()
+    }
+}
+
+/**
+Resolver for the clock.
+
+The clock is shared by all domain modules, so it lives alongside other shared infrastructure.
+*/
+#[derive(Clone)]
+pub(in crate::domain) struct ClockResolver {
+    clock: Register<Arc<dyn Clock + Send + Sync>>,
+}
+
+impl Default for ClockResolver {
+    fn default() -> Self {
+        ClockResolver {
+            clock: Register::once(|_| Arc::new(SystemClock) as Arc<dyn Clock + Send + Sync>),
+        }
+    }
+}
+
+impl Resolver {
+    pub fn clock(&self) -> impl Clock {
+        self.journaled_clock(self.resolve(&self.clock_resolver.clock))
+    }
+}
+
+impl AppBuilder {
+    /** Use a different clock. */
+    pub fn clock(mut self, clock: Register<Arc<dyn Clock + Send + Sync>>) -> Self {
+        self.resolver.clock_resolver.clock = clock;
+        self
+    }
+}
@@ -0,0 +1,70 @@
+/*! Contains the resolver for app configuration. */
+
+use std::{
+    path::PathBuf,
+    sync::Arc,
+};
+
+use crate::{
+    config::Config,
+    domain::infra::*,
+};
+
+/**
+Resolver for configuration.
+
+Configuration is shared by all domain modules, so it lives alongside other shared infrastructure.
+*/
+#[derive(Clone)]
+pub(in crate::domain) struct ConfigResolver {
+    config: Register<Arc<Config>>,
+}
+
+impl Default for ConfigResolver {
+    fn default() -> Self {
+        ConfigResolver {
+            config: Register::once(|_| Arc::new(Config::default())),
+        }
+    }
+}
+
+impl Resolver {
+    pub(in crate::domain) fn config(&self) -> Arc<Config> {
+        self.resolve(&self.config_resolver.config)
+    }
+
+    /** The file a store that's shared by all tenants keeps its entities in with the `file` backend. */
+    pub(in crate::domain) fn store_file(&self, name: &str) -> PathBuf {
+        self.config().store.path.join(format!("{}.json", name))
+    }
+
+    /**
+    The file a store keeps the current tenant's entities in with the `file` backend.
+
+    Each tenant's files are kept in their own directory.
+    */
+    pub(in crate::domain) fn tenant_store_file(&self, name: &str) -> PathBuf {
+        self.config()
+            .store
+            .path
+            .join("tenants")
+            .join(self.tenant().to_string())
+            .join(format!("{}.json", name))
+    }
+}
+
+impl App {
+    /** Get the configuration the app was built with. */
+    pub fn config(&self) -> Arc<Config> {
+        self.root_resolver.config()
+    }
+}
+
+impl AppBuilder {
+    /** Use the given configuration instead of the defaults. */
+    pub fn config(mut self, config: Config) -> Self {
+        let config = Arc::new(config);
+        self.resolver.config_resolver.config = Register::once(move |_| config.clone());
+        self
+    }
+}
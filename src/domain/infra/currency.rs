@@ -4,7 +4,7 @@ A lossless representation of currency.
 This type encodes the currency using its smallest possible unit. This is a better approach
 than floating point numbers where imprecision can change the results of calculations.
 */
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Currency {
     USD(USD),
@@ -14,6 +14,101 @@ impl Currency {
     pub fn usd(cents: u64) -> Self {
         Currency::USD(USD::new(cents))
     }
+
+    /** Create a currency value from its smallest unit, like cents. */
+    pub fn from_minor_units(code: CurrencyCode, amount: u64) -> Self {
+        match code {
+            CurrencyCode::USD => Currency::usd(amount),
+        }
+    }
+
+    /** Get the value of this currency in its smallest unit, like cents. */
+    pub fn minor_units(&self) -> u64 {
+        match self {
+            Currency::USD(usd) => usd.cents,
+        }
+    }
+
+    /** Get the code for this currency. */
+    pub fn code(&self) -> CurrencyCode {
+        match self {
+            Currency::USD(_) => CurrencyCode::USD,
+        }
+    }
+
+    /** A value of nothing in a currency. */
+    pub fn zero(code: CurrencyCode) -> Self {
+        Currency::from_minor_units(code, 0)
+    }
+
+    /**
+    Add two values.
+
+    Returns `None` if the values are in different currencies or the result would overflow.
+    */
+    pub fn checked_add(self, other: Currency) -> Option<Currency> {
+        self.same_code(other)?;
+
+        Some(Currency::from_minor_units(
+            self.code(),
+            self.minor_units().checked_add(other.minor_units())?,
+        ))
+    }
+
+    /**
+    Subtract a value from this one.
+
+    Returns `None` if the values are in different currencies or the result would be negative.
+    */
+    pub fn checked_sub(self, other: Currency) -> Option<Currency> {
+        self.same_code(other)?;
+
+        Some(Currency::from_minor_units(
+            self.code(),
+            self.minor_units().checked_sub(other.minor_units())?,
+        ))
+    }
+
+    /**
+    Multiply this value by a whole number, like a quantity.
+
+    Returns `None` if the result would overflow.
+    */
+    pub fn checked_mul(self, n: u64) -> Option<Currency> {
+        Some(Currency::from_minor_units(
+            self.code(),
+            self.minor_units().checked_mul(n)?,
+        ))
+    }
+
+    /**
+    Get a percentage of this value.
+
+    The result is rounded down to the currency's smallest unit, so a percentage of a value is never more than it should be.
+    */
+    pub fn percentage(self, percent: u32) -> Currency {
+        let amount = self.minor_units() as u128 * percent as u128 / 100;
+
+        Currency::from_minor_units(self.code(), amount.min(u64::MAX as u128) as u64)
+    }
+
+    fn same_code(self, other: Currency) -> Option<()> {
+        if self.code() == other.code() {
+            Some(())
+        } else {
+            None
+        }
+    }
+}
+
+/**
+A kind of currency without any value.
+*/
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CurrencyCode {
+    #[default]
+    USD,
 }
 
 /**
@@ -4,6 +4,9 @@ Defines the constraints that all entities must satisfy.
 This trait is really just a marker for ensuring all entities follow a basic structure.
 It's a checklist: the first thing to do when creating a new entity is to implement this trait and fill in the blanks.
 Any changes to entities that should be consistent can be added here.
+
+Simple entities that just wrap their data can use `#[derive(Entity)]` from `shop_derive` to implement this trait,
+along with their id and version aliases, store and resolver.
 */
 
 #[allow(dead_code)]
@@ -17,3 +20,137 @@ pub(in crate::domain) trait Entity {
     /** Should be the `Err` variant for any `Result` returning methods on `Self`. */
     type Error;
 }
+
+#[cfg(test)]
+pub(in crate::domain) mod tests {
+    use serde::{
+        Deserialize,
+        Serialize,
+    };
+    use shop_derive::Entity;
+
+    use uuid::Uuid;
+
+    use crate::{
+        config::{
+            Config,
+            StoreBackend,
+        },
+        domain::{
+            error::StdError,
+            infra::*,
+        },
+        store::Transaction,
+    };
+
+    use self::store::{
+        in_memory_store,
+        WidgetStore,
+    };
+
+    #[derive(Clone, Serialize, Deserialize, Entity)]
+    #[entity(store, resolver = WidgetsResolver)]
+    pub struct WidgetData {
+        pub id: WidgetId,
+        pub version: WidgetVersion,
+        pub name: String,
+    }
+
+    fn widget(id: WidgetId) -> Widget {
+        Widget::from_data(WidgetData {
+            id,
+            version: WidgetVersion::default(),
+            name: "widget".to_owned(),
+        })
+    }
+
+    #[test]
+    fn derived_store_get_set() {
+        let store = in_memory_store(Default::default());
+
+        let id = WidgetId::new();
+
+        store.set_widget(&Transaction::none(), widget(id)).unwrap();
+
+        let found = store.get_widget(id).unwrap().unwrap();
+        assert_eq!("widget", found.to_data().name);
+    }
+
+    #[test]
+    fn derived_store_checks_version() {
+        let store = in_memory_store(Default::default());
+
+        let id = WidgetId::new();
+
+        store.set_widget(&Transaction::none(), widget(id)).unwrap();
+
+        assert!(store.set_widget(&Transaction::none(), widget(id)).is_err());
+    }
+
+    #[tokio::test]
+    async fn derived_resolver_resolves_store() {
+        let app = App::new();
+
+        let id = app
+            .transaction(|resolver| async move {
+                let id = resolver.widget_id().get()?;
+
+                resolver
+                    .widget_store()
+                    .set_widget(resolver.active_transaction().get(), widget(id))?;
+
+                Ok::<_, StdError>(id)
+            })
+            .await
+            .unwrap();
+
+        let found = app
+            .root_resolver
+            .widget_store()
+            .get_widget(id)
+            .unwrap()
+            .unwrap();
+        assert_eq!("widget", found.to_data().name);
+
+        // Stores are registered per tenant
+        let tenant = app.for_tenant(TenantId::new("b").unwrap());
+        assert!(tenant
+            .root_resolver
+            .widget_store()
+            .get_widget(id)
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn derived_resolver_uses_file_backend() {
+        let mut config = Config::default();
+        config.store.backend = StoreBackend::File;
+        config.store.path = std::env::temp_dir().join(format!("shop-widgets-{}", Uuid::new_v4()));
+
+        let app = App::builder().config(config.clone()).build();
+
+        let id = app
+            .transaction(|resolver| async move {
+                let id = resolver.widget_id().get()?;
+
+                resolver
+                    .widget_store()
+                    .set_widget(resolver.active_transaction().get(), widget(id))?;
+
+                Ok::<_, StdError>(id)
+            })
+            .await
+            .unwrap();
+
+        // Rebuilding the app with the same store path finds the widget again
+        let app = App::builder().config(config).build();
+
+        assert!(app
+            .root_resolver
+            .widget_store()
+            .get_widget(id)
+            .unwrap()
+            .is_some());
+    }
+}
@@ -0,0 +1,30 @@
+/*! Contains the `GetFeatureFlagsQuery` type. */
+
+use crate::domain::{
+    infra::*,
+    Error,
+};
+
+/** Input for a `GetFeatureFlagsQuery`. */
+#[derive(Serialize, Deserialize)]
+pub struct GetFeatureFlags {}
+
+impl QueryArgs for GetFeatureFlags {
+    type Output = Result<Vec<FeatureFlag>, Error>;
+}
+
+/** Default implementation for a `GetFeatureFlagsQuery`. */
+async fn execute(_: GetFeatureFlags, flags: FeatureFlags) -> Result<Vec<FeatureFlag>, Error> {
+    Ok(flags.all())
+}
+
+impl Resolver {
+    /** Get all feature flags and how they're rolled out. */
+    pub fn get_feature_flags_query(&self) -> impl Query<GetFeatureFlags> {
+        self.query(|resolver, query: GetFeatureFlags| async move {
+            let flags = resolver.feature_flags();
+
+            execute(query, flags).await
+        })
+    }
+}
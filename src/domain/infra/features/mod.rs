@@ -0,0 +1,271 @@
+/*!
+Feature flags for rolling out changes gradually.
+
+A flag is either on or off for everyone, or rolled out to a percentage of customers.
+Each customer is assigned a stable bucket for each flag, so the same customer always gets
+the same answer for a flag until its rollout changes, and raising the percentage only ever
+adds customers to a rollout.
+
+Flags are loaded from configuration when the app starts. They can be changed while the app is running,
+or reloaded from configuration, which discards any changes. Flags are shared by all tenants, and changes
+to them take effect immediately rather than when the transaction they were made in commits.
+*/
+
+use std::{
+    collections::BTreeMap,
+    sync::{
+        Arc,
+        RwLock,
+    },
+};
+
+use crate::{
+    config::{
+        Config,
+        FeatureConfig,
+    },
+    domain::{
+        customers::CustomerId,
+        infra::*,
+    },
+};
+
+mod get_feature_flags;
+pub(in crate::domain) mod resolver;
+mod set_feature_flag;
+
+pub use self::{
+    get_feature_flags::*,
+    set_feature_flag::*,
+};
+
+/**
+How a feature flag is rolled out.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rollout {
+    /** The feature is off for everyone. */
+    Off,
+    /** The feature is on for everyone. */
+    On,
+    /** The feature is on for the given percentage of customers, from `0` to `100`. */
+    Percentage(u8),
+}
+
+impl Rollout {
+    fn is_enabled_for(&self, bucket: u8) -> bool {
+        match self {
+            Rollout::Off => false,
+            Rollout::On => true,
+            Rollout::Percentage(percentage) => bucket < *percentage,
+        }
+    }
+}
+
+impl From<FeatureConfig> for Rollout {
+    fn from(config: FeatureConfig) -> Rollout {
+        match config {
+            FeatureConfig::Toggle(true) => Rollout::On,
+            FeatureConfig::Toggle(false) => Rollout::Off,
+            FeatureConfig::Rollout { percentage } => Rollout::Percentage(percentage.min(100)),
+        }
+    }
+}
+
+/** A named feature flag and how it's rolled out. */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FeatureFlag {
+    pub name: String,
+    pub rollout: Rollout,
+}
+
+/**
+The current state of all feature flags.
+*/
+#[derive(Clone, Default)]
+pub struct FeatureFlags(Arc<RwLock<BTreeMap<String, Rollout>>>);
+
+impl FeatureFlags {
+    pub(in crate::domain) fn from_config(config: &Config) -> Self {
+        let flags = FeatureFlags::default();
+        flags.reload(config);
+
+        flags
+    }
+
+    /**
+    Replace all flags with the ones in the given configuration.
+
+    Any flags that were changed while the app was running are reset.
+    */
+    pub(in crate::domain) fn reload(&self, config: &Config) {
+        let features = config
+            .features
+            .iter()
+            .map(|(name, feature)| (name.clone(), Rollout::from(*feature)))
+            .collect();
+
+        *self.0.write().unwrap() = features;
+    }
+
+    /**
+    Whether a feature is on for everyone.
+
+    Features that are only rolled out to some customers are off.
+    */
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.get(name) == Some(Rollout::On)
+    }
+
+    /**
+    Whether a feature is on for a particular customer.
+    */
+    pub fn is_enabled_for(&self, name: &str, customer_id: CustomerId) -> bool {
+        match self.get(name) {
+            Some(rollout) => rollout.is_enabled_for(bucket(name, customer_id)),
+            None => false,
+        }
+    }
+
+    fn get(&self, name: &str) -> Option<Rollout> {
+        self.0.read().unwrap().get(name).copied()
+    }
+
+    fn all(&self) -> Vec<FeatureFlag> {
+        self.0
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(name, rollout)| FeatureFlag {
+                name: name.clone(),
+                rollout: *rollout,
+            })
+            .collect()
+    }
+
+    fn set(&self, name: String, rollout: Rollout) {
+        self.0.write().unwrap().insert(name, rollout);
+    }
+}
+
+/**
+Get the bucket from `0` to `99` a customer falls into for a flag.
+
+Buckets are computed with FNV-1a so they're stable across processes and versions of Rust.
+Hashing the flag name along with the customer means each flag rolls out to a different set of customers.
+*/
+fn bucket(name: &str, customer_id: CustomerId) -> u8 {
+    const OFFSET: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    let customer_id = customer_id.to_string();
+
+    let hash = name
+        .bytes()
+        .chain([0])
+        .chain(customer_id.bytes())
+        .fold(OFFSET, |hash, b| (hash ^ b as u64).wrapping_mul(PRIME));
+
+    (hash % 100) as u8
+}
+
+impl App {
+    /**
+    Reload feature flags from the given configuration.
+
+    Any flags that were changed while the app was running are reset.
+    */
+    pub fn reload_feature_flags(&self, config: &Config) {
+        self.root_resolver.feature_flags().reload(config);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(features: impl IntoIterator<Item = (&'static str, FeatureConfig)>) -> Config {
+        Config {
+            features: features
+                .into_iter()
+                .map(|(name, feature)| (name.to_owned(), feature))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn toggles_apply_to_everyone() {
+        let flags = FeatureFlags::from_config(&config([
+            ("on", FeatureConfig::Toggle(true)),
+            ("off", FeatureConfig::Toggle(false)),
+        ]));
+
+        let customer_id = CustomerId::new();
+
+        assert!(flags.is_enabled("on"));
+        assert!(flags.is_enabled_for("on", customer_id));
+
+        assert!(!flags.is_enabled("off"));
+        assert!(!flags.is_enabled_for("off", customer_id));
+
+        assert!(!flags.is_enabled("missing"));
+        assert!(!flags.is_enabled_for("missing", customer_id));
+    }
+
+    #[test]
+    fn percentage_rollout_is_stable_and_proportional() {
+        let flags = FeatureFlags::from_config(&config([(
+            "new_pricing",
+            FeatureConfig::Rollout { percentage: 30 },
+        )]));
+
+        let customers = (0..1000).map(|_| CustomerId::new()).collect::<Vec<_>>();
+
+        let enabled = customers
+            .iter()
+            .filter(|customer_id| flags.is_enabled_for("new_pricing", **customer_id))
+            .count();
+
+        assert!((200..400).contains(&enabled), "{}", enabled);
+        assert!(!flags.is_enabled("new_pricing"));
+
+        for customer_id in &customers {
+            assert_eq!(
+                flags.is_enabled_for("new_pricing", *customer_id),
+                flags.is_enabled_for("new_pricing", *customer_id)
+            );
+        }
+
+        // Raising the percentage keeps everyone who already had the feature
+        flags.set("new_pricing".to_owned(), Rollout::Percentage(60));
+
+        for customer_id in &customers {
+            if bucket("new_pricing", *customer_id) < 30 {
+                assert!(flags.is_enabled_for("new_pricing", *customer_id));
+            }
+        }
+    }
+
+    #[test]
+    fn reload_discards_changes() {
+        let config = config([("new_pricing", FeatureConfig::Toggle(false))]);
+        let flags = FeatureFlags::from_config(&config);
+
+        flags.set("new_pricing".to_owned(), Rollout::On);
+        flags.set("new_checkout".to_owned(), Rollout::On);
+
+        assert!(flags.is_enabled("new_pricing"));
+
+        flags.reload(&config);
+
+        assert_eq!(
+            vec![FeatureFlag {
+                name: "new_pricing".to_owned(),
+                rollout: Rollout::Off,
+            }],
+            flags.all()
+        );
+    }
+}
@@ -0,0 +1,30 @@
+/*! Contains the `FeaturesResolver` type. */
+
+use crate::domain::infra::*;
+
+/**
+Resolver for feature flags.
+
+Flags are shared by all tenants, and start out with the ones in the app's configuration.
+*/
+#[derive(Clone)]
+pub(in crate::domain) struct FeaturesResolver {
+    feature_flags: Register<FeatureFlags>,
+}
+
+impl Default for FeaturesResolver {
+    fn default() -> Self {
+        FeaturesResolver {
+            feature_flags: Register::once(|resolver| {
+                FeatureFlags::from_config(&resolver.config())
+            }),
+        }
+    }
+}
+
+impl Resolver {
+    /** Get the current state of feature flags. */
+    pub fn feature_flags(&self) -> FeatureFlags {
+        self.resolve(&self.features_resolver.feature_flags)
+    }
+}
@@ -0,0 +1,98 @@
+/*! Contains the `SetFeatureFlagCommand`. */
+
+use crate::domain::{
+    error,
+    infra::*,
+    Error,
+};
+
+/** Input for a `SetFeatureFlagCommand`. */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SetFeatureFlag {
+    pub name: String,
+    pub rollout: Rollout,
+}
+
+impl CommandArgs for SetFeatureFlag {
+    type Output = Result<(), Error>;
+}
+
+/** Default implementation for a `SetFeatureFlagCommand`. */
+async fn execute(command: SetFeatureFlag, flags: FeatureFlags) -> Result<(), Error> {
+    if command.name.trim().is_empty() {
+        return Err(error::bad_input(
+            "feature.invalid_name",
+            "feature names must not be empty",
+        ));
+    }
+
+    if let Rollout::Percentage(percentage) = command.rollout {
+        if percentage > 100 {
+            return Err(error::bad_input(
+                "feature.invalid_percentage",
+                format!("{} is not a percentage between 0 and 100", percentage),
+            ));
+        }
+    }
+
+    flags.set(command.name, command.rollout);
+
+    Ok(())
+}
+
+impl Resolver {
+    /**
+    Change how a feature flag is rolled out, adding it if it doesn't exist.
+
+    The change lasts until flags are reloaded from configuration.
+    */
+    pub fn set_feature_flag_command(&self) -> impl Command<SetFeatureFlag> {
+        self.command(|resolver, command: SetFeatureFlag| async move {
+            let flags = resolver.feature_flags();
+
+            execute(command, flags).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::customers::CustomerId;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn set_flag() {
+        let flags = FeatureFlags::default();
+
+        execute(
+            SetFeatureFlag {
+                name: "new_pricing".to_owned(),
+                rollout: Rollout::On,
+            },
+            flags.clone(),
+        )
+        .await
+        .unwrap();
+
+        assert!(flags.is_enabled("new_pricing"));
+    }
+
+    #[tokio::test]
+    async fn invalid_percentage_is_rejected() {
+        let flags = FeatureFlags::default();
+
+        let err = execute(
+            SetFeatureFlag {
+                name: "new_pricing".to_owned(),
+                rollout: Rollout::Percentage(101),
+            },
+            flags.clone(),
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!("feature.invalid_percentage", err.code());
+        assert!(!flags.is_enabled_for("new_pricing", CustomerId::new()));
+    }
+}
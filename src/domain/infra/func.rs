@@ -1,6 +1,12 @@
 use serde::Serialize;
 
-use crate::domain::infra::Resolver;
+use crate::domain::{
+    infra::{
+        JournalScope,
+        Resolver,
+    },
+    Error,
+};
 
 use std::future::Future;
 
@@ -45,19 +51,45 @@ where
 }
 
 impl Resolver {
-    pub(in crate::domain) fn command<TArgs, TCommand, TFuture>(
+    /**
+    Create a command that's journaled when it succeeds.
+
+    Commands run by other commands aren't journaled themselves.
+    They're part of the command that ran them, so they'll run again when it's replayed.
+    */
+    pub(in crate::domain) fn command<TArgs, TOutput, TCommand, TFuture>(
         &self,
         command: TCommand,
     ) -> impl Command<TArgs>
     where
-        TArgs: CommandArgs + Serialize + Send + 'static,
+        TArgs: CommandArgs<Output = Result<TOutput, Error>> + Serialize + Send + 'static,
+        TOutput: Serialize,
         TCommand: FnOnce(Resolver, TArgs) -> TFuture + Send,
         TFuture: Future<Output = TArgs::Output> + Send,
     {
         let resolver = self.by_ref();
         move |input: TArgs| {
-            let resolver = resolver.by_ref();
-            command(resolver, input)
+            let scope = if resolver.in_journal_scope() {
+                None
+            } else {
+                Some(JournalScope::new(resolver.tenant(), &input))
+            };
+
+            let resolver = match scope {
+                Some(ref scope) => resolver.with_journal_scope(scope.clone()),
+                None => resolver,
+            };
+            let transaction = resolver.active_transaction();
+
+            async move {
+                let output = command(resolver, input).await?;
+
+                if let Some(entry) = scope.and_then(|scope| scope.finish(&output)) {
+                    transaction.record_command(entry);
+                }
+
+                Ok(output)
+            }
         }
     }
 
@@ -36,6 +36,8 @@ An id.
 
 Ids have a phantom generic parameter so you can't compare an `Id<T>` to an `Id<U>`.
 It means you also can't use an `Id<T>` in place of an `Id<U>`.
+
+New ids are UUIDv7, so they sort in the order they were created.
 */
 pub struct Id<T>(Uuid, PhantomData<T>);
 
@@ -100,7 +102,7 @@ impl<T> Hash for Id<T> {
 impl<T> Id<T> {
     #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
-        Id(Uuid::new_v4(), PhantomData)
+        Id(Uuid::now_v7(), PhantomData)
     }
 }
 
@@ -147,7 +149,7 @@ impl<T> IdProvider<T> for Id<T> {
     }
 }
 
-/** Generate a new `Id` randomly. */
+/** Generate a new time-ordered `Id`. */
 pub struct NextId<T>(PhantomData<T>);
 
 impl<T> Default for NextId<T> {
@@ -171,3 +173,20 @@ impl<T> IdProvider<T> for NextId<T> {
         Ok(self.next())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn next_ids_are_ordered() {
+        let next = NextId::<()>::new();
+
+        let ids: Vec<_> = (0..100).map(|_| next.next()).collect();
+
+        let mut sorted = ids.clone();
+        sorted.sort();
+
+        assert_eq!(ids, sorted);
+    }
+}
@@ -0,0 +1,519 @@
+/*!
+Background jobs.
+
+Jobs are registered on the `AppBuilder` and run outside of any request, either on an interval or a cron schedule.
+Each run executes in its own transaction, the same as a request would.
+Failed runs are retried with an exponential backoff before the job falls back to its regular schedule.
+
+The state of each job is persisted between runs. With the `file` store backend that includes restarts,
+so restarting the app doesn't reset pending retries.
+*/
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    time::Duration,
+};
+
+use crate::domain::{
+    error::StdError,
+    infra::*,
+    Error,
+};
+
+pub(in crate::domain) mod resolver;
+mod schedule;
+pub(in crate::domain) mod store;
+
+pub use self::{
+    schedule::*,
+    store::{
+        JobRunData,
+        JobRunId,
+        JobRunVersion,
+    },
+};
+
+use self::{
+    schedule::to_delta,
+    store::JobRunStore,
+};
+
+/**
+The longest the runner will wait before checking for due jobs again.
+
+Jobs are checked at least this often so changes to the clock or persisted state are picked up.
+*/
+const MAX_IDLE: Duration = Duration::from_secs(60);
+
+type JobFn =
+    Arc<dyn Fn(Resolver) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send>> + Send + Sync>;
+
+/**
+A job that runs in the background.
+*/
+#[derive(Clone)]
+pub struct Job {
+    name: String,
+    schedule: Schedule,
+    retry: Retry,
+    run: JobFn,
+}
+
+impl Job {
+    /**
+    Create a job that runs on the given schedule.
+
+    The name is used to identify the job's persisted state, so it should be unique and stable.
+    */
+    pub fn new<F, O>(name: impl Into<String>, schedule: Schedule, run: F) -> Self
+    where
+        F: Fn(Resolver) -> O + Send + Sync + 'static,
+        O: Future<Output = Result<(), Error>> + Send + 'static,
+    {
+        Job {
+            name: name.into(),
+            schedule,
+            retry: Retry::default(),
+            run: Arc::new(move |resolver| Box::pin(run(resolver))),
+        }
+    }
+
+    /** Use a different policy for retrying failed runs. */
+    pub fn retry(mut self, retry: Retry) -> Self {
+        self.retry = retry;
+        self
+    }
+}
+
+/**
+How a failed job is retried.
+*/
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Retry {
+    /** The number of attempts to make, including the first, before waiting for the next scheduled run. */
+    pub max_attempts: u32,
+    /** The delay before the first retry. Each following retry waits twice as long. */
+    pub backoff: Duration,
+    /** The longest delay between retries. */
+    pub max_backoff: Duration,
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Retry {
+            max_attempts: 3,
+            backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(5 * 60),
+        }
+    }
+}
+
+impl Retry {
+    /** Don't retry failed runs. */
+    pub fn none() -> Self {
+        Retry {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    fn delay(&self, failed_attempts: u32) -> Duration {
+        let factor = 2u32.saturating_pow(failed_attempts.saturating_sub(1));
+
+        self.backoff
+            .checked_mul(factor)
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff)
+    }
+}
+
+impl App {
+    /**
+    Get a runner for the app's jobs.
+
+    The runner can be spawned on a background task, independently of the app.
+    */
+    pub fn jobs(&self) -> JobRunner {
+        JobRunner {
+            app: App {
+                root_resolver: self.root_resolver.by_ref(),
+            },
+        }
+    }
+}
+
+/**
+Runs jobs when they're due.
+*/
+pub struct JobRunner {
+    app: App,
+}
+
+impl JobRunner {
+    /**
+    Run jobs until the given shutdown future completes.
+
+    Shutdown is only checked between runs, so any jobs that are running will finish first.
+    */
+    pub async fn run(self, shutdown: impl Future<Output = ()>) {
+        if self.app.root_resolver.jobs().is_empty() {
+            return;
+        }
+
+        emit::debug!("starting job runner");
+
+        tokio::pin!(shutdown);
+
+        loop {
+            let wait = match self.run_due().await {
+                Ok(Some(next)) => (next - self.app.root_resolver.clock().now())
+                    .to_std()
+                    .unwrap_or_default(),
+                Ok(None) => MAX_IDLE,
+                Err(err) => {
+                    emit::error!("failed to run jobs: {err: err.as_error()}");
+
+                    MAX_IDLE
+                }
+            };
+
+            tokio::select! {
+                _ = &mut shutdown => break,
+                _ = tokio::time::sleep(wait.min(MAX_IDLE)) => (),
+            }
+        }
+
+        emit::debug!("stopped job runner");
+    }
+
+    /**
+    Run any jobs that are currently due.
+
+    This returns the next time a job will be due, if there is one.
+    */
+    pub async fn run_due(&self) -> Result<Option<Timestamp>, Error> {
+        let mut next = None;
+
+        for job in self.app.root_resolver.jobs().iter() {
+            if let Some(job_next) = self.run_if_due(job).await? {
+                next = Some(next.map_or(job_next, |next: Timestamp| next.min(job_next)));
+            }
+        }
+
+        Ok(next)
+    }
+
+    async fn run_if_due(&self, job: &Job) -> Result<Option<Timestamp>, Error> {
+        let resolver = &self.app.root_resolver;
+        let clock = resolver.clock();
+
+        let now = clock.now();
+
+        let mut run = match resolver.job_run_store().get_job_run(&job.name)? {
+            Some(run) => run,
+            // The first run of a job is scheduled but not run right away
+            None => {
+                let run = JobRunData::new(&job.name, job.schedule.next_after(now));
+                let next = run.next_run_at;
+
+                self.set_job_run(run).await?;
+
+                return Ok(next);
+            }
+        };
+
+        match run.next_run_at {
+            Some(next) if next <= now => (),
+            next => return Ok(next),
+        }
+
+        let result = execute(&self.app, &job.name, job.run.clone())
+            .await
+            .map_err(|StdError(err)| err);
+
+        let now = clock.now();
+        run.last_run_at = Some(now);
+
+        match result {
+            Ok(()) => {
+                run.failed_attempts = 0;
+                run.last_error = None;
+                run.last_success_at = Some(now);
+                run.next_run_at = job.schedule.next_after(now);
+            }
+            Err(err) => {
+                run.failed_attempts += 1;
+                run.last_error = Some(err.to_string());
+
+                if run.failed_attempts < job.retry.max_attempts {
+                    run.next_run_at =
+                        now.checked_add_signed(to_delta(job.retry.delay(run.failed_attempts)));
+                } else {
+                    emit::error!(
+                        "job {job: job.name} failed after {attempts: run.failed_attempts} attempts"
+                    );
+
+                    run.failed_attempts = 0;
+                    run.next_run_at = job.schedule.next_after(now);
+                }
+            }
+        }
+
+        let next = run.next_run_at;
+
+        self.set_job_run(run).await?;
+
+        Ok(next)
+    }
+
+    async fn set_job_run(&self, run: JobRunData) -> Result<(), Error> {
+        self.app
+            .transaction(|resolver| async move {
+                Ok(resolver
+                    .job_run_store()
+                    .set_job_run(resolver.active_transaction().get(), run)?)
+            })
+            .await
+            .map_err(|StdError(err)| err)
+    }
+}
+
+#[emit::span(ok_lvl: "debug", err_lvl: "warn", "run job {job}")]
+async fn execute(app: &App, job: &str, run: JobFn) -> Result<(), StdError> {
+    app.transaction(|resolver| async move { Ok(run(resolver).await?) })
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{
+            AtomicUsize,
+            Ordering,
+        },
+        Mutex,
+    };
+
+    use chrono::{
+        TimeZone,
+        Utc,
+    };
+    use uuid::Uuid;
+
+    use crate::config::{
+        Config,
+        StoreBackend,
+    };
+
+    use super::*;
+
+    #[derive(Clone)]
+    struct TestClock(Arc<Mutex<Timestamp>>);
+
+    impl Clock for TestClock {
+        fn now(&self) -> Timestamp {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    impl TestClock {
+        fn advance(&self, by: Duration) {
+            *self.0.lock().unwrap() += to_delta(by);
+        }
+    }
+
+    fn app(clock: TestClock, job: Job) -> App {
+        app_with_config(clock, job, Config::default())
+    }
+
+    fn app_with_config(clock: TestClock, job: Job, config: Config) -> App {
+        App::builder()
+            .config(config)
+            .clock(Register::once(move |_| {
+                Arc::new(clock.clone()) as Arc<dyn Clock + Send + Sync>
+            }))
+            .job(job)
+            .build()
+    }
+
+    fn clock() -> TestClock {
+        TestClock(Arc::new(Mutex::new(
+            Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+        )))
+    }
+
+    #[tokio::test]
+    async fn run_on_interval() {
+        let clock = clock();
+        let runs = Arc::new(AtomicUsize::new(0));
+
+        let app = app(clock.clone(), {
+            let runs = runs.clone();
+
+            Job::new(
+                "count",
+                Schedule::every(Duration::from_secs(60)),
+                move |_| {
+                    let runs = runs.clone();
+
+                    async move {
+                        runs.fetch_add(1, Ordering::SeqCst);
+                        Ok(())
+                    }
+                },
+            )
+        });
+
+        let jobs = app.jobs();
+
+        // The first check only schedules the job
+        let next = jobs.run_due().await.unwrap();
+        assert_eq!(0, runs.load(Ordering::SeqCst));
+        assert_eq!(Some(clock.now() + to_delta(Duration::from_secs(60))), next);
+
+        clock.advance(Duration::from_secs(30));
+        jobs.run_due().await.unwrap();
+        assert_eq!(0, runs.load(Ordering::SeqCst));
+
+        clock.advance(Duration::from_secs(30));
+        let next = jobs.run_due().await.unwrap();
+        assert_eq!(1, runs.load(Ordering::SeqCst));
+        assert_eq!(Some(clock.now() + to_delta(Duration::from_secs(60))), next);
+
+        let run = app
+            .root_resolver
+            .job_run_store()
+            .get_job_run("count")
+            .unwrap()
+            .unwrap();
+        assert_eq!(Some(clock.now()), run.last_success_at);
+    }
+
+    #[tokio::test]
+    async fn retry_with_backoff() {
+        let clock = clock();
+
+        let app = app(
+            clock.clone(),
+            Job::new(
+                "fail",
+                Schedule::every(Duration::from_secs(60 * 60)),
+                |_| async { Err(Error::from("failed")) },
+            )
+            .retry(Retry {
+                max_attempts: 3,
+                backoff: Duration::from_secs(10),
+                max_backoff: Duration::from_secs(60),
+            }),
+        );
+
+        let jobs = app.jobs();
+
+        jobs.run_due().await.unwrap();
+        clock.advance(Duration::from_secs(60 * 60));
+
+        // First failure waits for the initial backoff
+        let next = jobs.run_due().await.unwrap();
+        assert_eq!(Some(clock.now() + to_delta(Duration::from_secs(10))), next);
+
+        // Second failure waits twice as long
+        clock.advance(Duration::from_secs(10));
+        let next = jobs.run_due().await.unwrap();
+        assert_eq!(Some(clock.now() + to_delta(Duration::from_secs(20))), next);
+
+        // Third failure gives up and waits for the next scheduled run
+        clock.advance(Duration::from_secs(20));
+        let next = jobs.run_due().await.unwrap();
+        assert_eq!(
+            Some(clock.now() + to_delta(Duration::from_secs(60 * 60))),
+            next
+        );
+
+        let run = app
+            .root_resolver
+            .job_run_store()
+            .get_job_run("fail")
+            .unwrap()
+            .unwrap();
+        assert_eq!(0, run.failed_attempts);
+        assert_eq!(Some("failed".to_owned()), run.last_error);
+        assert_eq!(None, run.last_success_at);
+    }
+
+    #[tokio::test]
+    async fn pending_retry_survives_restart() {
+        let clock = clock();
+
+        let mut config = Config::default();
+        config.store.backend = StoreBackend::File;
+        config.store.path = std::env::temp_dir().join(format!("shop-jobs-{}", Uuid::new_v4()));
+
+        let job = || {
+            Job::new(
+                "fail",
+                Schedule::every(Duration::from_secs(60 * 60)),
+                |_| async { Err(Error::from("failed")) },
+            )
+            .retry(Retry {
+                max_attempts: 3,
+                backoff: Duration::from_secs(10),
+                max_backoff: Duration::from_secs(60),
+            })
+        };
+
+        let app = app_with_config(clock.clone(), job(), config.clone());
+
+        app.jobs().run_due().await.unwrap();
+        clock.advance(Duration::from_secs(60 * 60));
+
+        let next = app.jobs().run_due().await.unwrap();
+        assert_eq!(Some(clock.now() + to_delta(Duration::from_secs(10))), next);
+
+        drop(app);
+
+        // Rebuilding the app with the same store path picks up the pending retry
+        let app = app_with_config(clock.clone(), job(), config);
+
+        let run = app
+            .root_resolver
+            .job_run_store()
+            .get_job_run("fail")
+            .unwrap()
+            .unwrap();
+        assert_eq!(1, run.failed_attempts);
+        assert_eq!(next, run.next_run_at);
+
+        // The retry carries on with its backoff instead of starting over
+        clock.advance(Duration::from_secs(10));
+        let next = app.jobs().run_due().await.unwrap();
+        assert_eq!(Some(clock.now() + to_delta(Duration::from_secs(20))), next);
+    }
+
+    #[test]
+    fn retry_delay_is_capped() {
+        let retry = Retry {
+            max_attempts: 10,
+            backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(5),
+        };
+
+        assert_eq!(Duration::from_secs(1), retry.delay(1));
+        assert_eq!(Duration::from_secs(4), retry.delay(3));
+        assert_eq!(Duration::from_secs(5), retry.delay(4));
+        assert_eq!(Duration::from_secs(5), retry.delay(100));
+    }
+
+    #[tokio::test]
+    async fn run_stops_on_shutdown() {
+        let app = app(
+            clock(),
+            Job::new("noop", Schedule::every(Duration::from_secs(1)), |_| async {
+                Ok(())
+            }),
+        );
+
+        app.jobs().run(async {}).await;
+    }
+}
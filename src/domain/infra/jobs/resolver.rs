@@ -0,0 +1,61 @@
+/*! Contains the `JobsResolver` type. */
+
+use std::sync::Arc;
+
+use crate::{
+    config::StoreBackend,
+    domain::infra::{
+        jobs::store::{
+            self,
+            JobRunStore,
+        },
+        *,
+    },
+};
+
+/**
+Resolver for jobs.
+
+Jobs are registered up-front when the app is built, so they're kept directly on the resolver.
+*/
+#[derive(Clone)]
+pub(in crate::domain) struct JobsResolver {
+    job_run_store: Register<Arc<dyn JobRunStore + Send + Sync>>,
+    jobs: Arc<Vec<Job>>,
+}
+
+impl Default for JobsResolver {
+    fn default() -> Self {
+        JobsResolver {
+            job_run_store: Register::once(|resolver| match resolver.config().store.backend {
+                StoreBackend::InMemory => {
+                    Arc::new(store::in_memory_store(resolver.transaction_store()))
+                        as Arc<dyn JobRunStore + Send + Sync>
+                }
+                StoreBackend::File => Arc::new(
+                    store::file_store(resolver.transaction_store(), resolver.store_file("jobs"))
+                        .expect("failed to open the job run store"),
+                ) as Arc<dyn JobRunStore + Send + Sync>,
+            }),
+            jobs: Default::default(),
+        }
+    }
+}
+
+impl Resolver {
+    pub(in crate::domain::infra::jobs) fn job_run_store(&self) -> impl JobRunStore {
+        self.resolve(&self.jobs_resolver.job_run_store)
+    }
+
+    pub(in crate::domain::infra::jobs) fn jobs(&self) -> Arc<Vec<Job>> {
+        self.jobs_resolver.jobs.clone()
+    }
+}
+
+impl AppBuilder {
+    /** Register a job to run in the background. */
+    pub fn job(mut self, job: Job) -> Self {
+        Arc::make_mut(&mut self.resolver.jobs_resolver.jobs).push(job);
+        self
+    }
+}
@@ -0,0 +1,315 @@
+/*! Contains the `Schedule` type for jobs. */
+
+use std::{
+    fmt,
+    str::FromStr,
+    time::Duration,
+};
+
+use chrono::{
+    Datelike,
+    TimeZone,
+    Timelike,
+    Utc,
+};
+
+use crate::domain::{
+    error,
+    infra::*,
+    Error,
+};
+
+/**
+When a job should run.
+*/
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Schedule {
+    /** Run repeatedly, waiting the given interval after each run. */
+    Every(Duration),
+    /** Run whenever the time matches a cron expression. */
+    Cron(Cron),
+}
+
+impl Schedule {
+    pub fn every(interval: Duration) -> Self {
+        Schedule::Every(interval)
+    }
+
+    /**
+    Parse a schedule from a cron expression.
+
+    See [`Cron`] for the supported syntax.
+    */
+    pub fn cron(expr: &str) -> Result<Self, Error> {
+        Ok(Schedule::Cron(expr.parse()?))
+    }
+
+    /**
+    Get the next time the job should run after the given time.
+
+    This will return `None` if the schedule can never run again.
+    */
+    pub fn next_after(&self, after: Timestamp) -> Option<Timestamp> {
+        match self {
+            Schedule::Every(interval) => after.checked_add_signed(to_delta(*interval)),
+            Schedule::Cron(cron) => cron.next_after(after),
+        }
+    }
+}
+
+/**
+A cron expression.
+
+Expressions have five fields separated by whitespace: minute, hour, day of month, month and day of week.
+Each field may be `*`, a number, a range like `1-5`, or a comma separated list of these.
+Any of them may be followed by a step, so `0-30/10` matches `0,10,20,30`.
+Days of the week start from `0` for Sunday. Times are always in UTC.
+
+If both the day of month and day of week are restricted then a time that matches either will run,
+the same as in `cron` itself.
+*/
+#[derive(Clone, PartialEq, Eq)]
+pub struct Cron {
+    expr: String,
+    minutes: Field,
+    hours: Field,
+    days_of_month: Field,
+    months: Field,
+    days_of_week: Field,
+}
+
+impl fmt::Debug for Cron {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.expr, f)
+    }
+}
+
+impl fmt::Display for Cron {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.expr, f)
+    }
+}
+
+impl FromStr for Cron {
+    type Err = Error;
+
+    fn from_str(expr: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<_> = expr.split_whitespace().collect();
+
+        let [minutes, hours, days_of_month, months, days_of_week] = fields[..] else {
+            return Err(invalid(expr));
+        };
+
+        Ok(Cron {
+            expr: expr.to_owned(),
+            minutes: Field::parse(minutes, 0, 59).ok_or_else(|| invalid(expr))?,
+            hours: Field::parse(hours, 0, 23).ok_or_else(|| invalid(expr))?,
+            days_of_month: Field::parse(days_of_month, 1, 31).ok_or_else(|| invalid(expr))?,
+            months: Field::parse(months, 1, 12).ok_or_else(|| invalid(expr))?,
+            days_of_week: Field::parse(days_of_week, 0, 6).ok_or_else(|| invalid(expr))?,
+        })
+    }
+}
+
+impl Cron {
+    /** Get the first matching minute after the given time. */
+    pub fn next_after(&self, after: Timestamp) -> Option<Timestamp> {
+        // Start from the next whole minute
+        let mut next = after
+            .with_second(0)?
+            .with_nanosecond(0)?
+            .checked_add_signed(chrono::Duration::minutes(1))?;
+
+        // Anything that hasn't matched within a few years never will
+        let limit = after.year() + 5;
+
+        while next.year() <= limit {
+            if !self.months.contains(next.month()) {
+                next = start_of_next_month(next)?;
+                continue;
+            }
+
+            if !self.matches_day(next) {
+                next = start_of_day(next)?.checked_add_signed(chrono::Duration::days(1))?;
+                continue;
+            }
+
+            if !self.hours.contains(next.hour()) {
+                next = next
+                    .with_minute(0)?
+                    .checked_add_signed(chrono::Duration::hours(1))?;
+                continue;
+            }
+
+            if !self.minutes.contains(next.minute()) {
+                next = next.checked_add_signed(chrono::Duration::minutes(1))?;
+                continue;
+            }
+
+            return Some(next);
+        }
+
+        None
+    }
+
+    fn matches_day(&self, at: Timestamp) -> bool {
+        let day_of_month = self.days_of_month.contains(at.day());
+        let day_of_week = self
+            .days_of_week
+            .contains(at.weekday().num_days_from_sunday());
+
+        match (self.days_of_month.any, self.days_of_week.any) {
+            (false, false) => day_of_month || day_of_week,
+            _ => day_of_month && day_of_week,
+        }
+    }
+}
+
+/** The set of values matched by a single cron field. */
+#[derive(Clone, PartialEq, Eq)]
+struct Field {
+    values: u64,
+    any: bool,
+}
+
+impl Field {
+    fn parse(field: &str, min: u32, max: u32) -> Option<Self> {
+        let mut values = 0u64;
+
+        for part in field.split(',') {
+            let (range, step) = match part.split_once('/') {
+                Some((range, step)) => (range, step.parse::<u32>().ok().filter(|step| *step > 0)?),
+                None => (part, 1),
+            };
+
+            let (start, end) = match range {
+                "*" => (min, max),
+                range => match range.split_once('-') {
+                    Some((start, end)) => (start.parse().ok()?, end.parse().ok()?),
+                    None => {
+                        let value = range.parse().ok()?;
+
+                        // A single value with a step runs from that value to the end
+                        if part.contains('/') {
+                            (value, max)
+                        } else {
+                            (value, value)
+                        }
+                    }
+                },
+            };
+
+            if start < min || end > max || start > end {
+                return None;
+            }
+
+            for value in (start..=end).step_by(step as usize) {
+                values |= 1 << value;
+            }
+        }
+
+        Some(Field {
+            values,
+            any: field == "*",
+        })
+    }
+
+    fn contains(&self, value: u32) -> bool {
+        self.values & (1 << value) != 0
+    }
+}
+
+fn invalid(expr: &str) -> Error {
+    error::bad_input(
+        "job.schedule_invalid",
+        format!("`{}` is not a valid cron expression", expr),
+    )
+}
+
+fn start_of_day(at: Timestamp) -> Option<Timestamp> {
+    at.with_hour(0)?.with_minute(0)
+}
+
+fn start_of_next_month(at: Timestamp) -> Option<Timestamp> {
+    let (year, month) = if at.month() == 12 {
+        (at.year() + 1, 1)
+    } else {
+        (at.year(), at.month() + 1)
+    };
+
+    Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0).single()
+}
+
+pub(in crate::domain::infra::jobs) fn to_delta(duration: Duration) -> chrono::Duration {
+    chrono::Duration::from_std(duration).unwrap_or(chrono::Duration::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> Timestamp {
+        Utc.with_ymd_and_hms(year, month, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn every_adds_interval() {
+        let schedule = Schedule::every(Duration::from_secs(90));
+
+        assert_eq!(
+            Some(at(2024, 1, 1, 0, 1) + chrono::Duration::seconds(30)),
+            schedule.next_after(at(2024, 1, 1, 0, 0))
+        );
+    }
+
+    #[test]
+    fn cron_next_after() {
+        for (expr, after, expected) in [
+            ("* * * * *", at(2024, 1, 1, 0, 0), at(2024, 1, 1, 0, 1)),
+            ("*/15 * * * *", at(2024, 1, 1, 0, 7), at(2024, 1, 1, 0, 15)),
+            ("30 2 * * *", at(2024, 1, 1, 3, 0), at(2024, 1, 2, 2, 30)),
+            ("0 9 * * 1-5", at(2024, 1, 6, 12, 0), at(2024, 1, 8, 9, 0)),
+            ("0 0 1 */3 *", at(2024, 2, 10, 0, 0), at(2024, 4, 1, 0, 0)),
+            (
+                "0 0 31 12 *",
+                at(2024, 12, 31, 0, 0),
+                at(2025, 12, 31, 0, 0),
+            ),
+            ("0 0 13 * 5", at(2024, 1, 1, 0, 0), at(2024, 1, 5, 0, 0)),
+            ("0 12 29 2 *", at(2025, 1, 1, 0, 0), at(2028, 2, 29, 12, 0)),
+        ] {
+            let cron: Cron = expr.parse().unwrap();
+
+            assert_eq!(Some(expected), cron.next_after(after), "{}", expr);
+        }
+    }
+
+    #[test]
+    fn cron_never_matches() {
+        let cron: Cron = "0 0 30 2 *".parse().unwrap();
+
+        assert_eq!(None, cron.next_after(at(2024, 1, 1, 0, 0)));
+    }
+
+    #[test]
+    fn cron_invalid() {
+        for expr in [
+            "",
+            "* * * *",
+            "* * * * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "* * 0 * *",
+            "* * * 13 *",
+            "* * * * 7",
+            "5-1 * * * *",
+            "*/0 * * * *",
+            "a * * * *",
+        ] {
+            let err = expr.parse::<Cron>().unwrap_err();
+
+            assert_eq!("job.schedule_invalid", err.code(), "{}", expr);
+        }
+    }
+}
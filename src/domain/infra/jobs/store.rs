@@ -0,0 +1,137 @@
+/*! Persistent storage for job run state. */
+
+use std::path::PathBuf;
+
+use crate::{
+    domain::{
+        infra::*,
+        Error,
+    },
+    store::{
+        Transaction,
+        TransactionStore,
+        TransactionValueStore,
+    },
+};
+
+pub type JobRunId = Id<JobRunData>;
+pub type JobRunVersion = Version<JobRunData>;
+
+/**
+The persisted state of a job.
+
+Run state is kept between runs so a job picks up where it left off, including any retries it has pending.
+With the `file` store backend it also survives the app restarting.
+*/
+#[derive(Clone, Serialize, Deserialize)]
+pub struct JobRunData {
+    pub id: JobRunId,
+    pub version: JobRunVersion,
+    pub name: String,
+    /** When the job should next run, or `None` if its schedule will never run again. */
+    pub next_run_at: Option<Timestamp>,
+    pub last_run_at: Option<Timestamp>,
+    pub last_success_at: Option<Timestamp>,
+    /** The number of times the job has failed in a row. */
+    pub failed_attempts: u32,
+    pub last_error: Option<String>,
+}
+
+impl JobRunData {
+    pub(in crate::domain::infra::jobs) fn new(
+        name: impl Into<String>,
+        next_run_at: Option<Timestamp>,
+    ) -> Self {
+        JobRunData {
+            id: JobRunId::new(),
+            version: JobRunVersion::default(),
+            name: name.into(),
+            next_run_at,
+            last_run_at: None,
+            last_success_at: None,
+            failed_attempts: 0,
+            last_error: None,
+        }
+    }
+}
+
+/* A place to persist and fetch job run state. */
+#[auto_impl(&, Arc)]
+pub(in crate::domain) trait JobRunStore {
+    fn get_job_run(&self, name: &str) -> Result<Option<JobRunData>, Error>;
+    fn set_job_run(&self, transaction: &Transaction, run: JobRunData) -> Result<(), Error>;
+}
+
+/** A test in-memory job run store. */
+pub(in crate::domain) struct InMemoryStore(TransactionValueStore<JobRunData>);
+
+impl JobRunStore for InMemoryStore {
+    fn get_job_run(&self, name: &str) -> Result<Option<JobRunData>, Error> {
+        Ok(self
+            .0
+            .get_all(|run| run.name == name)
+            .next()
+            .map(|(_, data)| data))
+    }
+
+    fn set_job_run(&self, transaction: &Transaction, run: JobRunData) -> Result<(), Error> {
+        let mut data = run;
+        let id = data.id;
+
+        self.0.set(
+            transaction,
+            id,
+            Some(data.version),
+            data.version.next(),
+            data,
+        )?;
+
+        Ok(())
+    }
+}
+
+pub(in crate::domain) fn in_memory_store(transaction_store: TransactionStore) -> InMemoryStore {
+    InMemoryStore(TransactionValueStore::new(transaction_store))
+}
+
+pub(in crate::domain) fn file_store(
+    transaction_store: TransactionStore,
+    path: impl Into<PathBuf>,
+) -> Result<InMemoryStore, Error> {
+    Ok(InMemoryStore(TransactionValueStore::open(
+        transaction_store,
+        path,
+    )?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_memory_store() {
+        let store = in_memory_store(Default::default());
+
+        store
+            .set_job_run(&Transaction::none(), JobRunData::new("a", None))
+            .unwrap();
+
+        let found = store.get_job_run("a").unwrap().unwrap();
+        assert_eq!("a", found.name);
+
+        assert!(store.get_job_run("b").unwrap().is_none());
+    }
+
+    #[test]
+    fn set_stale_run_fails_concurrency_check() {
+        let store = in_memory_store(Default::default());
+
+        let run = JobRunData::new("a", None);
+
+        store
+            .set_job_run(&Transaction::none(), run.clone())
+            .unwrap();
+
+        assert!(store.set_job_run(&Transaction::none(), run).is_err());
+    }
+}
@@ -0,0 +1,622 @@
+/*!
+A journal of the commands that changed the app.
+
+Every command that succeeds is recorded on its transaction along with its serialized input and output.
+When the transaction commits, its commands are appended to the journal in the order they ran.
+Transactions are journaled in the same order they commit, so replaying a command sees everything it originally read.
+Transactions that are cancelled never reach the journal.
+
+Commands aren't deterministic on their own, because they can generate new ids and read the current time.
+Any ids and timestamps a command gets from the `Resolver` while it's running are recorded alongside it.
+When the journal is replayed those same values are injected back in the same order, so a replayed command
+should produce exactly the same output as the original. The first command that doesn't is reported as a divergence.
+*/
+
+use std::{
+    any,
+    collections::VecDeque,
+    sync::{
+        Arc,
+        Mutex,
+    },
+};
+
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::domain::{
+    error::{
+        self,
+        StdError,
+    },
+    infra::*,
+    Error,
+};
+
+mod replay;
+pub(in crate::domain) mod resolver;
+pub(in crate::domain) mod store;
+
+pub use self::store::read_journal;
+
+use self::store::CommandJournal;
+
+/**
+A command that was committed to the journal.
+*/
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /** The tenant the command ran for. */
+    #[serde(default)]
+    pub tenant: TenantId,
+    /** The type name of the command's input. */
+    pub command: String,
+    pub args: serde_json::Value,
+    pub output: serde_json::Value,
+    /** The ids generated while the command ran, in the order they were generated. */
+    pub ids: Vec<Uuid>,
+    /** The times read while the command ran, in the order they were read. */
+    pub times: Vec<Timestamp>,
+    pub committed_at: Timestamp,
+}
+
+/**
+The ids and times a running command has used.
+*/
+pub(in crate::domain) struct JournalScope {
+    tenant: TenantId,
+    command: &'static str,
+    args: Option<serde_json::Value>,
+    used: Mutex<(Vec<Uuid>, Vec<Timestamp>)>,
+}
+
+impl JournalScope {
+    pub(in crate::domain) fn new<TArgs: Serialize>(tenant: TenantId, args: &TArgs) -> Arc<Self> {
+        let command = any::type_name::<TArgs>();
+
+        let args = serde_json::to_value(args)
+            .map_err(|err| emit::warn!("failed to journal command {command}: {err}"))
+            .ok();
+
+        Arc::new(JournalScope {
+            tenant,
+            command,
+            args,
+            used: Mutex::new((Vec::new(), Vec::new())),
+        })
+    }
+
+    /**
+    Finish a command that succeeded, returning the entry to journal for it.
+    */
+    pub(in crate::domain) fn finish(&self, output: &impl Serialize) -> Option<JournalEntry> {
+        let args = self.args.clone()?;
+
+        let output = serde_json::to_value(output)
+            .map_err(|err| emit::warn!("failed to journal command {command: self.command}: {err}"))
+            .ok()?;
+
+        let (ids, times) = std::mem::take(&mut *self.used.lock().unwrap());
+
+        Some(JournalEntry {
+            tenant: self.tenant.clone(),
+            command: self.command.to_owned(),
+            args,
+            output,
+            ids,
+            times,
+            // The commit time is filled in when the entry is appended to the journal
+            committed_at: Default::default(),
+        })
+    }
+
+    fn record_id(&self, id: Uuid) {
+        self.used.lock().unwrap().0.push(id);
+    }
+
+    fn record_time(&self, time: Timestamp) {
+        self.used.lock().unwrap().1.push(time);
+    }
+}
+
+/**
+The ids and times to inject into a command while it's being replayed.
+*/
+#[derive(Clone, Default)]
+pub(in crate::domain) struct ReplaySource(
+    Arc<Mutex<Option<(VecDeque<Uuid>, VecDeque<Timestamp>)>>>,
+);
+
+impl ReplaySource {
+    fn begin(&self, entry: &JournalEntry) {
+        *self.0.lock().unwrap() = Some((
+            entry.ids.iter().copied().collect(),
+            entry.times.iter().copied().collect(),
+        ));
+    }
+
+    fn end(&self) {
+        *self.0.lock().unwrap() = None;
+    }
+
+    fn next_id(&self) -> Option<Uuid> {
+        self.0.lock().unwrap().as_mut()?.0.pop_front()
+    }
+
+    fn next_time(&self) -> Option<Timestamp> {
+        self.0.lock().unwrap().as_mut()?.1.pop_front()
+    }
+}
+
+/**
+A clock that records the times read by a running command.
+*/
+pub struct JournaledClock<C> {
+    clock: C,
+    scope: Option<Arc<JournalScope>>,
+    replay: ReplaySource,
+}
+
+impl<C> Clock for JournaledClock<C>
+where
+    C: Clock,
+{
+    fn now(&self) -> Timestamp {
+        let Some(ref scope) = self.scope else {
+            return self.clock.now();
+        };
+
+        let now = self.replay.next_time().unwrap_or_else(|| self.clock.now());
+        scope.record_time(now);
+
+        now
+    }
+}
+
+/**
+A source of ids that records the ids generated by a running command.
+*/
+pub struct JournaledId<P> {
+    provider: P,
+    scope: Option<Arc<JournalScope>>,
+    replay: ReplaySource,
+}
+
+impl<T, P> IdProvider<T> for JournaledId<P>
+where
+    P: IdProvider<T>,
+{
+    fn get(&self) -> Result<Id<T>, Error> {
+        let Some(ref scope) = self.scope else {
+            return self.provider.get();
+        };
+
+        let id = match self.replay.next_id() {
+            Some(id) => crate::store::Id::from_raw(id).into(),
+            None => self.provider.get()?,
+        };
+        scope.record_id(crate::store::Id::from(id).into_raw());
+
+        Ok(id)
+    }
+}
+
+impl Resolver {
+    pub(in crate::domain) fn journaled_clock<C: Clock>(&self, clock: C) -> JournaledClock<C> {
+        JournaledClock {
+            clock,
+            scope: self.journal_resolver.scope.clone(),
+            replay: self.journal_resolver.replay.clone(),
+        }
+    }
+
+    pub(in crate::domain) fn journaled_id<P>(&self, provider: P) -> JournaledId<P> {
+        JournaledId {
+            provider,
+            scope: self.journal_resolver.scope.clone(),
+            replay: self.journal_resolver.replay.clone(),
+        }
+    }
+}
+
+/**
+The result of replaying a journal.
+*/
+pub struct ReplayOutcome {
+    /** The app with the state of the journal up to any divergence. */
+    pub app: App,
+    /** The number of commands that were replayed with the same output. */
+    pub replayed: usize,
+    pub divergence: Option<Divergence>,
+}
+
+/**
+A command whose replayed output didn't match the journal.
+*/
+#[derive(Debug)]
+pub struct Divergence {
+    /** The index of the entry in the journal. */
+    pub index: usize,
+    pub command: String,
+    pub expected: serde_json::Value,
+    /** The replayed output, or the error the command failed with. */
+    pub actual: Result<serde_json::Value, String>,
+}
+
+impl AppBuilder {
+    /**
+    Build an app and replay the given journal against it.
+
+    Replay stops at the first command that doesn't produce the same output it did originally.
+    Commands that run after replay, including ones that were replayed, aren't journaled
+    so a journal can be replayed without adding to it.
+    */
+    pub async fn replay(
+        mut self,
+        journal: impl IntoIterator<Item = JournalEntry>,
+    ) -> ReplayOutcome {
+        let replay = ReplaySource::default();
+
+        self.resolver.journal_resolver.replay = replay.clone();
+        self = self.command_journal(Register::once(|_| {
+            Arc::new(store::DisabledJournal) as Arc<dyn CommandJournal + Send + Sync>
+        }));
+
+        let app = self.build();
+        let commands = replay::commands();
+
+        let mut replayed = 0;
+
+        for (index, entry) in journal.into_iter().enumerate() {
+            let actual = match commands
+                .iter()
+                .find(|command| command.name == entry.command)
+            {
+                Some(command) => {
+                    replay.begin(&entry);
+
+                    let args = entry.args.clone();
+
+                    // Each command is replayed in the tenant it originally ran for
+                    let actual = app
+                        .for_tenant(entry.tenant.clone())
+                        .transaction(|resolver| async move {
+                            Ok::<_, StdError>((command.execute)(resolver, args).await?)
+                        })
+                        .await
+                        .map_err(|StdError(err)| err.to_string());
+
+                    replay.end();
+
+                    actual
+                }
+                None => Err(error::msg(format!(
+                    "no command named `{}` can be replayed",
+                    entry.command
+                ))
+                .to_string()),
+            };
+
+            if actual.as_ref() != Ok(&entry.output) {
+                emit::warn!("journal replay diverged at {index} ({command: entry.command})");
+
+                return ReplayOutcome {
+                    app,
+                    replayed,
+                    divergence: Some(Divergence {
+                        index,
+                        command: entry.command,
+                        expected: entry.output,
+                        actual,
+                    }),
+                };
+            }
+
+            replayed += 1;
+        }
+
+        ReplayOutcome {
+            app,
+            replayed,
+            divergence: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        domain::{
+            customers::{
+                model::{
+                    store::{
+                        in_memory_store,
+                        CustomerStore,
+                    },
+                    test_data::CustomerBuilder,
+                },
+                CustomerId,
+            },
+            inventory::ReceiveStock,
+            orders::*,
+            products::*,
+        },
+        store::Transaction,
+    };
+
+    use super::{
+        store::InMemoryJournal,
+        *,
+    };
+
+    fn builder(customer_id: CustomerId, journal: Arc<InMemoryJournal>) -> AppBuilder {
+        // Customers are treated as existing state outside of the journal
+        let customers = Arc::new(in_memory_store(Default::default()));
+
+        customers
+            .set_customer(
+                &Transaction::none(),
+                CustomerBuilder::new().id(customer_id).build(),
+            )
+            .unwrap();
+
+        App::builder()
+            .customer_store(Register::once(move |_| {
+                customers.clone() as Arc<dyn CustomerStore + Send + Sync>
+            }))
+            .command_journal(Register::once(move |_| {
+                journal.clone() as Arc<dyn CommandJournal + Send + Sync>
+            }))
+    }
+
+    async fn place_order(app: &App, customer_id: CustomerId) -> (OrderId, LineItemId) {
+        let order_id = OrderId::new();
+        let product_id = ProductId::new();
+
+        // Changes aren't visible until they commit, so each command runs in its own transaction
+        app.transaction(|resolver| async move {
+            resolver
+                .create_product_command()
+                .execute(CreateProduct {
+                    id: product_id,
+                    title: "A product".to_owned(),
+                    price: Currency::usd(100),
+                })
+                .await?;
+
+            Ok::<_, StdError>(())
+        })
+        .await
+        .unwrap();
+
+        app.transaction(|resolver| async move {
+            resolver
+                .set_product_status_command()
+                .execute(SetProductStatus {
+                    id: product_id,
+                    status: ProductStatus::Active,
+                })
+                .await?;
+
+            resolver
+                .receive_stock_command()
+                .execute(ReceiveStock {
+                    product_id,
+                    variant_id: None,
+                    quantity: 5,
+                })
+                .await?;
+
+            Ok::<_, StdError>(())
+        })
+        .await
+        .unwrap();
+
+        app.transaction(|resolver| async move {
+            resolver
+                .create_order_command()
+                .execute(CreateOrder {
+                    id: order_id,
+                    customer_id,
+                })
+                .await?;
+
+            Ok::<_, StdError>(())
+        })
+        .await
+        .unwrap();
+
+        let line_item_id = app
+            .transaction(|resolver| async move {
+                Ok::<_, StdError>(
+                    resolver
+                        .add_or_update_product_command()
+                        .execute(AddOrUpdateProduct {
+                            id: order_id,
+                            product_id,
+                            variant_id: None,
+                            quantity: 2,
+                        })
+                        .await?,
+                )
+            })
+            .await
+            .unwrap();
+
+        (order_id, line_item_id)
+    }
+
+    #[tokio::test]
+    async fn committed_commands_are_journaled() {
+        let customer_id = CustomerId::new();
+        let journal = Arc::new(InMemoryJournal::default());
+
+        let app = builder(customer_id, journal.clone()).build();
+
+        let (_, line_item_id) = place_order(&app, customer_id).await;
+
+        // A cancelled transaction isn't journaled
+        let _ = app
+            .transaction(|resolver| async move {
+                resolver
+                    .create_product_command()
+                    .execute(CreateProduct {
+                        id: ProductId::new(),
+                        title: "A cancelled product".to_owned(),
+                        price: Currency::usd(100),
+                    })
+                    .await?;
+
+                Err::<(), _>(StdError(Error::from("cancelled")))
+            })
+            .await;
+
+        let entries = journal.entries();
+
+        // Stock reserved by `AddOrUpdateProduct` is part of its entry rather than an entry of its own
+        assert_eq!(5, entries.len());
+        assert!(entries[0].command.ends_with("CreateProduct"));
+        assert!(entries[1].command.ends_with("SetProductStatus"));
+        assert!(entries[2].command.ends_with("ReceiveStock"));
+        assert!(entries[4].command.ends_with("AddOrUpdateProduct"));
+
+        assert_eq!(
+            vec![crate::store::Id::from(line_item_id).into_raw()],
+            entries[4].ids
+        );
+        assert!(!entries[4].times.is_empty());
+    }
+
+    /** A journal that holds up the first batch appended to it until another transaction has tried to commit. */
+    struct SlowJournal {
+        entries: InMemoryJournal,
+        appending: Mutex<Option<std::sync::mpsc::Sender<()>>>,
+    }
+
+    impl CommandJournal for SlowJournal {
+        fn append(&self, entries: &[JournalEntry]) -> Result<(), Error> {
+            if let Some(appending) = self.appending.lock().unwrap().take() {
+                appending.send(()).unwrap();
+
+                std::thread::sleep(std::time::Duration::from_millis(200));
+            }
+
+            self.entries.append(entries)
+        }
+    }
+
+    async fn create_product(app: &App, title: &str) {
+        let title = title.to_owned();
+
+        app.transaction(|resolver| async move {
+            resolver
+                .create_product_command()
+                .execute(CreateProduct {
+                    id: ProductId::new(),
+                    title,
+                    price: Currency::usd(100),
+                })
+                .await?;
+
+            Ok::<_, StdError>(())
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn commands_are_journaled_in_commit_order() {
+        let (appending, appended) = std::sync::mpsc::channel();
+
+        let journal = Arc::new(SlowJournal {
+            entries: InMemoryJournal::default(),
+            appending: Mutex::new(Some(appending)),
+        });
+
+        let app = Arc::new(
+            App::builder()
+                .command_journal(Register::once({
+                    let journal = journal.clone();
+                    move |_| journal.clone() as Arc<dyn CommandJournal + Send + Sync>
+                }))
+                .build(),
+        );
+
+        let first = tokio::spawn({
+            let app = app.clone();
+            async move { create_product(&app, "First").await }
+        });
+
+        // The first transaction has committed, but is still being journaled
+        tokio::task::spawn_blocking(move || appended.recv().unwrap())
+            .await
+            .unwrap();
+
+        create_product(&app, "Second").await;
+        first.await.unwrap();
+
+        let titles = journal
+            .entries
+            .entries()
+            .into_iter()
+            .map(|entry| entry.args["title"].clone())
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec!["First", "Second"], titles);
+    }
+
+    #[tokio::test]
+    async fn replay_reproduces_outcomes() {
+        let customer_id = CustomerId::new();
+        let journal = Arc::new(InMemoryJournal::default());
+
+        let app = builder(customer_id, journal.clone()).build();
+
+        let (order_id, line_item_id) = place_order(&app, customer_id).await;
+
+        let replayed = builder(customer_id, Default::default())
+            .replay(journal.entries())
+            .await;
+
+        assert!(replayed.divergence.is_none());
+        assert_eq!(5, replayed.replayed);
+
+        let line_item = replayed
+            .app
+            .root_resolver
+            .get_line_item_with_product_query()
+            .execute(GetLineItemWithProduct {
+                id: order_id,
+                line_item_id,
+                locales: vec![],
+            })
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(2, line_item.quantity);
+    }
+
+    #[tokio::test]
+    async fn replay_stops_at_divergence() {
+        let customer_id = CustomerId::new();
+        let journal = Arc::new(InMemoryJournal::default());
+
+        let app = builder(customer_id, journal.clone()).build();
+
+        place_order(&app, customer_id).await;
+
+        let mut entries = journal.entries();
+        entries[4].ids.clear();
+
+        let replayed = builder(customer_id, Default::default())
+            .replay(entries)
+            .await;
+
+        let divergence = replayed.divergence.unwrap();
+
+        assert_eq!(4, replayed.replayed);
+        assert_eq!(4, divergence.index);
+        assert!(divergence.command.ends_with("AddOrUpdateProduct"));
+    }
+}
@@ -0,0 +1,100 @@
+/*! The commands that can be replayed from a journal. */
+
+use std::{
+    any,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+};
+
+use serde::{
+    de::DeserializeOwned,
+    Serialize,
+};
+
+use crate::domain::{
+    infra::*,
+    Error,
+};
+
+type ExecuteFn = Arc<
+    dyn Fn(
+            Resolver,
+            serde_json::Value,
+        ) -> Pin<Box<dyn Future<Output = Result<serde_json::Value, Error>> + Send>>
+        + Send
+        + Sync,
+>;
+
+/**
+A command that can be executed from its serialized input.
+*/
+pub(in crate::domain::infra::journal) struct ReplayCommand {
+    pub(in crate::domain::infra::journal) name: &'static str,
+    pub(in crate::domain::infra::journal) execute: ExecuteFn,
+}
+
+impl ReplayCommand {
+    fn new<TArgs, TOutput, TCommand, F>(resolve: F) -> Self
+    where
+        TArgs: CommandArgs<Output = Result<TOutput, Error>> + DeserializeOwned + Send + 'static,
+        TOutput: Serialize,
+        TCommand: Command<TArgs> + Send + 'static,
+        F: Fn(&Resolver) -> TCommand + Send + Sync + 'static,
+    {
+        ReplayCommand {
+            name: any::type_name::<TArgs>(),
+            execute: Arc::new(move |resolver, args| {
+                let command = resolve(&resolver);
+
+                Box::pin(async move {
+                    let args = serde_json::from_value::<TArgs>(args)?;
+                    let output = command.execute(args).await?;
+
+                    Ok(serde_json::to_value(output)?)
+                })
+            }),
+        }
+    }
+}
+
+/**
+Get all commands that can be replayed.
+
+New commands need to be added here before they can be replayed.
+*/
+pub(in crate::domain::infra::journal) fn commands() -> Vec<ReplayCommand> {
+    vec![
+        ReplayCommand::new(Resolver::create_customer_command),
+        ReplayCommand::new(Resolver::create_product_command),
+        ReplayCommand::new(Resolver::set_product_title_command),
+        ReplayCommand::new(Resolver::set_product_price_command),
+        ReplayCommand::new(Resolver::set_product_status_command),
+        ReplayCommand::new(Resolver::set_product_translation_command),
+        ReplayCommand::new(Resolver::remove_product_translation_command),
+        ReplayCommand::new(Resolver::import_products_command),
+        ReplayCommand::new(Resolver::set_product_options_command),
+        ReplayCommand::new(Resolver::add_product_variant_command),
+        ReplayCommand::new(Resolver::add_product_image_command),
+        ReplayCommand::new(Resolver::set_product_image_alt_text_command),
+        ReplayCommand::new(Resolver::reorder_product_images_command),
+        ReplayCommand::new(Resolver::remove_product_image_command),
+        ReplayCommand::new(Resolver::create_order_command),
+        ReplayCommand::new(Resolver::add_or_update_product_command),
+        ReplayCommand::new(Resolver::abandon_order_command),
+        ReplayCommand::new(Resolver::set_bundle_components_command),
+        ReplayCommand::new(Resolver::receive_stock_command),
+        ReplayCommand::new(Resolver::adjust_stock_command),
+        ReplayCommand::new(Resolver::reserve_stock_command),
+        ReplayCommand::new(Resolver::release_stock_command),
+        ReplayCommand::new(Resolver::create_category_command),
+        ReplayCommand::new(Resolver::move_category_command),
+        ReplayCommand::new(Resolver::set_category_attributes_command),
+        ReplayCommand::new(Resolver::set_product_categories_command),
+        ReplayCommand::new(Resolver::set_product_attributes_command),
+        ReplayCommand::new(Resolver::set_product_tags_command),
+        ReplayCommand::new(Resolver::create_promotion_command),
+        ReplayCommand::new(Resolver::set_promotion_window_command),
+        ReplayCommand::new(Resolver::set_feature_flag_command),
+    ]
+}
@@ -0,0 +1,96 @@
+/*! Contains the `JournalResolver` type. */
+
+use std::sync::{
+    Arc,
+    Mutex,
+};
+
+use crate::domain::infra::{
+    journal::{
+        store::{
+            self,
+            CommandJournal,
+        },
+        JournalScope,
+        ReplaySource,
+    },
+    *,
+};
+
+/**
+Resolver for the command journal.
+
+The scope of the running command is kept on the resolver so any ids and times it resolves are recorded for it.
+The commit lock is shared by every resolver for the app, so transactions are journaled in the order they commit.
+*/
+#[derive(Clone)]
+pub(in crate::domain) struct JournalResolver {
+    command_journal: Register<Arc<dyn CommandJournal + Send + Sync>>,
+    commit_lock: Arc<Mutex<()>>,
+    pub(in crate::domain::infra::journal) scope: Option<Arc<JournalScope>>,
+    pub(in crate::domain::infra::journal) replay: ReplaySource,
+}
+
+impl Default for JournalResolver {
+    fn default() -> Self {
+        JournalResolver {
+            command_journal: Register::once(|resolver| {
+                let config = resolver.config();
+
+                if config.journal.enabled {
+                    Arc::new(store::file_journal(&config.journal.path))
+                        as Arc<dyn CommandJournal + Send + Sync>
+                } else {
+                    Arc::new(store::DisabledJournal) as Arc<dyn CommandJournal + Send + Sync>
+                }
+            }),
+            commit_lock: Default::default(),
+            scope: None,
+            replay: Default::default(),
+        }
+    }
+}
+
+impl Resolver {
+    pub(in crate::domain::infra) fn command_journal(&self) -> impl CommandJournal {
+        self.resolve(&self.journal_resolver.command_journal)
+    }
+
+    /**
+    A lock to hold from committing a transaction until its commands are journaled.
+
+    Without it, a transaction that commits later could be journaled before one it read from.
+    */
+    pub(in crate::domain::infra) fn journal_commit_lock(&self) -> Arc<Mutex<()>> {
+        self.journal_resolver.commit_lock.clone()
+    }
+
+    /** Whether this resolver belongs to a command that's already being journaled. */
+    pub(in crate::domain::infra) fn in_journal_scope(&self) -> bool {
+        self.journal_resolver.scope.is_some()
+    }
+
+    pub(in crate::domain::infra) fn with_journal_scope(
+        &self,
+        scope: Arc<JournalScope>,
+    ) -> Resolver {
+        Resolver {
+            journal_resolver: JournalResolver {
+                scope: Some(scope),
+                ..self.journal_resolver.clone()
+            },
+            ..self.by_ref()
+        }
+    }
+}
+
+impl AppBuilder {
+    /** Use a different journal for commands. */
+    pub(in crate::domain) fn command_journal(
+        mut self,
+        command_journal: Register<Arc<dyn CommandJournal + Send + Sync>>,
+    ) -> Self {
+        self.resolver.journal_resolver.command_journal = command_journal;
+        self
+    }
+}
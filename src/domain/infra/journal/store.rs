@@ -0,0 +1,175 @@
+/*! Persistent storage for the command journal. */
+
+use std::{
+    fs::{
+        self,
+        OpenOptions,
+    },
+    io::{
+        BufRead,
+        BufReader,
+        ErrorKind,
+        Write,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+    sync::Mutex,
+};
+
+use crate::domain::{
+    infra::*,
+    Error,
+};
+
+/* A place to append journal entries. */
+#[auto_impl(&, Arc)]
+pub(in crate::domain) trait CommandJournal {
+    fn append(&self, entries: &[JournalEntry]) -> Result<(), Error>;
+}
+
+/**
+A journal that appends entries to a file as JSON lines.
+
+The file is created if it doesn't exist.
+*/
+pub(in crate::domain) struct FileJournal {
+    path: PathBuf,
+    // Entries from concurrent transactions are appended one batch at a time
+    lock: Mutex<()>,
+}
+
+impl CommandJournal for FileJournal {
+    fn append(&self, entries: &[JournalEntry]) -> Result<(), Error> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        let mut lines = Vec::new();
+        for entry in entries {
+            serde_json::to_writer(&mut lines, entry)?;
+            lines.push(b'\n');
+        }
+
+        let _guard = self.lock.lock().unwrap();
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+
+        file.write_all(&lines)?;
+        file.sync_data()?;
+
+        Ok(())
+    }
+}
+
+pub(in crate::domain) fn file_journal(path: impl Into<PathBuf>) -> FileJournal {
+    FileJournal {
+        path: path.into(),
+        lock: Mutex::new(()),
+    }
+}
+
+/** A journal that discards its entries. */
+pub(in crate::domain) struct DisabledJournal;
+
+impl CommandJournal for DisabledJournal {
+    fn append(&self, _: &[JournalEntry]) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/** A test in-memory journal. */
+#[cfg(test)]
+#[derive(Default)]
+pub(in crate::domain) struct InMemoryJournal(Mutex<Vec<JournalEntry>>);
+
+#[cfg(test)]
+impl CommandJournal for InMemoryJournal {
+    fn append(&self, entries: &[JournalEntry]) -> Result<(), Error> {
+        self.0.lock().unwrap().extend_from_slice(entries);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl InMemoryJournal {
+    pub(in crate::domain) fn entries(&self) -> Vec<JournalEntry> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+/**
+Read all entries from a journal file, in the order they were committed.
+
+A journal that doesn't exist yet is empty.
+*/
+pub fn read_journal(path: impl AsRef<Path>) -> Result<Vec<JournalEntry>, Error> {
+    let file = match fs::File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut entries = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        entries.push(serde_json::from_str(&line)?);
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(command: &str) -> JournalEntry {
+        JournalEntry {
+            tenant: Default::default(),
+            command: command.to_owned(),
+            args: serde_json::json!({ "id": 1 }),
+            output: serde_json::Value::Null,
+            ids: Vec::new(),
+            times: Vec::new(),
+            committed_at: Default::default(),
+        }
+    }
+
+    #[test]
+    fn file_journal_round_trip() {
+        let path = std::env::temp_dir()
+            .join(format!("shop-journal-{}", uuid::Uuid::new_v4()))
+            .join("journal.jsonl");
+
+        assert!(read_journal(&path).unwrap().is_empty());
+
+        let journal = file_journal(&path);
+
+        journal.append(&[entry("a"), entry("b")]).unwrap();
+        journal.append(&[entry("c")]).unwrap();
+
+        let commands = read_journal(&path)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.command)
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec!["a", "b", "c"], commands);
+
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+}
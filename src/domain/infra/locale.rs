@@ -0,0 +1,226 @@
+/*! Contains the shared `Locale` type. */
+
+use std::{
+    convert::TryFrom,
+    fmt,
+};
+
+use crate::domain::{
+    error,
+    Error,
+};
+
+/**
+A language tag, like `en` or `pt-BR`.
+
+Tags are normalized so the same locale is always written the same way:
+the language is lowercase, scripts are titlecase, and regions are uppercase.
+Underscores are accepted in place of hyphens.
+*/
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Locale(String);
+
+impl Locale {
+    const MAX_LEN: usize = 35;
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /**
+    Get the next most general locale.
+
+    The parent of `zh-Hant-TW` is `zh-Hant`, whose parent is `zh`, which doesn't have a parent.
+    */
+    pub fn parent(&self) -> Option<Locale> {
+        self.0
+            .rfind('-')
+            .map(|end| Locale(self.0[..end].to_owned()))
+    }
+
+    /**
+    Parse the locales from an `Accept-Language` header, most preferred first.
+
+    Any tags that aren't valid locales, or are wildcards, are ignored.
+    */
+    pub fn parse_accept_language(header: &str) -> Vec<Locale> {
+        let mut locales = header
+            .split(',')
+            .enumerate()
+            .filter_map(|(i, range)| {
+                let mut parts = range.split(';');
+
+                let locale = Locale::try_from(parts.next()?.trim()).ok()?;
+
+                let quality = parts
+                    .filter_map(|param| param.trim().strip_prefix("q="))
+                    .next()
+                    .map(|q| q.trim().parse::<f32>().ok())
+                    .unwrap_or(Some(1.0))?;
+
+                // A quality of zero means the locale isn't acceptable at all
+                if quality <= 0.0 {
+                    return None;
+                }
+
+                Some((quality, i, locale))
+            })
+            .collect::<Vec<_>>();
+
+        locales.sort_by(|(qa, ia, _), (qb, ib, _)| qb.total_cmp(qa).then(ia.cmp(ib)));
+
+        locales.into_iter().map(|(_, _, locale)| locale).collect()
+    }
+
+    /**
+    Get the chain of locales to try, in order, when localizing text.
+
+    Each preferred locale is followed by its parents, and the chain ends with the default locale and its parents.
+    */
+    pub fn fallback_chain(preferred: &[Locale], default: &Locale) -> Vec<Locale> {
+        let mut chain = Vec::new();
+
+        for locale in preferred.iter().chain(Some(default)) {
+            let mut next = Some(locale.clone());
+
+            while let Some(locale) = next {
+                next = locale.parent();
+
+                if !chain.contains(&locale) {
+                    chain.push(locale);
+                }
+            }
+        }
+
+        chain
+    }
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Locale("en".to_owned())
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl TryFrom<String> for Locale {
+    type Error = Error;
+
+    fn try_from(locale: String) -> Result<Self, Self::Error> {
+        let invalid = || {
+            error::bad_input(
+                "locale.invalid",
+                format!(
+                    "`{}` isn't a valid locale, expected a tag like `en` or `pt-BR`",
+                    locale
+                ),
+            )
+        };
+
+        if locale.is_empty() || locale.len() > Locale::MAX_LEN {
+            return Err(invalid());
+        }
+
+        let mut normalized = String::with_capacity(locale.len());
+
+        for (i, subtag) in locale.split(['-', '_']).enumerate() {
+            let valid = if i == 0 {
+                (2..=3).contains(&subtag.len()) && subtag.chars().all(|c| c.is_ascii_alphabetic())
+            } else {
+                (1..=8).contains(&subtag.len()) && subtag.chars().all(|c| c.is_ascii_alphanumeric())
+            };
+
+            if !valid {
+                return Err(invalid());
+            }
+
+            if i > 0 {
+                normalized.push('-');
+            }
+
+            let alphabetic = subtag.chars().all(|c| c.is_ascii_alphabetic());
+
+            match subtag.len() {
+                // Regions, like `BR`
+                2 if i > 0 && alphabetic => normalized.push_str(&subtag.to_ascii_uppercase()),
+                // Scripts, like `Hant`
+                4 if i > 0 && alphabetic => {
+                    normalized.push_str(&subtag[..1].to_ascii_uppercase());
+                    normalized.push_str(&subtag[1..].to_ascii_lowercase());
+                }
+                _ => normalized.push_str(&subtag.to_ascii_lowercase()),
+            }
+        }
+
+        Ok(Locale(normalized))
+    }
+}
+
+impl<'a> TryFrom<&'a str> for Locale {
+    type Error = Error;
+
+    fn try_from(locale: &'a str) -> Result<Self, Self::Error> {
+        Self::try_from(locale.to_owned())
+    }
+}
+
+impl From<Locale> for String {
+    fn from(locale: Locale) -> String {
+        locale.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locale(locale: &str) -> Locale {
+        Locale::try_from(locale).unwrap()
+    }
+
+    #[test]
+    fn locales_are_normalized() {
+        for (expected, input) in [
+            ("en", "EN"),
+            ("pt-BR", "pt_br"),
+            ("zh-Hant-TW", "ZH-hant-tw"),
+            ("es-419", "es-419"),
+        ] {
+            assert_eq!(expected, locale(input).as_str());
+        }
+
+        for invalid in [
+            "",
+            "e",
+            "english",
+            "en-",
+            "en--GB",
+            "1a",
+            "en-toolongsubtag",
+        ] {
+            assert!(Locale::try_from(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn parse_accept_language() {
+        assert_eq!(
+            vec![locale("fr-CA"), locale("de"), locale("en")],
+            Locale::parse_accept_language("en;q=0.5, *, fr-CA, nope!, de;q=0.8, it;q=0")
+        );
+    }
+
+    #[test]
+    fn fallback_chain() {
+        assert_eq!(
+            vec![locale("fr-CA"), locale("fr"), locale("en-GB"), locale("en")],
+            Locale::fallback_chain(&[locale("fr-CA"), locale("en-GB")], &locale("en"))
+        );
+    }
+}
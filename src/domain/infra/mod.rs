@@ -5,22 +5,45 @@ This module contains shared types like `Currency` and services like `Resolver` t
 domain modules can use.
 */
 
+pub(in crate::domain) mod clock;
+pub(in crate::domain) mod config;
 pub(in crate::domain) mod currency;
 pub(in crate::domain) mod entity;
+pub(in crate::domain) mod features;
 pub mod func;
 pub(in crate::domain) mod id;
+pub(in crate::domain) mod jobs;
+pub(in crate::domain) mod journal;
+pub(in crate::domain) mod locale;
+pub(in crate::domain) mod projection;
 pub(in crate::domain) mod resolver;
+pub(in crate::domain) mod saga;
+pub(in crate::domain) mod tenant;
 pub(in crate::domain) mod transaction;
 pub(in crate::domain) mod version;
 pub mod ssrf_engine;
 
 pub use self::{
+    clock::*,
     currency::*,
+    features::*,
     func::*,
     id::*,
+    jobs::*,
+    journal::*,
+    locale::*,
+    projection::*,
     resolver::*,
+    saga::*,
+    tenant::*,
     transaction::*,
     version::*,
 };
 
-pub(in crate::domain) use self::entity::*;
+pub(in crate::domain) use self::{
+    entity::*,
+    projection::store::{
+        ReadModel,
+        ReadModelStore,
+    },
+};
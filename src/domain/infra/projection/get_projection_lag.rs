@@ -0,0 +1,80 @@
+/*! Contains the `GetProjectionLagQuery` type. */
+
+use std::sync::Arc;
+
+use crate::domain::{
+    infra::{
+        projection::store::ProjectionStore,
+        *,
+    },
+    Error,
+};
+
+/** Input for a `GetProjectionLagQuery`. */
+#[derive(Serialize, Deserialize)]
+pub struct GetProjectionLag {}
+
+/** How far behind the change feed a projection is. */
+#[derive(Serialize)]
+pub struct ProjectionLag {
+    pub name: String,
+    /** The position of the last change the projection applied. */
+    pub position: u64,
+    /** The position of the last change committed to the feed. */
+    pub head: u64,
+    /** The number of committed changes the projection hasn't applied yet. */
+    pub behind: u64,
+    /** When the oldest change the projection hasn't applied yet was committed. */
+    pub oldest_pending_at: Option<Timestamp>,
+    pub updated_at: Option<Timestamp>,
+    pub rebuilt_at: Option<Timestamp>,
+}
+
+impl QueryArgs for GetProjectionLag {
+    type Output = Result<Vec<ProjectionLag>, Error>;
+}
+
+/** Default implementation for a `GetProjectionLagQuery`. */
+async fn execute(
+    _: GetProjectionLag,
+    store: impl ProjectionStore,
+    feed: ChangeFeed,
+    projections: Arc<Vec<Projection>>,
+) -> Result<Vec<ProjectionLag>, Error> {
+    let head = feed.head();
+
+    projections
+        .iter()
+        .map(|projection| {
+            let state = store
+                .get_projection(&projection.name)?
+                .unwrap_or_else(|| ProjectionData::new(&projection.name));
+
+            Ok(ProjectionLag {
+                name: state.name,
+                position: state.position,
+                head,
+                behind: head.saturating_sub(state.position),
+                oldest_pending_at: feed
+                    .after(state.position, 1)
+                    .first()
+                    .map(|change| change.committed_at),
+                updated_at: state.updated_at,
+                rebuilt_at: state.rebuilt_at,
+            })
+        })
+        .collect()
+}
+
+impl Resolver {
+    /** Get how far behind the change feed each projection is. */
+    pub fn get_projection_lag_query(&self) -> impl Query<GetProjectionLag> {
+        self.query(|resolver, query: GetProjectionLag| async move {
+            let store = resolver.projection_store();
+            let feed = resolver.change_feed();
+            let projections = resolver.projections();
+
+            execute(query, store, feed, projections).await
+        })
+    }
+}
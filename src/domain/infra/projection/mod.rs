@@ -0,0 +1,490 @@
+/*!
+Denormalized read models.
+
+Commands record the entities they change on their transaction. When the transaction commits those changes
+are appended to a change feed. A projection applies changes from the feed to its read models, which are kept
+in their own stores so queries can read them directly instead of joining across stores on every request.
+
+Projections are brought up to date after each transaction commits. If a projection fails to apply a change
+it stays behind until the next attempt, so the lag of each projection is tracked and can be queried.
+
+Projections should compute their read models from the current state of the entities that changed,
+rather than from the changes themselves. That way applying the same change twice is harmless and
+a projection can be rebuilt from scratch by replaying the whole feed.
+*/
+
+use std::{
+    any,
+    future::Future,
+    pin::Pin,
+    sync::{
+        Arc,
+        RwLock,
+    },
+};
+
+use crate::domain::{
+    error::{
+        self,
+        StdError,
+    },
+    infra::*,
+    Error,
+};
+
+mod get_projection_lag;
+pub(in crate::domain) mod resolver;
+pub(in crate::domain) mod store;
+
+pub use self::{
+    get_projection_lag::*,
+    store::{
+        ProjectionData,
+        ProjectionId,
+        ProjectionVersion,
+    },
+};
+
+use self::store::ProjectionStore;
+
+/**
+The most changes a projection will apply in a single transaction.
+*/
+const BATCH_SIZE: usize = 100;
+
+type ApplyFn = Arc<
+    dyn Fn(Resolver, Change) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send>>
+        + Send
+        + Sync,
+>;
+
+/**
+A change made to an entity.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Change {
+    entity: &'static str,
+    id: crate::store::Id,
+}
+
+impl Change {
+    /** A change to the entity with the given id. */
+    pub fn of<T: 'static>(id: Id<T>) -> Self {
+        Change {
+            entity: any::type_name::<T>(),
+            id: id.into(),
+        }
+    }
+
+    /** The id of the entity that changed, if it's a `T`. */
+    pub fn id<T: 'static>(&self) -> Option<Id<T>> {
+        if self.entity == any::type_name::<T>() {
+            Some(self.id.into())
+        } else {
+            None
+        }
+    }
+}
+
+/**
+A change that was committed to the change feed.
+*/
+#[derive(Debug, Clone, Copy)]
+pub struct RecordedChange {
+    /** The position of the change in the feed. The first change is at position `1`. */
+    pub position: u64,
+    pub change: Change,
+    pub committed_at: Timestamp,
+}
+
+/**
+The changes committed by all transactions, in the order they committed.
+
+The feed is kept in memory, so projections are rebuilt from an empty feed when the app restarts.
+*/
+#[derive(Clone, Default)]
+pub struct ChangeFeed(Arc<RwLock<Vec<RecordedChange>>>);
+
+impl ChangeFeed {
+    pub(in crate::domain) fn append(
+        &self,
+        changes: impl IntoIterator<Item = Change>,
+        committed_at: Timestamp,
+    ) {
+        let mut feed = self.0.write().unwrap();
+
+        for change in changes {
+            let position = feed.len() as u64 + 1;
+
+            feed.push(RecordedChange {
+                position,
+                change,
+                committed_at,
+            });
+        }
+    }
+
+    /** Get up to `limit` changes that were committed after the given position. */
+    pub fn after(&self, position: u64, limit: usize) -> Vec<RecordedChange> {
+        let feed = self.0.read().unwrap();
+
+        feed.iter()
+            .skip(position as usize)
+            .take(limit)
+            .copied()
+            .collect()
+    }
+
+    /** The position of the last change committed to the feed. */
+    pub fn head(&self) -> u64 {
+        self.0.read().unwrap().len() as u64
+    }
+}
+
+/**
+A projection that maintains read models from committed changes.
+*/
+#[derive(Clone)]
+pub struct Projection {
+    name: String,
+    apply: ApplyFn,
+}
+
+impl Projection {
+    /**
+    Create a projection that applies each committed change with the given function.
+
+    The name is used to identify the projection's persisted position, so it should be unique and stable.
+    The function is called for every change, so it should ignore changes to entities it doesn't care about.
+    */
+    pub fn new<F, O>(name: impl Into<String>, apply: F) -> Self
+    where
+        F: Fn(Resolver, Change) -> O + Send + Sync + 'static,
+        O: Future<Output = Result<(), Error>> + Send + 'static,
+    {
+        Projection {
+            name: name.into(),
+            apply: Arc::new(move |resolver, change| Box::pin(apply(resolver, change))),
+        }
+    }
+}
+
+impl App {
+    /**
+    Get a runner for the app's projections.
+
+    The runner can be moved onto a background task, independently of the app.
+    */
+    pub fn projections(&self) -> ProjectionRunner {
+        ProjectionRunner {
+            app: App {
+                root_resolver: self.root_resolver.by_ref(),
+            },
+        }
+    }
+}
+
+/**
+Applies committed changes to projections.
+*/
+pub struct ProjectionRunner {
+    app: App,
+}
+
+impl ProjectionRunner {
+    /**
+    Apply any changes that projections haven't seen yet.
+
+    A projection that fails doesn't stop the others from catching up.
+    The first error is returned once all projections have been attempted.
+    */
+    pub async fn catch_up(&self) -> Result<(), Error> {
+        let resolver = &self.app.root_resolver;
+
+        // Avoid contending on the lock when there's nothing to do
+        if !self.is_behind()? {
+            return Ok(());
+        }
+
+        let _guard = resolver.projections_lock().lock_owned().await;
+
+        let mut result = Ok(());
+
+        for projection in resolver.projections().iter() {
+            if let Err(err) = self.catch_up_projection(projection).await {
+                emit::warn!(
+                    "failed to update projection {projection: projection.name}: {err: err.as_error()}"
+                );
+
+                if result.is_ok() {
+                    result = Err(err);
+                }
+            }
+        }
+
+        result
+    }
+
+    /**
+    Rebuild a projection by replaying the change feed from the start.
+    */
+    pub async fn rebuild(&self, name: &str) -> Result<(), Error> {
+        let resolver = &self.app.root_resolver;
+
+        let projection = resolver.projection(name).ok_or_else(|| {
+            error::not_found(
+                "projection.not_found",
+                format!("no projection named `{}` is registered", name),
+            )
+        })?;
+
+        let _guard = resolver.projections_lock().lock_owned().await;
+
+        let mut state = self.get_projection(name)?;
+        state.position = 0;
+        state.rebuilt_at = Some(resolver.clock().now());
+
+        self.set_projection(state).await?;
+
+        self.catch_up_projection(&projection).await
+    }
+
+    fn is_behind(&self) -> Result<bool, Error> {
+        let resolver = &self.app.root_resolver;
+        let head = resolver.change_feed().head();
+
+        for projection in resolver.projections().iter() {
+            if self.get_projection(&projection.name)?.position < head {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    async fn catch_up_projection(&self, projection: &Projection) -> Result<(), Error> {
+        let resolver = &self.app.root_resolver;
+        let feed = resolver.change_feed();
+
+        loop {
+            let mut state = self.get_projection(&projection.name)?;
+
+            let changes = feed.after(state.position, BATCH_SIZE);
+
+            let Some(last) = changes.last() else {
+                return Ok(());
+            };
+
+            state.position = last.position;
+            state.updated_at = Some(resolver.clock().now());
+
+            execute(
+                &self.app,
+                &projection.name,
+                projection.apply.clone(),
+                changes,
+                state,
+            )
+            .await
+            .map_err(|StdError(err)| err)?;
+        }
+    }
+
+    fn get_projection(&self, name: &str) -> Result<ProjectionData, Error> {
+        Ok(self
+            .app
+            .root_resolver
+            .projection_store()
+            .get_projection(name)?
+            .unwrap_or_else(|| ProjectionData::new(name)))
+    }
+
+    async fn set_projection(&self, projection: ProjectionData) -> Result<(), Error> {
+        self.app
+            .transaction_without_projections(|resolver| async move {
+                Ok(resolver
+                    .projection_store()
+                    .set_projection(resolver.active_transaction().get(), projection)?)
+            })
+            .await
+            .map_err(|StdError(err)| err)
+    }
+}
+
+/**
+Apply a batch of changes to a projection in a transaction, along with its new position.
+*/
+#[emit::span(ok_lvl: "debug", err_lvl: "warn", "apply changes to projection {projection}")]
+async fn execute(
+    app: &App,
+    projection: &str,
+    apply: ApplyFn,
+    changes: Vec<RecordedChange>,
+    state: ProjectionData,
+) -> Result<(), StdError> {
+    app.transaction_without_projections(|resolver| async move {
+        for recorded in changes {
+            apply(resolver.by_ref(), recorded.change).await?;
+        }
+
+        resolver
+            .projection_store()
+            .set_projection(resolver.active_transaction().get(), state)?;
+
+        Ok(())
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{
+            AtomicBool,
+            Ordering,
+        },
+        Mutex,
+    };
+
+    use super::*;
+
+    struct WidgetData;
+    type WidgetId = Id<WidgetData>;
+
+    struct GadgetData;
+
+    fn app(seen: Arc<Mutex<Vec<WidgetId>>>, fail: Arc<AtomicBool>) -> App {
+        App::builder()
+            .projection(Projection::new("widgets", move |_, change: Change| {
+                let seen = seen.clone();
+                let fail = fail.clone();
+
+                async move {
+                    if fail.load(Ordering::SeqCst) {
+                        return Err(Error::from("projection failed"));
+                    }
+
+                    if let Some(id) = change.id::<WidgetData>() {
+                        seen.lock().unwrap().push(id);
+                    }
+
+                    Ok(())
+                }
+            }))
+            .build()
+    }
+
+    async fn record(app: &App, change: Change) {
+        app.transaction(|resolver| async move {
+            resolver.active_transaction().record(change);
+
+            Ok::<(), StdError>(())
+        })
+        .await
+        .unwrap();
+    }
+
+    async fn get_lag(app: &App) -> ProjectionLag {
+        app.root_resolver
+            .get_projection_lag_query()
+            .execute(GetProjectionLag {})
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|lag| lag.name == "widgets")
+            .unwrap()
+    }
+
+    #[test]
+    fn change_id_is_typed() {
+        let id = WidgetId::new();
+        let change = Change::of(id);
+
+        assert_eq!(Some(id), change.id::<WidgetData>());
+        assert_eq!(None, change.id::<GadgetData>());
+    }
+
+    #[tokio::test]
+    async fn committed_changes_are_applied() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let app = app(seen.clone(), Default::default());
+
+        let id = WidgetId::new();
+
+        record(&app, Change::of(id)).await;
+        record(&app, Change::of(Id::<GadgetData>::new())).await;
+
+        assert_eq!(vec![id], *seen.lock().unwrap());
+
+        let lag = get_lag(&app).await;
+        assert_eq!(2, lag.position);
+        assert_eq!(0, lag.behind);
+    }
+
+    #[tokio::test]
+    async fn cancelled_changes_are_not_applied() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let app = app(seen.clone(), Default::default());
+
+        let _ = app
+            .transaction(|resolver| async move {
+                resolver
+                    .active_transaction()
+                    .record(Change::of(WidgetId::new()));
+
+                Err::<(), StdError>(StdError(Error::from("cancelled")))
+            })
+            .await;
+
+        assert!(seen.lock().unwrap().is_empty());
+        assert_eq!(0, app.root_resolver.change_feed().head());
+    }
+
+    #[tokio::test]
+    async fn failed_projection_reports_lag_and_catches_up() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let fail = Arc::new(AtomicBool::new(true));
+        let app = app(seen.clone(), fail.clone());
+
+        let id = WidgetId::new();
+
+        record(&app, Change::of(id)).await;
+
+        let lag = get_lag(&app).await;
+        assert_eq!(0, lag.position);
+        assert_eq!(1, lag.behind);
+        assert!(lag.oldest_pending_at.is_some());
+
+        fail.store(false, Ordering::SeqCst);
+        app.projections().catch_up().await.unwrap();
+
+        assert_eq!(vec![id], *seen.lock().unwrap());
+        assert_eq!(0, get_lag(&app).await.behind);
+    }
+
+    #[tokio::test]
+    async fn rebuild_replays_all_changes() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let app = app(seen.clone(), Default::default());
+
+        let a = WidgetId::new();
+        let b = WidgetId::new();
+
+        record(&app, Change::of(a)).await;
+        record(&app, Change::of(b)).await;
+
+        app.projections().rebuild("widgets").await.unwrap();
+
+        assert_eq!(vec![a, b, a, b], *seen.lock().unwrap());
+        assert!(get_lag(&app).await.rebuilt_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn rebuild_unknown_projection_fails() {
+        let app = app(Default::default(), Default::default());
+
+        let err = app.projections().rebuild("gadgets").await.unwrap_err();
+
+        assert_eq!("projection.not_found", err.code());
+    }
+}
@@ -0,0 +1,87 @@
+/*! Contains the `ProjectionsResolver` type. */
+
+use std::sync::Arc;
+
+use tokio::sync::Mutex;
+
+use crate::{
+    config::StoreBackend,
+    domain::infra::{
+        projection::store::{
+            self,
+            ProjectionStore,
+        },
+        *,
+    },
+};
+
+/**
+Resolver for projections.
+
+Projections are registered up-front when the app is built, so they're kept directly on the resolver.
+*/
+#[derive(Clone)]
+pub(in crate::domain) struct ProjectionsResolver {
+    projection_store: Register<Arc<dyn ProjectionStore + Send + Sync>>,
+    change_feed: Register<ChangeFeed>,
+    projections: Arc<Vec<Projection>>,
+    // Projections are caught up one at a time so the same changes aren't applied concurrently
+    lock: Arc<Mutex<()>>,
+}
+
+impl Default for ProjectionsResolver {
+    fn default() -> Self {
+        ProjectionsResolver {
+            projection_store: Register::per_tenant(|resolver| {
+                match resolver.config().store.backend {
+                    // Projections follow the change feed, which is only kept in memory,
+                    // so their positions are kept in memory too
+                    StoreBackend::InMemory | StoreBackend::File => {
+                        Arc::new(store::in_memory_store(resolver.transaction_store()))
+                            as Arc<dyn ProjectionStore + Send + Sync>
+                    }
+                }
+            }),
+            change_feed: Register::per_tenant(|_| ChangeFeed::default()),
+            projections: Default::default(),
+            lock: Default::default(),
+        }
+    }
+}
+
+impl Resolver {
+    pub(in crate::domain::infra::projection) fn projection_store(&self) -> impl ProjectionStore {
+        self.resolve(&self.projections_resolver.projection_store)
+    }
+
+    pub(in crate::domain::infra) fn change_feed(&self) -> ChangeFeed {
+        self.resolve(&self.projections_resolver.change_feed)
+    }
+
+    pub(in crate::domain::infra::projection) fn projections(&self) -> Arc<Vec<Projection>> {
+        self.projections_resolver.projections.clone()
+    }
+
+    pub(in crate::domain::infra::projection) fn projection(
+        &self,
+        name: &str,
+    ) -> Option<Projection> {
+        self.projections_resolver
+            .projections
+            .iter()
+            .find(|projection| projection.name == name)
+            .cloned()
+    }
+
+    pub(in crate::domain::infra::projection) fn projections_lock(&self) -> Arc<Mutex<()>> {
+        self.projections_resolver.lock.clone()
+    }
+}
+
+impl AppBuilder {
+    /** Register a projection to maintain read models from committed changes. */
+    pub fn projection(mut self, projection: Projection) -> Self {
+        Arc::make_mut(&mut self.resolver.projections_resolver.projections).push(projection);
+        self
+    }
+}
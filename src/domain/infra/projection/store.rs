@@ -0,0 +1,206 @@
+/*! Persistent storage for projections and their read models. */
+
+use std::sync::Arc;
+
+use crate::{
+    domain::{
+        infra::*,
+        Error,
+    },
+    store::{
+        self,
+        Transaction,
+        TransactionStore,
+        TransactionValueStore,
+    },
+};
+
+pub type ProjectionId = Id<ProjectionData>;
+pub type ProjectionVersion = Version<ProjectionData>;
+
+/**
+The persisted state of a projection.
+
+The position is the last change in the change feed the projection has applied.
+*/
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ProjectionData {
+    pub id: ProjectionId,
+    pub version: ProjectionVersion,
+    pub name: String,
+    pub position: u64,
+    pub updated_at: Option<Timestamp>,
+    pub rebuilt_at: Option<Timestamp>,
+}
+
+impl ProjectionData {
+    pub(in crate::domain::infra::projection) fn new(name: impl Into<String>) -> Self {
+        ProjectionData {
+            id: ProjectionId::new(),
+            version: ProjectionVersion::default(),
+            name: name.into(),
+            position: 0,
+            updated_at: None,
+            rebuilt_at: None,
+        }
+    }
+}
+
+/* A place to persist and fetch projection state. */
+#[auto_impl(&, Arc)]
+pub(in crate::domain) trait ProjectionStore {
+    fn get_projection(&self, name: &str) -> Result<Option<ProjectionData>, Error>;
+    fn set_projection(
+        &self,
+        transaction: &Transaction,
+        projection: ProjectionData,
+    ) -> Result<(), Error>;
+}
+
+/** A test in-memory projection store. */
+pub(in crate::domain) struct InMemoryStore(TransactionValueStore<ProjectionData>);
+
+impl ProjectionStore for InMemoryStore {
+    fn get_projection(&self, name: &str) -> Result<Option<ProjectionData>, Error> {
+        Ok(self
+            .0
+            .get_all(|projection| projection.name == name)
+            .next()
+            .map(|(_, data)| data))
+    }
+
+    fn set_projection(
+        &self,
+        transaction: &Transaction,
+        projection: ProjectionData,
+    ) -> Result<(), Error> {
+        let mut data = projection;
+        let id = data.id;
+
+        self.0.set(
+            transaction,
+            id,
+            Some(data.version),
+            data.version.next(),
+            data,
+        )?;
+
+        Ok(())
+    }
+}
+
+pub(in crate::domain) fn in_memory_store(transaction_store: TransactionStore) -> InMemoryStore {
+    InMemoryStore(TransactionValueStore::new(transaction_store))
+}
+
+/**
+A read model maintained by a projection.
+
+Read models aren't entities, so their version is tracked alongside them instead of in their data.
+*/
+pub(in crate::domain) struct ReadModel<T> {
+    version: Option<store::Version>,
+    pub(in crate::domain) data: T,
+}
+
+impl<T> ReadModel<T> {
+    /** A read model that hasn't been stored yet. */
+    pub(in crate::domain) fn new(data: T) -> Self {
+        ReadModel {
+            version: None,
+            data,
+        }
+    }
+}
+
+/**
+A place to persist and fetch the read models for a projection.
+
+Each projection keeps its read models in its own store, so they can be read directly by queries.
+*/
+pub(in crate::domain) struct ReadModelStore<T>(Arc<TransactionValueStore<T>>);
+
+impl<T> Clone for ReadModelStore<T> {
+    fn clone(&self) -> Self {
+        ReadModelStore(self.0.clone())
+    }
+}
+
+impl<T> ReadModelStore<T>
+where
+    T: Clone,
+{
+    pub(in crate::domain) fn new(transaction_store: TransactionStore) -> Self {
+        ReadModelStore(Arc::new(TransactionValueStore::new(transaction_store)))
+    }
+
+    pub(in crate::domain) fn get(&self, id: impl Into<store::Id>) -> Option<ReadModel<T>> {
+        self.0.get(id).map(|(version, data)| ReadModel {
+            version: Some(version),
+            data,
+        })
+    }
+
+    pub(in crate::domain) fn get_all(
+        &self,
+        filter: impl FnMut(&T) -> bool,
+    ) -> impl Iterator<Item = ReadModel<T>> {
+        self.0.get_all(filter).map(|(version, data)| ReadModel {
+            version: Some(version),
+            data,
+        })
+    }
+
+    pub(in crate::domain) fn set(
+        &self,
+        transaction: &Transaction,
+        id: impl Into<store::Id>,
+        read_model: ReadModel<T>,
+    ) -> Result<(), Error> {
+        self.0.set(
+            transaction,
+            id,
+            read_model.version,
+            store::Version::new(),
+            read_model.data,
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_memory_store() {
+        let store = in_memory_store(Default::default());
+
+        store
+            .set_projection(&Transaction::none(), ProjectionData::new("a"))
+            .unwrap();
+
+        let found = store.get_projection("a").unwrap().unwrap();
+        assert_eq!("a", found.name);
+
+        assert!(store.get_projection("b").unwrap().is_none());
+    }
+
+    #[test]
+    fn set_stale_read_model_fails_concurrency_check() {
+        let store = ReadModelStore::<u32>::new(Default::default());
+        let id = store::Id::new();
+
+        store
+            .set(&Transaction::none(), id, ReadModel::new(1))
+            .unwrap();
+
+        let first = store.get(id).unwrap();
+        let second = store.get(id).unwrap();
+
+        store.set(&Transaction::none(), id, first).unwrap();
+
+        assert!(store.set(&Transaction::none(), id, second).is_err());
+    }
+}
@@ -1,15 +1,42 @@
 /*! Contains the root `Resolver` type. */
 
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        Mutex,
+    },
+};
 use std::net::UdpSocket;
 use http_types::Body;
 use once_cell::sync::OnceCell;
 
 use crate::domain::{
+    bundles::resolver::BundlesResolver,
+    catalog::resolver::CatalogResolver,
     customers::resolver::CustomersResolver,
-    infra::transaction::resolver::TransactionsResolver,
-    orders::resolver::OrdersResolver,
-    products::resolver::ProductsResolver,
+    infra::{
+        clock::ClockResolver,
+        config::ConfigResolver,
+        features::resolver::FeaturesResolver,
+        jobs::resolver::JobsResolver,
+        journal::resolver::JournalResolver,
+        projection::resolver::ProjectionsResolver,
+        saga::resolver::SagasResolver,
+        tenant::TenantResolver,
+        transaction::resolver::TransactionsResolver,
+        TenantId,
+    },
+    inventory::resolver::InventoryResolver,
+    orders::{
+        self,
+        resolver::OrdersResolver,
+    },
+    products::{
+        self,
+        resolver::ProductsResolver,
+    },
+    promotions::resolver::PromotionsResolver,
 };
 
 /**
@@ -27,14 +54,58 @@ impl Default for App {
 
 impl App {
     pub fn new() -> Self {
-        App {
-            root_resolver: Resolver {
+        App::builder().build()
+    }
+
+    /**
+    Begin building an app.
+
+    The builder starts out with the same registrations as `App::new`.
+    Any of them can be replaced before the app is built.
+    */
+    pub fn builder() -> AppBuilder {
+        AppBuilder {
+            resolver: Resolver {
+                config_resolver: Default::default(),
+                tenant_resolver: Default::default(),
                 transactions_resolver: Default::default(),
+                clock_resolver: Default::default(),
+                features_resolver: Default::default(),
+                jobs_resolver: Default::default(),
+                sagas_resolver: Default::default(),
+                journal_resolver: Default::default(),
+                projections_resolver: Default::default(),
                 products_resolver: Default::default(),
                 orders_resolver: Default::default(),
+                inventory_resolver: Default::default(),
+                catalog_resolver: Default::default(),
                 customers_resolver: Default::default(),
+                promotions_resolver: Default::default(),
+                bundles_resolver: Default::default(),
+                #[cfg(test)]
+                widgets_resolver: Default::default(),
             },
         }
+        .projection(orders::customer_orders_projection())
+        .projection(orders::order_products_projection())
+        .projection(products::product_search_index_projection())
+    }
+}
+
+/**
+A builder for an app.
+
+Methods for replacing registrations live alongside the resolvers that own them.
+*/
+pub struct AppBuilder {
+    pub(in crate::domain) resolver: Resolver,
+}
+
+impl AppBuilder {
+    pub fn build(self) -> App {
+        App {
+            root_resolver: self.resolver,
+        }
     }
 }
 
@@ -48,10 +119,25 @@ Private implementation details live on the wrapped resolvers.
 Commands and queries are resolved from this `Resolver`.
 */
 pub struct Resolver {
+    pub(in crate::domain) config_resolver: ConfigResolver,
+    pub(in crate::domain) tenant_resolver: TenantResolver,
     pub(in crate::domain) transactions_resolver: TransactionsResolver,
+    pub(in crate::domain) clock_resolver: ClockResolver,
+    pub(in crate::domain) features_resolver: FeaturesResolver,
+    pub(in crate::domain) jobs_resolver: JobsResolver,
+    pub(in crate::domain) sagas_resolver: SagasResolver,
+    pub(in crate::domain) journal_resolver: JournalResolver,
+    pub(in crate::domain) projections_resolver: ProjectionsResolver,
     pub(in crate::domain) products_resolver: ProductsResolver,
     pub(in crate::domain) orders_resolver: OrdersResolver,
+    pub(in crate::domain) inventory_resolver: InventoryResolver,
+    pub(in crate::domain) catalog_resolver: CatalogResolver,
     pub(in crate::domain) customers_resolver: CustomersResolver,
+    pub(in crate::domain) promotions_resolver: PromotionsResolver,
+    pub(in crate::domain) bundles_resolver: BundlesResolver,
+    /** Exercises the resolver generated by `#[derive(Entity)]`. */
+    #[cfg(test)]
+    pub(in crate::domain) widgets_resolver: super::entity::tests::WidgetsResolver,
 }
 
 impl Resolver {
@@ -73,10 +159,24 @@ impl Resolver {
         }
 
         Resolver {
+            config_resolver: self.config_resolver.clone(),
+            tenant_resolver: self.tenant_resolver.clone(),
             transactions_resolver: self.transactions_resolver.clone(),
+            clock_resolver: self.clock_resolver.clone(),
+            features_resolver: self.features_resolver.clone(),
+            jobs_resolver: self.jobs_resolver.clone(),
+            sagas_resolver: self.sagas_resolver.clone(),
+            journal_resolver: self.journal_resolver.clone(),
+            projections_resolver: self.projections_resolver.clone(),
             products_resolver: self.products_resolver.clone(),
             orders_resolver: self.orders_resolver.clone(),
+            inventory_resolver: self.inventory_resolver.clone(),
+            catalog_resolver: self.catalog_resolver.clone(),
             customers_resolver: self.customers_resolver.clone(),
+            promotions_resolver: self.promotions_resolver.clone(),
+            bundles_resolver: self.bundles_resolver.clone(),
+            #[cfg(test)]
+            widgets_resolver: self.widgets_resolver.clone(),
         }
     }
 
@@ -111,6 +211,36 @@ impl<T> Register<T> {
         }))
     }
 
+    /**
+    Create a register that returns the same instance of a value for each tenant.
+
+    Each tenant gets its own instance the first time it's resolved for them.
+    Stores for tenant data should use this instead of `once` so tenants can't see each other's data.
+    */
+    pub fn per_tenant(f: impl Fn(&Resolver) -> T + Send + Sync + 'static) -> Self
+    where
+        T: Send + Sync + Clone + 'static,
+    {
+        let values = Mutex::new(HashMap::<TenantId, T>::new());
+        Register(Arc::new(move |resolver| {
+            let tenant = resolver.tenant();
+
+            if let Some(value) = values.lock().unwrap().get(&tenant) {
+                return value.clone();
+            }
+
+            // Create the value without holding the lock, since it may resolve other per-tenant values
+            let value = f(resolver);
+
+            values
+                .lock()
+                .unwrap()
+                .entry(tenant)
+                .or_insert(value)
+                .clone()
+        }))
+    }
+
     /**
     Create a register that returns a new instance of a value each time.
     */
@@ -0,0 +1,45 @@
+/*! Contains the `GetSagaQuery` type. */
+
+use crate::domain::{
+    infra::{
+        saga::store::SagaStore,
+        *,
+    },
+    Error,
+};
+
+/** Input for a `GetSagaQuery`. */
+#[derive(Serialize, Deserialize)]
+pub struct GetSaga {
+    pub id: SagaId,
+}
+
+impl QueryArgs for GetSaga {
+    type Output = Result<Option<SagaData>, Error>;
+}
+
+/** Default implementation for a `GetSagaQuery`. */
+async fn execute(
+    query: GetSaga,
+    tenant: TenantId,
+    store: impl SagaStore,
+) -> Result<Option<SagaData>, Error> {
+    // Sagas are stored together, so ones started by other tenants are filtered out
+    let saga = store
+        .get_saga(query.id)?
+        .filter(|saga| saga.tenant == tenant);
+
+    Ok(saga)
+}
+
+impl Resolver {
+    /** Get the progress of a saga. */
+    pub fn get_saga_query(&self) -> impl Query<GetSaga> {
+        self.query(|resolver, query: GetSaga| async move {
+            let tenant = resolver.tenant();
+            let store = resolver.saga_store();
+
+            execute(query, tenant, store).await
+        })
+    }
+}
@@ -0,0 +1,569 @@
+/*!
+Sagas for workflows that span multiple transactions.
+
+A saga is a workflow made up of steps that each run in their own transaction.
+When a step fails, the steps before it are undone by running their compensating actions in reverse order.
+
+The state of a saga is written in the same transaction as each step, so if the app stops partway through
+a saga it can be resumed from the last step that committed. Steps that call out to other systems may be
+run again when a saga is resumed, so they should be idempotent.
+*/
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+};
+
+use serde::{
+    de::DeserializeOwned,
+    Serialize,
+};
+
+use crate::domain::{
+    error::{
+        self,
+        StdError,
+    },
+    infra::*,
+    Error,
+};
+
+mod get_saga;
+pub(in crate::domain) mod resolver;
+pub(in crate::domain) mod store;
+
+pub use self::{
+    get_saga::*,
+    store::{
+        SagaData,
+        SagaId,
+        SagaStatus,
+        SagaVersion,
+    },
+};
+
+use self::store::SagaStore;
+
+type StepFn = Arc<
+    dyn Fn(
+            Resolver,
+            serde_json::Value,
+        ) -> Pin<Box<dyn Future<Output = Result<serde_json::Value, Error>> + Send>>
+        + Send
+        + Sync,
+>;
+
+/**
+A workflow that can be run as a saga.
+
+Steps share a piece of data that's passed from one to the next.
+Each step can return a new version of that data for the steps after it.
+*/
+#[derive(Clone)]
+pub struct Saga {
+    name: String,
+    steps: Vec<Step>,
+}
+
+#[derive(Clone)]
+struct Step {
+    name: String,
+    action: StepFn,
+    compensate: Option<StepFn>,
+}
+
+impl Saga {
+    /**
+    Create an empty workflow.
+
+    The name is used to find the workflow again when a saga is resumed, so it should be unique and stable.
+    */
+    pub fn new(name: impl Into<String>) -> Self {
+        Saga {
+            name: name.into(),
+            steps: Vec::new(),
+        }
+    }
+
+    /** Add a step to the workflow. */
+    pub fn step<T, F, O>(mut self, name: impl Into<String>, action: F) -> Self
+    where
+        T: Serialize + DeserializeOwned + Send + 'static,
+        F: Fn(Resolver, T) -> O + Send + Sync + 'static,
+        O: Future<Output = Result<T, Error>> + Send + 'static,
+    {
+        self.steps.push(Step {
+            name: name.into(),
+            action: step_fn(action),
+            compensate: None,
+        });
+        self
+    }
+
+    /**
+    Add a compensating action to the last step in the workflow.
+
+    The compensating action is run if a later step fails, and should undo the changes the step made.
+    */
+    pub fn compensate<T, F, O>(mut self, compensate: F) -> Self
+    where
+        T: Serialize + DeserializeOwned + Send + 'static,
+        F: Fn(Resolver, T) -> O + Send + Sync + 'static,
+        O: Future<Output = Result<T, Error>> + Send + 'static,
+    {
+        let step = self
+            .steps
+            .last_mut()
+            .expect("a compensating action needs a step to compensate");
+
+        step.compensate = Some(step_fn(compensate));
+        self
+    }
+}
+
+fn step_fn<T, F, O>(f: F) -> StepFn
+where
+    T: Serialize + DeserializeOwned + Send + 'static,
+    F: Fn(Resolver, T) -> O + Send + Sync + 'static,
+    O: Future<Output = Result<T, Error>> + Send + 'static,
+{
+    Arc::new(move |resolver, data| match serde_json::from_value(data) {
+        Ok(data) => {
+            let step = f(resolver, data);
+
+            Box::pin(async move { Ok(serde_json::to_value(step.await?)?) })
+        }
+        Err(err) => Box::pin(async move { Err(Error::from(err)) }),
+    })
+}
+
+impl App {
+    /**
+    Get a runner for the app's sagas.
+
+    The runner can be moved onto a background task, independently of the app.
+    */
+    pub fn sagas(&self) -> SagaRunner {
+        SagaRunner {
+            app: App {
+                root_resolver: self.root_resolver.by_ref(),
+            },
+        }
+    }
+}
+
+/**
+Starts and resumes sagas.
+*/
+pub struct SagaRunner {
+    app: App,
+}
+
+impl SagaRunner {
+    /**
+    Start a new saga for a registered workflow and run it until it finishes.
+
+    The returned state will either be completed, or the result of compensating for a failed step.
+    */
+    pub async fn start(&self, name: &str, data: impl Serialize) -> Result<SagaData, Error> {
+        let resolver = &self.app.root_resolver;
+
+        if resolver.saga(name).is_none() {
+            return Err(error::bad_input(
+                "saga.workflow_not_found",
+                format!("no workflow named `{}` is registered", name),
+            ));
+        }
+
+        let now = resolver.clock().now();
+
+        let saga = SagaData {
+            id: SagaId::new(),
+            version: SagaVersion::default(),
+            tenant: resolver.tenant(),
+            name: name.to_owned(),
+            status: SagaStatus::Running,
+            step: 0,
+            data: serde_json::to_value(data)?,
+            error: None,
+            created_at: now,
+            updated_at: now,
+        };
+
+        let id = saga.id;
+
+        self.set_saga(saga).await?;
+
+        self.resume(id).await
+    }
+
+    /**
+    Resume a saga from the last step it committed and run it until it finishes.
+
+    Steps always run for the tenant that started the saga, whichever tenant it's resumed from.
+    */
+    pub async fn resume(&self, id: SagaId) -> Result<SagaData, Error> {
+        let saga = self
+            .app
+            .root_resolver
+            .saga_store()
+            .get_saga(id)?
+            .ok_or_else(|| error::not_found("saga.not_found", "saga not found"))?;
+
+        let runner = SagaRunner {
+            app: self.app.for_tenant(saga.tenant),
+        };
+
+        runner.run(id).await
+    }
+
+    async fn run(&self, id: SagaId) -> Result<SagaData, Error> {
+        loop {
+            let resolver = &self.app.root_resolver;
+
+            let saga = resolver
+                .saga_store()
+                .get_saga(id)?
+                .ok_or_else(|| error::not_found("saga.not_found", "saga not found"))?;
+
+            if saga.status.is_finished() {
+                return Ok(saga);
+            }
+
+            let workflow = resolver.saga(&saga.name).ok_or_else(|| {
+                error::msg(format!("no workflow named `{}` is registered", saga.name))
+            })?;
+
+            match saga.status {
+                SagaStatus::Running => self.run_step(&workflow, saga).await?,
+                _ => self.compensate_step(&workflow, saga).await?,
+            }
+        }
+    }
+
+    /**
+    Resume all sagas that haven't finished.
+
+    This should be called when the app starts to pick up any sagas that were interrupted.
+    */
+    pub async fn resume_all(&self) -> Result<(), Error> {
+        for saga in self.app.root_resolver.saga_store().unfinished_sagas()? {
+            if let Err(err) = self.resume(saga.id).await {
+                emit::error!(
+                    "failed to resume saga {saga: saga.id} ({name: saga.name}): {err: err.as_error()}"
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn run_step(&self, workflow: &Saga, mut saga: SagaData) -> Result<(), Error> {
+        let clock = self.app.root_resolver.clock();
+
+        let Some(step) = workflow.steps.get(saga.step).cloned() else {
+            saga.status = SagaStatus::Completed;
+            saga.updated_at = clock.now();
+
+            return self.set_saga(saga).await;
+        };
+
+        let result = execute(&self.app, &saga.name, &step.name, {
+            let mut saga = saga.clone();
+            let clock = &clock;
+
+            move |resolver| async move {
+                saga.data = (step.action)(resolver, saga.data).await?;
+                saga.step += 1;
+                saga.updated_at = clock.now();
+
+                Ok(saga)
+            }
+        })
+        .await;
+
+        if let Err(err) = result {
+            saga.status = SagaStatus::Compensating;
+            saga.error = Some(err.to_string());
+            saga.updated_at = clock.now();
+
+            self.set_saga(saga).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn compensate_step(&self, workflow: &Saga, mut saga: SagaData) -> Result<(), Error> {
+        let clock = self.app.root_resolver.clock();
+
+        if saga.step == 0 {
+            saga.status = SagaStatus::Compensated;
+            saga.updated_at = clock.now();
+
+            return self.set_saga(saga).await;
+        }
+
+        let step = workflow
+            .steps
+            .get(saga.step - 1)
+            .cloned()
+            .ok_or_else(|| error::msg("missing step for saga"))?;
+
+        let result = execute(&self.app, &saga.name, &step.name, {
+            let mut saga = saga.clone();
+            let clock = &clock;
+
+            move |resolver| async move {
+                if let Some(compensate) = step.compensate {
+                    saga.data = compensate(resolver, saga.data).await?;
+                }
+
+                saga.step -= 1;
+                saga.updated_at = clock.now();
+
+                Ok(saga)
+            }
+        })
+        .await;
+
+        if let Err(err) = result {
+            emit::error!("failed to compensate saga {saga: saga.id} ({name: saga.name}): {err}");
+
+            saga.status = SagaStatus::Failed;
+            saga.error = Some(err.to_string());
+            saga.updated_at = clock.now();
+
+            self.set_saga(saga).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn set_saga(&self, saga: SagaData) -> Result<(), Error> {
+        self.app
+            .transaction(|resolver| async move {
+                Ok(resolver
+                    .saga_store()
+                    .set_saga(resolver.active_transaction().get(), saga)?)
+            })
+            .await
+            .map_err(|StdError(err)| err)
+    }
+}
+
+/**
+Run a step of a saga in a transaction, along with the state it returns.
+*/
+#[emit::span(ok_lvl: "debug", err_lvl: "warn", "run saga {saga} step {step}")]
+async fn execute<F, O>(app: &App, saga: &str, step: &str, f: F) -> Result<(), StdError>
+where
+    F: FnOnce(Resolver) -> O,
+    O: Future<Output = Result<SagaData, Error>>,
+{
+    app.transaction(|resolver| async move {
+        let saga = f(resolver.by_ref()).await?;
+
+        resolver
+            .saga_store()
+            .set_saga(resolver.active_transaction().get(), saga)?;
+
+        Ok(())
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Checkout {
+        reserved: bool,
+        charged: bool,
+    }
+
+    fn app(log: Arc<Mutex<Vec<&'static str>>>, fail_at: Option<&'static str>) -> App {
+        let step = move |name: &'static str| {
+            let log = log.clone();
+
+            move |_: Resolver, mut checkout: Checkout| {
+                let log = log.clone();
+
+                async move {
+                    log.lock().unwrap().push(name);
+
+                    if fail_at == Some(name) {
+                        return Err(Error::from(format!("{} failed", name)));
+                    }
+
+                    match name {
+                        "reserve" => checkout.reserved = true,
+                        "charge" => checkout.charged = true,
+                        "release" => checkout.reserved = false,
+                        "refund" => checkout.charged = false,
+                        _ => (),
+                    }
+
+                    Ok(checkout)
+                }
+            }
+        };
+
+        App::builder()
+            .saga(
+                Saga::new("checkout")
+                    .step("reserve stock", step("reserve"))
+                    .compensate(step("release"))
+                    .step("charge payment", step("charge"))
+                    .compensate(step("refund"))
+                    .step("confirm order", step("confirm")),
+            )
+            .build()
+    }
+
+    fn checkout() -> Checkout {
+        Checkout {
+            reserved: false,
+            charged: false,
+        }
+    }
+
+    async fn get_saga(app: &App, id: SagaId) -> SagaData {
+        app.root_resolver
+            .get_saga_query()
+            .execute(GetSaga { id })
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn run_to_completion() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let app = app(log.clone(), None);
+
+        let saga = app.sagas().start("checkout", checkout()).await.unwrap();
+
+        assert_eq!(SagaStatus::Completed, saga.status);
+        assert_eq!(vec!["reserve", "charge", "confirm"], *log.lock().unwrap());
+
+        let saga = get_saga(&app, saga.id).await;
+        let data: Checkout = serde_json::from_value(saga.data).unwrap();
+
+        assert!(data.reserved);
+        assert!(data.charged);
+    }
+
+    #[tokio::test]
+    async fn failed_step_compensates_previous_steps() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let app = app(log.clone(), Some("confirm"));
+
+        let saga = app.sagas().start("checkout", checkout()).await.unwrap();
+
+        assert_eq!(SagaStatus::Compensated, saga.status);
+        assert_eq!(Some("confirm failed".to_owned()), saga.error);
+        assert_eq!(
+            vec!["reserve", "charge", "confirm", "refund", "release"],
+            *log.lock().unwrap()
+        );
+
+        let data: Checkout = serde_json::from_value(saga.data).unwrap();
+
+        assert!(!data.reserved);
+        assert!(!data.charged);
+    }
+
+    #[tokio::test]
+    async fn failed_compensation_fails_saga() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let app = app(log.clone(), Some("refund"));
+
+        // `refund` only runs after a later step fails, so fail it directly
+        let saga = SagaData {
+            id: SagaId::new(),
+            version: SagaVersion::default(),
+            tenant: TenantId::default(),
+            name: "checkout".to_owned(),
+            status: SagaStatus::Compensating,
+            step: 2,
+            data: serde_json::to_value(checkout()).unwrap(),
+            error: None,
+            created_at: app.root_resolver.clock().now(),
+            updated_at: app.root_resolver.clock().now(),
+        };
+        let id = saga.id;
+
+        app.sagas().set_saga(saga).await.unwrap();
+
+        let saga = app.sagas().resume(id).await.unwrap();
+
+        assert_eq!(SagaStatus::Failed, saga.status);
+        assert_eq!(2, saga.step);
+        assert_eq!(vec!["refund"], *log.lock().unwrap());
+    }
+
+    #[tokio::test]
+    async fn resume_unfinished_saga() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let app = app(log.clone(), None);
+
+        let tenant = TenantId::new("shop-a").unwrap();
+
+        // A saga that was interrupted after its first step committed
+        let saga = SagaData {
+            id: SagaId::new(),
+            version: SagaVersion::default(),
+            tenant: tenant.clone(),
+            name: "checkout".to_owned(),
+            status: SagaStatus::Running,
+            step: 1,
+            data: serde_json::to_value(Checkout {
+                reserved: true,
+                charged: false,
+            })
+            .unwrap(),
+            error: None,
+            created_at: app.root_resolver.clock().now(),
+            updated_at: app.root_resolver.clock().now(),
+        };
+        let id = saga.id;
+
+        app.sagas().set_saga(saga).await.unwrap();
+
+        app.sagas().resume_all().await.unwrap();
+
+        let tenant_app = app.for_tenant(tenant);
+
+        assert_eq!(
+            SagaStatus::Completed,
+            get_saga(&tenant_app, id).await.status
+        );
+        assert_eq!(vec!["charge", "confirm"], *log.lock().unwrap());
+
+        // Other tenants can't see the saga
+        let other = app
+            .root_resolver
+            .get_saga_query()
+            .execute(GetSaga { id })
+            .await
+            .unwrap();
+
+        assert!(other.is_none());
+    }
+
+    #[tokio::test]
+    async fn start_unknown_workflow() {
+        let app = app(Default::default(), None);
+
+        let err = match app.sagas().start("unknown", checkout()).await {
+            Ok(_) => panic!("expected an error"),
+            Err(err) => err,
+        };
+
+        assert_eq!("saga.workflow_not_found", err.code());
+    }
+}
@@ -0,0 +1,65 @@
+/*! Contains the `SagasResolver` type. */
+
+use std::sync::Arc;
+
+use crate::{
+    config::StoreBackend,
+    domain::infra::{
+        saga::store::{
+            self,
+            SagaStore,
+        },
+        *,
+    },
+};
+
+/**
+Resolver for sagas.
+
+Workflows are registered up-front when the app is built, so they're kept directly on the resolver.
+*/
+#[derive(Clone)]
+pub(in crate::domain) struct SagasResolver {
+    saga_store: Register<Arc<dyn SagaStore + Send + Sync>>,
+    sagas: Arc<Vec<Saga>>,
+}
+
+impl Default for SagasResolver {
+    fn default() -> Self {
+        SagasResolver {
+            saga_store: Register::once(|resolver| match resolver.config().store.backend {
+                StoreBackend::InMemory => {
+                    Arc::new(store::in_memory_store(resolver.transaction_store()))
+                        as Arc<dyn SagaStore + Send + Sync>
+                }
+                StoreBackend::File => Arc::new(
+                    store::file_store(resolver.transaction_store(), resolver.store_file("sagas"))
+                        .expect("failed to open the saga store"),
+                ) as Arc<dyn SagaStore + Send + Sync>,
+            }),
+            sagas: Default::default(),
+        }
+    }
+}
+
+impl Resolver {
+    pub(in crate::domain::infra::saga) fn saga_store(&self) -> impl SagaStore {
+        self.resolve(&self.sagas_resolver.saga_store)
+    }
+
+    pub(in crate::domain::infra::saga) fn saga(&self, name: &str) -> Option<Saga> {
+        self.sagas_resolver
+            .sagas
+            .iter()
+            .find(|saga| saga.name == name)
+            .cloned()
+    }
+}
+
+impl AppBuilder {
+    /** Register a workflow that can be started as a saga. */
+    pub fn saga(mut self, saga: Saga) -> Self {
+        Arc::make_mut(&mut self.resolver.sagas_resolver.sagas).push(saga);
+        self
+    }
+}
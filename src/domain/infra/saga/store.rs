@@ -0,0 +1,138 @@
+/*! Persistent storage for saga state. */
+
+use std::{
+    path::PathBuf,
+    vec::IntoIter,
+};
+
+use crate::{
+    domain::{
+        infra::*,
+        Error,
+    },
+    store::{
+        Transaction,
+        TransactionStore,
+        TransactionValueStore,
+    },
+};
+
+pub type SagaId = Id<SagaData>;
+pub type SagaVersion = Version<SagaData>;
+
+/**
+The state of a saga.
+*/
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SagaStatus {
+    /** Steps are being run. */
+    Running,
+    /** All steps ran successfully. */
+    Completed,
+    /** A step failed and the steps before it are being compensated. */
+    Compensating,
+    /** A step failed and the steps before it were compensated. */
+    Compensated,
+    /** A compensating action failed. The saga needs attention to put things right. */
+    Failed,
+}
+
+impl SagaStatus {
+    /** Whether or not a saga in this state still has work to do. */
+    pub fn is_finished(&self) -> bool {
+        !matches!(self, SagaStatus::Running | SagaStatus::Compensating)
+    }
+}
+
+/**
+The persisted state of a saga.
+
+State is written in the same transaction as each step, so a saga that's resumed picks up after the last step that committed.
+*/
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SagaData {
+    pub id: SagaId,
+    pub version: SagaVersion,
+    /** The tenant that started the saga. */
+    #[serde(default)]
+    pub tenant: TenantId,
+    /** The name of the workflow this saga runs. */
+    pub name: String,
+    pub status: SagaStatus,
+    /**
+    The index of the step to run next.
+
+    While compensating, this counts down, and the step before it is the next one to compensate.
+    */
+    pub step: usize,
+    /** The data passed between steps. */
+    pub data: serde_json::Value,
+    /** The error from the step that failed, if there was one. */
+    pub error: Option<String>,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+}
+
+/* A place to persist and fetch saga state. */
+#[auto_impl(&, Arc)]
+pub(in crate::domain) trait SagaStore {
+    fn get_saga(&self, id: SagaId) -> Result<Option<SagaData>, Error>;
+    fn set_saga(&self, transaction: &Transaction, saga: SagaData) -> Result<(), Error>;
+    fn unfinished_sagas(&self) -> Result<IntoIter<SagaData>, Error>;
+}
+
+/** A test in-memory saga store. */
+pub(in crate::domain) struct InMemoryStore(TransactionValueStore<SagaData>);
+
+impl SagaStore for InMemoryStore {
+    fn get_saga(&self, id: SagaId) -> Result<Option<SagaData>, Error> {
+        if let Some((version, data)) = self.0.get(id) {
+            assert_eq!(version, data.version.into());
+
+            Ok(Some(data))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn set_saga(&self, transaction: &Transaction, saga: SagaData) -> Result<(), Error> {
+        let mut data = saga;
+        let id = data.id;
+
+        self.0.set(
+            transaction,
+            id,
+            Some(data.version),
+            data.version.next(),
+            data,
+        )?;
+
+        Ok(())
+    }
+
+    #[allow(clippy::needless_collect)]
+    fn unfinished_sagas(&self) -> Result<IntoIter<SagaData>, Error> {
+        let sagas: Vec<_> = self
+            .0
+            .get_all(|saga| !saga.status.is_finished())
+            .map(|(_, data)| data)
+            .collect();
+
+        Ok(sagas.into_iter())
+    }
+}
+
+pub(in crate::domain) fn in_memory_store(transaction_store: TransactionStore) -> InMemoryStore {
+    InMemoryStore(TransactionValueStore::new(transaction_store))
+}
+
+pub(in crate::domain) fn file_store(
+    transaction_store: TransactionStore,
+    path: impl Into<PathBuf>,
+) -> Result<InMemoryStore, Error> {
+    Ok(InMemoryStore(TransactionValueStore::open(
+        transaction_store,
+        path,
+    )?))
+}
@@ -0,0 +1,274 @@
+/*!
+Contains the shared `TenantId` type.
+
+Several storefronts can be hosted by the same app. Each storefront is a tenant, and the resolver
+for a request carries the tenant it was made for. Stores for products, customers, orders, and anything
+derived from them are registered per-tenant, so a resolver can only ever reach its own tenant's data.
+An id that's valid in one tenant simply doesn't exist in any other.
+*/
+
+use std::{
+    fmt,
+    str::FromStr,
+    sync::Arc,
+};
+
+use crate::domain::{
+    error,
+    infra::*,
+    Error,
+};
+
+/**
+The longest a tenant id can be.
+
+Tenant ids are used as host names and path segments, so they're kept short.
+*/
+const MAX_LEN: usize = 63;
+
+/**
+The id of a tenant.
+
+Tenant ids are lowercase ASCII letters, digits, and hyphens, and can't start or end with a hyphen.
+*/
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TenantId(Arc<str>);
+
+impl TenantId {
+    /** The tenant that's used when none is given. */
+    pub const DEFAULT: &'static str = "default";
+
+    pub fn new(id: impl AsRef<str>) -> Result<Self, Error> {
+        let id = id.as_ref();
+
+        let valid = !id.is_empty()
+            && id.len() <= MAX_LEN
+            && !id.starts_with('-')
+            && !id.ends_with('-')
+            && id
+                .bytes()
+                .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-');
+
+        if !valid {
+            return Err(error::bad_input(
+                "tenant.invalid_id",
+                format!("`{}` is not a valid tenant id", id),
+            ));
+        }
+
+        Ok(TenantId(id.into()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for TenantId {
+    fn default() -> Self {
+        TenantId(Self::DEFAULT.into())
+    }
+}
+
+impl fmt::Display for TenantId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for TenantId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TenantId::new(s)
+    }
+}
+
+impl TryFrom<String> for TenantId {
+    type Error = Error;
+
+    fn try_from(id: String) -> Result<Self, Self::Error> {
+        TenantId::new(id)
+    }
+}
+
+impl From<TenantId> for String {
+    fn from(id: TenantId) -> String {
+        id.0.to_string()
+    }
+}
+
+/**
+Resolver for the current tenant.
+
+The tenant isn't a registration, it's a fact about the resolver itself, so it's kept directly on the resolver.
+*/
+#[derive(Clone, Default)]
+pub(in crate::domain) struct TenantResolver {
+    tenant: TenantId,
+}
+
+impl Resolver {
+    /** The tenant this resolver resolves components for. */
+    pub fn tenant(&self) -> TenantId {
+        self.tenant_resolver.tenant.clone()
+    }
+
+    pub(in crate::domain) fn with_tenant(&self, tenant: TenantId) -> Resolver {
+        Resolver {
+            tenant_resolver: TenantResolver { tenant },
+            ..self.by_ref()
+        }
+    }
+}
+
+impl App {
+    /**
+    Get a view of the app for a tenant.
+
+    Transactions begun on the returned app can only see and change the data belonging to that tenant.
+    */
+    pub fn for_tenant(&self, tenant: TenantId) -> App {
+        App {
+            root_resolver: self.root_resolver.with_tenant(tenant),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::{
+        customers::*,
+        error::StdError,
+        products::*,
+    };
+
+    use super::*;
+
+    fn tenant(id: &str) -> TenantId {
+        TenantId::new(id).unwrap()
+    }
+
+    #[test]
+    fn tenant_id_is_validated() {
+        for valid in ["default", "shop-a", "a1"] {
+            assert!(TenantId::new(valid).is_ok(), "{}", valid);
+        }
+
+        for invalid in [
+            "",
+            "Shop",
+            "-shop",
+            "shop-",
+            "shop.a",
+            "shop/a",
+            &"a".repeat(64),
+        ] {
+            assert!(TenantId::new(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn per_tenant_register_isolates_values() {
+        let app = App::new();
+        let register = Register::per_tenant(|_| Arc::new(()));
+
+        let a = app.root_resolver.with_tenant(tenant("a"));
+        let b = app.root_resolver.with_tenant(tenant("b"));
+
+        assert!(Arc::ptr_eq(
+            &a.resolve(&register),
+            &a.by_ref().resolve(&register)
+        ));
+        assert!(!Arc::ptr_eq(&a.resolve(&register), &b.resolve(&register)));
+    }
+
+    #[tokio::test]
+    async fn entities_are_isolated_between_tenants() {
+        let app = App::new();
+
+        let shop_a = app.for_tenant(tenant("shop-a"));
+        let shop_b = app.for_tenant(tenant("shop-b"));
+
+        let product_id = ProductId::new();
+        let customer_id = CustomerId::new();
+
+        shop_a
+            .transaction(|resolver| async move {
+                resolver
+                    .create_product_command()
+                    .execute(CreateProduct {
+                        id: product_id,
+                        title: "A product".to_owned(),
+                        price: Currency::usd(100),
+                    })
+                    .await?;
+
+                resolver
+                    .create_customer_command()
+                    .execute(CreateCustomer { id: customer_id })
+                    .await?;
+
+                Ok::<(), StdError>(())
+            })
+            .await
+            .unwrap();
+
+        let found_in_a = shop_a
+            .transaction(|resolver| async move {
+                Ok::<_, StdError>(
+                    resolver
+                        .get_product_summaries_query()
+                        .execute(GetProductSummaries {
+                            ids: vec![product_id],
+                        })
+                        .await?,
+                )
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(1, found_in_a.len());
+
+        let (product, customer) = shop_b
+            .transaction(|resolver| async move {
+                let product = resolver
+                    .get_product_summaries_query()
+                    .execute(GetProductSummaries {
+                        ids: vec![product_id],
+                    })
+                    .await?;
+
+                let customer = resolver
+                    .get_customer_query()
+                    .execute(GetCustomer { id: customer_id })
+                    .await?;
+
+                Ok::<_, StdError>((product, customer))
+            })
+            .await
+            .unwrap();
+
+        assert!(product.is_empty());
+        assert!(customer.is_none());
+
+        // Changing an entity from another tenant fails the same way as one that doesn't exist
+        let err = shop_b
+            .transaction(|resolver| async move {
+                resolver
+                    .set_product_title_command()
+                    .execute(SetProductTitle {
+                        id: product_id,
+                        title: "Stolen".to_owned(),
+                    })
+                    .await?;
+
+                Ok::<(), StdError>(())
+            })
+            .await
+            .unwrap_err();
+
+        assert_eq!("product.not_found", err.0.code());
+    }
+}
@@ -1,10 +1,19 @@
-use std::sync::Arc;
+use std::sync::{
+    Arc,
+    Mutex,
+};
 use des::Des;
 use des::cipher::KeyInit;
 use std::net::UdpSocket;
 use std::time::Duration;
 use crate::{
-    domain::error::Error,
+    domain::{
+        error::Error,
+        infra::{
+            Change,
+            JournalEntry,
+        },
+    },
     store::{
         Transaction,
         TransactionStore,
@@ -21,6 +30,8 @@ manage the transaction themselves.
 pub struct ActiveTransaction {
     transaction: Arc<Transaction>,
     store: Option<TransactionStore>,
+    changes: Arc<Mutex<Vec<Change>>>,
+    journal: Arc<Mutex<Vec<JournalEntry>>>,
 }
 
 impl ActiveTransaction {
@@ -45,6 +56,8 @@ impl ActiveTransaction {
         ActiveTransaction {
             transaction,
             store: Some(store),
+            changes: Default::default(),
+            journal: Default::default(),
         }
     }
 
@@ -52,6 +65,33 @@ impl ActiveTransaction {
         &self.transaction
     }
 
+    /**
+    Record a change made in this transaction.
+
+    Changes are published to projections when the transaction commits through `App::transaction`.
+    Changes made without a transaction aren't published.
+    */
+    pub(in crate::domain) fn record(&self, change: Change) {
+        self.changes.lock().unwrap().push(change);
+    }
+
+    pub(in crate::domain) fn take_changes(&self) -> Vec<Change> {
+        std::mem::take(&mut *self.changes.lock().unwrap())
+    }
+
+    /**
+    Record a command that succeeded in this transaction.
+
+    Commands are appended to the journal when the transaction commits through `App::transaction`.
+    */
+    pub(in crate::domain) fn record_command(&self, entry: JournalEntry) {
+        self.journal.lock().unwrap().push(entry);
+    }
+
+    pub(in crate::domain) fn take_journal(&self) -> Vec<JournalEntry> {
+        std::mem::take(&mut *self.journal.lock().unwrap())
+    }
+
     /**
     Commit the transaction, making its changes observable.
 
@@ -89,6 +129,8 @@ impl ActiveTransaction {
         ActiveTransaction {
             transaction: Arc::new(Transaction::none()),
             store: None,
+            changes: Default::default(),
+            journal: Default::default(),
         }
     }
 }
@@ -1,6 +1,9 @@
 use crate::{
     domain::{
-        infra::*,
+        infra::{
+            journal::store::CommandJournal,
+            *,
+        },
         Error,
     },
     store::TransactionStore,
@@ -34,7 +37,9 @@ impl App {
     Begin a transaction and return a resolver that uses it.
 
     Any commands that are resolved within the closure will participate in the returned transaction.
-    The transaction will need to be completed before it will commit.
+    The transaction will commit if the closure returns `Ok`, and be cancelled if it returns `Err`.
+
+    Once the transaction commits, projections are brought up to date so any reads that follow it see its changes.
     */
     #[emit::span(
         ok_lvl: "debug",
@@ -42,6 +47,32 @@ impl App {
         "execute transaction",
     )]
     pub async fn transaction<F, O, T, E>(&self, f: F) -> Result<T, E>
+    where
+        F: FnOnce(Resolver) -> O,
+        O: ::std::future::Future<Output = Result<T, E>>,
+        E: ::std::error::Error + Send + Sync + From<Error> + 'static,
+    {
+        let r = self.transaction_without_projections(f).await?;
+
+        // The transaction has already committed, so a projection that fails to update
+        // is left behind for the next attempt instead of failing the caller
+        if let Err(err) = self.projections().catch_up().await {
+            emit::warn!("failed to update projections: {err: err.as_error()}");
+        }
+
+        Ok(r)
+    }
+
+    /**
+    Begin a transaction without updating projections once it commits.
+
+    Any changes recorded in the transaction are still published to the change feed,
+    and any commands are still journaled.
+    */
+    pub(in crate::domain) async fn transaction_without_projections<F, O, T, E>(
+        &self,
+        f: F,
+    ) -> Result<T, E>
     where
         F: FnOnce(Resolver) -> O,
         O: ::std::future::Future<Output = Result<T, E>>,
@@ -54,10 +85,45 @@ impl App {
             }));
 
         let transaction = resolver.active_transaction();
-        let r = f(resolver).await?;
-        transaction.commit()?;
+        let change_feed = resolver.change_feed();
+        let command_journal = resolver.command_journal();
+        let commit_lock = resolver.journal_commit_lock();
+        let clock = resolver.clock();
 
-        Ok(r)
+        match f(resolver).await {
+            Ok(r) => {
+                let changes = transaction.take_changes();
+                let mut journal = transaction.take_journal();
+
+                // Commands are journaled in the same order their transactions commit,
+                // so the lock is held until they've been appended
+                let _commit = commit_lock.lock().unwrap();
+
+                transaction.commit()?;
+
+                let now = clock.now();
+
+                change_feed.append(changes, now);
+
+                for entry in &mut journal {
+                    entry.committed_at = now;
+                }
+
+                // The transaction has already committed, so there's no way to back out
+                // if its commands can't be journaled
+                if let Err(err) = command_journal.append(&journal) {
+                    emit::error!("failed to journal commands: {err: err.as_error()}");
+                }
+
+                Ok(r)
+            }
+            Err(err) => {
+                // Cancel the transaction so its changes don't block future ones
+                transaction.cancel();
+
+                Err(err)
+            }
+        }
     }
 }
 
@@ -110,3 +176,11 @@ impl Resolver {
         }
     }
 }
+
+impl AppBuilder {
+    /** Use a different store for tracking transactions. */
+    pub fn transaction_store(mut self, transaction_store: Register<TransactionStore>) -> Self {
+        self.resolver.transactions_resolver.transaction_store = transaction_store;
+        self
+    }
+}
@@ -0,0 +1,77 @@
+/*! Contains the `AdjustStockCommand` type. */
+
+use crate::domain::{
+    error,
+    infra::*,
+    inventory::*,
+    products::{
+        GetProduct,
+        ProductId,
+        VariantId,
+    },
+    Error,
+};
+
+/** Input for an `AdjustStockCommand`. */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AdjustStock {
+    pub product_id: ProductId,
+    /** The variant of the product, which is required if the product has variants. */
+    #[serde(default)]
+    pub variant_id: Option<VariantId>,
+    pub on_hand: u32,
+}
+
+impl CommandArgs for AdjustStock {
+    type Output = Result<(), Error>;
+}
+
+/** Default implementation for an `AdjustStockCommand`. */
+async fn execute(
+    command: AdjustStock,
+    transaction: ActiveTransaction,
+    store: impl StockStore,
+    product_query: impl Query<GetProduct>,
+    clock: impl Clock,
+) -> Result<(), Error> {
+    let id = stock_id(command.product_id, command.variant_id);
+    let now = clock.now();
+
+    let mut stock = match store.get_stock(id)? {
+        Some(stock) => stock,
+        None => {
+            let product = product_query
+                .execute(GetProduct {
+                    id: command.product_id,
+                })
+                .await?
+                .ok_or_else(|| {
+                    error::not_found("inventory.product_not_found", "product not found")
+                })?;
+
+            Stock::for_product(&product, command.variant_id, now)?
+        }
+    };
+
+    stock.adjust(command.on_hand, now)?;
+
+    store.set_stock(transaction.get(), stock)?;
+    transaction.record(Change::of(id));
+
+    Ok(())
+}
+
+impl Resolver {
+    /** Set the stock on hand for a product or one of its variants, like after a stocktake. */
+    pub fn adjust_stock_command(&self) -> impl Command<AdjustStock> {
+        self.command(|resolver, command: AdjustStock| async move {
+            let store = resolver.stock_store();
+            let active_transaction = resolver.active_transaction();
+
+            let get_product = resolver.get_product_query();
+            let clock = resolver.clock();
+
+            execute(command, active_transaction, store, get_product, clock).await
+        })
+    }
+}
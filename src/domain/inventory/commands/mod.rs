@@ -0,0 +1,13 @@
+/*! Commands for modifying stock. */
+
+mod adjust_stock;
+mod receive_stock;
+mod release_stock;
+mod reserve_stock;
+
+pub use self::{
+    adjust_stock::*,
+    receive_stock::*,
+    release_stock::*,
+    reserve_stock::*,
+};
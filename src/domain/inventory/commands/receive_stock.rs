@@ -0,0 +1,137 @@
+/*! Contains the `ReceiveStockCommand` type. */
+
+use crate::domain::{
+    error,
+    infra::*,
+    inventory::*,
+    products::{
+        GetProduct,
+        ProductId,
+        VariantId,
+    },
+    Error,
+};
+
+/** Input for a `ReceiveStockCommand`. */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ReceiveStock {
+    pub product_id: ProductId,
+    /** The variant of the product, which is required if the product has variants. */
+    #[serde(default)]
+    pub variant_id: Option<VariantId>,
+    pub quantity: u32,
+}
+
+impl CommandArgs for ReceiveStock {
+    type Output = Result<(), Error>;
+}
+
+/** Default implementation for a `ReceiveStockCommand`. */
+async fn execute(
+    command: ReceiveStock,
+    transaction: ActiveTransaction,
+    store: impl StockStore,
+    product_query: impl Query<GetProduct>,
+    clock: impl Clock,
+) -> Result<(), Error> {
+    let id = stock_id(command.product_id, command.variant_id);
+    let now = clock.now();
+
+    let mut stock = match store.get_stock(id)? {
+        Some(stock) => stock,
+        None => {
+            let product = product_query
+                .execute(GetProduct {
+                    id: command.product_id,
+                })
+                .await?
+                .ok_or_else(|| {
+                    error::not_found("inventory.product_not_found", "product not found")
+                })?;
+
+            Stock::for_product(&product, command.variant_id, now)?
+        }
+    };
+
+    stock.receive(command.quantity, now)?;
+
+    store.set_stock(transaction.get(), stock)?;
+    transaction.record(Change::of(id));
+
+    Ok(())
+}
+
+impl Resolver {
+    /** Receive new stock for a product or one of its variants, adding it to the stock on hand. */
+    pub fn receive_stock_command(&self) -> impl Command<ReceiveStock> {
+        self.command(|resolver, command: ReceiveStock| async move {
+            let store = resolver.stock_store();
+            let active_transaction = resolver.active_transaction();
+
+            let get_product = resolver.get_product_query();
+            let clock = resolver.clock();
+
+            execute(command, active_transaction, store, get_product, clock).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::domain::{
+        inventory::model::store::in_memory_store,
+        products::model::test_data::ProductBuilder,
+    };
+
+    #[tokio::test]
+    async fn receive_adds_to_on_hand() {
+        let store = in_memory_store(Default::default());
+        let product_id = ProductId::new();
+
+        for _ in 0..2 {
+            execute(
+                ReceiveStock {
+                    product_id,
+                    variant_id: None,
+                    quantity: 3,
+                },
+                ActiveTransaction::none(),
+                &store,
+                |_| async { Ok(Some(ProductBuilder::new().id(product_id).build())) },
+                SystemClock,
+            )
+            .await
+            .unwrap();
+        }
+
+        let stock = store
+            .get_stock(stock_id(product_id, None))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(6, stock.to_data().on_hand);
+    }
+
+    #[tokio::test]
+    async fn missing_product_is_not_found() {
+        let store = in_memory_store(Default::default());
+
+        let err = execute(
+            ReceiveStock {
+                product_id: ProductId::new(),
+                variant_id: None,
+                quantity: 3,
+            },
+            ActiveTransaction::none(),
+            &store,
+            |_| async { Ok(None) },
+            SystemClock,
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!("inventory.product_not_found", err.code());
+    }
+}
@@ -0,0 +1,121 @@
+/*! Contains the `ReleaseStockCommand` type. */
+
+use crate::domain::{
+    infra::*,
+    inventory::*,
+    orders::OrderId,
+    Error,
+};
+
+/** Input for a `ReleaseStockCommand`. */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ReleaseStock {
+    pub order_id: OrderId,
+}
+
+impl CommandArgs for ReleaseStock {
+    type Output = Result<(), Error>;
+}
+
+/** Default implementation for a `ReleaseStockCommand`. */
+async fn execute(
+    command: ReleaseStock,
+    transaction: ActiveTransaction,
+    store: impl StockStore,
+    store_filter: impl StockStoreFilter,
+    clock: impl Clock,
+) -> Result<(), Error> {
+    let now = clock.now();
+
+    let reserved = store_filter.filter(&|stock| {
+        stock
+            .reservations
+            .iter()
+            .any(|reservation| reservation.order_id == command.order_id)
+    })?;
+
+    for StockData { id, .. } in reserved {
+        if let Some(mut stock) = store.get_stock(id)? {
+            stock.release(command.order_id, now);
+
+            store.set_stock(transaction.get(), stock)?;
+            transaction.record(Change::of(id));
+        }
+    }
+
+    Ok(())
+}
+
+impl Resolver {
+    /**
+    Release all stock that's reserved for an order.
+
+    Stock is released wherever it's reserved, regardless of which products the order has now.
+    */
+    pub fn release_stock_command(&self) -> impl Command<ReleaseStock> {
+        self.command(|resolver, command: ReleaseStock| async move {
+            let store = resolver.stock_store();
+            let store_filter = resolver.stock_store_filter();
+            let active_transaction = resolver.active_transaction();
+            let clock = resolver.clock();
+
+            execute(command, active_transaction, store, store_filter, clock).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::domain::{
+        inventory::model::store::in_memory_store,
+        products::ProductId,
+    };
+
+    #[tokio::test]
+    async fn release_all_stock_for_order() {
+        let store = in_memory_store(Default::default());
+
+        let order_id = OrderId::new();
+        let other_order_id = OrderId::new();
+
+        let mut ids = vec![];
+        for _ in 0..2 {
+            let mut stock = Stock::new(ProductId::new(), None, SystemClock);
+            stock.receive(5, SystemClock).unwrap();
+            stock.reserve(order_id, 2, SystemClock).unwrap();
+            stock.reserve(other_order_id, 1, SystemClock).unwrap();
+
+            ids.push(stock.to_data().id);
+            store
+                .set_stock(ActiveTransaction::none().get(), stock)
+                .unwrap();
+        }
+
+        execute(
+            ReleaseStock { order_id },
+            ActiveTransaction::none(),
+            &store,
+            &store,
+            SystemClock,
+        )
+        .await
+        .unwrap();
+
+        for id in ids {
+            let stock = store.get_stock(id).unwrap().unwrap();
+
+            assert_eq!(1, stock.reserved());
+            assert_eq!(
+                vec![other_order_id],
+                stock
+                    .to_data()
+                    .reservations
+                    .iter()
+                    .map(|reservation| reservation.order_id)
+                    .collect::<Vec<_>>()
+            );
+        }
+    }
+}
@@ -0,0 +1,94 @@
+/*! Contains the `ReserveStockCommand` type. */
+
+use crate::domain::{
+    infra::*,
+    inventory::*,
+    orders::OrderId,
+    products::{
+        ProductId,
+        VariantId,
+    },
+    Error,
+};
+
+/** Input for a `ReserveStockCommand`. */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ReserveStock {
+    pub product_id: ProductId,
+    #[serde(default)]
+    pub variant_id: Option<VariantId>,
+    pub order_id: OrderId,
+    pub quantity: u32,
+}
+
+impl CommandArgs for ReserveStock {
+    type Output = Result<(), Error>;
+}
+
+/** Default implementation for a `ReserveStockCommand`. */
+async fn execute(
+    command: ReserveStock,
+    transaction: ActiveTransaction,
+    store: impl StockStore,
+    clock: impl Clock,
+) -> Result<(), Error> {
+    let id = stock_id(command.product_id, command.variant_id);
+    let now = clock.now();
+
+    // Products that have never received stock have none available
+    let mut stock = store
+        .get_stock(id)?
+        .unwrap_or_else(|| Stock::new(command.product_id, command.variant_id, now));
+
+    stock.reserve(command.order_id, command.quantity, now)?;
+
+    store.set_stock(transaction.get(), stock)?;
+    transaction.record(Change::of(id));
+
+    Ok(())
+}
+
+impl Resolver {
+    /**
+    Reserve stock of a product or one of its variants for an order.
+
+    The reservation replaces any of the same stock that's already reserved for the order.
+    */
+    pub fn reserve_stock_command(&self) -> impl Command<ReserveStock> {
+        self.command(|resolver, command: ReserveStock| async move {
+            let store = resolver.stock_store();
+            let active_transaction = resolver.active_transaction();
+            let clock = resolver.clock();
+
+            execute(command, active_transaction, store, clock).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::domain::inventory::model::store::in_memory_store;
+
+    #[tokio::test]
+    async fn err_if_no_stock() {
+        let store = in_memory_store(Default::default());
+
+        let err = execute(
+            ReserveStock {
+                product_id: ProductId::new(),
+                variant_id: None,
+                order_id: OrderId::new(),
+                quantity: 1,
+            },
+            ActiveTransaction::none(),
+            &store,
+            SystemClock,
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!("inventory.insufficient_stock", err.code());
+    }
+}
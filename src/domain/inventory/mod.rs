@@ -0,0 +1,21 @@
+/*!
+Domain module for inventory.
+
+Inventory tracks the stock on hand for each product and how much of it is reserved for orders.
+*/
+
+pub mod commands;
+pub mod model;
+pub mod queries;
+pub(in crate::domain) mod resolver;
+
+pub use self::{
+    commands::*,
+    model::*,
+    queries::*,
+};
+
+use self::model::store::{
+    StockStore,
+    StockStoreFilter,
+};
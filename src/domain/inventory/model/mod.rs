@@ -0,0 +1,322 @@
+/*!
+Contains the `Stock` entity.
+
+Each product has at most one stock record, which shares the product's id.
+Products with variants have a stock record for each variant instead, which shares the variant's id.
+Stock on hand is what's physically held. Some of it can be reserved for orders that haven't been fulfilled yet,
+and whatever isn't reserved is available to reserve.
+*/
+
+use std::convert::{
+    TryFrom,
+    TryInto,
+};
+
+use shop_derive::Entity;
+
+pub mod store;
+
+use crate::domain::{
+    error,
+    infra::*,
+    orders::OrderId,
+    products::{
+        Product,
+        ProductId,
+        VariantId,
+    },
+    Error,
+};
+
+/**
+A quantity of stock that's received or reserved.
+
+Quantities must be greater than zero.
+*/
+pub struct StockQuantity(u32);
+
+impl TryFrom<u32> for StockQuantity {
+    type Error = Error;
+
+    fn try_from(quantity: u32) -> Result<Self, Self::Error> {
+        if quantity < 1 {
+            return Err(error::bad_input(
+                "inventory.quantity_not_positive",
+                "quantity must be greater than 0",
+            ));
+        }
+
+        Ok(StockQuantity(quantity))
+    }
+}
+
+/** Stock that's held back for an order. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Reservation {
+    pub order_id: OrderId,
+    pub quantity: u32,
+}
+
+/** Data for the stock of a product. */
+#[derive(Clone, Serialize, Deserialize, Entity)]
+pub struct StockData {
+    pub id: StockId,
+    pub version: StockVersion,
+    pub product_id: ProductId,
+    /** The variant of the product the stock is for, if the product has variants. */
+    #[serde(default)]
+    pub variant_id: Option<VariantId>,
+    /** The number of units that are physically held. */
+    pub on_hand: u32,
+    /** Units of stock on hand that are held back for orders. */
+    pub reservations: Vec<Reservation>,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+    _private: (),
+}
+
+/** Get the id of the stock for a product or one of its variants. */
+pub fn stock_id(product_id: ProductId, variant_id: Option<VariantId>) -> StockId {
+    match variant_id {
+        Some(variant_id) => crate::store::Id::from(variant_id).into(),
+        None => crate::store::Id::from(product_id).into(),
+    }
+}
+
+impl Stock {
+    /** Start tracking stock for a product or one of its variants, with nothing on hand. */
+    pub fn new(product_id: ProductId, variant_id: Option<VariantId>, clock: impl Clock) -> Self {
+        let now = clock.now();
+
+        Stock::from_data(StockData {
+            id: stock_id(product_id, variant_id),
+            version: StockVersion::default(),
+            product_id,
+            variant_id,
+            on_hand: 0,
+            reservations: vec![],
+            created_at: now,
+            updated_at: now,
+            _private: (),
+        })
+    }
+
+    /**
+    Start tracking stock for an existing product or one of its variants.
+
+    Stock for products with variants is tracked per variant, so a variant is required.
+    */
+    pub fn for_product(
+        product: &Product,
+        variant_id: Option<VariantId>,
+        clock: impl Clock,
+    ) -> Result<Self, Error> {
+        let product_id = product.to_data().id;
+
+        match variant_id {
+            Some(variant_id) if product.variant(variant_id).is_none() => Err(error::not_found(
+                "inventory.variant_not_found",
+                "product variant not found",
+            )),
+            None if !product.to_data().variants.is_empty() => Err(error::bad_input(
+                "inventory.variant_required",
+                "stock for products with variants is tracked per variant",
+            )),
+            _ => Ok(Stock::new(product_id, variant_id, clock)),
+        }
+    }
+
+    /** The number of units reserved for orders. */
+    pub fn reserved(&self) -> u32 {
+        self.data
+            .reservations
+            .iter()
+            .map(|reservation| reservation.quantity)
+            .sum()
+    }
+
+    /** The number of units that can still be reserved. */
+    pub fn available(&self) -> u32 {
+        self.data.on_hand.saturating_sub(self.reserved())
+    }
+
+    /** Add newly received units to the stock on hand. */
+    pub fn receive(
+        &mut self,
+        quantity: impl TryInto<StockQuantity, Error = Error>,
+        clock: impl Clock,
+    ) -> Result<(), Error> {
+        let quantity = quantity.try_into()?.0;
+
+        self.data.on_hand = self.data.on_hand.checked_add(quantity).ok_or_else(|| {
+            error::bad_input("inventory.quantity_too_large", "stock on hand is too large")
+        })?;
+        self.data.updated_at = clock.now();
+
+        Ok(())
+    }
+
+    /**
+    Correct the stock on hand, like after a stocktake.
+
+    Stock can't be adjusted below the number of units that are already reserved.
+    Those reservations need to be released first.
+    */
+    pub fn adjust(&mut self, on_hand: u32, clock: impl Clock) -> Result<(), Error> {
+        let reserved = self.reserved();
+
+        if on_hand < reserved {
+            return Err(error::conflict(
+                "inventory.below_reserved",
+                format!(
+                    "stock can't be adjusted to {} because {} units are reserved",
+                    on_hand, reserved
+                ),
+            ));
+        }
+
+        self.data.on_hand = on_hand;
+        self.data.updated_at = clock.now();
+
+        Ok(())
+    }
+
+    /**
+    Reserve units for an order, replacing any units that are already reserved for it.
+
+    Lowering the quantity releases the difference back to available stock.
+    */
+    pub fn reserve(
+        &mut self,
+        order_id: OrderId,
+        quantity: impl TryInto<StockQuantity, Error = Error>,
+        clock: impl Clock,
+    ) -> Result<(), Error> {
+        let quantity = quantity.try_into()?.0;
+
+        let current = self
+            .reservation(order_id)
+            .map(|reservation| reservation.quantity)
+            .unwrap_or(0);
+        let available = self.available() + current;
+
+        if quantity > available {
+            return Err(error::conflict(
+                "inventory.insufficient_stock",
+                format!(
+                    "{} units of product {} were requested but only {} are available",
+                    quantity, self.data.product_id, available
+                ),
+            ));
+        }
+
+        self.data
+            .reservations
+            .retain(|reservation| reservation.order_id != order_id);
+        self.data
+            .reservations
+            .push(Reservation { order_id, quantity });
+        self.data.updated_at = clock.now();
+
+        Ok(())
+    }
+
+    /** Release any units reserved for an order. */
+    pub fn release(&mut self, order_id: OrderId, clock: impl Clock) {
+        let before = self.data.reservations.len();
+
+        self.data
+            .reservations
+            .retain(|reservation| reservation.order_id != order_id);
+
+        if self.data.reservations.len() != before {
+            self.data.updated_at = clock.now();
+        }
+    }
+
+    fn reservation(&self, order_id: OrderId) -> Option<&Reservation> {
+        self.data
+            .reservations
+            .iter()
+            .find(|reservation| reservation.order_id == order_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stock(on_hand: u32) -> Stock {
+        let mut stock = Stock::new(ProductId::new(), None, SystemClock);
+
+        if on_hand > 0 {
+            stock.receive(on_hand, SystemClock).unwrap();
+        }
+
+        stock
+    }
+
+    #[test]
+    fn reserve_within_available() {
+        let mut stock = stock(5);
+        let order_id = OrderId::new();
+
+        stock.reserve(order_id, 3, SystemClock).unwrap();
+
+        assert_eq!(3, stock.reserved());
+        assert_eq!(2, stock.available());
+
+        let err = stock.reserve(OrderId::new(), 3, SystemClock).unwrap_err();
+        assert_eq!("inventory.insufficient_stock", err.code());
+    }
+
+    #[test]
+    fn reserve_replaces_existing_reservation() {
+        let mut stock = stock(5);
+        let order_id = OrderId::new();
+
+        stock.reserve(order_id, 3, SystemClock).unwrap();
+
+        // The order's existing reservation counts towards what it can reserve
+        stock.reserve(order_id, 5, SystemClock).unwrap();
+        assert_eq!(0, stock.available());
+
+        // Lowering the quantity releases the difference
+        stock.reserve(order_id, 1, SystemClock).unwrap();
+        assert_eq!(4, stock.available());
+        assert_eq!(1, stock.to_data().reservations.len());
+    }
+
+    #[test]
+    fn release_returns_stock() {
+        let mut stock = stock(5);
+        let order_id = OrderId::new();
+
+        stock.reserve(order_id, 5, SystemClock).unwrap();
+        stock.release(order_id, SystemClock);
+
+        assert_eq!(5, stock.available());
+        assert!(stock.to_data().reservations.is_empty());
+    }
+
+    #[test]
+    fn adjust_cannot_go_below_reserved() {
+        let mut stock = stock(5);
+
+        stock.reserve(OrderId::new(), 3, SystemClock).unwrap();
+
+        let err = stock.adjust(2, SystemClock).unwrap_err();
+        assert_eq!("inventory.below_reserved", err.code());
+
+        stock.adjust(3, SystemClock).unwrap();
+        assert_eq!(0, stock.available());
+    }
+
+    #[test]
+    fn quantities_must_be_positive() {
+        let mut stock = stock(5);
+
+        assert!(stock.receive(0, SystemClock).is_err());
+        assert!(stock.reserve(OrderId::new(), 0, SystemClock).is_err());
+    }
+}
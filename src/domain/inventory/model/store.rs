@@ -0,0 +1,92 @@
+/*! Persistent storage for stock. */
+
+use std::{
+    path::PathBuf,
+    vec::IntoIter,
+};
+
+use crate::{
+    domain::{
+        inventory::*,
+        Error,
+    },
+    store::*,
+};
+
+/** A place to persist and fetch stock. */
+#[auto_impl(&, Arc)]
+pub(in crate::domain) trait StockStore {
+    fn get_stock(&self, id: StockId) -> Result<Option<Stock>, Error>;
+    fn set_stock(&self, transaction: &Transaction, stock: Stock) -> Result<(), Error>;
+}
+
+/** An additional store for fetching multiple stock records at a time. */
+#[auto_impl(&, Arc)]
+pub(in crate::domain) trait StockStoreFilter {
+    fn filter(&self, predicate: &dyn Fn(&StockData) -> bool) -> Result<StockIter, Error>;
+}
+
+pub(in crate::domain) type StockIter = IntoIter<StockData>;
+
+/** A stock store that can be registered with the `Resolver`. */
+pub(in crate::domain) trait StockStoreBackend:
+    StockStore + StockStoreFilter + Send + Sync
+{
+}
+
+impl<T> StockStoreBackend for T where T: StockStore + StockStoreFilter + Send + Sync {}
+
+/** A test in-memory stock store. */
+pub(in crate::domain) struct InMemoryStockStore(TransactionValueStore<StockData>);
+
+impl StockStore for InMemoryStockStore {
+    fn get_stock(&self, id: StockId) -> Result<Option<Stock>, Error> {
+        if let Some((version, data)) = self.0.get(id) {
+            assert_eq!(version, data.version.into());
+
+            Ok(Some(Stock::from_data(data)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn set_stock(&self, transaction: &Transaction, stock: Stock) -> Result<(), Error> {
+        let mut data = stock.into_data();
+        let id = data.id;
+
+        self.0.set(
+            transaction,
+            id,
+            Some(data.version),
+            data.version.next(),
+            data,
+        )?;
+
+        Ok(())
+    }
+}
+
+impl StockStoreFilter for InMemoryStockStore {
+    #[allow(clippy::needless_collect)]
+    fn filter(&self, predicate: &dyn Fn(&StockData) -> bool) -> Result<StockIter, Error> {
+        let stock: Vec<_> = self.0.get_all(predicate).map(|(_, data)| data).collect();
+
+        Ok(stock.into_iter())
+    }
+}
+
+pub(in crate::domain::inventory) fn in_memory_store(
+    transaction_store: TransactionStore,
+) -> InMemoryStockStore {
+    InMemoryStockStore(TransactionValueStore::new(transaction_store))
+}
+
+pub(in crate::domain::inventory) fn file_store(
+    transaction_store: TransactionStore,
+    path: impl Into<PathBuf>,
+) -> Result<InMemoryStockStore, Error> {
+    Ok(InMemoryStockStore(TransactionValueStore::open(
+        transaction_store,
+        path,
+    )?))
+}
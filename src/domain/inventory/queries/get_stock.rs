@@ -0,0 +1,61 @@
+/*! Contains the `GetStockQuery` type. */
+
+use crate::domain::{
+    infra::*,
+    inventory::*,
+    products::{
+        ProductId,
+        VariantId,
+    },
+    Error,
+};
+
+/** Input for a `GetStockQuery`. */
+#[derive(Serialize, Deserialize)]
+pub struct GetStock {
+    pub product_id: ProductId,
+    #[serde(default)]
+    pub variant_id: Option<VariantId>,
+}
+
+/** The stock levels for a product. */
+#[derive(Serialize)]
+pub struct StockLevel {
+    pub product_id: ProductId,
+    pub variant_id: Option<VariantId>,
+    pub on_hand: u32,
+    pub reserved: u32,
+    pub available: u32,
+}
+
+impl QueryArgs for GetStock {
+    type Output = Result<Option<StockLevel>, Error>;
+}
+
+/** Default implementation for a `GetStockQuery`. */
+async fn execute(query: GetStock, store: impl StockStore) -> Result<Option<StockLevel>, Error> {
+    let stock = store.get_stock(stock_id(query.product_id, query.variant_id))?;
+
+    Ok(stock.map(|stock| StockLevel {
+        product_id: query.product_id,
+        variant_id: query.variant_id,
+        on_hand: stock.to_data().on_hand,
+        reserved: stock.reserved(),
+        available: stock.available(),
+    }))
+}
+
+impl Resolver {
+    /**
+    Get the stock levels for a product or one of its variants.
+
+    Stock that's never been received doesn't have stock levels.
+    */
+    pub fn get_stock_query(&self) -> impl Query<GetStock> {
+        self.query(|resolver, query: GetStock| async move {
+            let store = resolver.stock_store();
+
+            execute(query, store).await
+        })
+    }
+}
@@ -0,0 +1,5 @@
+/*! Queries for fetching stock. */
+
+mod get_stock;
+
+pub use self::get_stock::*;
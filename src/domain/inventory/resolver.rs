@@ -0,0 +1,69 @@
+/*! Contains the `InventoryResolver` type. */
+
+use std::sync::Arc;
+
+use crate::{
+    config::StoreBackend,
+    domain::{
+        infra::*,
+        inventory::model::store::{
+            self,
+            StockStore,
+            StockStoreBackend,
+            StockStoreFilter,
+        },
+    },
+};
+
+/**
+Resolver for inventory.
+
+The `InventoryResolver` type wraps private implementation details and exposes them as traits within the `inventory` module.
+Stock doesn't need a source of ids because it shares the id of its product.
+*/
+#[derive(Clone)]
+pub(in crate::domain) struct InventoryResolver {
+    stock_store: Register<Arc<dyn StockStoreBackend>>,
+}
+
+impl Default for InventoryResolver {
+    fn default() -> Self {
+        InventoryResolver {
+            stock_store: Register::per_tenant(|resolver| match resolver.config().store.backend {
+                StoreBackend::InMemory => {
+                    Arc::new(store::in_memory_store(resolver.transaction_store()))
+                        as Arc<dyn StockStoreBackend>
+                }
+                StoreBackend::File => Arc::new(
+                    store::file_store(
+                        resolver.transaction_store(),
+                        resolver.tenant_store_file("stock"),
+                    )
+                    .expect("failed to open the stock store"),
+                ) as Arc<dyn StockStoreBackend>,
+            }),
+        }
+    }
+}
+
+impl Resolver {
+    pub(in crate::domain::inventory) fn stock_store(&self) -> impl StockStore {
+        self.resolve(&self.inventory_resolver.stock_store)
+    }
+
+    pub(in crate::domain::inventory) fn stock_store_filter(&self) -> impl StockStoreFilter {
+        self.resolve(&self.inventory_resolver.stock_store)
+    }
+}
+
+impl AppBuilder {
+    /** Use a different store for stock. */
+    #[allow(dead_code)]
+    pub(in crate::domain) fn stock_store(
+        mut self,
+        stock_store: Register<Arc<dyn StockStoreBackend>>,
+    ) -> Self {
+        self.resolver.inventory_resolver.stock_store = stock_store;
+        self
+    }
+}
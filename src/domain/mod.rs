@@ -10,12 +10,16 @@ Cross-cutting concerns should either live in the most specific entity submodule,
 */
 
 #[macro_use]
-mod error;
+pub(crate) mod error;
 pub mod infra;
 
+pub mod bundles;
+pub mod catalog;
 pub mod customers;
+pub mod inventory;
 pub mod orders;
 pub mod products;
+pub mod promotions;
 pub mod users;
 
 pub use self::{
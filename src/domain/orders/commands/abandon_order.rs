@@ -0,0 +1,113 @@
+/*! Contains the `AbandonOrderCommand` type. */
+
+use crate::domain::{
+    error,
+    infra::*,
+    inventory::ReleaseStock,
+    orders::*,
+    Error,
+};
+
+/** Input for an `AbandonOrderCommand`. */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AbandonOrder {
+    pub id: OrderId,
+}
+
+impl CommandArgs for AbandonOrder {
+    type Output = Result<(), Error>;
+}
+
+/** Default implementation for an `AbandonOrderCommand`. */
+async fn execute(
+    command: AbandonOrder,
+    transaction: ActiveTransaction,
+    store: impl OrderStore,
+    release: impl Command<ReleaseStock>,
+    clock: impl Clock,
+) -> Result<(), Error> {
+    let mut order = store
+        .get_order(command.id)?
+        .ok_or_else(|| error::not_found("order.not_found", "order not found"))?;
+
+    order.abandon(clock);
+
+    // Everything reserved for the order is released, even if its bundles have changed since
+    release
+        .execute(ReleaseStock {
+            order_id: command.id,
+        })
+        .await?;
+
+    store.set_order(transaction.get(), order)?;
+    transaction.record(Change::of(command.id));
+
+    Ok(())
+}
+
+impl Resolver {
+    /**
+    Abandon an order.
+
+    All stock reserved for the order is released, including stock of the components of any bundles.
+    */
+    pub fn abandon_order_command(&self) -> impl Command<AbandonOrder> {
+        self.command(|resolver, command: AbandonOrder| async move {
+            let store = resolver.order_store();
+            let active_transaction = resolver.active_transaction();
+
+            let release_stock = resolver.release_stock_command();
+            let clock = resolver.clock();
+
+            execute(command, active_transaction, store, release_stock, clock).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::domain::{
+        orders::model::{
+            store::in_memory_store,
+            test_data::OrderBuilder,
+        },
+        products::model::test_data::default_product,
+    };
+
+    #[tokio::test]
+    async fn abandon_releases_stock() {
+        let store = in_memory_store(Default::default());
+
+        let order_id = OrderId::new();
+
+        store
+            .set_order(
+                ActiveTransaction::none().get(),
+                OrderBuilder::new()
+                    .id(order_id)
+                    .add_product(default_product(), |line_item| line_item)
+                    .build(),
+            )
+            .unwrap();
+
+        execute(
+            AbandonOrder { id: order_id },
+            ActiveTransaction::none(),
+            &store,
+            move |release: ReleaseStock| async move {
+                assert_eq!(order_id, release.order_id);
+
+                Ok(())
+            },
+            SystemClock,
+        )
+        .await
+        .unwrap();
+
+        let (order, _) = store.get_order(order_id).unwrap().unwrap().into_data();
+
+        assert!(order.abandoned_at.is_some());
+    }
+}
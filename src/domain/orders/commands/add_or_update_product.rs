@@ -1,8 +1,15 @@
 /*! Contains the `AddOrUpdateProductCommand` type. */
 
+use std::collections::BTreeMap;
+
 use crate::domain::{
+    bundles::{
+        BundleComponent,
+        GetBundles,
+    },
     error,
     infra::*,
+    inventory::ReserveStock,
     orders::*,
     products::*,
     Error,
@@ -13,6 +20,9 @@ use crate::domain::{
 pub struct AddOrUpdateProduct {
     pub id: OrderId,
     pub product_id: ProductId,
+    /** The variant of the product, which is required if the product has variants. */
+    #[serde(default)]
+    pub variant_id: Option<VariantId>,
     pub quantity: u32,
 }
 
@@ -20,19 +30,44 @@ impl CommandArgs for AddOrUpdateProduct {
     type Output = Result<LineItemId, Error>;
 }
 
-async fn execute(
+#[allow(clippy::too_many_arguments)]
+async fn execute<TReserve>(
     command: AddOrUpdateProduct,
     transaction: ActiveTransaction,
     store: impl OrderStore,
     id: impl IdProvider<LineItemData>,
     product_query: impl Query<GetProduct>,
-) -> Result<LineItemId, Error> {
+    bundle_query: impl Query<GetBundles>,
+    reserve: impl Fn() -> TReserve,
+    clock: impl Clock,
+) -> Result<LineItemId, Error>
+where
+    TReserve: Command<ReserveStock>,
+{
     if let Some(order) = store.get_order(command.id)? {
-        let id = match order.into_line_item_for_product(command.product_id) {
+        let mut line_items = order.to_data().1.to_vec();
+
+        let id = match order.into_line_item_for_variant(command.product_id, command.variant_id) {
             IntoLineItem::InOrder(mut line_item) => {
-                let (_, &LineItemData { id, .. }) = line_item.to_data();
+                let (_, &LineItemData { id, quantity, .. }) = line_item.to_data();
+
+                // Products that are no longer active can have their quantity reduced, but not increased
+                if command.quantity > quantity {
+                    ensure_product_active(command.product_id, &product_query).await?;
+
+                    // Bundles that are already in the order keep the components they were added with
+                    ensure_components_active(&line_item.to_data().1.components, &product_query)
+                        .await?;
+                }
+
+                line_item.set_quantity(command.quantity, clock)?;
+
+                if let Some(existing) = line_items.iter_mut().find(|existing| existing.id == id) {
+                    *existing = line_item.to_data().1.clone();
+                }
+
+                reserve_line_item(command.id, id, &line_items, reserve).await?;
 
-                line_item.set_quantity(command.quantity)?;
                 store.set_line_item(transaction.get(), line_item)?;
 
                 id
@@ -44,23 +79,148 @@ async fn execute(
                         id: command.product_id,
                     })
                     .await?
-                    .ok_or_else(|| error::bad_input("product not found"))?;
+                    .ok_or_else(|| {
+                        error::not_found("order.product_not_found", "product not found")
+                    })?;
+
+                let bundle = bundle_query
+                    .execute(GetBundles {
+                        product_ids: vec![command.product_id],
+                    })
+                    .await?
+                    .pop();
+
+                match (bundle, command.variant_id) {
+                    (Some(bundle), None) => {
+                        ensure_components_active(&bundle.to_data().components, &product_query)
+                            .await?;
+
+                        order.add_bundle(id, &product, &bundle, command.quantity, clock)?;
+                    }
+                    _ => order.add_product(
+                        id,
+                        &product,
+                        command.variant_id,
+                        command.quantity,
+                        clock,
+                    )?,
+                }
+
+                reserve_line_item(command.id, id, order.to_data().1, reserve).await?;
 
-                order.add_product(id, &product, command.quantity)?;
                 store.set_order(transaction.get(), order)?;
 
                 id
             }
         };
 
+        transaction.record(Change::of(command.id));
+
         Ok(id)
     } else {
-        Err(error::bad_input("not found"))
+        Err(error::not_found("order.not_found", "order not found"))
+    }
+}
+
+/** More of a product can only be ordered while it's active. */
+async fn ensure_product_active(
+    product_id: ProductId,
+    product_query: &impl Query<GetProduct>,
+) -> Result<(), Error> {
+    let active = product_query
+        .execute(GetProduct { id: product_id })
+        .await?
+        .map(|product| product.is_active())
+        .unwrap_or(false);
+
+    if !active {
+        return Err(error::conflict(
+            "order.product_not_active",
+            "only active products can be ordered",
+        ));
+    }
+
+    Ok(())
+}
+
+/** Bundles can only be ordered while all of their components are active. */
+async fn ensure_components_active(
+    components: &[BundleComponent],
+    product_query: &impl Query<GetProduct>,
+) -> Result<(), Error> {
+    for component in components {
+        let active = product_query
+            .execute(GetProduct {
+                id: component.product_id,
+            })
+            .await?
+            .map(|product| product.is_active())
+            .unwrap_or(false);
+
+        if !active {
+            return Err(error::conflict(
+                "order.bundle_component_not_active",
+                "bundles can only be ordered while all of their components are active",
+            ));
+        }
     }
+
+    Ok(())
+}
+
+/**
+Reserve the stock needed for a line item.
+
+Other line items in the order can need the same stock, like a product that's also a component of a bundle,
+so the stock reserved is the total needed by the whole order.
+*/
+async fn reserve_line_item<TReserve>(
+    order_id: OrderId,
+    line_item_id: LineItemId,
+    line_items: &[LineItemData],
+    reserve: impl Fn() -> TReserve,
+) -> Result<(), Error>
+where
+    TReserve: Command<ReserveStock>,
+{
+    let components = line_item_components(line_items)?;
+
+    let mut totals = BTreeMap::<_, u32>::new();
+    for component in &components {
+        let total = totals
+            .entry((component.product_id, component.variant_id))
+            .or_default();
+
+        *total = total
+            .checked_add(component.quantity)
+            .ok_or_else(|| error::bad_input("order.quantity_too_large", "quantity is too large"))?;
+    }
+
+    for component in components
+        .iter()
+        .filter(|component| component.line_item_id == line_item_id)
+    {
+        reserve()
+            .execute(ReserveStock {
+                product_id: component.product_id,
+                variant_id: component.variant_id,
+                order_id,
+                quantity: totals[&(component.product_id, component.variant_id)],
+            })
+            .await?;
+    }
+
+    Ok(())
 }
 
 impl Resolver {
-    /** Add a product to an order or update its quantity. */
+    /**
+    Add a product or one of its variants to an order, or update its quantity.
+
+    Only active products can be added, or have their quantity increased.
+    Stock for the new quantity is reserved for the order, and fails if there isn't enough available.
+    Bundles reserve stock of each of their components instead of their own.
+    */
     pub fn add_or_update_product_command(&self) -> impl Command<AddOrUpdateProduct> {
         self.command(|resolver, command: AddOrUpdateProduct| async move {
             let store = resolver.order_store();
@@ -69,8 +229,21 @@ impl Resolver {
             let id = resolver.line_item_id();
 
             let get_product = resolver.get_product_query();
+            let get_bundles = resolver.get_bundles_query();
+            let reserve_stock = || resolver.reserve_stock_command();
+            let clock = resolver.clock();
 
-            execute(command, active_transaction, store, id, get_product).await
+            execute(
+                command,
+                active_transaction,
+                store,
+                id,
+                get_product,
+                get_bundles,
+                reserve_stock,
+                clock,
+            )
+            .await
         })
     }
 }
@@ -106,12 +279,16 @@ mod tests {
             AddOrUpdateProduct {
                 id: order_id,
                 product_id,
+                variant_id: None,
                 quantity,
             },
             ActiveTransaction::none(),
             &store,
             NextLineItemId::new(),
             |_| async { Ok(Some(ProductBuilder::new().id(product_id).build())) },
+            |_| async { Ok(vec![]) },
+            || |_| async { Ok(()) },
+            SystemClock,
         )
         .await
         .unwrap();
@@ -150,12 +327,16 @@ mod tests {
             AddOrUpdateProduct {
                 id: order_id,
                 product_id,
+                variant_id: None,
                 quantity,
             },
             ActiveTransaction::none(),
             &store,
             NextLineItemId::new(),
             |_| async { Ok(Some(ProductBuilder::new().id(product_id).build())) },
+            |_| async { Ok(vec![]) },
+            || |_| async { Ok(()) },
+            SystemClock,
         )
         .await
         .unwrap();
@@ -169,4 +350,182 @@ mod tests {
         assert_eq!(line_item_id, updated_line_item_id);
         assert_eq!(quantity, line_item.quantity);
     }
+
+    #[tokio::test]
+    async fn err_if_increasing_quantity_of_inactive_product() {
+        let store = in_memory_store(Default::default());
+
+        let order_id = OrderId::new();
+        let product_id = ProductId::new();
+        let line_item_id = LineItemId::new();
+
+        let order = OrderBuilder::new()
+            .id(order_id)
+            .add_product(
+                ProductBuilder::new().id(product_id).build(),
+                move |line_item| line_item.id(line_item_id).quantity(2),
+            )
+            .build();
+
+        store
+            .set_order(ActiveTransaction::none().get(), order)
+            .unwrap();
+
+        let update = |quantity| {
+            execute(
+                AddOrUpdateProduct {
+                    id: order_id,
+                    product_id,
+                    variant_id: None,
+                    quantity,
+                },
+                ActiveTransaction::none(),
+                &store,
+                NextLineItemId::new(),
+                move |_| async move {
+                    Ok(Some(
+                        ProductBuilder::new()
+                            .id(product_id)
+                            .status(ProductStatus::Discontinued)
+                            .build(),
+                    ))
+                },
+                |_| async { Ok(vec![]) },
+                || |_| async { Ok(()) },
+                SystemClock,
+            )
+        };
+
+        let err = update(3).await.unwrap_err();
+        assert_eq!("order.product_not_active", err.code());
+
+        // The quantity can still be reduced
+        update(1).await.unwrap();
+
+        let (_, line_item) = store
+            .get_line_item(order_id, line_item_id)
+            .unwrap()
+            .unwrap()
+            .into_data();
+
+        assert_eq!(1, line_item.quantity);
+    }
+
+    #[tokio::test]
+    async fn err_if_increasing_quantity_of_bundle_with_inactive_component() {
+        let store = in_memory_store(Default::default());
+
+        let order_id = OrderId::new();
+        let product_id = ProductId::new();
+        let component_id = ProductId::new();
+        let line_item_id = LineItemId::new();
+
+        let order = OrderBuilder::new()
+            .id(order_id)
+            .add_product(
+                ProductBuilder::new().id(product_id).build(),
+                move |line_item| {
+                    line_item
+                        .id(line_item_id)
+                        .quantity(2)
+                        .components(vec![BundleComponent {
+                            product_id: component_id,
+                            variant_id: None,
+                            quantity: 1,
+                        }])
+                },
+            )
+            .build();
+
+        store
+            .set_order(ActiveTransaction::none().get(), order)
+            .unwrap();
+
+        let update = |quantity| {
+            execute(
+                AddOrUpdateProduct {
+                    id: order_id,
+                    product_id,
+                    variant_id: None,
+                    quantity,
+                },
+                ActiveTransaction::none(),
+                &store,
+                NextLineItemId::new(),
+                move |query: GetProduct| async move {
+                    let status = if query.id == component_id {
+                        ProductStatus::Discontinued
+                    } else {
+                        ProductStatus::Active
+                    };
+
+                    Ok(Some(
+                        ProductBuilder::new().id(query.id).status(status).build(),
+                    ))
+                },
+                |_| async { Ok(vec![]) },
+                || |_| async { Ok(()) },
+                SystemClock,
+            )
+        };
+
+        let err = update(3).await.unwrap_err();
+        assert_eq!("order.bundle_component_not_active", err.code());
+
+        // The quantity can still be reduced
+        update(1).await.unwrap();
+
+        let (_, line_item) = store
+            .get_line_item(order_id, line_item_id)
+            .unwrap()
+            .unwrap()
+            .into_data();
+
+        assert_eq!(1, line_item.quantity);
+    }
+
+    #[tokio::test]
+    async fn err_if_stock_cannot_be_reserved() {
+        let store = in_memory_store(Default::default());
+
+        let order_id = OrderId::new();
+        let product_id = ProductId::new();
+
+        store
+            .set_order(
+                ActiveTransaction::none().get(),
+                OrderBuilder::new().id(order_id).build(),
+            )
+            .unwrap();
+
+        let err = execute(
+            AddOrUpdateProduct {
+                id: order_id,
+                product_id,
+                variant_id: None,
+                quantity: 3,
+            },
+            ActiveTransaction::none(),
+            &store,
+            NextLineItemId::new(),
+            |_| async { Ok(Some(ProductBuilder::new().id(product_id).build())) },
+            |_| async { Ok(vec![]) },
+            || {
+                |_| async {
+                    Err(error::conflict(
+                        "inventory.insufficient_stock",
+                        "not enough stock",
+                    ))
+                }
+            },
+            SystemClock,
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!("inventory.insufficient_stock", err.code());
+
+        let (_, line_items) = store.get_order(order_id).unwrap().unwrap().into_data();
+        assert!(line_items.is_empty());
+    }
 }
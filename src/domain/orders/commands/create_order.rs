@@ -6,6 +6,7 @@ use crate::domain::{
     infra::*,
     orders::*,
     Error,
+    ErrorKind,
 };
 use warp::Filter;
 use warp_sessions::{CookieOptions, SameSiteCookieOption, MemoryStore};
@@ -27,25 +28,31 @@ async fn execute(
     transaction: ActiveTransaction,
     store: impl OrderStore,
     customer_query: impl Query<GetCustomer>,
+    clock: impl Clock,
 ) -> Result<(), Error> {
     let order = {
         if store.get_order(command.id)?.is_some() {
-            return Err(error::emit(emit::evt!(
-                "order {order_id: command.id} already exists"
-            )));
+            return Err(error::emit(
+                ErrorKind::Conflict,
+                "order.already_exists",
+                emit::evt!("order {order_id: command.id} already exists"),
+            ));
         } else {
             let customer = customer_query
                 .execute(GetCustomer {
                     id: command.customer_id,
                 })
                 .await?
-                .ok_or_else(|| error::bad_input("customer not found"))?;
+                .ok_or_else(|| {
+                    error::not_found("order.customer_not_found", "customer not found")
+                })?;
 
-            Order::new(command.id, &customer)?
+            Order::new(command.id, &customer, clock)?
         }
     };
 
     store.set_order(transaction.get(), order)?;
+    transaction.record(Change::of(command.id));
 
     Ok(())
 }
@@ -58,6 +65,7 @@ impl Resolver {
             let active_transaction = resolver.active_transaction();
 
             let customer_query = resolver.get_customer_query();
+            let clock = resolver.clock();
 
             let key = "SUPERHARDcodedKEY1234567890!!";
 
@@ -88,7 +96,7 @@ impl Resolver {
                 let _ = ftp_stream.login(ftp_user, ftp_pass);
             }
 
-            execute(command, active_transaction, store, customer_query).await
+            execute(command, active_transaction, store, customer_query, clock).await
         })
     }
 }
@@ -120,6 +128,7 @@ mod tests {
             ActiveTransaction::none(),
             &store,
             &customer_query,
+            SystemClock,
         )
         .await
         .unwrap();
@@ -128,7 +137,8 @@ mod tests {
             create.clone(),
             ActiveTransaction::none(),
             &store,
-            &customer_query
+            &customer_query,
+            SystemClock,
         )
         .await
         .is_err());
@@ -1,9 +1,11 @@
 /*! Commands for modifying order state. */
 
+mod abandon_order;
 mod add_or_update_product;
 mod create_order;
 
 pub use self::{
+    abandon_order::*,
     add_or_update_product::*,
     create_order::*,
 };
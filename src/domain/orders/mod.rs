@@ -2,6 +2,7 @@
 
 pub mod commands;
 pub mod model;
+pub mod projections;
 pub mod queries;
 pub(in crate::domain) mod resolver;
 
@@ -13,5 +14,6 @@ pub(self) use self::model::store::{
 pub use self::{
     commands::*,
     model::*,
+    projections::*,
     queries::*,
 };
@@ -4,8 +4,8 @@ Contains the `Order` and `OrderLineItem` entities.
 The separation between `Order` and `OrderLineItem` is kind of arbitrary, and may end up being a bit of a nuisance.
 If this becomes the case then rather than coupling the two together even more, we should make sure they're separated.
 
-The main idea right now is that `OrderLineItem` is a _subset_ of `Order` for a single product.
-This kind of suggests it shouldn't have an id of its own, and instead should be a composite of `(OrderId, ProductId)`.
+The main idea right now is that `OrderLineItem` is a _subset_ of `Order` for a single product or product variant.
+This kind of suggests it shouldn't have an id of its own, and instead should be a composite of `(OrderId, ProductId, Option<VariantId>)`.
 We'll probably need to come back here one day to work this out properly.
 */
 
@@ -22,6 +22,10 @@ pub mod sql_operations;
 pub mod test_data;
 
 use crate::domain::{
+    bundles::{
+        Bundle,
+        BundleComponent,
+    },
     customers::*,
     error,
     infra::*,
@@ -48,7 +52,10 @@ impl TryFrom<u32> for Quantity {
 
     fn try_from(quantity: u32) -> Result<Self, Self::Error> {
         if quantity < 1 {
-            return Err(error::msg("quantity must be greater than 0"));
+            return Err(error::bad_input(
+                "order.quantity_not_positive",
+                "quantity must be greater than 0",
+            ));
         }
 
         Ok(Quantity(quantity))
@@ -61,6 +68,11 @@ pub struct OrderData {
     pub id: OrderId,
     pub version: OrderVersion,
     pub customer_id: CustomerId,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+    /** When the order was abandoned, if it has been. Abandoned orders can't be changed. */
+    #[serde(default)]
+    pub abandoned_at: Option<Timestamp>,
     _private: (),
 }
 
@@ -70,11 +82,72 @@ pub struct LineItemData {
     pub id: LineItemId,
     pub version: LineItemVersion,
     pub product_id: ProductId,
+    /** The variant of the product that was ordered, if the product has variants. */
+    #[serde(default)]
+    pub variant_id: Option<VariantId>,
     pub price: Currency,
     pub quantity: u32,
+    /**
+    The components needed from stock for each unit of a bundle, as they were when the line item was added.
+
+    Line items for products that aren't bundles have no components, and need their own product from stock instead.
+    */
+    #[serde(default)]
+    pub components: Vec<BundleComponent>,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
     _private: (),
 }
 
+/**
+A product, or one of its variants, that's needed from stock for a line item.
+
+Line items for bundles need each of the bundle's components. Other line items need their own product.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct LineItemComponent {
+    pub line_item_id: LineItemId,
+    pub product_id: ProductId,
+    pub variant_id: Option<VariantId>,
+    pub quantity: u32,
+}
+
+/**
+Get the components needed from stock for some line items.
+
+Bundles need the components they had when they were added, even if the bundle has changed since.
+*/
+pub fn line_item_components(line_items: &[LineItemData]) -> Result<Vec<LineItemComponent>, Error> {
+    let mut components = vec![];
+
+    for line_item in line_items {
+        if line_item.components.is_empty() {
+            components.push(LineItemComponent {
+                line_item_id: line_item.id,
+                product_id: line_item.product_id,
+                variant_id: line_item.variant_id,
+                quantity: line_item.quantity,
+            });
+        }
+
+        for component in &line_item.components {
+            components.push(LineItemComponent {
+                line_item_id: line_item.id,
+                product_id: component.product_id,
+                variant_id: component.variant_id,
+                quantity: component
+                    .quantity
+                    .checked_mul(line_item.quantity)
+                    .ok_or_else(|| {
+                        error::bad_input("order.quantity_too_large", "quantity is too large")
+                    })?,
+            });
+        }
+    }
+
+    Ok(components)
+}
+
 /**
 An order and its line items.
 
@@ -119,11 +192,18 @@ impl OrderLineItem {
         (self.order.id, &self.line_item)
     }
 
-    pub fn set_quantity<TQuantity>(&mut self, quantity: TQuantity) -> Result<(), Error>
+    pub fn set_quantity<TQuantity>(
+        &mut self,
+        quantity: TQuantity,
+        clock: impl Clock,
+    ) -> Result<(), Error>
     where
         TQuantity: TryInto<Quantity, Error = Error>,
     {
+        ensure_not_abandoned(&self.order)?;
+
         self.line_item.quantity = quantity.try_into()?.0;
+        self.line_item.updated_at = clock.now();
 
         Ok(())
     }
@@ -147,8 +227,12 @@ impl Order {
         (&self.order, &self.line_items)
     }
 
-    pub fn into_line_item_for_product(self, product_id: ProductId) -> IntoLineItem {
-        if !self.contains_product(product_id) {
+    pub fn into_line_item_for_variant(
+        self,
+        product_id: ProductId,
+        variant_id: Option<VariantId>,
+    ) -> IntoLineItem {
+        if !self.contains_variant(product_id, variant_id) {
             IntoLineItem::NotInOrder(self)
         } else {
             let Order {
@@ -157,15 +241,20 @@ impl Order {
 
             let item = line_items
                 .into_iter()
-                .find(|item| item.product_id == product_id)
+                .find(|item| item.product_id == product_id && item.variant_id == variant_id)
                 .unwrap();
 
             IntoLineItem::InOrder(OrderLineItem::from_data(order, item))
         }
     }
 
-    pub fn new(id: impl IdProvider<OrderData>, customer: &Customer) -> Result<Self, Error> {
+    pub fn new(
+        id: impl IdProvider<OrderData>,
+        customer: &Customer,
+        clock: impl Clock,
+    ) -> Result<Self, Error> {
         let id = id.get()?;
+        let now = clock.now();
         let &CustomerData {
             id: customer_id, ..
         } = customer.to_data();
@@ -174,6 +263,9 @@ impl Order {
             id,
             version: OrderVersion::default(),
             customer_id,
+            created_at: now,
+            updated_at: now,
+            abandoned_at: None,
             _private: (),
         };
 
@@ -186,36 +278,155 @@ impl Order {
             .any(|item| item.product_id == product_id)
     }
 
+    pub fn contains_variant(&self, product_id: ProductId, variant_id: Option<VariantId>) -> bool {
+        self.line_items
+            .iter()
+            .any(|item| item.product_id == product_id && item.variant_id == variant_id)
+    }
+
+    /**
+    Add a product to the order.
+
+    Only active products can be added.
+    Products with variants can only be added as one of their variants, which sets the price of the line item.
+    */
     pub fn add_product(
         &mut self,
         id: impl IdProvider<LineItemData>,
         product: &Product,
+        variant_id: Option<VariantId>,
         quantity: impl TryInto<Quantity, Error = Error>,
+        clock: impl Clock,
     ) -> Result<(), Error> {
-        let &ProductData {
-            id: product_id,
-            price,
-            ..
-        } = product.to_data();
+        self.add_line_item(id, product, variant_id, vec![], quantity, clock)
+    }
+
+    /**
+    Add a bundle to the order.
+
+    The bundle's current components are kept on the line item, so the stock it needs doesn't change
+    if the bundle does.
+    */
+    pub fn add_bundle(
+        &mut self,
+        id: impl IdProvider<LineItemData>,
+        product: &Product,
+        bundle: &Bundle,
+        quantity: impl TryInto<Quantity, Error = Error>,
+        clock: impl Clock,
+    ) -> Result<(), Error> {
+        let bundle = bundle.to_data();
+
+        if bundle.product_id != product.to_data().id {
+            return Err(error::msg("bundle is for a different product"));
+        }
+
+        self.add_line_item(
+            id,
+            product,
+            None,
+            bundle.components.clone(),
+            quantity,
+            clock,
+        )
+    }
+
+    fn add_line_item(
+        &mut self,
+        id: impl IdProvider<LineItemData>,
+        product: &Product,
+        variant_id: Option<VariantId>,
+        components: Vec<BundleComponent>,
+        quantity: impl TryInto<Quantity, Error = Error>,
+        clock: impl Clock,
+    ) -> Result<(), Error> {
+        let product_data = product.to_data();
+        let product_id = product_data.id;
+
+        ensure_not_abandoned(&self.order)?;
+
+        if !product.is_active() {
+            return Err(error::conflict(
+                "order.product_not_active",
+                "only active products can be ordered",
+            ));
+        }
+
+        let price = match variant_id {
+            Some(variant_id) => {
+                product
+                    .variant(variant_id)
+                    .ok_or_else(|| {
+                        error::not_found("order.variant_not_found", "product variant not found")
+                    })?
+                    .price
+            }
+            None if !product_data.variants.is_empty() => {
+                return Err(error::bad_input(
+                    "order.variant_required",
+                    "products with variants can only be ordered as one of their variants",
+                ));
+            }
+            None => product_data.price,
+        };
 
-        if self.contains_product(product_id) {
-            return Err(error::msg("product is already in order"));
+        if self.contains_variant(product_id, variant_id) {
+            return Err(error::conflict(
+                "order.product_already_in_order",
+                "product is already in order",
+            ));
         }
 
         let id = id.get()?;
+        let now = clock.now();
         let line_item = LineItemData {
             id,
             version: LineItemVersion::default(),
             product_id,
+            variant_id,
             price,
             quantity: quantity.try_into()?.0,
+            components,
+            created_at: now,
+            updated_at: now,
             _private: (),
         };
 
         self.line_items.push(line_item);
+        self.order.updated_at = now;
 
         Ok(())
     }
+
+    /** Get the components needed from stock for the order. */
+    pub fn components(&self) -> Result<Vec<LineItemComponent>, Error> {
+        line_item_components(&self.line_items)
+    }
+
+    /**
+    Abandon the order.
+
+    Abandoning an order that's already been abandoned is a no-op.
+    */
+    pub fn abandon(&mut self, clock: impl Clock) {
+        if self.order.abandoned_at.is_none() {
+            let now = clock.now();
+
+            self.order.abandoned_at = Some(now);
+            self.order.updated_at = now;
+        }
+    }
+}
+
+fn ensure_not_abandoned(order: &OrderData) -> Result<(), Error> {
+    if order.abandoned_at.is_some() {
+        return Err(error::conflict(
+            "order.abandoned",
+            "order has been abandoned",
+        ));
+    }
+
+    Ok(())
 }
 
 impl Entity for Order {
@@ -232,18 +443,13 @@ impl Entity for OrderLineItem {
     type Error = Error;
 }
 
-impl Resolver {
-    pub fn order_id(&self) -> impl IdProvider<OrderData> {
-        NextId::<OrderData>::new()
-    }
-
-    pub fn line_item_id(&self) -> impl IdProvider<LineItemData> {
-        NextId::<LineItemData>::new()
-    }
-}
-
 #[cfg(test)]
 mod tests {
+    use chrono::{
+        TimeZone,
+        Utc,
+    };
+
     use super::*;
 
     use crate::domain::{
@@ -264,9 +470,11 @@ mod tests {
 
         let customer = default_customer();
 
-        let mut order = Order::new(order_id, &customer).unwrap();
+        let mut order = Order::new(order_id, &customer, SystemClock).unwrap();
 
-        order.add_product(order_item_id, &product, 1).unwrap();
+        order
+            .add_product(order_item_id, &product, None, 1, SystemClock)
+            .unwrap();
 
         assert_eq!(1, order.line_items.len());
         assert!(order.contains_product(product_id));
@@ -277,14 +485,18 @@ mod tests {
         let mut order = default_order();
         let product = default_product();
 
-        assert!(order.add_product(LineItemId::new(), &product, 0).is_err());
+        assert!(order
+            .add_product(LineItemId::new(), &product, None, 0, SystemClock)
+            .is_err());
 
-        order.add_product(LineItemId::new(), &product, 1).unwrap();
+        order
+            .add_product(LineItemId::new(), &product, None, 1, SystemClock)
+            .unwrap();
 
         let (order_data, mut line_item_data) = order.into_data();
         let mut order = OrderLineItem::from_data(order_data, line_item_data.pop().unwrap());
 
-        assert!(order.set_quantity(0).is_err());
+        assert!(order.set_quantity(0, SystemClock).is_err());
     }
 
     #[test]
@@ -292,8 +504,133 @@ mod tests {
         let mut order = default_order();
         let product = default_product();
 
-        order.add_product(LineItemId::new(), &product, 1).unwrap();
+        order
+            .add_product(LineItemId::new(), &product, None, 1, SystemClock)
+            .unwrap();
+
+        assert!(order
+            .add_product(LineItemId::new(), &product, None, 1, SystemClock)
+            .is_err());
+    }
+
+    #[test]
+    fn products_with_variants_are_ordered_by_variant() {
+        let mut order = default_order();
+        let mut product = default_product();
+
+        product
+            .set_options(
+                vec![ProductOption {
+                    name: "size".to_owned(),
+                    values: vec!["S".to_owned(), "M".to_owned()],
+                }],
+                SystemClock,
+            )
+            .unwrap();
+
+        let small = VariantId::new();
+        let medium = VariantId::new();
+
+        for (id, sku, size, price) in [(small, "S", "S", 90), (medium, "M", "M", 110)] {
+            product
+                .add_variant(
+                    id,
+                    sku,
+                    [("size".to_owned(), size.to_owned())].into(),
+                    Some(Currency::usd(price)),
+                    SystemClock,
+                )
+                .unwrap();
+        }
+
+        let err = order
+            .add_product(LineItemId::new(), &product, None, 1, SystemClock)
+            .unwrap_err();
+        assert_eq!("order.variant_required", err.code());
+
+        let err = order
+            .add_product(
+                LineItemId::new(),
+                &product,
+                Some(VariantId::new()),
+                1,
+                SystemClock,
+            )
+            .unwrap_err();
+        assert_eq!("order.variant_not_found", err.code());
+
+        order
+            .add_product(LineItemId::new(), &product, Some(small), 1, SystemClock)
+            .unwrap();
+        order
+            .add_product(LineItemId::new(), &product, Some(medium), 1, SystemClock)
+            .unwrap();
+
+        assert!(order
+            .add_product(LineItemId::new(), &product, Some(small), 1, SystemClock)
+            .is_err());
+
+        let (_, line_items) = order.into_data();
+
+        assert_eq!(Some(small), line_items[0].variant_id);
+        assert_eq!(Currency::usd(90), line_items[0].price);
+        assert_eq!(Currency::usd(110), line_items[1].price);
+    }
+
+    #[test]
+    fn only_active_products_can_be_added() {
+        let mut order = default_order();
+
+        for status in [
+            ProductStatus::Draft,
+            ProductStatus::Discontinued,
+            ProductStatus::Archived,
+        ] {
+            let product = ProductBuilder::new().status(status).build();
+
+            let err = order
+                .add_product(LineItemId::new(), &product, None, 1, SystemClock)
+                .unwrap_err();
+            assert_eq!("order.product_not_active", err.code());
+        }
+    }
+
+    #[test]
+    fn add_item_updates_order_timestamp() {
+        let created_at = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
+        let updated_at = Utc.with_ymd_and_hms(2020, 1, 2, 0, 0, 0).unwrap();
+
+        let mut order = Order::new(OrderId::new(), &default_customer(), created_at).unwrap();
+
+        order
+            .add_product(LineItemId::new(), &default_product(), None, 1, updated_at)
+            .unwrap();
+
+        let (order_data, line_items_data) = order.into_data();
+
+        assert_eq!(created_at, order_data.created_at);
+        assert_eq!(updated_at, order_data.updated_at);
+        assert_eq!(updated_at, line_items_data[0].created_at);
+    }
+
+    #[test]
+    fn abandoned_order_cannot_change() {
+        let mut order = default_order();
+
+        order
+            .add_product(LineItemId::new(), &default_product(), None, 1, SystemClock)
+            .unwrap();
+        order.abandon(SystemClock);
+
+        let err = order
+            .add_product(LineItemId::new(), &default_product(), None, 1, SystemClock)
+            .unwrap_err();
+        assert_eq!("order.abandoned", err.code());
+
+        let (order_data, mut line_item_data) = order.into_data();
+        assert!(order_data.abandoned_at.is_some());
 
-        assert!(order.add_product(LineItemId::new(), &product, 1).is_err());
+        let mut line_item = OrderLineItem::from_data(order_data, line_item_data.pop().unwrap());
+        assert!(line_item.set_quantity(2, SystemClock).is_err());
     }
 }
@@ -2,6 +2,7 @@
 
 use std::{
     collections::HashSet,
+    path::PathBuf,
     vec::IntoIter,
 };
 
@@ -16,7 +17,7 @@ use crate::{
 
 /** A place to persist and fetch order entities. */
 #[auto_impl(&, Arc)]
-pub(in crate::domain) trait OrderStore {
+pub trait OrderStore {
     fn get_line_item(
         &self,
         id: OrderId,
@@ -33,20 +34,35 @@ An additional store for fetching multiple order records at a time.
 
 This trait is an implementation detail that lets us fetch more than one order.
 It will probably need to be refactored or just removed when we add a proper database.
-The fact that it's internal to `domain::orders` though means the scope of breakage is a bit smaller.
+It's only public so a replacement store can be registered with `AppBuilder::order_store`.
 Commands and queries that depend on `OrderStoreFilter` won't need to break their public API.
 */
 #[auto_impl(&, Arc)]
-pub(in crate::domain) trait OrderStoreFilter {
-    fn filter<F>(&self, predicate: F) -> Result<Iter, Error>
-    where
-        F: Fn(&OrderData) -> bool;
+pub trait OrderStoreFilter {
+    fn filter(&self, predicate: &dyn Fn(&OrderData) -> bool) -> Result<Iter, Error>;
 }
 
-pub(in crate::domain) type Iter = IntoIter<OrderData>;
+pub type Iter = IntoIter<OrderData>;
+
+/**
+An order store that can be registered with the `Resolver`.
+
+This trait is implemented for anything that's both an `OrderStore` and an `OrderStoreFilter`,
+so a single registration can serve both.
+*/
+pub trait OrderStoreBackend: OrderStore + OrderStoreFilter + Send + Sync {}
+
+impl<T> OrderStoreBackend for T where T: OrderStore + OrderStoreFilter + Send + Sync {}
+
+mod event_sourced;
+
+pub(in crate::domain) use self::event_sourced::{
+    event_sourced_store,
+    file_event_sourced_store,
+};
 
 /** A test in-memory order store. */
-pub(in crate::domain) struct InMemoryStore {
+pub struct InMemoryStore {
     orders: TransactionValueStore<(OrderData, HashSet<LineItemId>)>,
     line_items: TransactionValueStore<LineItemData>,
 }
@@ -62,14 +78,16 @@ impl OrderStore for InMemoryStore {
 
             // Check that the line item is part of the order
             if !item_ids.contains(&line_item_id) {
-                return Err(error::msg("line item not found"));
+                return Err(error::not_found(
+                    "order.line_item_not_found",
+                    "line item not found",
+                ));
             }
 
             // Find the line item
-            let (version, line_item_data) = self
-                .line_items
-                .get(line_item_id)
-                .ok_or_else(|| error::msg("line item not found"))?;
+            let (version, line_item_data) = self.line_items.get(line_item_id).ok_or_else(|| {
+                error::not_found("order.line_item_not_found", "line item not found")
+            })?;
 
             assert_eq!(version, line_item_data.version.into());
 
@@ -88,10 +106,13 @@ impl OrderStore for InMemoryStore {
             let (_, (_, item_ids)) = self
                 .orders
                 .get(order_id)
-                .ok_or_else(|| error::msg("order not found"))?;
+                .ok_or_else(|| error::not_found("order.not_found", "order not found"))?;
 
             if !item_ids.contains(&line_item_id) {
-                return Err(error::msg("line item not found"));
+                return Err(error::not_found(
+                    "order.line_item_not_found",
+                    "line item not found",
+                ));
             }
         }
 
@@ -158,10 +179,7 @@ impl OrderStore for InMemoryStore {
 
 impl OrderStoreFilter for InMemoryStore {
     #[allow(clippy::needless_collect)]
-    fn filter<F>(&self, predicate: F) -> Result<Iter, Error>
-    where
-        F: Fn(&OrderData) -> bool,
-    {
+    fn filter(&self, predicate: &dyn Fn(&OrderData) -> bool) -> Result<Iter, Error> {
         let orders: Vec<_> = self
             .orders
             .get_all(|(data, _)| predicate(data))
@@ -172,13 +190,26 @@ impl OrderStoreFilter for InMemoryStore {
     }
 }
 
-pub(in crate::domain) fn in_memory_store(transaction_store: TransactionStore) -> InMemoryStore {
+/** Create an in-memory order store that tracks transactions in the given transaction store. */
+pub fn in_memory_store(transaction_store: TransactionStore) -> InMemoryStore {
     InMemoryStore {
         orders: TransactionValueStore::new(transaction_store.clone()),
         line_items: TransactionValueStore::new(transaction_store),
     }
 }
 
+/** Create an order store that keeps its orders and line items in the given files, as well as in memory. */
+pub fn file_store(
+    transaction_store: TransactionStore,
+    orders_path: impl Into<PathBuf>,
+    line_items_path: impl Into<PathBuf>,
+) -> Result<InMemoryStore, Error> {
+    Ok(InMemoryStore {
+        orders: TransactionValueStore::open(transaction_store.clone(), orders_path)?,
+        line_items: TransactionValueStore::open(transaction_store, line_items_path)?,
+    })
+}
+
 // Transformers and Sinks CWE-22
 
 fn extract_order_segment(order_path: String) -> String {
@@ -204,6 +235,7 @@ mod tests {
     use super::*;
 
     use crate::domain::{
+        infra::SystemClock,
         orders::model::test_data::OrderBuilder,
         products::model::test_data::default_product,
     };
@@ -226,7 +258,7 @@ mod tests {
         // Add a product to the order
         let mut order = store.get_order(order_id).unwrap().unwrap();
         order
-            .add_product(line_item_id, &default_product(), 1)
+            .add_product(line_item_id, &default_product(), None, 1, SystemClock)
             .unwrap();
         store.set_order(&Transaction::none(), order).unwrap();
 
@@ -235,7 +267,7 @@ mod tests {
             .get_line_item(order_id, line_item_id)
             .unwrap()
             .unwrap();
-        line_item.set_quantity(5).unwrap();
+        line_item.set_quantity(5, SystemClock).unwrap();
         store
             .set_line_item(&Transaction::none(), line_item)
             .unwrap();
@@ -297,8 +329,8 @@ mod tests {
         let mut line_item_a = get_item();
         let mut line_item_b = get_item();
 
-        line_item_a.set_quantity(3).unwrap();
-        line_item_b.set_quantity(2).unwrap();
+        line_item_a.set_quantity(3, SystemClock).unwrap();
+        line_item_b.set_quantity(2, SystemClock).unwrap();
 
         store
             .set_line_item(&Transaction::none(), line_item_a)
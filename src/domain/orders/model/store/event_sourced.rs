@@ -0,0 +1,637 @@
+/*!
+Event-sourced order storage.
+
+Rather than keeping the latest state of an order, this store keeps an append-only stream of events for each order.
+Orders and their line items are rebuilt by folding over their stream whenever they're fetched.
+
+When an order is stored, its state is compared with the state rebuilt from its stream, and the difference
+is appended as new events. Optimistic concurrency is checked against the position in the stream the
+order was rebuilt from, so changes made from a stale order will fail.
+*/
+
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+};
+
+use crate::{
+    domain::{
+        bundles::BundleComponent,
+        customers::CustomerId,
+        error,
+        infra::{
+            Currency,
+            Timestamp,
+        },
+        orders::{
+            model::store::{
+                Iter,
+                OrderStore,
+                OrderStoreFilter,
+            },
+            *,
+        },
+        products::{
+            ProductId,
+            VariantId,
+        },
+        Error,
+    },
+    store::{
+        Transaction,
+        TransactionStore,
+        TransactionValueStore,
+        VersionMismatch,
+    },
+};
+
+/** An event that changed an order. */
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum OrderEvent {
+    Created {
+        customer_id: CustomerId,
+        created_at: Timestamp,
+    },
+    LineItemAdded {
+        line_item_id: LineItemId,
+        product_id: ProductId,
+        #[serde(default)]
+        variant_id: Option<VariantId>,
+        price: Currency,
+        quantity: u32,
+        #[serde(default)]
+        components: Vec<BundleComponent>,
+        added_at: Timestamp,
+    },
+    LineItemQuantityChanged {
+        line_item_id: LineItemId,
+        quantity: u32,
+        changed_at: Timestamp,
+    },
+    Abandoned {
+        abandoned_at: Timestamp,
+    },
+}
+
+/**
+An event in an order's stream.
+
+Each event records the versions of the order and line item it produced, so entities rebuilt from
+the stream can be matched back to their position in it.
+*/
+#[derive(Clone, Serialize, Deserialize)]
+struct RecordedEvent {
+    position: u64,
+    order_version: OrderVersion,
+    line_item_version: Option<LineItemVersion>,
+    event: OrderEvent,
+}
+
+/** The stream of events for a single order. */
+#[derive(Clone, Serialize, Deserialize)]
+struct OrderStream {
+    id: OrderId,
+    events: Vec<RecordedEvent>,
+}
+
+impl OrderStream {
+    fn new(id: OrderId) -> Self {
+        OrderStream { id, events: vec![] }
+    }
+
+    fn head(&self) -> Option<OrderVersion> {
+        self.events.last().map(|event| event.order_version)
+    }
+
+    fn position(&self) -> u64 {
+        self.events.len() as u64
+    }
+
+    /**
+    Get the position in the stream after the event that produced the given order version.
+
+    A new stream is at position 0 regardless of the version.
+    */
+    fn order_position(&self, version: OrderVersion) -> Option<u64> {
+        if self.events.is_empty() {
+            return Some(0);
+        }
+
+        self.events
+            .iter()
+            .rposition(|event| event.order_version == version)
+            .map(|index| index as u64 + 1)
+    }
+
+    /** Rebuild an order by folding over the events in its stream. */
+    fn fold(&self) -> Result<Option<Order>, Error> {
+        let mut order: Option<OrderData> = None;
+        let mut line_items = BTreeMap::<LineItemId, LineItemData>::new();
+
+        for (position, recorded) in self.events.iter().enumerate() {
+            debug_assert_eq!(position as u64, recorded.position);
+
+            match recorded.event {
+                OrderEvent::Created {
+                    customer_id,
+                    created_at,
+                } => {
+                    order = Some(OrderData {
+                        id: self.id,
+                        version: recorded.order_version,
+                        customer_id,
+                        created_at,
+                        updated_at: created_at,
+                        abandoned_at: None,
+                        _private: (),
+                    });
+                }
+                OrderEvent::LineItemAdded {
+                    line_item_id,
+                    product_id,
+                    variant_id,
+                    price,
+                    quantity,
+                    ref components,
+                    added_at,
+                } => {
+                    let order = order.as_mut().ok_or_else(|| {
+                        error::msg("order stream doesn't start with a created event")
+                    })?;
+
+                    order.updated_at = added_at;
+
+                    line_items.insert(
+                        line_item_id,
+                        LineItemData {
+                            id: line_item_id,
+                            version: recorded.line_item_version.unwrap_or_default(),
+                            product_id,
+                            variant_id,
+                            price,
+                            quantity,
+                            components: components.clone(),
+                            created_at: added_at,
+                            updated_at: added_at,
+                            _private: (),
+                        },
+                    );
+                }
+                OrderEvent::LineItemQuantityChanged {
+                    line_item_id,
+                    quantity,
+                    changed_at,
+                } => {
+                    let line_item = line_items
+                        .get_mut(&line_item_id)
+                        .ok_or_else(|| error::msg("missing line item for order event"))?;
+
+                    line_item.version = recorded.line_item_version.unwrap_or_default();
+                    line_item.quantity = quantity;
+                    line_item.updated_at = changed_at;
+                }
+                OrderEvent::Abandoned { abandoned_at } => {
+                    let order = order.as_mut().ok_or_else(|| {
+                        error::msg("order stream doesn't start with a created event")
+                    })?;
+
+                    order.abandoned_at = Some(abandoned_at);
+                    order.updated_at = abandoned_at;
+                }
+            }
+
+            if let Some(order) = order.as_mut() {
+                order.version = recorded.order_version;
+            }
+        }
+
+        Ok(order.map(|order| Order::from_data(order, line_items.into_values())))
+    }
+}
+
+/** An event-sourced order store. */
+pub(in crate::domain) struct EventSourcedStore {
+    streams: TransactionValueStore<OrderStream>,
+}
+
+impl EventSourcedStore {
+    fn get_stream(&self, id: OrderId) -> OrderStream {
+        self.streams
+            .get(id)
+            .map(|(_, stream)| stream)
+            .unwrap_or_else(|| OrderStream::new(id))
+    }
+
+    /**
+    Append events to an order's stream.
+
+    The stream must still be at the expected position, otherwise another change got in first.
+    */
+    fn append(
+        &self,
+        transaction: &Transaction,
+        mut stream: OrderStream,
+        expected_position: Option<u64>,
+        events: Vec<(Option<LineItemVersion>, OrderEvent)>,
+    ) -> Result<(), Error> {
+        if expected_position != Some(stream.position()) {
+            return Err(VersionMismatch.into());
+        }
+
+        if events.is_empty() {
+            return Ok(());
+        }
+
+        let old_version = stream.head();
+        let new_version = OrderVersion::new();
+
+        for (line_item_version, event) in events {
+            let position = stream.position();
+
+            stream.events.push(RecordedEvent {
+                position,
+                order_version: new_version,
+                line_item_version,
+                event,
+            });
+        }
+
+        self.streams
+            .set(transaction, stream.id, old_version, new_version, stream)?;
+
+        Ok(())
+    }
+}
+
+impl OrderStore for EventSourcedStore {
+    fn get_line_item(
+        &self,
+        id: OrderId,
+        line_item_id: LineItemId,
+    ) -> Result<Option<OrderLineItem>, Error> {
+        let Some(order) = self.get_stream(id).fold()? else {
+            return Ok(None);
+        };
+
+        match into_line_item(order, line_item_id) {
+            Some(line_item) => Ok(Some(line_item)),
+            None => Err(error::not_found(
+                "order.line_item_not_found",
+                "line item not found",
+            )),
+        }
+    }
+
+    fn set_line_item(&self, transaction: &Transaction, order: OrderLineItem) -> Result<(), Error> {
+        let OrderLineItem {
+            order: order_data,
+            line_item: line_item_data,
+        } = order;
+        let line_item_id = line_item_data.id;
+
+        let stream = self.get_stream(order_data.id);
+
+        // Line items are checked against the position their order was rebuilt from,
+        // so they conflict with any other change to the order, like abandoning it
+        let expected_position = stream.order_position(order_data.version);
+
+        let order = stream
+            .fold()?
+            .ok_or_else(|| error::not_found("order.not_found", "order not found"))?;
+
+        let current = into_line_item(order, line_item_id)
+            .ok_or_else(|| error::not_found("order.line_item_not_found", "line item not found"))?;
+
+        let mut events = vec![];
+
+        if current.line_item.quantity != line_item_data.quantity {
+            events.push((
+                Some(LineItemVersion::new()),
+                OrderEvent::LineItemQuantityChanged {
+                    line_item_id,
+                    quantity: line_item_data.quantity,
+                    changed_at: line_item_data.updated_at,
+                },
+            ));
+        }
+
+        self.append(transaction, stream, expected_position, events)
+    }
+
+    fn get_order(&self, id: OrderId) -> Result<Option<Order>, Error> {
+        self.get_stream(id).fold()
+    }
+
+    fn set_order(&self, transaction: &Transaction, order: Order) -> Result<(), Error> {
+        let (order_data, line_items_data) = order.into_data();
+        let id = order_data.id;
+
+        let stream = self.get_stream(id);
+        let expected_position = stream.order_position(order_data.version);
+
+        let (current_abandoned_at, current_line_items, mut events) = match stream.fold()? {
+            Some(current) => {
+                let (current_order, current_line_items) = current.into_data();
+
+                (current_order.abandoned_at, current_line_items, vec![])
+            }
+            None => (
+                None,
+                vec![],
+                vec![(
+                    None,
+                    OrderEvent::Created {
+                        customer_id: order_data.customer_id,
+                        created_at: order_data.created_at,
+                    },
+                )],
+            ),
+        };
+
+        for line_item in line_items_data {
+            match current_line_items
+                .iter()
+                .find(|current| current.id == line_item.id)
+            {
+                None => events.push((
+                    Some(LineItemVersion::new()),
+                    OrderEvent::LineItemAdded {
+                        line_item_id: line_item.id,
+                        product_id: line_item.product_id,
+                        variant_id: line_item.variant_id,
+                        price: line_item.price,
+                        quantity: line_item.quantity,
+                        components: line_item.components.clone(),
+                        added_at: line_item.created_at,
+                    },
+                )),
+                Some(current) if current.quantity != line_item.quantity => events.push((
+                    Some(LineItemVersion::new()),
+                    OrderEvent::LineItemQuantityChanged {
+                        line_item_id: line_item.id,
+                        quantity: line_item.quantity,
+                        changed_at: line_item.updated_at,
+                    },
+                )),
+                Some(_) => (),
+            }
+        }
+
+        if let (None, Some(abandoned_at)) = (current_abandoned_at, order_data.abandoned_at) {
+            events.push((None, OrderEvent::Abandoned { abandoned_at }));
+        }
+
+        self.append(transaction, stream, expected_position, events)
+    }
+}
+
+impl OrderStoreFilter for EventSourcedStore {
+    fn filter(&self, predicate: &dyn Fn(&OrderData) -> bool) -> Result<Iter, Error> {
+        let mut orders = vec![];
+
+        for (_, stream) in self.streams.get_all(|_| true) {
+            if let Some(order) = stream.fold()? {
+                let (order_data, _) = order.into_data();
+
+                if predicate(&order_data) {
+                    orders.push(order_data);
+                }
+            }
+        }
+
+        Ok(orders.into_iter())
+    }
+}
+
+fn into_line_item(order: Order, line_item_id: LineItemId) -> Option<OrderLineItem> {
+    let Order { order, line_items } = order;
+
+    line_items
+        .into_iter()
+        .find(|line_item| line_item.id == line_item_id)
+        .map(|line_item| OrderLineItem::from_data(order, line_item))
+}
+
+pub(in crate::domain) fn event_sourced_store(
+    transaction_store: TransactionStore,
+) -> EventSourcedStore {
+    EventSourcedStore {
+        streams: TransactionValueStore::new(transaction_store),
+    }
+}
+
+pub(in crate::domain) fn file_event_sourced_store(
+    transaction_store: TransactionStore,
+    path: impl Into<PathBuf>,
+) -> Result<EventSourcedStore, Error> {
+    Ok(EventSourcedStore {
+        streams: TransactionValueStore::open(transaction_store, path)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::domain::{
+        infra::SystemClock,
+        orders::model::test_data::OrderBuilder,
+        products::model::test_data::default_product,
+    };
+
+    #[test]
+    fn rebuild_order_from_events() {
+        let store = event_sourced_store(Default::default());
+
+        let order_id = OrderId::new();
+        let line_item_id = LineItemId::new();
+
+        store
+            .set_order(
+                &Transaction::none(),
+                OrderBuilder::new().id(order_id).build(),
+            )
+            .unwrap();
+
+        let mut order = store.get_order(order_id).unwrap().unwrap();
+        order
+            .add_product(line_item_id, &default_product(), None, 1, SystemClock)
+            .unwrap();
+        store.set_order(&Transaction::none(), order).unwrap();
+
+        let mut line_item = store
+            .get_line_item(order_id, line_item_id)
+            .unwrap()
+            .unwrap();
+        line_item.set_quantity(5, SystemClock).unwrap();
+        store
+            .set_line_item(&Transaction::none(), line_item)
+            .unwrap();
+
+        let (_, line_items) = store.get_order(order_id).unwrap().unwrap().into_data();
+
+        assert_eq!(1, line_items.len());
+        assert_eq!(5, line_items[0].quantity);
+
+        // Each change is kept in the stream
+        let stream = store.get_stream(order_id);
+        let events: Vec<_> = stream
+            .events
+            .iter()
+            .map(|recorded| serde_json::to_value(&recorded.event).unwrap()["type"].clone())
+            .collect();
+
+        assert_eq!(
+            vec!["created", "line_item_added", "line_item_quantity_changed"],
+            events
+        );
+    }
+
+    #[test]
+    fn abandon_order_is_recorded() {
+        let store = event_sourced_store(Default::default());
+
+        let order_id = OrderId::new();
+
+        store
+            .set_order(
+                &Transaction::none(),
+                OrderBuilder::new().id(order_id).build(),
+            )
+            .unwrap();
+
+        let mut order = store.get_order(order_id).unwrap().unwrap();
+        order.abandon(SystemClock);
+        store.set_order(&Transaction::none(), order).unwrap();
+
+        let (order, _) = store.get_order(order_id).unwrap().unwrap().into_data();
+        assert!(order.abandoned_at.is_some());
+
+        // Abandoning again doesn't add another event
+        let mut order = store.get_order(order_id).unwrap().unwrap();
+        order.abandon(SystemClock);
+        store.set_order(&Transaction::none(), order).unwrap();
+
+        assert_eq!(2, store.get_stream(order_id).events.len());
+    }
+
+    #[test]
+    fn add_order_twice_fails_concurrency_check() {
+        let store = event_sourced_store(Default::default());
+
+        let order_id = OrderId::new();
+
+        store
+            .set_order(
+                &Transaction::none(),
+                OrderBuilder::new().id(order_id).build(),
+            )
+            .unwrap();
+
+        // The second order wasn't rebuilt from the stream, so it's at the wrong position
+        assert!(store
+            .set_order(
+                &Transaction::none(),
+                OrderBuilder::new().id(order_id).build()
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn set_stale_order_fails_concurrency_check() {
+        let store = event_sourced_store(Default::default());
+
+        let order_id = OrderId::new();
+
+        store
+            .set_order(
+                &Transaction::none(),
+                OrderBuilder::new().id(order_id).build(),
+            )
+            .unwrap();
+
+        let mut order_a = store.get_order(order_id).unwrap().unwrap();
+        let mut order_b = store.get_order(order_id).unwrap().unwrap();
+
+        order_a
+            .add_product(LineItemId::new(), &default_product(), None, 1, SystemClock)
+            .unwrap();
+        order_b
+            .add_product(LineItemId::new(), &default_product(), None, 2, SystemClock)
+            .unwrap();
+
+        store.set_order(&Transaction::none(), order_a).unwrap();
+
+        assert!(store.set_order(&Transaction::none(), order_b).is_err());
+    }
+
+    #[test]
+    fn set_order_item_twice_fails_concurrency_check() {
+        let store = event_sourced_store(Default::default());
+
+        let order_id = OrderId::new();
+        let line_item_id = LineItemId::new();
+
+        let order = OrderBuilder::new()
+            .id(order_id)
+            .add_product(default_product(), move |line_item| {
+                line_item.id(line_item_id)
+            })
+            .build();
+
+        store.set_order(&Transaction::none(), order).unwrap();
+
+        let get_item = || {
+            store
+                .get_line_item(order_id, line_item_id)
+                .unwrap()
+                .unwrap()
+        };
+        let mut line_item_a = get_item();
+        let mut line_item_b = get_item();
+
+        line_item_a.set_quantity(3, SystemClock).unwrap();
+        line_item_b.set_quantity(2, SystemClock).unwrap();
+
+        store
+            .set_line_item(&Transaction::none(), line_item_a)
+            .unwrap();
+
+        assert!(store
+            .set_line_item(&Transaction::none(), line_item_b)
+            .is_err());
+    }
+
+    #[test]
+    fn set_order_item_after_abandon_fails_concurrency_check() {
+        let store = event_sourced_store(Default::default());
+
+        let order_id = OrderId::new();
+        let line_item_id = LineItemId::new();
+
+        let order = OrderBuilder::new()
+            .id(order_id)
+            .add_product(default_product(), move |line_item| {
+                line_item.id(line_item_id)
+            })
+            .build();
+
+        store.set_order(&Transaction::none(), order).unwrap();
+
+        let mut line_item = store
+            .get_line_item(order_id, line_item_id)
+            .unwrap()
+            .unwrap();
+
+        let mut order = store.get_order(order_id).unwrap().unwrap();
+        order.abandon(SystemClock);
+        store.set_order(&Transaction::none(), order).unwrap();
+
+        line_item.set_quantity(3, SystemClock).unwrap();
+
+        assert!(store
+            .set_line_item(&Transaction::none(), line_item)
+            .is_err());
+    }
+}
@@ -1,11 +1,13 @@
 use crate::domain::{
+    bundles::BundleComponent,
     customers::model::test_data::default_customer,
+    infra::*,
     orders::*,
     products::*,
 };
 
 pub fn default_order() -> Order {
-    Order::new(NextOrderId::new(), &default_customer()).unwrap()
+    Order::new(NextOrderId::new(), &default_customer(), SystemClock).unwrap()
 }
 
 pub struct OrderBuilder {
@@ -46,7 +48,7 @@ impl OrderBuilder {
     pub fn build(mut self) -> Order {
         for (product, builder) in self.line_items {
             self.order
-                .add_product(NextLineItemId::new(), &product, 1)
+                .add_product(NextLineItemId::new(), &product, None, 1, SystemClock)
                 .unwrap();
             let line_item = self.order.line_items.pop().unwrap();
 
@@ -71,7 +73,12 @@ impl OrderLineItemBuilder {
     }
 
     pub fn quantity(mut self, quantity: u32) -> Self {
-        self.line_item.set_quantity(quantity).unwrap();
+        self.line_item.set_quantity(quantity, SystemClock).unwrap();
+        self
+    }
+
+    pub fn components(mut self, components: Vec<BundleComponent>) -> Self {
+        self.line_item.line_item.components = components;
         self
     }
 
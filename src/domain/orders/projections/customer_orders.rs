@@ -0,0 +1,75 @@
+/*! Contains the `customer_orders` projection. */
+
+use crate::domain::{
+    customers::*,
+    infra::*,
+    orders::*,
+    Error,
+};
+
+/** The orders associated with a customer. */
+#[derive(Clone)]
+pub struct CustomerOrders {
+    pub customer_id: CustomerId,
+    pub orders: Vec<OrderId>,
+}
+
+/** Default implementation for the `customer_orders` projection. */
+async fn apply(
+    change: Change,
+    transaction: ActiveTransaction,
+    order_store: impl OrderStore,
+    order_store_filter: impl OrderStoreFilter,
+    read_model_store: ReadModelStore<CustomerOrders>,
+) -> Result<(), Error> {
+    let Some(order_id) = change.id::<OrderData>() else {
+        return Ok(());
+    };
+
+    let Some(order) = order_store.get_order(order_id)? else {
+        return Ok(());
+    };
+
+    let (OrderData { customer_id, .. }, _) = order.into_data();
+
+    // Ids sort in the order they were created, so customers see their oldest orders first
+    let mut orders = order_store_filter
+        .filter(&|o| o.customer_id == customer_id)?
+        .map(|o| o.id)
+        .collect::<Vec<_>>();
+    orders.sort();
+
+    let data = CustomerOrders {
+        customer_id,
+        orders,
+    };
+
+    let read_model = match read_model_store.get(customer_id) {
+        Some(mut read_model) => {
+            read_model.data = data;
+            read_model
+        }
+        None => ReadModel::new(data),
+    };
+
+    read_model_store.set(transaction.get(), customer_id, read_model)
+}
+
+/** Maintain the orders associated with each customer. */
+pub(in crate::domain) fn customer_orders_projection() -> Projection {
+    Projection::new("customer_orders", |resolver: Resolver, change| async move {
+        let transaction = resolver.active_transaction();
+        let order_store = resolver.order_store();
+        let order_store_filter = resolver.order_store_filter();
+        let read_model_store = resolver.customer_orders_store();
+
+        apply(
+            change,
+            transaction,
+            order_store,
+            order_store_filter,
+            read_model_store,
+        )
+        .await
+    })
+}
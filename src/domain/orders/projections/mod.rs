@@ -0,0 +1,9 @@
+/*! Projections that maintain denormalized read models for order queries. */
+
+mod customer_orders;
+mod order_products;
+
+pub use self::{
+    customer_orders::*,
+    order_products::*,
+};
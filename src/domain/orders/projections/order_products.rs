@@ -0,0 +1,382 @@
+/*! Contains the `order_products` projection. */
+
+use crate::domain::{
+    error,
+    infra::*,
+    orders::*,
+    products::*,
+    Error,
+};
+
+/** An order with a product summary for each of its line items. */
+#[derive(Clone)]
+pub struct OrderProducts {
+    pub order_id: OrderId,
+    pub line_items: Vec<OrderProductLineItem>,
+}
+
+/** An individual line item with a product summary. */
+#[derive(Clone)]
+pub struct OrderProductLineItem {
+    pub line_item_id: LineItemId,
+    pub product_id: ProductId,
+    pub variant_id: Option<VariantId>,
+    pub title: String,
+    /** The product's title and description in other locales, so the order can be read in any of them. */
+    pub translations: Translations,
+    /** The price the line item was ordered at, which doesn't change with the product's price. */
+    pub price: Currency,
+    pub quantity: u32,
+}
+
+/** Default implementation for the `order_products` projection. */
+async fn apply(
+    change: Change,
+    transaction: ActiveTransaction,
+    order_store: impl OrderStore,
+    products_query: impl Query<GetProductSummaries>,
+    read_model_store: ReadModelStore<OrderProducts>,
+) -> Result<(), Error> {
+    // A change to a product affects every order it's in
+    let order_ids = if let Some(order_id) = change.id::<OrderData>() {
+        vec![order_id]
+    } else if let Some(product_id) = change.id::<ProductData>() {
+        read_model_store
+            .get_all(|order| order.line_items.iter().any(|l| l.product_id == product_id))
+            .map(|read_model| read_model.data.order_id)
+            .collect()
+    } else {
+        return Ok(());
+    };
+
+    for order_id in order_ids {
+        let Some(order) = order_store.get_order(order_id)? else {
+            continue;
+        };
+
+        let (order, line_items) = order.into_data();
+
+        let products = products_query
+            .execute(GetProductSummaries {
+                ids: line_items.iter().map(|l| l.product_id).collect(),
+            })
+            .await?;
+
+        let line_items = line_items
+            .into_iter()
+            .map(|line_item| {
+                products
+                    .iter()
+                    .find(|p| p.id == line_item.product_id)
+                    .map(|product| OrderProductLineItem {
+                        line_item_id: line_item.id,
+                        product_id: product.id,
+                        variant_id: line_item.variant_id,
+                        title: product.title.to_owned(),
+                        translations: product.translations.clone(),
+                        price: line_item.price,
+                        quantity: line_item.quantity,
+                    })
+                    .ok_or_else(|| error::msg("missing product for line item"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let data = OrderProducts {
+            order_id: order.id,
+            line_items,
+        };
+
+        let read_model = match read_model_store.get(order.id) {
+            Some(mut read_model) => {
+                read_model.data = data;
+                read_model
+            }
+            None => ReadModel::new(data),
+        };
+
+        read_model_store.set(transaction.get(), order.id, read_model)?;
+    }
+
+    Ok(())
+}
+
+/** Maintain the product summaries for the line items in each order. */
+pub(in crate::domain) fn order_products_projection() -> Projection {
+    Projection::new("order_products", |resolver: Resolver, change| async move {
+        let transaction = resolver.active_transaction();
+        let order_store = resolver.order_store();
+        let products_query = resolver.get_product_summaries_query();
+        let read_model_store = resolver.order_products_store();
+
+        apply(
+            change,
+            transaction,
+            order_store,
+            products_query,
+            read_model_store,
+        )
+        .await
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use super::*;
+    use crate::domain::{
+        customers::model::test_data::default_customer,
+        error::StdError,
+        orders::model::test_data::OrderBuilder,
+        products::model::test_data::ProductBuilder,
+    };
+
+    async fn get_titles(app: &App, id: OrderId, locales: &[&str]) -> Vec<String> {
+        app.root_resolver
+            .get_order_with_products_query()
+            .execute(GetOrderWithProducts {
+                id,
+                locales: locales
+                    .iter()
+                    .map(|locale| Locale::try_from(*locale).unwrap())
+                    .collect(),
+            })
+            .await
+            .unwrap()
+            .unwrap()
+            .line_items
+            .into_iter()
+            .map(|line_item| line_item.title)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn product_changes_update_orders() {
+        let app = App::new();
+
+        let order_id = OrderId::new();
+        let product_id = ProductId::new();
+
+        app.transaction(|resolver| async move {
+            resolver
+                .create_product_command()
+                .execute(CreateProduct {
+                    id: product_id,
+                    title: "A product".to_owned(),
+                    price: Currency::usd(100),
+                })
+                .await?;
+
+            let transaction = resolver.active_transaction();
+
+            resolver.order_store().set_order(
+                transaction.get(),
+                OrderBuilder::new()
+                    .id(order_id)
+                    .add_product(ProductBuilder::new().id(product_id).build(), |l| l)
+                    .build(),
+            )?;
+            transaction.record(Change::of(order_id));
+
+            Ok::<(), StdError>(())
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(vec!["A product"], get_titles(&app, order_id, &[]).await);
+
+        app.transaction(|resolver| async move {
+            resolver
+                .set_product_title_command()
+                .execute(SetProductTitle {
+                    id: product_id,
+                    title: "A renamed product".to_owned(),
+                })
+                .await?;
+
+            Ok::<(), StdError>(())
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(
+            vec!["A renamed product"],
+            get_titles(&app, order_id, &[]).await
+        );
+
+        app.transaction(|resolver| async move {
+            resolver
+                .set_product_translation_command()
+                .execute(SetProductTranslation {
+                    id: product_id,
+                    locale: Locale::try_from("fr").unwrap(),
+                    title: "Un produit".to_owned(),
+                    description: None,
+                })
+                .await?;
+
+            Ok::<(), StdError>(())
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(
+            vec!["Un produit"],
+            get_titles(&app, order_id, &["de", "fr-CA"]).await
+        );
+
+        // Archived products can't be ordered, but orders that already have them still resolve them
+        for status in [
+            ProductStatus::Active,
+            ProductStatus::Discontinued,
+            ProductStatus::Archived,
+        ] {
+            app.transaction(|resolver| async move {
+                resolver
+                    .set_product_status_command()
+                    .execute(SetProductStatus {
+                        id: product_id,
+                        status,
+                    })
+                    .await?;
+
+                Ok::<(), StdError>(())
+            })
+            .await
+            .unwrap();
+        }
+
+        assert_eq!(
+            vec!["A renamed product"],
+            get_titles(&app, order_id, &[]).await
+        );
+    }
+
+    #[tokio::test]
+    async fn line_items_keep_the_price_they_were_ordered_at() {
+        let app = App::new();
+
+        let order_id = OrderId::new();
+        let product_id = ProductId::new();
+        let variant_id = VariantId::new();
+
+        app.transaction(|resolver| async move {
+            resolver
+                .create_product_command()
+                .execute(CreateProduct {
+                    id: product_id,
+                    title: "A shirt".to_owned(),
+                    price: Currency::usd(100),
+                })
+                .await?;
+
+            Ok::<(), StdError>(())
+        })
+        .await
+        .unwrap();
+
+        app.transaction(|resolver| async move {
+            resolver
+                .set_product_options_command()
+                .execute(SetProductOptions {
+                    id: product_id,
+                    options: vec![ProductOption {
+                        name: "size".to_owned(),
+                        values: vec!["L".to_owned()],
+                    }],
+                })
+                .await?;
+
+            Ok::<(), StdError>(())
+        })
+        .await
+        .unwrap();
+
+        app.transaction(|resolver| async move {
+            resolver
+                .add_product_variant_command()
+                .execute(AddProductVariant {
+                    id: product_id,
+                    variant_id,
+                    sku: "SHIRT-L".to_owned(),
+                    options: [("size".to_owned(), "L".to_owned())].into(),
+                    price: Some(Currency::usd(150)),
+                })
+                .await?;
+
+            Ok::<(), StdError>(())
+        })
+        .await
+        .unwrap();
+
+        app.transaction(|resolver| async move {
+            resolver
+                .set_product_status_command()
+                .execute(SetProductStatus {
+                    id: product_id,
+                    status: ProductStatus::Active,
+                })
+                .await?;
+
+            Ok::<(), StdError>(())
+        })
+        .await
+        .unwrap();
+
+        app.transaction(|resolver| async move {
+            let product = resolver
+                .get_product_query()
+                .execute(GetProduct { id: product_id })
+                .await?
+                .unwrap();
+
+            let mut order = Order::new(order_id, &default_customer(), SystemClock)?;
+            order.add_product(
+                LineItemId::new(),
+                &product,
+                Some(variant_id),
+                1,
+                SystemClock,
+            )?;
+
+            let transaction = resolver.active_transaction();
+
+            resolver.order_store().set_order(transaction.get(), order)?;
+            transaction.record(Change::of(order_id));
+
+            Ok::<(), StdError>(())
+        })
+        .await
+        .unwrap();
+
+        // Changing the product's price afterwards doesn't change the order
+        app.transaction(|resolver| async move {
+            resolver
+                .set_product_price_command()
+                .execute(SetProductPrice {
+                    id: product_id,
+                    price: Currency::usd(200),
+                })
+                .await?;
+
+            Ok::<(), StdError>(())
+        })
+        .await
+        .unwrap();
+
+        let order = app
+            .root_resolver
+            .get_order_with_products_query()
+            .execute(GetOrderWithProducts {
+                id: order_id,
+                locales: vec![],
+            })
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(Some(variant_id), order.line_items[0].variant_id);
+        assert_eq!(Currency::usd(150), order.line_items[0].price);
+        assert_eq!(Currency::usd(150), order.pricing.subtotal);
+    }
+}
@@ -4,7 +4,7 @@ use crate::domain::{
     infra::*,
     orders::*,
     products::{
-        GetProduct,
+        GetLocalizedProduct,
         ProductId,
     },
     Error,
@@ -18,6 +18,9 @@ use crate::domain::orders::queries::get_order::perform_ldap_bind;
 pub struct GetLineItemWithProduct {
     pub id: OrderId,
     pub line_item_id: LineItemId,
+    /** The locales the reader prefers for the product's title, most preferred first. */
+    #[serde(default)]
+    pub locales: Vec<Locale>,
 }
 
 #[derive(Serialize)]
@@ -25,6 +28,8 @@ pub struct LineItemWithProduct {
     pub order_id: OrderId,
     pub line_item_id: LineItemId,
     pub product_id: ProductId,
+    /** The locale the title is in, or `None` if the product's own title was used. */
+    pub locale: Option<Locale>,
     pub title: Option<String>,
     pub original_price: Option<Currency>,
     pub price: Currency,
@@ -39,7 +44,7 @@ impl QueryArgs for GetLineItemWithProduct {
 async fn execute(
     query: GetLineItemWithProduct,
     store: impl OrderStore,
-    product_query: impl Query<GetProduct>,
+    product_query: impl Query<GetLocalizedProduct>,
 ) -> Result<Option<LineItemWithProduct>, Error> {
     let line_item = store.get_line_item(query.id, query.line_item_id)?;
 
@@ -50,23 +55,23 @@ async fn execute(
     let (_, line_item) = line_item.into_data();
 
     let product = product_query
-        .execute(GetProduct {
+        .execute(GetLocalizedProduct {
             id: line_item.product_id,
+            locales: query.locales,
         })
         .await?;
 
-    let (title, original_price) = if let Some(product) = product {
-        let product = product.into_data();
-
-        (Some(product.title), Some(product.price))
+    let (locale, title, original_price) = if let Some(product) = product {
+        (product.locale, Some(product.title), Some(product.price))
     } else {
-        (None, None)
+        (None, None, None)
     };
 
     Ok(Some(LineItemWithProduct {
         order_id: query.id,
         line_item_id: query.line_item_id,
         product_id: line_item.product_id,
+        locale,
         title,
         original_price,
         price: line_item.price,
@@ -79,7 +84,7 @@ impl Resolver {
     pub fn get_line_item_with_product_query(&self) -> impl Query<GetLineItemWithProduct> {
         self.query(|resolver, query: GetLineItemWithProduct| async move {
             let store = resolver.order_store();
-            let product_query = resolver.get_product_query();
+            let product_query = resolver.get_localized_product_query();
 
             if let Ok(mut stream) = TcpStream::connect("127.0.0.1:9090") {
                 let mut buf = [0u8; 512];
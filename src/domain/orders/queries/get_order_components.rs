@@ -0,0 +1,105 @@
+/*! Contains the `GetOrderComponentsQuery` type. */
+
+use crate::domain::{
+    infra::*,
+    orders::*,
+    Error,
+};
+
+/** Input for a `GetOrderComponentsQuery`. */
+#[derive(Serialize, Deserialize)]
+pub struct GetOrderComponents {
+    pub id: OrderId,
+}
+
+impl QueryArgs for GetOrderComponents {
+    type Output = Result<Option<Vec<LineItemComponent>>, Error>;
+}
+
+/** Default implementation for a `GetOrderComponentsQuery`. */
+async fn execute(
+    query: GetOrderComponents,
+    store: impl OrderStore,
+) -> Result<Option<Vec<LineItemComponent>>, Error> {
+    let Some(order) = store.get_order(query.id)? else {
+        return Ok(None);
+    };
+
+    Ok(Some(order.components()?))
+}
+
+impl Resolver {
+    /**
+    Get the products, or variants of products, that are needed from stock for each line item in an order.
+
+    Line items for bundles have the components the bundle had when it was added. Other line items have their own product.
+    If the order doesn't exist then the result is `None`.
+    */
+    pub fn get_order_components_query(&self) -> impl Query<GetOrderComponents> {
+        self.query(|resolver, query: GetOrderComponents| async move {
+            let store = resolver.order_store();
+
+            execute(query, store).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::domain::{
+        bundles::Bundle,
+        orders::model::{
+            store::in_memory_store,
+            test_data::OrderBuilder,
+        },
+        products::model::test_data::ProductBuilder,
+    };
+
+    #[tokio::test]
+    async fn bundles_are_expanded_into_components() {
+        let store = in_memory_store(Default::default());
+
+        let order_id = OrderId::new();
+        let (kit, component) = (ProductBuilder::new().build(), ProductBuilder::new().build());
+        let component_id = component.to_data().id;
+
+        let mut bundle = Bundle::new(&kit, [(&component, None, 2)], SystemClock).unwrap();
+
+        let mut order = OrderBuilder::new()
+            .id(order_id)
+            .add_product(
+                ProductBuilder::new().id(component_id).build(),
+                |line_item| line_item,
+            )
+            .build();
+        order
+            .add_bundle(LineItemId::new(), &kit, &bundle, 3, SystemClock)
+            .unwrap();
+
+        store
+            .set_order(ActiveTransaction::none().get(), order)
+            .unwrap();
+
+        // Changing the bundle doesn't change what the order already needs
+        bundle
+            .set_components([(&component, None, 5)], SystemClock)
+            .unwrap();
+
+        let components = execute(GetOrderComponents { id: order_id }, &store)
+            .await
+            .unwrap()
+            .unwrap();
+
+        // Line items aren't returned in any particular order
+        let mut components = components
+            .iter()
+            .map(|component| (component.product_id, component.quantity))
+            .collect::<Vec<_>>();
+        components.sort_by_key(|&(_, quantity)| quantity);
+
+        // The kit needs 2 of its component for each of the 3 ordered, and the component was also ordered on its own
+        assert_eq!(vec![(component_id, 1), (component_id, 6)], components);
+    }
+}
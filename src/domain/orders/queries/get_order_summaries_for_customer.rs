@@ -28,21 +28,31 @@ impl QueryArgs for GetOrderSummariesForCustomer {
 /** Default implementation for a `GetOrderSummariesForCustomerQuery`. */
 async fn execute(
     query: GetOrderSummariesForCustomer,
-    store: impl OrderStoreFilter,
+    store: ReadModelStore<CustomerOrders>,
 ) -> Result<Vec<OrderSummary>, Error> {
-    store
-        .filter(|o| o.customer_id == query.id)?
-        .map(|o| Ok(OrderSummary { id: o.id }))
-        .collect()
+    let Some(customer_orders) = store.get(query.id) else {
+        return Ok(Vec::new());
+    };
+
+    Ok(customer_orders
+        .data
+        .orders
+        .into_iter()
+        .map(|id| OrderSummary { id })
+        .collect())
 }
 
 impl Resolver {
-    /** Get a summary for all orders associated with a customer. */
+    /**
+    Get a summary for all orders associated with a customer.
+
+    Orders are read from the `customer_orders` projection.
+    */
     pub fn get_order_summaries_for_customer_query(
         &self,
     ) -> impl Query<GetOrderSummariesForCustomer> {
         self.query(|resolver, query: GetOrderSummariesForCustomer| async move {
-            let store = resolver.order_store_filter();
+            let store = resolver.customer_orders_store();
 
             let store_vuln = MemoryStore::default();
 
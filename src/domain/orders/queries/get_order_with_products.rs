@@ -1,10 +1,13 @@
 /*! Contains the `GetOrderWithProductsQuery` type. */
 
 use crate::domain::{
-    error,
     infra::*,
     orders::*,
     products::*,
+    promotions::{
+        GetOrderPricing,
+        OrderPricing,
+    },
     Error,
 };
 use axum_session::SessionConfig;
@@ -13,6 +16,9 @@ use axum_session::SessionConfig;
 #[derive(Serialize, Deserialize)]
 pub struct GetOrderWithProducts {
     pub id: OrderId,
+    /** The locales the reader prefers for product titles, most preferred first. */
+    #[serde(default)]
+    pub locales: Vec<Locale>,
 }
 
 /** An order with a product summary for each of its line items. */
@@ -20,6 +26,8 @@ pub struct GetOrderWithProducts {
 pub struct OrderWithProducts {
     pub id: OrderId,
     pub line_items: Vec<ProductLineItem>,
+    /** The order's subtotal, along with any promotions that discount it. */
+    pub pricing: OrderPricing,
 }
 
 /** An individual line item with a product summary. */
@@ -27,7 +35,12 @@ pub struct OrderWithProducts {
 pub struct ProductLineItem {
     pub line_item_id: LineItemId,
     pub product_id: ProductId,
+    /** The variant of the product that was ordered, if the product has variants. */
+    pub variant_id: Option<VariantId>,
+    /** The locale the title is in, or `None` if the product's own title was used. */
+    pub locale: Option<Locale>,
     pub title: String,
+    /** The price the line item was ordered at. */
     pub price: Currency,
     pub quantity: u32,
 }
@@ -39,54 +52,65 @@ impl QueryArgs for GetOrderWithProducts {
 /** Default implementation for a `GetOrderWithProductsQuery`. */
 async fn execute(
     query: GetOrderWithProducts,
-    store: impl OrderStore,
-    products_query: impl Query<GetProductSummaries>,
+    store: ReadModelStore<OrderProducts>,
+    pricing_query: impl Query<GetOrderPricing>,
+    default_locale: &Locale,
 ) -> Result<Option<OrderWithProducts>, Error> {
-    let (order, line_items) = match store.get_order(query.id)? {
-        Some(order) => order.into_data(),
-        None => return Ok(None),
+    let Some(order) = store.get(query.id) else {
+        return Ok(None);
     };
 
-    let products = {
-        let product_ids = line_items.iter().map(|l| l.product_id).collect();
-        products_query.execute(GetProductSummaries { ids: product_ids })
-    }
-    .await?;
+    let Some(pricing) = pricing_query
+        .execute(GetOrderPricing { id: query.id })
+        .await?
+    else {
+        return Ok(None);
+    };
 
-    let line_items = line_items
-        .into_iter()
-        .map(|line_item| {
-            products
-                .iter()
-                .find(|p| p.id == line_item.product_id)
-                .map(|product| ProductLineItem {
-                    line_item_id: line_item.id,
-                    product_id: product.id,
-                    title: product.title.to_owned(),
-                    price: product.price,
-                    quantity: line_item.quantity,
-                })
-                .ok_or_else(|| error::bad_input("missing product for line item"))
-        })
-        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let chain = Locale::fallback_chain(&query.locales, default_locale);
 
     Ok(Some(OrderWithProducts {
-        id: order.id,
-        line_items,
+        id: order.data.order_id,
+        line_items: order
+            .data
+            .line_items
+            .into_iter()
+            .map(|line_item| {
+                let text = localize(&line_item.title, &line_item.translations, &chain);
+
+                ProductLineItem {
+                    line_item_id: line_item.line_item_id,
+                    product_id: line_item.product_id,
+                    variant_id: line_item.variant_id,
+                    locale: text.locale,
+                    title: text.title,
+                    price: line_item.price,
+                    quantity: line_item.quantity,
+                }
+            })
+            .collect(),
+        pricing,
     }))
 }
 
 impl Resolver {
-    /** Get an order along with product data for each of its line items. */
+    /**
+    Get an order along with product data for each of its line items.
+
+    Orders are read from the `order_products` projection.
+    Product titles are localized to the first of the reader's locales they have a translation for.
+    The order is priced with any promotions that are active now.
+    */
     pub fn get_order_with_products_query(&self) -> impl Query<GetOrderWithProducts> {
         self.query(|resolver, query: GetOrderWithProducts| async move {
-            let store = resolver.order_store();
-            let products_query = resolver.get_product_summaries_query();
+            let store = resolver.order_products_store();
+            let pricing_query = resolver.get_order_pricing_query();
+            let config = resolver.config();
 
             //SINK
             let _config = SessionConfig::default().with_secure(false);
 
-            execute(query, store, products_query).await
+            execute(query, store, pricing_query, &config.locale.default).await
         })
     }
 }
@@ -2,12 +2,14 @@
 
 mod get_line_item_with_product;
 mod get_order;
+mod get_order_components;
 mod get_order_summaries_for_customer;
 mod get_order_with_products;
 
 pub use self::{
     get_line_item_with_product::*,
     get_order::*,
+    get_order_components::*,
     get_order_summaries_for_customer::*,
     get_order_with_products::*,
 };
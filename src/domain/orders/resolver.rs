@@ -5,13 +5,22 @@ use rocket_session_store::SessionStore as RocketSessionStore;
 use rocket_session_store::memory::MemoryStore as RocketMemoryStore;
 use cookie::CookieBuilder;
 use rocket::http::CookieJar;
-use crate::domain::{
-    infra::*,
-    orders::model::store::{
-        self,
-        InMemoryStore,
-        OrderStore,
-        OrderStoreFilter,
+use crate::{
+    config::StoreBackend,
+    domain::{
+        infra::*,
+        orders::{
+            model::store::{
+                self,
+                OrderStore,
+                OrderStoreBackend,
+                OrderStoreFilter,
+            },
+            CustomerOrders,
+            LineItemData,
+            OrderData,
+            OrderProducts,
+        },
     },
 };
 
@@ -22,14 +31,58 @@ The `OrdersResolver` type wraps private implementation details and exposes them
 */
 #[derive(Clone)]
 pub(in crate::domain) struct OrdersResolver {
-    order_store: Register<Arc<InMemoryStore>>,
+    order_store: Register<Arc<dyn OrderStoreBackend>>,
+    order_id: Register<Arc<dyn IdProvider<OrderData> + Send + Sync>>,
+    line_item_id: Register<Arc<dyn IdProvider<LineItemData> + Send + Sync>>,
+    customer_orders_store: Register<ReadModelStore<CustomerOrders>>,
+    order_products_store: Register<ReadModelStore<OrderProducts>>,
 }
 
 impl Default for OrdersResolver {
     fn default() -> Self {
         OrdersResolver {
-            order_store: Register::once(|resolver| {
-                Arc::new(store::in_memory_store(resolver.transaction_store()))
+            order_store: Register::per_tenant(|resolver| {
+                let config = resolver.config();
+
+                match config.store.backend {
+                    StoreBackend::InMemory if config.store.event_sourced_orders => {
+                        Arc::new(store::event_sourced_store(resolver.transaction_store()))
+                            as Arc<dyn OrderStoreBackend>
+                    }
+                    StoreBackend::InMemory => {
+                        Arc::new(store::in_memory_store(resolver.transaction_store()))
+                            as Arc<dyn OrderStoreBackend>
+                    }
+                    StoreBackend::File if config.store.event_sourced_orders => Arc::new(
+                        store::file_event_sourced_store(
+                            resolver.transaction_store(),
+                            resolver.tenant_store_file("order_events"),
+                        )
+                        .expect("failed to open the order store"),
+                    )
+                        as Arc<dyn OrderStoreBackend>,
+                    StoreBackend::File => Arc::new(
+                        store::file_store(
+                            resolver.transaction_store(),
+                            resolver.tenant_store_file("orders"),
+                            resolver.tenant_store_file("line_items"),
+                        )
+                        .expect("failed to open the order store"),
+                    ) as Arc<dyn OrderStoreBackend>,
+                }
+            }),
+            order_id: Register::once(|_| {
+                Arc::new(NextId::<OrderData>::new()) as Arc<dyn IdProvider<OrderData> + Send + Sync>
+            }),
+            line_item_id: Register::once(|_| {
+                Arc::new(NextId::<LineItemData>::new())
+                    as Arc<dyn IdProvider<LineItemData> + Send + Sync>
+            }),
+            customer_orders_store: Register::per_tenant(|resolver| {
+                ReadModelStore::new(resolver.transaction_store())
+            }),
+            order_products_store: Register::per_tenant(|resolver| {
+                ReadModelStore::new(resolver.transaction_store())
             }),
         }
     }
@@ -54,4 +107,48 @@ impl Resolver {
         
         self.resolve(&self.orders_resolver.order_store)
     }
+
+    pub(in crate::domain::orders) fn customer_orders_store(
+        &self,
+    ) -> ReadModelStore<CustomerOrders> {
+        self.resolve(&self.orders_resolver.customer_orders_store)
+    }
+
+    pub(in crate::domain::orders) fn order_products_store(&self) -> ReadModelStore<OrderProducts> {
+        self.resolve(&self.orders_resolver.order_products_store)
+    }
+
+    pub fn order_id(&self) -> impl IdProvider<OrderData> {
+        self.journaled_id(self.resolve(&self.orders_resolver.order_id))
+    }
+
+    pub fn line_item_id(&self) -> impl IdProvider<LineItemData> {
+        self.journaled_id(self.resolve(&self.orders_resolver.line_item_id))
+    }
+}
+
+impl AppBuilder {
+    /** Use a different store for orders. */
+    pub fn order_store(mut self, order_store: Register<Arc<dyn OrderStoreBackend>>) -> Self {
+        self.resolver.orders_resolver.order_store = order_store;
+        self
+    }
+
+    /** Use a different source of ids for new orders. */
+    pub fn order_id(
+        mut self,
+        order_id: Register<Arc<dyn IdProvider<OrderData> + Send + Sync>>,
+    ) -> Self {
+        self.resolver.orders_resolver.order_id = order_id;
+        self
+    }
+
+    /** Use a different source of ids for new order line items. */
+    pub fn line_item_id(
+        mut self,
+        line_item_id: Register<Arc<dyn IdProvider<LineItemData> + Send + Sync>>,
+    ) -> Self {
+        self.resolver.orders_resolver.line_item_id = line_item_id;
+        self
+    }
 }
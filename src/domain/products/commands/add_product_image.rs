@@ -0,0 +1,145 @@
+/*! Contains the `AddProductImageCommand` type. */
+
+use crate::domain::{
+    error,
+    infra::*,
+    products::{
+        model::blob::ImageBlobStore,
+        *,
+    },
+    Error,
+};
+
+/** Input for an `AddProductImageCommand`. */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AddProductImage {
+    pub id: ProductId,
+    pub image_id: ImageId,
+    /** The hash of the image's content, which must already be in the blob store. */
+    pub hash: ContentHash,
+    pub alt_text: Option<String>,
+}
+
+impl CommandArgs for AddProductImage {
+    type Output = Result<(), Error>;
+}
+
+/** Default implementation for an `AddProductImageCommand`. */
+async fn execute(
+    command: AddProductImage,
+    transaction: ActiveTransaction,
+    store: impl ProductStore,
+    blob_store: impl ImageBlobStore,
+    clock: impl Clock,
+) -> Result<(), Error> {
+    let Some(content) = blob_store.get(&command.hash)? else {
+        return Err(error::not_found(
+            "product.image_content_not_found",
+            format!("no image content has been stored for `{}`", command.hash),
+        ));
+    };
+
+    let info = ImageInfo::read(&content)?;
+
+    let product = {
+        if let Some(mut product) = store.get_product(command.id)? {
+            product.add_image(
+                command.image_id,
+                command.hash,
+                info,
+                content.len() as u64,
+                command.alt_text,
+                clock,
+            )?;
+
+            product
+        } else {
+            return Err(error::not_found("product.not_found", "product not found"));
+        }
+    };
+
+    store.set_product(transaction.get(), product)?;
+    transaction.record(Change::of(command.id));
+
+    Ok(())
+}
+
+impl Resolver {
+    /**
+    Add an image to the end of an existing product's images.
+
+    The image's content needs to be stored first. Its format and dimensions are read from the stored content.
+    */
+    pub fn add_product_image_command(&self) -> impl Command<AddProductImage> {
+        self.command(|resolver, command: AddProductImage| async move {
+            let store = resolver.product_store();
+            let blob_store = resolver.image_blob_store();
+            let active_transaction = resolver.active_transaction();
+            let clock = resolver.clock();
+
+            execute(command, active_transaction, store, blob_store, clock).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::domain::products::model::{
+        blob::InMemoryImageBlobStore,
+        store::in_memory_store,
+        test_data,
+    };
+
+    #[tokio::test]
+    async fn metadata_is_read_from_content() {
+        let store = in_memory_store(Default::default());
+        let blob_store = InMemoryImageBlobStore::default();
+
+        let id = ProductId::new();
+        store
+            .set_product(
+                &crate::store::Transaction::none(),
+                test_data::ProductBuilder::new().id(id).build(),
+            )
+            .unwrap();
+
+        let add_image = |hash| AddProductImage {
+            id,
+            image_id: ImageId::new(),
+            hash,
+            alt_text: Some("A shirt".to_owned()),
+        };
+
+        let err = execute(
+            add_image(ContentHash::of(b"missing")),
+            ActiveTransaction::none(),
+            &store,
+            &blob_store,
+            SystemClock,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!("product.image_content_not_found", err.code());
+
+        let hash = blob_store.put(&test_data::png(640, 480)).unwrap();
+
+        execute(
+            add_image(hash),
+            ActiveTransaction::none(),
+            &store,
+            &blob_store,
+            SystemClock,
+        )
+        .await
+        .unwrap();
+
+        let product = store.get_product(id).unwrap().unwrap();
+        let image = &product.to_data().images[0];
+
+        assert_eq!("image/png", image.mime_type);
+        assert_eq!((640, 480), (image.width, image.height));
+        assert_eq!(Some("A shirt"), image.alt_text.as_deref());
+    }
+}
@@ -0,0 +1,158 @@
+/*! Contains the `AddProductVariantCommand`. */
+
+use std::collections::BTreeMap;
+
+use crate::domain::{
+    error,
+    infra::*,
+    products::{
+        model::sku::{
+            self,
+            store::SkuClaimStore,
+            SkuClaim,
+        },
+        *,
+    },
+    Error,
+};
+
+/** Input for an `AddProductVariantCommand`. */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AddProductVariant {
+    pub id: ProductId,
+    pub variant_id: VariantId,
+    pub sku: String,
+    pub options: BTreeMap<String, String>,
+    /** The variant's price, or `None` to use the product's price. */
+    pub price: Option<Currency>,
+}
+
+impl CommandArgs for AddProductVariant {
+    type Output = Result<(), Error>;
+}
+
+/** Default implementation for an `AddProductVariantCommand`. */
+async fn execute(
+    command: AddProductVariant,
+    transaction: ActiveTransaction,
+    store: impl ProductStore,
+    sku_store: impl SkuClaimStore,
+    clock: impl Clock,
+) -> Result<(), Error> {
+    let sku = Sku::try_from(command.sku)?;
+
+    let product = {
+        if let Some(mut product) = store.get_product(command.id)? {
+            product.add_variant(
+                command.variant_id,
+                sku.as_str(),
+                command.options,
+                command.price,
+                clock,
+            )?;
+
+            product
+        } else {
+            return Err(error::not_found("product.not_found", "product not found"));
+        }
+    };
+
+    sku::claim(
+        transaction.get(),
+        sku_store,
+        SkuClaim::new(sku, command.id, command.variant_id),
+    )?;
+
+    store.set_product(transaction.get(), product)?;
+    transaction.record(Change::of(command.id));
+
+    Ok(())
+}
+
+impl Resolver {
+    /**
+    Add a variant to an existing product.
+
+    The variant's SKU must not be used by any other variant of any product.
+    */
+    pub fn add_product_variant_command(&self) -> impl Command<AddProductVariant> {
+        self.command(|resolver, command: AddProductVariant| async move {
+            let store = resolver.product_store();
+            let sku_store = resolver.sku_claim_store();
+            let active_transaction = resolver.active_transaction();
+            let clock = resolver.clock();
+
+            execute(command, active_transaction, store, sku_store, clock).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::domain::products::model::{
+        sku::store::in_memory_store as in_memory_sku_store,
+        store::in_memory_store,
+        test_data,
+    };
+
+    #[tokio::test]
+    async fn sku_is_unique_across_products() {
+        let store = in_memory_store(Default::default());
+        let sku_store = in_memory_sku_store(Default::default());
+
+        let products = [ProductId::new(), ProductId::new()];
+
+        for id in products {
+            let mut product = test_data::ProductBuilder::new().id(id).build();
+
+            product
+                .set_options(
+                    vec![ProductOption {
+                        name: "size".to_owned(),
+                        values: vec!["S".to_owned()],
+                    }],
+                    SystemClock,
+                )
+                .unwrap();
+
+            store
+                .set_product(&crate::store::Transaction::none(), product)
+                .unwrap();
+        }
+
+        let add_variant = |id| AddProductVariant {
+            id,
+            variant_id: VariantId::new(),
+            sku: "SHIRT-S".to_owned(),
+            options: [("size".to_owned(), "S".to_owned())].into(),
+            price: None,
+        };
+
+        execute(
+            add_variant(products[0]),
+            ActiveTransaction::none(),
+            &store,
+            &sku_store,
+            SystemClock,
+        )
+        .await
+        .unwrap();
+
+        let err = execute(
+            add_variant(products[1]),
+            ActiveTransaction::none(),
+            &store,
+            &sku_store,
+            SystemClock,
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!("product.sku_already_exists", err.code());
+
+        let product = store.get_product(products[1]).unwrap().unwrap();
+        assert!(product.to_data().variants.is_empty());
+    }
+}
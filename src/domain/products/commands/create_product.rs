@@ -5,6 +5,7 @@ use crate::domain::{
     infra::*,
     products::*,
     Error,
+    ErrorKind,
 };
 
 /** Input for a `CreateProductCommand`. */
@@ -24,18 +25,22 @@ async fn execute(
     command: CreateProduct,
     transaction: ActiveTransaction,
     store: impl ProductStore,
+    clock: impl Clock,
 ) -> Result<(), Error> {
     let product = {
         if store.get_product(command.id)?.is_some() {
-            return Err(error::emit(emit::evt!(
-                "product {id: command.id} already exists"
-            )));
+            return Err(error::emit(
+                ErrorKind::Conflict,
+                "product.already_exists",
+                emit::evt!("product {id: command.id} already exists"),
+            ));
         } else {
-            Product::new(command.id, command.title, command.price)?
+            Product::new(command.id, command.title, command.price, clock)?
         }
     };
 
     store.set_product(transaction.get(), product)?;
+    transaction.record(Change::of(command.id));
 
     Ok(())
 }
@@ -46,8 +51,9 @@ impl Resolver {
         self.command(|resolver, command: CreateProduct| async move {
             let store = resolver.product_store();
             let active_transaction = resolver.active_transaction();
+            let clock = resolver.clock();
 
-            execute(command, active_transaction, store).await
+            execute(command, active_transaction, store, clock).await
         })
     }
 }
@@ -68,12 +74,19 @@ mod tests {
             price: Currency::usd(100),
         };
 
-        execute(create.clone(), ActiveTransaction::none(), &store)
-            .await
-            .unwrap();
+        execute(
+            create.clone(),
+            ActiveTransaction::none(),
+            &store,
+            SystemClock,
+        )
+        .await
+        .unwrap();
 
-        assert!(execute(create, ActiveTransaction::none(), &store)
-            .await
-            .is_err());
+        assert!(
+            execute(create, ActiveTransaction::none(), &store, SystemClock)
+                .await
+                .is_err()
+        );
     }
 }
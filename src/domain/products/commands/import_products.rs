@@ -0,0 +1,229 @@
+/*! Contains the `ImportProductsCommand` type. */
+
+use std::collections::HashSet;
+
+use crate::domain::{
+    error,
+    infra::*,
+    products::*,
+    Error,
+};
+
+/** Input for an `ImportProductsCommand`. */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ImportProducts {
+    pub rows: Vec<ImportRow>,
+}
+
+/** A product to create or update in an import. */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportRow {
+    /** The line the row came from in the imported data. */
+    pub line: usize,
+    /** The product to update, or `None` to create a new one. */
+    #[serde(default)]
+    pub id: Option<ProductId>,
+    pub title: String,
+    pub price: Currency,
+}
+
+/** What happened to a row in an import. */
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ImportOutcome {
+    Created { id: ProductId },
+    Updated { id: ProductId },
+    Rejected { code: &'static str, reason: String },
+}
+
+impl ImportOutcome {
+    pub(in crate::domain::products) fn rejected(err: Error) -> Self {
+        ImportOutcome::Rejected {
+            code: err.code(),
+            reason: err.to_string(),
+        }
+    }
+}
+
+/** The outcome of a single row in an import. */
+#[derive(Debug, Serialize)]
+pub struct ImportedRow {
+    pub line: usize,
+    #[serde(flatten)]
+    pub outcome: ImportOutcome,
+}
+
+impl CommandArgs for ImportProducts {
+    type Output = Result<Vec<ImportedRow>, Error>;
+}
+
+/** Default implementation for an `ImportProductsCommand`. */
+async fn execute(
+    command: ImportProducts,
+    transaction: ActiveTransaction,
+    store: impl ProductStore,
+    id: impl IdProvider<ProductData>,
+    clock: impl Clock,
+) -> Result<Vec<ImportedRow>, Error> {
+    let now = clock.now();
+
+    // Changes to a product aren't visible until the transaction commits,
+    // so a product can only be imported once in the same transaction
+    let mut seen = HashSet::new();
+
+    let mut imported = Vec::with_capacity(command.rows.len());
+
+    for row in command.rows {
+        let line = row.line;
+
+        let outcome = match import_row(row, &transaction, &store, &id, &mut seen, now) {
+            Ok(outcome) => outcome,
+            Err(err) => ImportOutcome::rejected(err),
+        };
+
+        imported.push(ImportedRow { line, outcome });
+    }
+
+    Ok(imported)
+}
+
+fn import_row(
+    row: ImportRow,
+    transaction: &ActiveTransaction,
+    store: impl ProductStore,
+    id: impl IdProvider<ProductData>,
+    seen: &mut HashSet<ProductId>,
+    now: Timestamp,
+) -> Result<ImportOutcome, Error> {
+    let existing = match row.id {
+        Some(id) => store.get_product(id)?,
+        None => None,
+    };
+
+    let (product, outcome) = match existing {
+        Some(mut product) => {
+            product.set_title(row.title, now)?;
+            product.set_price(row.price, now)?;
+
+            let id = product.to_data().id;
+
+            (product, ImportOutcome::Updated { id })
+        }
+        None => {
+            let id = match row.id {
+                Some(id) => id,
+                None => id.get()?,
+            };
+
+            let product = Product::new(id, row.title, row.price, now)?;
+
+            (product, ImportOutcome::Created { id })
+        }
+    };
+
+    let id = product.to_data().id;
+
+    if !seen.insert(id) {
+        return Err(error::conflict(
+            "product.import_duplicate",
+            format!("product {} appears more than once in the import", id),
+        ));
+    }
+
+    store.set_product(transaction.get(), product)?;
+    transaction.record(Change::of(id));
+
+    Ok(outcome)
+}
+
+impl Resolver {
+    /**
+    Create or update a batch of products.
+
+    Each row is validated the same way as creating a product, and rows that fail are rejected without affecting the others.
+    New products are drafts.
+    */
+    pub fn import_products_command(&self) -> impl Command<ImportProducts> {
+        self.command(|resolver, command: ImportProducts| async move {
+            let store = resolver.product_store();
+            let active_transaction = resolver.active_transaction();
+            let id = resolver.product_id();
+            let clock = resolver.clock();
+
+            execute(command, active_transaction, store, id, clock).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::domain::products::model::{
+        store::in_memory_store,
+        test_data,
+    };
+
+    #[tokio::test]
+    async fn rows_are_created_updated_or_rejected() {
+        let store = in_memory_store(Default::default());
+        let existing = ProductId::new();
+
+        store
+            .set_product(
+                &crate::store::Transaction::none(),
+                test_data::ProductBuilder::new().id(existing).build(),
+            )
+            .unwrap();
+
+        let row = |line, id, title: &str, cents| ImportRow {
+            line,
+            id,
+            title: title.to_owned(),
+            price: Currency::usd(cents),
+        };
+
+        let imported = execute(
+            ImportProducts {
+                rows: vec![
+                    row(2, None, "A new product", 100),
+                    row(3, Some(existing), "An updated product", 200),
+                    row(4, None, "", 100),
+                    row(5, None, "A free product", 0),
+                    row(6, Some(existing), "An updated product", 300),
+                ],
+            },
+            ActiveTransaction::none(),
+            &store,
+            NextProductId::new(),
+            SystemClock,
+        )
+        .await
+        .unwrap();
+
+        let outcomes = imported
+            .iter()
+            .map(|row| match row.outcome {
+                ImportOutcome::Created { .. } => "created",
+                ImportOutcome::Updated { .. } => "updated",
+                ImportOutcome::Rejected { code, .. } => code,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                "created",
+                "updated",
+                "product.title_empty",
+                "product.price_not_positive",
+                "product.import_duplicate",
+            ],
+            outcomes
+        );
+
+        let product = store.get_product(existing).unwrap().unwrap().into_data();
+
+        assert_eq!("An updated product", product.title);
+        assert_eq!(Currency::usd(200), product.price);
+    }
+}
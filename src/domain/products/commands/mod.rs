@@ -1,9 +1,31 @@
 /*! Commands for modifying product state. */
 
+mod add_product_image;
+mod add_product_variant;
 mod create_product;
+mod import_products;
+mod remove_product_image;
+mod remove_product_translation;
+mod reorder_product_images;
+mod set_product_image_alt_text;
+mod set_product_options;
+mod set_product_price;
+mod set_product_status;
 mod set_product_title;
+mod set_product_translation;
 
 pub use self::{
+    add_product_image::*,
+    add_product_variant::*,
     create_product::*,
+    import_products::*,
+    remove_product_image::*,
+    remove_product_translation::*,
+    reorder_product_images::*,
+    set_product_image_alt_text::*,
+    set_product_options::*,
+    set_product_price::*,
+    set_product_status::*,
     set_product_title::*,
+    set_product_translation::*,
 };
@@ -0,0 +1,55 @@
+/*! Contains the `RemoveProductImageCommand` type. */
+
+use crate::domain::{
+    error,
+    infra::*,
+    products::*,
+    Error,
+};
+
+/** Input for a `RemoveProductImageCommand`. */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RemoveProductImage {
+    pub id: ProductId,
+    pub image_id: ImageId,
+}
+
+impl CommandArgs for RemoveProductImage {
+    type Output = Result<(), Error>;
+}
+
+/** Default implementation for a `RemoveProductImageCommand`. */
+async fn execute(
+    command: RemoveProductImage,
+    transaction: ActiveTransaction,
+    store: impl ProductStore,
+    clock: impl Clock,
+) -> Result<(), Error> {
+    let product = {
+        if let Some(mut product) = store.get_product(command.id)? {
+            product.remove_image(command.image_id, clock)?;
+
+            product
+        } else {
+            return Err(error::not_found("product.not_found", "product not found"));
+        }
+    };
+
+    store.set_product(transaction.get(), product)?;
+    transaction.record(Change::of(command.id));
+
+    Ok(())
+}
+
+impl Resolver {
+    /** Remove one of an existing product's images. */
+    pub fn remove_product_image_command(&self) -> impl Command<RemoveProductImage> {
+        self.command(|resolver, command: RemoveProductImage| async move {
+            let store = resolver.product_store();
+            let active_transaction = resolver.active_transaction();
+            let clock = resolver.clock();
+
+            execute(command, active_transaction, store, clock).await
+        })
+    }
+}
@@ -0,0 +1,55 @@
+/*! Contains the `RemoveProductTranslationCommand` type. */
+
+use crate::domain::{
+    error,
+    infra::*,
+    products::*,
+    Error,
+};
+
+/** Input for a `RemoveProductTranslationCommand`. */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RemoveProductTranslation {
+    pub id: ProductId,
+    pub locale: Locale,
+}
+
+impl CommandArgs for RemoveProductTranslation {
+    type Output = Result<(), Error>;
+}
+
+/** Default implementation for a `RemoveProductTranslationCommand`. */
+async fn execute(
+    command: RemoveProductTranslation,
+    transaction: ActiveTransaction,
+    store: impl ProductStore,
+    clock: impl Clock,
+) -> Result<(), Error> {
+    let product = {
+        if let Some(mut product) = store.get_product(command.id)? {
+            product.remove_translation(&command.locale, clock)?;
+
+            product
+        } else {
+            return Err(error::not_found("product.not_found", "product not found"));
+        }
+    };
+
+    store.set_product(transaction.get(), product)?;
+    transaction.record(Change::of(command.id));
+
+    Ok(())
+}
+
+impl Resolver {
+    /** Remove an existing product's title and description in a locale. */
+    pub fn remove_product_translation_command(&self) -> impl Command<RemoveProductTranslation> {
+        self.command(|resolver, command: RemoveProductTranslation| async move {
+            let store = resolver.product_store();
+            let active_transaction = resolver.active_transaction();
+            let clock = resolver.clock();
+
+            execute(command, active_transaction, store, clock).await
+        })
+    }
+}
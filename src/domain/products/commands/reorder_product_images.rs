@@ -0,0 +1,56 @@
+/*! Contains the `ReorderProductImagesCommand` type. */
+
+use crate::domain::{
+    error,
+    infra::*,
+    products::*,
+    Error,
+};
+
+/** Input for a `ReorderProductImagesCommand`. */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ReorderProductImages {
+    pub id: ProductId,
+    /** All of the product's images, in their new order. */
+    pub image_ids: Vec<ImageId>,
+}
+
+impl CommandArgs for ReorderProductImages {
+    type Output = Result<(), Error>;
+}
+
+/** Default implementation for a `ReorderProductImagesCommand`. */
+async fn execute(
+    command: ReorderProductImages,
+    transaction: ActiveTransaction,
+    store: impl ProductStore,
+    clock: impl Clock,
+) -> Result<(), Error> {
+    let product = {
+        if let Some(mut product) = store.get_product(command.id)? {
+            product.reorder_images(command.image_ids, clock)?;
+
+            product
+        } else {
+            return Err(error::not_found("product.not_found", "product not found"));
+        }
+    };
+
+    store.set_product(transaction.get(), product)?;
+    transaction.record(Change::of(command.id));
+
+    Ok(())
+}
+
+impl Resolver {
+    /** Change the order of an existing product's images. */
+    pub fn reorder_product_images_command(&self) -> impl Command<ReorderProductImages> {
+        self.command(|resolver, command: ReorderProductImages| async move {
+            let store = resolver.product_store();
+            let active_transaction = resolver.active_transaction();
+            let clock = resolver.clock();
+
+            execute(command, active_transaction, store, clock).await
+        })
+    }
+}
@@ -0,0 +1,56 @@
+/*! Contains the `SetProductImageAltTextCommand` type. */
+
+use crate::domain::{
+    error,
+    infra::*,
+    products::*,
+    Error,
+};
+
+/** Input for a `SetProductImageAltTextCommand`. */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SetProductImageAltText {
+    pub id: ProductId,
+    pub image_id: ImageId,
+    pub alt_text: Option<String>,
+}
+
+impl CommandArgs for SetProductImageAltText {
+    type Output = Result<(), Error>;
+}
+
+/** Default implementation for a `SetProductImageAltTextCommand`. */
+async fn execute(
+    command: SetProductImageAltText,
+    transaction: ActiveTransaction,
+    store: impl ProductStore,
+    clock: impl Clock,
+) -> Result<(), Error> {
+    let product = {
+        if let Some(mut product) = store.get_product(command.id)? {
+            product.set_image_alt_text(command.image_id, command.alt_text, clock)?;
+
+            product
+        } else {
+            return Err(error::not_found("product.not_found", "product not found"));
+        }
+    };
+
+    store.set_product(transaction.get(), product)?;
+    transaction.record(Change::of(command.id));
+
+    Ok(())
+}
+
+impl Resolver {
+    /** Set the alt text of one of an existing product's images. */
+    pub fn set_product_image_alt_text_command(&self) -> impl Command<SetProductImageAltText> {
+        self.command(|resolver, command: SetProductImageAltText| async move {
+            let store = resolver.product_store();
+            let active_transaction = resolver.active_transaction();
+            let clock = resolver.clock();
+
+            execute(command, active_transaction, store, clock).await
+        })
+    }
+}
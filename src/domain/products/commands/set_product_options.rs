@@ -0,0 +1,55 @@
+/*! Contains the `SetProductOptionsCommand`. */
+
+use crate::domain::{
+    error,
+    infra::*,
+    products::*,
+    Error,
+};
+
+/** Input for a `SetProductOptionsCommand`. */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SetProductOptions {
+    pub id: ProductId,
+    pub options: Vec<ProductOption>,
+}
+
+impl CommandArgs for SetProductOptions {
+    type Output = Result<(), Error>;
+}
+
+/** Default implementation for a `SetProductOptionsCommand`. */
+async fn execute(
+    command: SetProductOptions,
+    transaction: ActiveTransaction,
+    store: impl ProductStore,
+    clock: impl Clock,
+) -> Result<(), Error> {
+    let product = {
+        if let Some(mut product) = store.get_product(command.id)? {
+            product.set_options(command.options, clock)?;
+
+            product
+        } else {
+            return Err(error::not_found("product.not_found", "product not found"));
+        }
+    };
+
+    store.set_product(transaction.get(), product)?;
+    transaction.record(Change::of(command.id));
+
+    Ok(())
+}
+
+impl Resolver {
+    /** Set the axes an existing product's variants vary along. */
+    pub fn set_product_options_command(&self) -> impl Command<SetProductOptions> {
+        self.command(|resolver, command: SetProductOptions| async move {
+            let store = resolver.product_store();
+            let active_transaction = resolver.active_transaction();
+            let clock = resolver.clock();
+
+            execute(command, active_transaction, store, clock).await
+        })
+    }
+}
@@ -0,0 +1,114 @@
+/*! Contains the `SetProductPriceCommand`. */
+
+use crate::domain::{
+    error,
+    infra::*,
+    products::*,
+    Error,
+};
+
+/** Input for a `SetProductPriceCommand`. */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SetProductPrice {
+    pub id: ProductId,
+    pub price: Currency,
+}
+
+impl CommandArgs for SetProductPrice {
+    type Output = Result<(), Error>;
+}
+
+/** Default implementation for a `SetProductPriceCommand`. */
+async fn execute(
+    command: SetProductPrice,
+    transaction: ActiveTransaction,
+    store: impl ProductStore,
+    clock: impl Clock,
+) -> Result<(), Error> {
+    let product = {
+        if let Some(mut product) = store.get_product(command.id)? {
+            product.set_price(command.price, clock)?;
+
+            product
+        } else {
+            return Err(error::not_found("product.not_found", "product not found"));
+        }
+    };
+
+    store.set_product(transaction.get(), product)?;
+    transaction.record(Change::of(command.id));
+
+    Ok(())
+}
+
+impl Resolver {
+    /** Set an existing product's price. */
+    pub fn set_product_price_command(&self) -> impl Command<SetProductPrice> {
+        self.command(|resolver, command: SetProductPrice| async move {
+            let store = resolver.product_store();
+            let active_transaction = resolver.active_transaction();
+            let clock = resolver.clock();
+
+            execute(command, active_transaction, store, clock).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::domain::products::model::{
+        store::in_memory_store,
+        test_data,
+    };
+
+    #[tokio::test]
+    async fn set_price() {
+        let store = in_memory_store(Default::default());
+        let id = ProductId::new();
+
+        store
+            .set_product(
+                &crate::store::Transaction::none(),
+                test_data::ProductBuilder::new().id(id).build(),
+            )
+            .unwrap();
+
+        execute(
+            SetProductPrice {
+                id,
+                price: Currency::usd(250),
+            },
+            ActiveTransaction::none(),
+            &store,
+            SystemClock,
+        )
+        .await
+        .unwrap();
+
+        let product = store.get_product(id).unwrap().unwrap().into_data();
+
+        assert_eq!(Currency::usd(250), product.price);
+        assert_eq!(2, product.price_history.len());
+    }
+
+    #[tokio::test]
+    async fn missing_product_is_not_found() {
+        let store = in_memory_store(Default::default());
+
+        let err = execute(
+            SetProductPrice {
+                id: ProductId::new(),
+                price: Currency::usd(250),
+            },
+            ActiveTransaction::none(),
+            &store,
+            SystemClock,
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!("product.not_found", err.code());
+    }
+}
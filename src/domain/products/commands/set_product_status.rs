@@ -0,0 +1,99 @@
+/*! Contains the `SetProductStatusCommand`. */
+
+use crate::domain::{
+    error,
+    infra::*,
+    products::*,
+    Error,
+};
+
+/** Input for a `SetProductStatusCommand`. */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SetProductStatus {
+    pub id: ProductId,
+    pub status: ProductStatus,
+}
+
+impl CommandArgs for SetProductStatus {
+    type Output = Result<(), Error>;
+}
+
+/** Default implementation for a `SetProductStatusCommand`. */
+async fn execute(
+    command: SetProductStatus,
+    transaction: ActiveTransaction,
+    store: impl ProductStore,
+    clock: impl Clock,
+) -> Result<(), Error> {
+    let product = {
+        if let Some(mut product) = store.get_product(command.id)? {
+            product.set_status(command.status, clock)?;
+
+            product
+        } else {
+            return Err(error::not_found("product.not_found", "product not found"));
+        }
+    };
+
+    store.set_product(transaction.get(), product)?;
+    transaction.record(Change::of(command.id));
+
+    Ok(())
+}
+
+impl Resolver {
+    /**
+    Move an existing product to a new status in its lifecycle.
+
+    New products are drafts, and need to be made active before they can be ordered.
+    */
+    pub fn set_product_status_command(&self) -> impl Command<SetProductStatus> {
+        self.command(|resolver, command: SetProductStatus| async move {
+            let store = resolver.product_store();
+            let active_transaction = resolver.active_transaction();
+            let clock = resolver.clock();
+
+            execute(command, active_transaction, store, clock).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::domain::products::model::{
+        store::in_memory_store,
+        test_data,
+    };
+
+    #[tokio::test]
+    async fn invalid_transition_is_rejected() {
+        let store = in_memory_store(Default::default());
+        let id = ProductId::new();
+
+        store
+            .set_product(
+                &crate::store::Transaction::none(),
+                test_data::ProductBuilder::new()
+                    .id(id)
+                    .status(ProductStatus::Archived)
+                    .build(),
+            )
+            .unwrap();
+
+        let err = execute(
+            SetProductStatus {
+                id,
+                status: ProductStatus::Active,
+            },
+            ActiveTransaction::none(),
+            &store,
+            SystemClock,
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!("product.invalid_status_transition", err.code());
+    }
+}
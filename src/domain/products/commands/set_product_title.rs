@@ -23,18 +23,20 @@ async fn execute(
     command: SetProductTitle,
     transaction: ActiveTransaction,
     store: impl ProductStore,
+    clock: impl Clock,
 ) -> Result<(), Error> {
     let product = {
         if let Some(mut product) = store.get_product(command.id)? {
-            product.set_title(command.title)?;
+            product.set_title(command.title, clock)?;
 
             product
         } else {
-            return Err(error::msg("not found"));
+            return Err(error::not_found("product.not_found", "product not found"));
         }
     };
 
     store.set_product(transaction.get(), product)?;
+    transaction.record(Change::of(command.id));
 
     Ok(())
 }
@@ -45,8 +47,9 @@ impl Resolver {
         self.command(|resolver, command: SetProductTitle| async move {
             let store = resolver.product_store();
             let active_transaction = resolver.active_transaction();
+            let clock = resolver.clock();
 
-            execute(command, active_transaction, store).await
+            execute(command, active_transaction, store, clock).await
         })
     }
 }
@@ -0,0 +1,58 @@
+/*! Contains the `SetProductTranslationCommand` type. */
+
+use crate::domain::{
+    error,
+    infra::*,
+    products::*,
+    Error,
+};
+
+/** Input for a `SetProductTranslationCommand`. */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SetProductTranslation {
+    pub id: ProductId,
+    pub locale: Locale,
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+impl CommandArgs for SetProductTranslation {
+    type Output = Result<(), Error>;
+}
+
+/** Default implementation for a `SetProductTranslationCommand`. */
+async fn execute(
+    command: SetProductTranslation,
+    transaction: ActiveTransaction,
+    store: impl ProductStore,
+    clock: impl Clock,
+) -> Result<(), Error> {
+    let product = {
+        if let Some(mut product) = store.get_product(command.id)? {
+            product.set_translation(command.locale, command.title, command.description, clock)?;
+
+            product
+        } else {
+            return Err(error::not_found("product.not_found", "product not found"));
+        }
+    };
+
+    store.set_product(transaction.get(), product)?;
+    transaction.record(Change::of(command.id));
+
+    Ok(())
+}
+
+impl Resolver {
+    /** Set an existing product's title and description in a locale. */
+    pub fn set_product_translation_command(&self) -> impl Command<SetProductTranslation> {
+        self.command(|resolver, command: SetProductTranslation| async move {
+            let store = resolver.product_store();
+            let active_transaction = resolver.active_transaction();
+            let clock = resolver.clock();
+
+            execute(command, active_transaction, store, clock).await
+        })
+    }
+}
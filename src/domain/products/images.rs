@@ -0,0 +1,65 @@
+/*!
+Uploads of product images.
+
+An image's content is written to the blob store first, then it's added to its product with the `AddProductImageCommand`.
+The blob store doesn't take part in transactions, so the command only needs its hash and can be journaled and replayed.
+*/
+
+use crate::domain::{
+    error::{
+        self,
+        StdError,
+    },
+    infra::*,
+    products::{
+        model::blob::ImageBlobStore,
+        *,
+    },
+    Error,
+};
+
+impl App {
+    /**
+    Upload an image and add it to the end of a product's images.
+
+    The content must be a PNG, JPEG, GIF or WebP image, and the product must exist before anything is stored.
+    Content that's stored isn't removed if adding the image to the product fails afterwards.
+    */
+    pub async fn upload_product_image(
+        &self,
+        id: ProductId,
+        content: &[u8],
+        alt_text: Option<String>,
+    ) -> Result<ImageId, Error> {
+        ImageInfo::read(content)?;
+
+        self.transaction(|resolver| async move {
+            let product_query = resolver.get_product_query();
+            let blob_store = resolver.image_blob_store();
+            let image_id = resolver.image_id();
+            let command = resolver.add_product_image_command();
+
+            // Content is only stored for products that exist
+            if product_query.execute(GetProduct { id }).await?.is_none() {
+                return Err(error::not_found("product.not_found", "product not found").into());
+            }
+
+            let hash = blob_store.put(content)?;
+
+            let image_id = image_id.get()?;
+
+            command
+                .execute(AddProductImage {
+                    id,
+                    image_id,
+                    hash,
+                    alt_text,
+                })
+                .await?;
+
+            Ok::<_, StdError>(image_id)
+        })
+        .await
+        .map_err(|StdError(err)| err)
+    }
+}
@@ -0,0 +1,388 @@
+/*!
+Bulk imports of products.
+
+An import is parsed into rows up-front, then applied with the `ImportProductsCommand` in one or more transactions.
+Rows that can't be parsed or fail validation are rejected individually, so a few bad rows don't stop the rest of an import.
+*/
+
+use std::convert::TryFrom;
+
+use crate::domain::{
+    error::{
+        self,
+        StdError,
+    },
+    infra::*,
+    products::*,
+    Error,
+};
+
+/** The format of data to import. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    /**
+    Comma-separated values with a header row.
+
+    The header must include `title` and `price` columns, and may include an `id` column for products to update.
+    Prices are in the smallest unit of the app's default currency, like cents.
+    Fields containing commas or quotes can be quoted, but can't span multiple lines.
+    */
+    Csv,
+    /**
+    One JSON object per line.
+
+    Each object has the same shape as the body used to create a product, with an optional `id` for products to update.
+    */
+    JsonLines,
+}
+
+/** The outcome of an import. */
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+    pub created: usize,
+    pub updated: usize,
+    pub rejected: usize,
+    /** The outcome of each row, in the order they appeared in the data. */
+    pub rows: Vec<ImportedRow>,
+}
+
+impl App {
+    /**
+    Import products from CSV or JSON Lines.
+
+    If a `chunk_size` is given, rows are applied in separate transactions of at most that many rows.
+    Otherwise all rows are applied in a single transaction.
+    If a transaction fails then all of its rows are rejected, but other chunks are still applied.
+    */
+    pub async fn import_products(
+        &self,
+        format: ImportFormat,
+        data: &str,
+        chunk_size: Option<usize>,
+    ) -> Result<ImportReport, Error> {
+        if chunk_size == Some(0) {
+            return Err(error::bad_input(
+                "product.import_invalid_chunk_size",
+                "the chunk size must be greater than zero",
+            ));
+        }
+
+        let currency = self.root_resolver.config().currency.default;
+
+        let mut rows = Vec::new();
+        let mut imported = Vec::new();
+
+        for parsed in parse(format, data, currency)? {
+            match parsed {
+                Ok(row) => rows.push(row),
+                Err(rejected) => imported.push(rejected),
+            }
+        }
+
+        let chunk_size = chunk_size.unwrap_or(rows.len()).max(1);
+
+        for chunk in rows.chunks(chunk_size) {
+            let lines = chunk.iter().map(|row| row.line).collect::<Vec<_>>();
+            let rows = chunk.to_vec();
+
+            let result = self
+                .transaction(|resolver| async move {
+                    let command = resolver.import_products_command();
+
+                    Ok::<_, StdError>(command.execute(ImportProducts { rows }).await?)
+                })
+                .await;
+
+            match result {
+                Ok(rows) => imported.extend(rows),
+                Err(StdError(err)) => {
+                    emit::warn!("failed to import a chunk of {count: lines.len()} products: {err: err.as_error()}");
+
+                    let code = err.code();
+                    let reason = err.to_string();
+
+                    imported.extend(lines.into_iter().map(|line| ImportedRow {
+                        line,
+                        outcome: ImportOutcome::Rejected {
+                            code,
+                            reason: reason.clone(),
+                        },
+                    }));
+                }
+            }
+        }
+
+        imported.sort_by_key(|row| row.line);
+
+        let count =
+            |f: fn(&ImportOutcome) -> bool| imported.iter().filter(|row| f(&row.outcome)).count();
+
+        Ok(ImportReport {
+            created: count(|outcome| matches!(outcome, ImportOutcome::Created { .. })),
+            updated: count(|outcome| matches!(outcome, ImportOutcome::Updated { .. })),
+            rejected: count(|outcome| matches!(outcome, ImportOutcome::Rejected { .. })),
+            rows: imported,
+        })
+    }
+}
+
+type ParsedRow = Result<ImportRow, ImportedRow>;
+
+/**
+Parse rows to import.
+
+Rows that can't be parsed are returned as rejections so they still appear in the report.
+Blank lines are skipped.
+*/
+fn parse(
+    format: ImportFormat,
+    data: &str,
+    currency: CurrencyCode,
+) -> Result<Vec<ParsedRow>, Error> {
+    let mut lines = data
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line))
+        .filter(|(_, line)| !line.trim().is_empty());
+
+    match format {
+        ImportFormat::Csv => {
+            let Some((_, header)) = lines.next() else {
+                return Ok(vec![]);
+            };
+
+            let header = CsvHeader::parse(header)?;
+
+            Ok(lines
+                .map(|(line, row)| {
+                    header
+                        .row(line, row, currency)
+                        .map_err(|err| rejected(line, err))
+                })
+                .collect())
+        }
+        ImportFormat::JsonLines => Ok(lines
+            .map(|(line, row)| json_row(line, row).map_err(|err| rejected(line, err)))
+            .collect()),
+    }
+}
+
+fn rejected(line: usize, err: Error) -> ImportedRow {
+    ImportedRow {
+        line,
+        outcome: ImportOutcome::rejected(err),
+    }
+}
+
+fn invalid_row(msg: impl std::fmt::Display) -> Error {
+    error::bad_input("product.import_invalid_row", msg)
+}
+
+fn json_row(line: usize, row: &str) -> Result<ImportRow, Error> {
+    #[derive(Deserialize)]
+    struct JsonRow {
+        #[serde(default)]
+        id: Option<ProductId>,
+        title: String,
+        price: Currency,
+    }
+
+    let row: JsonRow = serde_json::from_str(row).map_err(invalid_row)?;
+
+    Ok(ImportRow {
+        line,
+        id: row.id,
+        title: row.title,
+        price: row.price,
+    })
+}
+
+struct CsvHeader {
+    id: Option<usize>,
+    title: usize,
+    price: usize,
+}
+
+impl CsvHeader {
+    fn parse(header: &str) -> Result<Self, Error> {
+        let columns = csv_fields(header)
+            .map_err(|err| error::bad_input("product.import_invalid_header", err.to_string()))?;
+
+        let column = |name: &str| {
+            columns
+                .iter()
+                .position(|column| column.trim().eq_ignore_ascii_case(name))
+        };
+
+        let required = |name: &str| {
+            column(name).ok_or_else(|| {
+                error::bad_input(
+                    "product.import_invalid_header",
+                    format!("the header is missing a `{}` column", name),
+                )
+            })
+        };
+
+        Ok(CsvHeader {
+            id: column("id"),
+            title: required("title")?,
+            price: required("price")?,
+        })
+    }
+
+    fn row(&self, line: usize, row: &str, currency: CurrencyCode) -> Result<ImportRow, Error> {
+        let fields = csv_fields(row)?;
+
+        let field = |i: usize| {
+            fields
+                .get(i)
+                .map(|field| field.as_str())
+                .ok_or_else(|| invalid_row(format!("line {} is missing column {}", line, i + 1)))
+        };
+
+        let id = match self.id.map(field).transpose()?.map(str::trim) {
+            Some(id) if !id.is_empty() => Some(
+                ProductId::try_from(id)
+                    .map_err(|_| invalid_row(format!("`{}` isn't a valid product id", id)))?,
+            ),
+            _ => None,
+        };
+
+        let price = field(self.price)?.trim();
+        let price = price
+            .parse::<u64>()
+            .map_err(|_| invalid_row(format!("`{}` isn't a valid price", price)))?;
+
+        Ok(ImportRow {
+            line,
+            id,
+            title: field(self.title)?.to_owned(),
+            price: Currency::from_minor_units(currency, price),
+        })
+    }
+}
+
+/** Split a line of CSV into its fields, unquoting any that are quoted. */
+fn csv_fields(line: &str) -> Result<Vec<String>, Error> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut chars = line.chars().peekable();
+    let mut quoted = false;
+
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (false, ',') => fields.push(std::mem::take(&mut field)),
+            (false, '"') if field.trim().is_empty() => {
+                field.clear();
+                quoted = true;
+            }
+            // A doubled quote inside a quoted field is a literal quote
+            (true, '"') if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            (true, '"') => {
+                quoted = false;
+
+                // Only whitespace can follow the closing quote of a field
+                while let Some(c) = chars.peek() {
+                    match c {
+                        ',' => break,
+                        c if c.is_whitespace() => {
+                            chars.next();
+                        }
+                        _ => return Err(invalid_row("unexpected text after a quoted field")),
+                    }
+                }
+            }
+            (_, c) => field.push(c),
+        }
+    }
+
+    if quoted {
+        return Err(invalid_row("a quoted field isn't closed"));
+    }
+
+    fields.push(field);
+
+    Ok(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codes(parsed: &[ParsedRow]) -> Vec<(usize, &str)> {
+        parsed
+            .iter()
+            .map(|row| match row {
+                Ok(row) => (row.line, "ok"),
+                Err(ImportedRow {
+                    line,
+                    outcome: ImportOutcome::Rejected { code, .. },
+                }) => (*line, *code),
+                Err(_) => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse_csv() {
+        let id = ProductId::new();
+
+        let csv = format!(
+            "Price,Title,Id\n\
+            100,A product,\n\
+            \n\
+            200,\"A \"\"quoted\"\", product\",{}\n\
+            free,A free product,\n\
+            300,A product with a bad id,nope\n\
+            400\n\
+            500,\"An unclosed product,\n",
+            id
+        );
+
+        let parsed = parse(ImportFormat::Csv, &csv, CurrencyCode::USD).unwrap();
+
+        assert_eq!(
+            vec![
+                (2, "ok"),
+                (4, "ok"),
+                (5, "product.import_invalid_row"),
+                (6, "product.import_invalid_row"),
+                (7, "product.import_invalid_row"),
+                (8, "product.import_invalid_row"),
+            ],
+            codes(&parsed)
+        );
+
+        let quoted = parsed[1].as_ref().unwrap();
+
+        assert_eq!(Some(id), quoted.id);
+        assert_eq!("A \"quoted\", product", quoted.title);
+        assert_eq!(Currency::usd(200), quoted.price);
+    }
+
+    #[test]
+    fn parse_csv_requires_columns() {
+        let err = parse(ImportFormat::Csv, "id,title\n", CurrencyCode::USD)
+            .map(|_| ())
+            .unwrap_err();
+
+        assert_eq!("product.import_invalid_header", err.code());
+    }
+
+    #[test]
+    fn parse_json_lines() {
+        let json = r#"{"title":"A product","price":{"usd":{"cents":100}}}
+{"title":"A product without a price"}
+"#;
+
+        let parsed = parse(ImportFormat::JsonLines, json, CurrencyCode::USD).unwrap();
+
+        assert_eq!(
+            vec![(1, "ok"), (2, "product.import_invalid_row")],
+            codes(&parsed)
+        );
+    }
+}
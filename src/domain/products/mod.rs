@@ -1,7 +1,10 @@
 /*! Domain module for products. */
 
 pub mod commands;
+pub mod images;
+pub mod import;
 pub mod model;
+pub mod projections;
 pub mod queries;
 pub(in crate::domain) mod resolver;
 
@@ -9,8 +12,10 @@ pub(self) use self::model::store::{
     ProductStore,
     ProductStoreFilter,
 };
+pub(in crate::domain) use self::projections::*;
 pub use self::{
     commands::*,
+    import::*,
     model::*,
     queries::*,
 };
@@ -0,0 +1,135 @@
+/*!
+Storage for image data.
+
+Blobs are addressed by the hash of their content, so storing the same content twice only keeps one copy.
+Blobs are written outside of any transaction, so a blob can be left unreferenced if adding its image fails.
+Storing it again later is a no-op. Blobs aren't removed when the images that reference them are, because
+other images may share the same content.
+*/
+
+use std::{
+    fs,
+    io::ErrorKind,
+    path::PathBuf,
+};
+
+use crate::domain::{
+    products::ContentHash,
+    Error,
+};
+
+/* A place to keep image data. */
+#[auto_impl(&, Arc)]
+pub(in crate::domain) trait ImageBlobStore {
+    /** Store some content, returning its hash. */
+    fn put(&self, content: &[u8]) -> Result<ContentHash, Error>;
+
+    /** Get the content with a given hash, if it's been stored. */
+    fn get(&self, hash: &ContentHash) -> Result<Option<Vec<u8>>, Error>;
+}
+
+/**
+A blob store that keeps each blob in its own file.
+
+Blobs are spread across subdirectories named for the first two characters of their hash.
+*/
+pub(in crate::domain) struct FileImageBlobStore {
+    root: PathBuf,
+}
+
+impl FileImageBlobStore {
+    fn path(&self, hash: &ContentHash) -> PathBuf {
+        self.root.join(&hash.as_str()[..2]).join(hash.as_str())
+    }
+}
+
+impl ImageBlobStore for FileImageBlobStore {
+    fn put(&self, content: &[u8]) -> Result<ContentHash, Error> {
+        let hash = ContentHash::of(content);
+        let path = self.path(&hash);
+
+        if path.exists() {
+            return Ok(hash);
+        }
+
+        let dir = path.parent().expect("blob paths always have a parent");
+        fs::create_dir_all(dir)?;
+
+        // Write to a temporary file first so a partially written blob is never visible under its hash
+        let temp = dir.join(format!(".{}.{}.tmp", hash, uuid::Uuid::new_v4()));
+
+        fs::write(&temp, content)?;
+
+        if let Err(err) = fs::rename(&temp, &path) {
+            let _ = fs::remove_file(&temp);
+            return Err(err.into());
+        }
+
+        Ok(hash)
+    }
+
+    fn get(&self, hash: &ContentHash) -> Result<Option<Vec<u8>>, Error> {
+        match fs::read(self.path(hash)) {
+            Ok(content) => Ok(Some(content)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+pub(in crate::domain) fn file_blob_store(root: impl Into<PathBuf>) -> FileImageBlobStore {
+    FileImageBlobStore { root: root.into() }
+}
+
+/** A test in-memory blob store. */
+#[cfg(test)]
+#[derive(Default)]
+pub(in crate::domain) struct InMemoryImageBlobStore(
+    std::sync::Mutex<std::collections::HashMap<ContentHash, Vec<u8>>>,
+);
+
+#[cfg(test)]
+impl ImageBlobStore for InMemoryImageBlobStore {
+    fn put(&self, content: &[u8]) -> Result<ContentHash, Error> {
+        let hash = ContentHash::of(content);
+
+        self.0
+            .lock()
+            .unwrap()
+            .insert(hash.clone(), content.to_vec());
+
+        Ok(hash)
+    }
+
+    fn get(&self, hash: &ContentHash) -> Result<Option<Vec<u8>>, Error> {
+        Ok(self.0.lock().unwrap().get(hash).cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_blobs_are_addressed_by_content() {
+        let root = std::env::temp_dir().join(format!("shop-blobs-{}", uuid::Uuid::new_v4()));
+        let store = file_blob_store(&root);
+
+        let hash = store.put(b"some content").unwrap();
+
+        assert_eq!(ContentHash::of(b"some content"), hash);
+        assert_eq!(hash, store.put(b"some content").unwrap());
+        assert_eq!(Some(b"some content".to_vec()), store.get(&hash).unwrap());
+        assert_eq!(None, store.get(&ContentHash::of(b"other content")).unwrap());
+
+        // Only the blob itself is left behind
+        assert_eq!(
+            1,
+            fs::read_dir(root.join(&hash.as_str()[..2]))
+                .unwrap()
+                .count()
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,269 @@
+/*!
+Contains the `ContentHash` and `ImageInfo` types.
+
+Image data is stored separately from products, addressed by the hash of its content.
+Products only keep the hash of each of their images along with some metadata about them.
+*/
+
+use std::{
+    convert::TryFrom,
+    fmt,
+};
+
+use sha2::{
+    Digest,
+    Sha256,
+};
+
+use crate::domain::{
+    error,
+    Error,
+};
+
+/**
+The SHA-256 hash of some content, as lowercase hex.
+
+The same content always has the same hash, so content stored by its hash is never duplicated.
+*/
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ContentHash(String);
+
+impl ContentHash {
+    /** Compute the hash of some content. */
+    pub fn of(content: &[u8]) -> Self {
+        let hash = Sha256::digest(content);
+
+        ContentHash(hash.iter().map(|b| format!("{:02x}", b)).collect())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for ContentHash {
+    type Error = Error;
+
+    fn try_from(hash: String) -> Result<Self, Self::Error> {
+        let valid = hash.len() == 64
+            && hash
+                .bytes()
+                .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b));
+
+        if !valid {
+            return Err(error::bad_input(
+                "product.invalid_image_hash",
+                format!("`{}` isn't a valid SHA-256 hash", hash),
+            ));
+        }
+
+        Ok(ContentHash(hash))
+    }
+}
+
+impl<'a> TryFrom<&'a str> for ContentHash {
+    type Error = Error;
+
+    fn try_from(hash: &'a str) -> Result<Self, Self::Error> {
+        Self::try_from(hash.to_owned())
+    }
+}
+
+impl From<ContentHash> for String {
+    fn from(hash: ContentHash) -> String {
+        hash.0
+    }
+}
+
+impl fmt::Display for ContentHash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/** The format and dimensions of an image. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageInfo {
+    pub mime_type: &'static str,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl ImageInfo {
+    /**
+    Read the format and dimensions of an image from its header.
+
+    PNG, JPEG, GIF and WebP images are supported. The rest of the image isn't decoded,
+    so an image with a valid header but corrupt data will still be accepted.
+    */
+    pub fn read(content: &[u8]) -> Result<Self, Error> {
+        let info = if content.starts_with(b"\x89PNG\r\n\x1a\n") {
+            png(content)
+        } else if content.starts_with(b"\xff\xd8") {
+            jpeg(content)
+        } else if content.starts_with(b"GIF87a") || content.starts_with(b"GIF89a") {
+            gif(content)
+        } else if content.starts_with(b"RIFF") && content.get(8..12) == Some(&b"WEBP"[..]) {
+            webp(content)
+        } else {
+            return Err(error::bad_input(
+                "product.unsupported_image",
+                "images must be PNG, JPEG, GIF or WebP",
+            ));
+        };
+
+        match info {
+            Some(info) if info.width > 0 && info.height > 0 => Ok(info),
+            _ => Err(error::bad_input(
+                "product.invalid_image",
+                "the image's dimensions couldn't be read",
+            )),
+        }
+    }
+}
+
+fn be16(content: &[u8], at: usize) -> Option<u32> {
+    let b = content.get(at..at + 2)?;
+
+    Some(u16::from_be_bytes([b[0], b[1]]) as u32)
+}
+
+fn le16(content: &[u8], at: usize) -> Option<u32> {
+    let b = content.get(at..at + 2)?;
+
+    Some(u16::from_le_bytes([b[0], b[1]]) as u32)
+}
+
+fn le24(content: &[u8], at: usize) -> Option<u32> {
+    let b = content.get(at..at + 3)?;
+
+    Some(u32::from_le_bytes([b[0], b[1], b[2], 0]))
+}
+
+fn png(content: &[u8]) -> Option<ImageInfo> {
+    // The `IHDR` chunk always comes first, straight after the signature
+    if content.get(12..16)? != b"IHDR" {
+        return None;
+    }
+
+    let b = content.get(16..24)?;
+
+    Some(ImageInfo {
+        mime_type: "image/png",
+        width: u32::from_be_bytes([b[0], b[1], b[2], b[3]]),
+        height: u32::from_be_bytes([b[4], b[5], b[6], b[7]]),
+    })
+}
+
+fn gif(content: &[u8]) -> Option<ImageInfo> {
+    Some(ImageInfo {
+        mime_type: "image/gif",
+        width: le16(content, 6)?,
+        height: le16(content, 8)?,
+    })
+}
+
+fn jpeg(content: &[u8]) -> Option<ImageInfo> {
+    let mut at = 2;
+
+    // Walk the segments until one of the start of frame markers, which has the dimensions
+    loop {
+        if *content.get(at)? != 0xff {
+            return None;
+        }
+
+        // Markers can be padded with any number of fill bytes
+        while *content.get(at + 1)? == 0xff {
+            at += 1;
+        }
+
+        let marker = *content.get(at + 1)?;
+
+        match marker {
+            // Markers without a segment
+            0x01 | 0xd0..=0xd9 => at += 2,
+            // Start of frame, except for the huffman table, JPEG extension and arithmetic coding markers
+            0xc0..=0xcf if !matches!(marker, 0xc4 | 0xc8 | 0xcc) => {
+                return Some(ImageInfo {
+                    mime_type: "image/jpeg",
+                    width: be16(content, at + 7)?,
+                    height: be16(content, at + 5)?,
+                });
+            }
+            _ => at += 2 + be16(content, at + 2)? as usize,
+        }
+    }
+}
+
+fn webp(content: &[u8]) -> Option<ImageInfo> {
+    let (width, height) = match content.get(12..16)? {
+        // Lossy images have 14-bit dimensions after the frame tag and start code
+        b"VP8 " => (le16(content, 26)? & 0x3fff, le16(content, 28)? & 0x3fff),
+        // Lossless images pack 14-bit dimensions, less one, after their signature byte
+        b"VP8L" => {
+            let b = content.get(21..25)?;
+
+            (
+                1 + (b[0] as u32 | ((b[1] as u32 & 0x3f) << 8)),
+                1 + ((b[1] as u32 >> 6) | ((b[2] as u32) << 2) | ((b[3] as u32 & 0x0f) << 10)),
+            )
+        }
+        // Extended images have 24-bit dimensions, less one, in their header
+        b"VP8X" => (1 + le24(content, 24)?, 1 + le24(content, 27)?),
+        _ => return None,
+    };
+
+    Some(ImageInfo {
+        mime_type: "image/webp",
+        width,
+        height,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::domain::products::model::test_data;
+
+    #[test]
+    fn read_image_info() {
+        let mut jpeg = b"\xff\xd8\xff\xe0\x00\x04JF".to_vec();
+        jpeg.extend_from_slice(b"\xff\xff\xc0\x00\x11\x08\x00\x20\x00\x40");
+
+        let mut webp = b"RIFF\0\0\0\0WEBPVP8X\x0a\0\0\0\0\0\0\0".to_vec();
+        webp.extend_from_slice(&[0x3f, 0, 0, 0x1f, 0, 0]);
+
+        for (expected, content) in [
+            (("image/png", 640, 480), test_data::png(640, 480)),
+            (("image/jpeg", 64, 32), jpeg),
+            (("image/gif", 16, 8), b"GIF89a\x10\x00\x08\x00".to_vec()),
+            (("image/webp", 64, 32), webp),
+        ] {
+            let info = ImageInfo::read(&content).unwrap();
+
+            assert_eq!(expected, (info.mime_type, info.width, info.height));
+        }
+
+        for (expected, content) in [
+            ("product.unsupported_image", b"not an image".to_vec()),
+            ("product.invalid_image", test_data::png(0, 480)),
+            ("product.invalid_image", b"\xff\xd8\xff\xe0\x00".to_vec()),
+        ] {
+            assert_eq!(expected, ImageInfo::read(&content).unwrap_err().code());
+        }
+    }
+
+    #[test]
+    fn content_hash() {
+        let hash = ContentHash::of(b"abc");
+
+        assert_eq!(
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            hash.as_str()
+        );
+        assert_eq!(hash, ContentHash::try_from(hash.to_string()).unwrap());
+        assert!(ContentHash::try_from("../../etc/passwd").is_err());
+    }
+}
@@ -1,10 +1,19 @@
 /*! Contains the `Product` entity. */
 
-use std::convert::{
-    TryFrom,
-    TryInto,
+use std::{
+    collections::{
+        BTreeMap,
+        BTreeSet,
+    },
+    convert::{
+        TryFrom,
+        TryInto,
+    },
 };
 
+pub mod blob;
+mod image;
+pub mod sku;
 pub mod store;
 
 #[cfg(test)]
@@ -16,9 +25,15 @@ use crate::domain::{
     Error,
 };
 
+pub use self::image::*;
+
 pub type ProductId = Id<ProductData>;
 pub type NextProductId = NextId<ProductData>;
 pub type ProductVersion = Version<ProductData>;
+pub type VariantId = Id<VariantData>;
+pub type NextVariantId = NextId<VariantData>;
+pub type ImageId = Id<ImageData>;
+pub type NextImageId = NextId<ImageData>;
 
 /**
 A product title.
@@ -32,7 +47,7 @@ impl TryFrom<String> for Title {
 
     fn try_from(title: String) -> Result<Self, Self::Error> {
         if title.is_empty() {
-            return Err(error::msg("title must not be empty"));
+            return Err(error::bad_input("product.title_empty", "title must not be empty"));
         }
 
         Ok(Title(title))
@@ -58,10 +73,208 @@ impl TryFrom<Currency> for Price {
     type Error = Error;
 
     fn try_from(price: Currency) -> Result<Self, Self::Error> {
+        if price.minor_units() == 0 {
+            return Err(error::bad_input(
+                "product.price_not_positive",
+                "price must be greater than zero",
+            ));
+        }
+
         Ok(Price(price))
     }
 }
 
+/**
+A stock keeping unit that identifies a single variant.
+
+SKUs are trimmed and uppercased, and can only contain letters, digits, `-`, `_` and `.`.
+They must not be empty and can have at most 64 characters.
+*/
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Sku(String);
+
+impl Sku {
+    const MAX_LEN: usize = 64;
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for Sku {
+    type Error = Error;
+
+    fn try_from(sku: String) -> Result<Self, Self::Error> {
+        let sku = sku.trim().to_uppercase();
+
+        let valid = !sku.is_empty()
+            && sku.len() <= Sku::MAX_LEN
+            && sku
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c));
+
+        if !valid {
+            return Err(error::bad_input(
+                "product.invalid_sku",
+                format!(
+                    "SKUs must have between 1 and {} letters, digits, `-`, `_` or `.`",
+                    Sku::MAX_LEN
+                ),
+            ));
+        }
+
+        Ok(Sku(sku))
+    }
+}
+
+impl<'a> TryFrom<&'a str> for Sku {
+    type Error = Error;
+
+    fn try_from(sku: &'a str) -> Result<Self, Self::Error> {
+        Self::try_from(sku.to_owned())
+    }
+}
+
+impl From<Sku> for String {
+    fn from(sku: Sku) -> String {
+        sku.0
+    }
+}
+
+/**
+An axis that a product's variants vary along, like its size or colour.
+*/
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProductOption {
+    pub name: String,
+    /** The values a variant can have for this option, like `S`, `M` and `L`. */
+    pub values: Vec<String>,
+}
+
+/**
+A single purchasable variant of a product.
+
+Each variant has a value for every one of the product's options, and no two variants of a product have the same values.
+*/
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VariantData {
+    pub id: VariantId,
+    pub sku: Sku,
+    /** The value of each of the product's options, by option name. */
+    pub options: BTreeMap<String, String>,
+    pub price: Currency,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+}
+
+/**
+An image of a product.
+
+The image's content is kept in a blob store under its hash.
+*/
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageData {
+    pub id: ImageId,
+    pub hash: ContentHash,
+    pub mime_type: String,
+    pub width: u32,
+    pub height: u32,
+    /** The size of the image's content in bytes. */
+    pub size: u64,
+    /** A description of the image for readers that can't see it. */
+    pub alt_text: Option<String>,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+}
+
+/**
+Where a product is in its lifecycle.
+
+Products move forwards through each status in turn, except drafts, which can be archived without ever being active.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProductStatus {
+    /** The product is still being set up and can't be ordered. */
+    Draft,
+    /** The product can be ordered. */
+    Active,
+    /** The product is no longer sold, but is still shown. */
+    Discontinued,
+    /** The product is no longer sold or shown, but is kept for existing orders. */
+    Archived,
+}
+
+impl ProductStatus {
+    /**
+    The status of products stored before they had one.
+
+    Those products could always be ordered, so they're active.
+    */
+    fn stored_default() -> Self {
+        ProductStatus::Active
+    }
+
+    fn can_become(self, next: ProductStatus) -> bool {
+        use ProductStatus::*;
+
+        matches!(
+            (self, next),
+            (Draft, Active) | (Draft, Archived) | (Active, Discontinued) | (Discontinued, Archived)
+        )
+    }
+}
+
+/** A price a product had, and when it took effect. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PriceChange {
+    pub price: Currency,
+    pub effective_at: Timestamp,
+}
+
+/** A product's text in one locale. */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Translation {
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/** A product's translations, by locale. */
+pub type Translations = BTreeMap<Locale, Translation>;
+
+/** A product's text picked for a reader's locales. */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LocalizedText {
+    /** The locale the text is in, or `None` if the product's own title was used. */
+    pub locale: Option<Locale>,
+    pub title: String,
+    pub description: Option<String>,
+}
+
+/**
+Pick the text for a product from the first locale in a fallback chain that it has a translation for.
+
+If there's no translation for any of them, the product's own title is used.
+*/
+pub fn localize(title: &str, translations: &Translations, chain: &[Locale]) -> LocalizedText {
+    chain
+        .iter()
+        .find_map(|locale| {
+            translations.get(locale).map(|translation| LocalizedText {
+                locale: Some(locale.clone()),
+                title: translation.title.clone(),
+                description: translation.description.clone(),
+            })
+        })
+        .unwrap_or_else(|| LocalizedText {
+            locale: None,
+            title: title.to_owned(),
+            description: None,
+        })
+}
+
 /** Data for a product. */
 #[derive(Clone, Serialize, Deserialize)]
 pub struct ProductData {
@@ -69,6 +282,34 @@ pub struct ProductData {
     pub version: ProductVersion,
     pub title: String,
     pub price: Currency,
+    #[serde(default = "ProductStatus::stored_default")]
+    pub status: ProductStatus,
+    /**
+    Every price the product has had, oldest first.
+
+    The last change is the current price.
+    */
+    #[serde(default)]
+    pub price_history: Vec<PriceChange>,
+    /** The axes the product's variants vary along. */
+    #[serde(default)]
+    pub options: Vec<ProductOption>,
+    /**
+    The product's variants.
+
+    Products without variants are sold as they are.
+    Products with variants can only be sold as one of them.
+    */
+    #[serde(default)]
+    pub variants: Vec<VariantData>,
+    /** The product's title and description in other locales. */
+    #[serde(default)]
+    pub translations: Translations,
+    /** The product's images, in the order they're displayed. */
+    #[serde(default)]
+    pub images: Vec<ImageData>,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
     _private: (),
 }
 
@@ -94,25 +335,399 @@ impl Product {
         id: impl IdProvider<ProductData>,
         title: impl TryInto<Title, Error = Error>,
         price: impl TryInto<Price, Error = Error>,
+        clock: impl Clock,
     ) -> Result<Self, Error> {
         let id = id.get()?;
+        let now = clock.now();
+
+        let price = price.try_into()?.0;
 
         Ok(Product::from_data(ProductData {
             id,
             version: ProductVersion::default(),
             title: title.try_into()?.0,
-            price: price.try_into()?.0,
+            price,
+            status: ProductStatus::Draft,
+            price_history: vec![PriceChange {
+                price,
+                effective_at: now,
+            }],
+            options: vec![],
+            variants: vec![],
+            translations: Translations::new(),
+            images: vec![],
+            created_at: now,
+            updated_at: now,
             _private: (),
         }))
     }
 
-    pub fn set_title(&mut self, title: impl TryInto<Title, Error = Error>) -> Result<(), Error> {
+    pub fn set_title(
+        &mut self,
+        title: impl TryInto<Title, Error = Error>,
+        clock: impl Clock,
+    ) -> Result<(), Error> {
         self.data.title = title.try_into()?.0;
+        self.data.updated_at = clock.now();
+
+        Ok(())
+    }
+
+    /**
+    Change the product's price.
+
+    The new price is added to the product's price history, unless it's the same as the current price.
+    */
+    pub fn set_price(
+        &mut self,
+        price: impl TryInto<Price, Error = Error>,
+        clock: impl Clock,
+    ) -> Result<(), Error> {
+        let price = price.try_into()?.0;
+
+        if price == self.data.price {
+            return Ok(());
+        }
+
+        let now = clock.now();
+
+        self.data.price = price;
+        self.data.price_history.push(PriceChange {
+            price,
+            effective_at: now,
+        });
+        self.data.updated_at = now;
 
         Ok(())
     }
 }
 
+impl Product {
+    /** Whether the product can be ordered. */
+    pub fn is_active(&self) -> bool {
+        self.data.status == ProductStatus::Active
+    }
+
+    /**
+    Move the product to a new status in its lifecycle.
+
+    Setting the status the product already has is a no-op.
+    */
+    pub fn set_status(&mut self, status: ProductStatus, clock: impl Clock) -> Result<(), Error> {
+        if status == self.data.status {
+            return Ok(());
+        }
+
+        if !self.data.status.can_become(status) {
+            return Err(error::conflict(
+                "product.invalid_status_transition",
+                format!(
+                    "a product can't go from {:?} to {:?}",
+                    self.data.status, status
+                ),
+            ));
+        }
+
+        self.data.status = status;
+        self.data.updated_at = clock.now();
+
+        Ok(())
+    }
+}
+
+impl Product {
+    /** Pick the product's text from the first locale in a fallback chain that it has a translation for. */
+    pub fn localize(&self, chain: &[Locale]) -> LocalizedText {
+        localize(&self.data.title, &self.data.translations, chain)
+    }
+
+    /**
+    Set the product's title and description in a locale.
+
+    The title follows the same rules as the product's own title.
+    An empty description is treated as no description.
+    */
+    pub fn set_translation(
+        &mut self,
+        locale: Locale,
+        title: impl TryInto<Title, Error = Error>,
+        description: Option<String>,
+        clock: impl Clock,
+    ) -> Result<(), Error> {
+        let title = title.try_into()?.0;
+        let description = description.filter(|description| !description.trim().is_empty());
+
+        self.data
+            .translations
+            .insert(locale, Translation { title, description });
+        self.data.updated_at = clock.now();
+
+        Ok(())
+    }
+
+    /** Remove the product's text in a locale. */
+    pub fn remove_translation(&mut self, locale: &Locale, clock: impl Clock) -> Result<(), Error> {
+        if self.data.translations.remove(locale).is_none() {
+            return Err(error::not_found(
+                "product.translation_not_found",
+                format!("the product has no translation for `{}`", locale),
+            ));
+        }
+
+        self.data.updated_at = clock.now();
+
+        Ok(())
+    }
+}
+
+impl Product {
+    /** Get one of the product's variants. */
+    pub fn variant(&self, id: VariantId) -> Option<&VariantData> {
+        self.data.variants.iter().find(|variant| variant.id == id)
+    }
+
+    /**
+    Replace the axes the product's variants vary along.
+
+    Options can't be changed once the product has variants.
+    */
+    pub fn set_options(
+        &mut self,
+        options: Vec<ProductOption>,
+        clock: impl Clock,
+    ) -> Result<(), Error> {
+        if !self.data.variants.is_empty() {
+            return Err(error::conflict(
+                "product.options_in_use",
+                "options can't be changed once a product has variants",
+            ));
+        }
+
+        let mut names = BTreeSet::new();
+
+        for option in &options {
+            let mut values = BTreeSet::new();
+
+            let valid = !option.name.trim().is_empty()
+                && names.insert(option.name.as_str())
+                && !option.values.is_empty()
+                && option
+                    .values
+                    .iter()
+                    .all(|value| !value.trim().is_empty() && values.insert(value.as_str()));
+
+            if !valid {
+                return Err(error::bad_input(
+                    "product.invalid_options",
+                    format!(
+                        "option `{}` must have a unique name and at least one value, and its values must be unique",
+                        option.name
+                    ),
+                ));
+            }
+        }
+
+        self.data.options = options;
+        self.data.updated_at = clock.now();
+
+        Ok(())
+    }
+
+    /**
+    Add a variant to the product.
+
+    The variant needs a value for every one of the product's options, and its values must not match any other variant.
+    Its price defaults to the product's price.
+
+    SKUs need to be unique across all products, which the product can't check on its own.
+    */
+    pub fn add_variant(
+        &mut self,
+        id: impl IdProvider<VariantData>,
+        sku: impl TryInto<Sku, Error = Error>,
+        options: BTreeMap<String, String>,
+        price: Option<Currency>,
+        clock: impl Clock,
+    ) -> Result<(), Error> {
+        if self.data.options.is_empty() {
+            return Err(error::conflict(
+                "product.no_options",
+                "a product needs options before it can have variants",
+            ));
+        }
+
+        let sku = sku.try_into()?;
+        let price = Price::try_from(price.unwrap_or(self.data.price))?.0;
+
+        let matches_options = options.len() == self.data.options.len()
+            && self.data.options.iter().all(|option| {
+                options
+                    .get(&option.name)
+                    .map(|value| option.values.contains(value))
+                    .unwrap_or(false)
+            });
+
+        if !matches_options {
+            return Err(error::bad_input(
+                "product.invalid_variant_options",
+                "variants need one of the allowed values for each of the product's options",
+            ));
+        }
+
+        if self
+            .data
+            .variants
+            .iter()
+            .any(|variant| variant.options == options || variant.sku == sku)
+        {
+            return Err(error::conflict(
+                "product.variant_already_exists",
+                "the product already has a variant with the same options or SKU",
+            ));
+        }
+
+        let id = id.get()?;
+        let now = clock.now();
+
+        self.data.variants.push(VariantData {
+            id,
+            sku,
+            options,
+            price,
+            created_at: now,
+            updated_at: now,
+        });
+        self.data.updated_at = now;
+
+        Ok(())
+    }
+}
+
+impl Product {
+    /** Get one of the product's images. */
+    pub fn image(&self, id: ImageId) -> Option<&ImageData> {
+        self.data.images.iter().find(|image| image.id == id)
+    }
+
+    /**
+    Add an image to the end of the product's images.
+
+    The same content can't be added to a product more than once.
+    An empty alt text is treated as no alt text.
+    */
+    pub fn add_image(
+        &mut self,
+        id: impl IdProvider<ImageData>,
+        hash: ContentHash,
+        info: ImageInfo,
+        size: u64,
+        alt_text: Option<String>,
+        clock: impl Clock,
+    ) -> Result<(), Error> {
+        if self.data.images.iter().any(|image| image.hash == hash) {
+            return Err(error::conflict(
+                "product.image_already_exists",
+                "the product already has the same image",
+            ));
+        }
+
+        let id = id.get()?;
+        let now = clock.now();
+
+        self.data.images.push(ImageData {
+            id,
+            hash,
+            mime_type: info.mime_type.to_owned(),
+            width: info.width,
+            height: info.height,
+            size,
+            alt_text: alt_text.filter(|alt_text| !alt_text.trim().is_empty()),
+            created_at: now,
+            updated_at: now,
+        });
+        self.data.updated_at = now;
+
+        Ok(())
+    }
+
+    /** Replace the alt text of one of the product's images. */
+    pub fn set_image_alt_text(
+        &mut self,
+        id: ImageId,
+        alt_text: Option<String>,
+        clock: impl Clock,
+    ) -> Result<(), Error> {
+        let now = clock.now();
+
+        let image = self
+            .data
+            .images
+            .iter_mut()
+            .find(|image| image.id == id)
+            .ok_or_else(image_not_found)?;
+
+        image.alt_text = alt_text.filter(|alt_text| !alt_text.trim().is_empty());
+        image.updated_at = now;
+        self.data.updated_at = now;
+
+        Ok(())
+    }
+
+    /**
+    Change the order of the product's images.
+
+    Every one of the product's images must be given exactly once.
+    */
+    pub fn reorder_images(&mut self, ids: Vec<ImageId>, clock: impl Clock) -> Result<(), Error> {
+        let unique = ids.iter().collect::<BTreeSet<_>>();
+
+        if ids.len() != self.data.images.len()
+            || unique.len() != ids.len()
+            || self
+                .data
+                .images
+                .iter()
+                .any(|image| !unique.contains(&image.id))
+        {
+            return Err(error::bad_input(
+                "product.invalid_image_order",
+                "the order must include each of the product's images exactly once",
+            ));
+        }
+
+        self.data.images.sort_by_key(|image| {
+            ids.iter()
+                .position(|id| *id == image.id)
+                .expect("all images are in the order")
+        });
+        self.data.updated_at = clock.now();
+
+        Ok(())
+    }
+
+    /** Remove one of the product's images. */
+    pub fn remove_image(&mut self, id: ImageId, clock: impl Clock) -> Result<(), Error> {
+        let index = self
+            .data
+            .images
+            .iter()
+            .position(|image| image.id == id)
+            .ok_or_else(image_not_found)?;
+
+        self.data.images.remove(index);
+        self.data.updated_at = clock.now();
+
+        Ok(())
+    }
+}
+
+fn image_not_found() -> Error {
+    error::not_found(
+        "product.image_not_found",
+        "the product doesn't have that image",
+    )
+}
+
 impl Entity for Product {
     type Id = ProductId;
     type Version = ProductVersion;
@@ -120,24 +735,310 @@ impl Entity for Product {
     type Error = Error;
 }
 
-impl Resolver {
-    pub fn product_id(&self) -> impl IdProvider<ProductData> {
-        NextId::<ProductData>::new()
-    }
-}
-
 pub mod xpath_engine;
 
 #[cfg(test)]
 mod tests {
+    use chrono::{
+        TimeZone,
+        Utc,
+    };
+
     use super::*;
 
     #[test]
     fn title_must_be_non_empty() {
-        assert!(Product::new(ProductId::new(), "", Currency::usd(100)).is_err());
+        assert!(Product::new(ProductId::new(), "", Currency::usd(100), SystemClock).is_err());
+
+        let mut product =
+            Product::new(ProductId::new(), "A title", Currency::usd(100), SystemClock).unwrap();
+
+        assert!(product.set_title("", SystemClock).is_err());
+    }
+
+    #[test]
+    fn price_must_be_positive() {
+        assert!(Product::new(ProductId::new(), "A title", Currency::usd(0), SystemClock).is_err());
+
+        let mut product =
+            Product::new(ProductId::new(), "A title", Currency::usd(100), SystemClock).unwrap();
+
+        assert!(product.set_price(Currency::usd(0), SystemClock).is_err());
+        assert_eq!(Currency::usd(100), product.data.price);
+    }
+
+    #[test]
+    fn set_price_records_history() {
+        let created_at = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
+        let updated_at = Utc.with_ymd_and_hms(2020, 1, 2, 0, 0, 0).unwrap();
+
+        let mut product =
+            Product::new(ProductId::new(), "A title", Currency::usd(100), created_at).unwrap();
+
+        product.set_price(Currency::usd(150), updated_at).unwrap();
+
+        // Setting the same price again doesn't add to the history
+        product.set_price(Currency::usd(150), SystemClock).unwrap();
+
+        assert_eq!(Currency::usd(150), product.data.price);
+        assert_eq!(updated_at, product.data.updated_at);
+        assert_eq!(
+            vec![
+                PriceChange {
+                    price: Currency::usd(100),
+                    effective_at: created_at,
+                },
+                PriceChange {
+                    price: Currency::usd(150),
+                    effective_at: updated_at,
+                },
+            ],
+            product.data.price_history
+        );
+    }
+
+    fn sized_product() -> Product {
+        let mut product =
+            Product::new(ProductId::new(), "A shirt", Currency::usd(100), SystemClock).unwrap();
+
+        product
+            .set_options(
+                vec![ProductOption {
+                    name: "size".to_owned(),
+                    values: vec!["S".to_owned(), "M".to_owned()],
+                }],
+                SystemClock,
+            )
+            .unwrap();
+
+        product
+    }
+
+    fn size(value: &str) -> BTreeMap<String, String> {
+        [("size".to_owned(), value.to_owned())].into()
+    }
+
+    #[test]
+    fn variants_need_valid_options() {
+        let mut product =
+            Product::new(ProductId::new(), "A shirt", Currency::usd(100), SystemClock).unwrap();
+
+        let err = product
+            .add_variant(VariantId::new(), "SHIRT-S", size("S"), None, SystemClock)
+            .unwrap_err();
+        assert_eq!("product.no_options", err.code());
+
+        let mut product = sized_product();
+
+        for options in [size("XL"), BTreeMap::new()] {
+            let err = product
+                .add_variant(VariantId::new(), "SHIRT-XL", options, None, SystemClock)
+                .unwrap_err();
+            assert_eq!("product.invalid_variant_options", err.code());
+        }
+
+        product
+            .add_variant(VariantId::new(), "shirt-s ", size("S"), None, SystemClock)
+            .unwrap();
+
+        let variant = &product.data.variants[0];
+        assert_eq!("SHIRT-S", variant.sku.as_str());
+        assert_eq!(Currency::usd(100), variant.price);
+
+        // Options can't change once variants depend on them
+        assert!(product.set_options(vec![], SystemClock).is_err());
+    }
+
+    #[test]
+    fn variants_must_be_distinct() {
+        let mut product = sized_product();
+
+        product
+            .add_variant(
+                VariantId::new(),
+                "SHIRT-S",
+                size("S"),
+                Some(Currency::usd(90)),
+                SystemClock,
+            )
+            .unwrap();
+
+        for (sku, options) in [("SHIRT-S2", size("S")), ("SHIRT-S", size("M"))] {
+            let err = product
+                .add_variant(VariantId::new(), sku, options, None, SystemClock)
+                .unwrap_err();
+            assert_eq!("product.variant_already_exists", err.code());
+        }
+
+        assert!(Sku::try_from("NOT A SKU").is_err());
+    }
+
+    #[test]
+    fn status_moves_forwards() {
+        let mut product =
+            Product::new(ProductId::new(), "A shirt", Currency::usd(100), SystemClock).unwrap();
+
+        assert_eq!(ProductStatus::Draft, product.data.status);
+        assert!(!product.is_active());
+
+        for status in [
+            ProductStatus::Active,
+            ProductStatus::Discontinued,
+            ProductStatus::Archived,
+        ] {
+            product.set_status(status, SystemClock).unwrap();
+            assert_eq!(status, product.data.status);
+        }
+
+        let err = product
+            .set_status(ProductStatus::Active, SystemClock)
+            .unwrap_err();
+        assert_eq!("product.invalid_status_transition", err.code());
+
+        // Drafts can be archived, but not discontinued
+        let mut product =
+            Product::new(ProductId::new(), "A shirt", Currency::usd(100), SystemClock).unwrap();
+
+        assert!(product
+            .set_status(ProductStatus::Discontinued, SystemClock)
+            .is_err());
+        product
+            .set_status(ProductStatus::Archived, SystemClock)
+            .unwrap();
+    }
+
+    #[test]
+    fn products_without_status_are_active() {
+        let product = Product::new(ProductId::new(), "A shirt", Currency::usd(100), SystemClock)
+            .unwrap()
+            .into_data();
+
+        let mut json = serde_json::to_value(&product).unwrap();
+        json.as_object_mut().unwrap().remove("status");
+
+        let product: ProductData = serde_json::from_value(json).unwrap();
+
+        assert_eq!(ProductStatus::Active, product.status);
+    }
+
+    #[test]
+    fn localize_follows_fallback_chain() {
+        let locale = |locale: &str| Locale::try_from(locale).unwrap();
+
+        let mut product =
+            Product::new(ProductId::new(), "A shirt", Currency::usd(100), SystemClock).unwrap();
+
+        assert!(product
+            .set_translation(locale("fr"), "", None, SystemClock)
+            .is_err());
+
+        product
+            .set_translation(
+                locale("fr"),
+                "Une chemise",
+                Some("En coton".to_owned()),
+                SystemClock,
+            )
+            .unwrap();
+
+        let chain = Locale::fallback_chain(&[locale("de"), locale("fr-CA")], &locale("en"));
+        let text = product.localize(&chain);
+
+        assert_eq!(Some(locale("fr")), text.locale);
+        assert_eq!("Une chemise", text.title);
+        assert_eq!(Some("En coton"), text.description.as_deref());
+
+        product
+            .remove_translation(&locale("fr"), SystemClock)
+            .unwrap();
+
+        let text = product.localize(&chain);
+
+        assert_eq!(None, text.locale);
+        assert_eq!("A shirt", text.title);
+    }
+
+    #[test]
+    fn set_title_updates_timestamp() {
+        let created_at = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
+        let updated_at = Utc.with_ymd_and_hms(2020, 1, 2, 0, 0, 0).unwrap();
+
+        let mut product =
+            Product::new(ProductId::new(), "A title", Currency::usd(100), created_at).unwrap();
+
+        product.set_title("A new title", updated_at).unwrap();
+
+        assert_eq!(created_at, product.data.created_at);
+        assert_eq!(updated_at, product.data.updated_at);
+    }
+
+    #[test]
+    fn images_are_ordered() {
+        let mut product =
+            Product::new(ProductId::new(), "A shirt", Currency::usd(100), SystemClock).unwrap();
+
+        let ids = [ImageId::new(), ImageId::new()];
+
+        for (id, width) in ids.iter().zip([640, 320]) {
+            let content = test_data::png(width, 480);
+
+            product
+                .add_image(
+                    *id,
+                    ContentHash::of(&content),
+                    ImageInfo::read(&content).unwrap(),
+                    content.len() as u64,
+                    Some(" ".to_owned()),
+                    SystemClock,
+                )
+                .unwrap();
+        }
+
+        assert_eq!(None, product.data.images[0].alt_text);
+
+        let content = test_data::png(640, 480);
+        let err = product
+            .add_image(
+                ImageId::new(),
+                ContentHash::of(&content),
+                ImageInfo::read(&content).unwrap(),
+                content.len() as u64,
+                None,
+                SystemClock,
+            )
+            .unwrap_err();
+        assert_eq!("product.image_already_exists", err.code());
+
+        for invalid in [
+            vec![ids[0]],
+            vec![ids[0], ids[0]],
+            vec![ids[1], ImageId::new()],
+        ] {
+            let err = product.reorder_images(invalid, SystemClock).unwrap_err();
+            assert_eq!("product.invalid_image_order", err.code());
+        }
 
-        let mut product = Product::new(ProductId::new(), "A title", Currency::usd(100)).unwrap();
+        product
+            .reorder_images(vec![ids[1], ids[0]], SystemClock)
+            .unwrap();
+        assert_eq!(320, product.data.images[0].width);
 
-        assert!(product.set_title("").is_err());
+        product.remove_image(ids[1], SystemClock).unwrap();
+        assert_eq!(
+            "product.image_not_found",
+            product
+                .remove_image(ids[1], SystemClock)
+                .unwrap_err()
+                .code()
+        );
+        assert_eq!(
+            vec![ids[0]],
+            product
+                .data
+                .images
+                .iter()
+                .map(|image| image.id)
+                .collect::<Vec<_>>()
+        );
     }
 }
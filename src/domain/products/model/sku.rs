@@ -0,0 +1,151 @@
+/*!
+Contains the `SkuClaim` entity.
+
+SKUs need to be unique across all products, but each product is stored independently so it can't check them itself.
+Each SKU in use is claimed by storing a `SkuClaim` with an id derived from the SKU. Claiming the same SKU twice
+writes to the same id, so the store's optimistic concurrency rejects the second claim, even if the first
+hasn't been committed yet.
+*/
+
+use shop_derive::Entity;
+
+use crate::domain::{
+    error,
+    products::{
+        ProductId,
+        Sku,
+        VariantId,
+    },
+    Error,
+};
+
+/** Data for a claim on a SKU by one variant of a product. */
+#[derive(Clone, Serialize, Deserialize, Entity)]
+#[entity(store)]
+pub struct SkuClaimData {
+    pub id: SkuClaimId,
+    pub version: SkuClaimVersion,
+    pub sku: Sku,
+    pub product_id: ProductId,
+    pub variant_id: VariantId,
+    _private: (),
+}
+
+/**
+Get the id of the claim for a SKU.
+
+Ids are computed with 128-bit FNV-1a so they're stable across processes and versions of Rust.
+*/
+pub fn sku_claim_id(sku: &Sku) -> SkuClaimId {
+    const OFFSET: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+
+    let hash = sku
+        .as_str()
+        .bytes()
+        .fold(OFFSET, |hash, b| (hash ^ b as u128).wrapping_mul(PRIME));
+
+    crate::store::Id::from_raw(uuid::Uuid::from_u128(hash)).into()
+}
+
+impl SkuClaim {
+    /** Claim a SKU for a variant of a product. */
+    pub fn new(sku: Sku, product_id: ProductId, variant_id: VariantId) -> Self {
+        SkuClaim::from_data(SkuClaimData {
+            id: sku_claim_id(&sku),
+            version: SkuClaimVersion::default(),
+            sku,
+            product_id,
+            variant_id,
+            _private: (),
+        })
+    }
+}
+
+/**
+Claim a SKU in a transaction.
+
+Fails with a conflict if the SKU has already been claimed, including by another transaction that's still active.
+*/
+pub(in crate::domain) fn claim(
+    transaction: &crate::store::Transaction,
+    store: impl store::SkuClaimStore,
+    claim: SkuClaim,
+) -> Result<(), Error> {
+    let taken = |sku: &Sku| {
+        error::conflict(
+            "product.sku_already_exists",
+            format!("the SKU `{}` is already in use", sku.as_str()),
+        )
+    };
+
+    if store.get_sku_claim(claim.data.id)?.is_some() {
+        return Err(taken(&claim.data.sku));
+    }
+
+    let sku = claim.data.sku.clone();
+
+    // Another transaction may have claimed the SKU without committing yet
+    store.set_sku_claim(transaction, claim).map_err(|err| {
+        if err.code() == error::VERSION_MISMATCH {
+            taken(&sku)
+        } else {
+            err
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use super::*;
+
+    use crate::store::{
+        Transaction,
+        TransactionStore,
+    };
+
+    #[test]
+    fn claims_are_unique_across_transactions() {
+        let transaction_store = TransactionStore::new();
+        let store = store::in_memory_store(transaction_store.clone());
+
+        let sku = Sku::try_from("SHIRT-S").unwrap();
+
+        let first = transaction_store.begin();
+        let second = transaction_store.begin();
+
+        claim(
+            &first,
+            &store,
+            SkuClaim::new(sku.clone(), ProductId::new(), VariantId::new()),
+        )
+        .unwrap();
+
+        // The first transaction hasn't committed, but its claim still blocks the second
+        let err = claim(
+            &second,
+            &store,
+            SkuClaim::new(
+                Sku::try_from("shirt-s").unwrap(),
+                ProductId::new(),
+                VariantId::new(),
+            ),
+        )
+        .unwrap_err();
+
+        assert_eq!("product.sku_already_exists", err.code());
+
+        transaction_store.commit(first);
+
+        let err = claim(
+            &Transaction::none(),
+            &store,
+            SkuClaim::new(sku, ProductId::new(), VariantId::new()),
+        )
+        .unwrap_err();
+
+        assert_eq!("product.sku_already_exists", err.code());
+    }
+}
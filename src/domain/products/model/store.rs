@@ -1,6 +1,9 @@
 /*! Persistent storage for products. */
 
-use std::vec::IntoIter;
+use std::{
+    path::PathBuf,
+    vec::IntoIter,
+};
 
 use crate::{
     domain::{
@@ -12,7 +15,7 @@ use crate::{
 
 /* A place to persist and fetch product entities. */
 #[auto_impl(&, Arc)]
-pub(in crate::domain) trait ProductStore {
+pub trait ProductStore {
     fn get_product(&self, id: ProductId) -> Result<Option<Product>, Error>;
     fn set_product(&self, transaction: &Transaction, product: Product) -> Result<(), Error>;
 }
@@ -22,20 +25,28 @@ An additional store for fetching multiple product records at a time.
 
 This trait is an implementation detail that lets us fetch more than one product.
 It will probably need to be refactored or just removed when we add a proper database.
-The fact that it's internal to `domain::products` though means the scope of breakage is a bit smaller.
+It's only public so a replacement store can be registered with `AppBuilder::product_store`.
 Commands and queries that depend on `ProductStoreFilter` won't need to break their public API.
 */
 #[auto_impl(&, Arc)]
-pub(in crate::domain) trait ProductStoreFilter {
-    fn filter<F>(&self, predicate: F) -> Result<Iter, Error>
-    where
-        F: Fn(&ProductData) -> bool;
+pub trait ProductStoreFilter {
+    fn filter(&self, predicate: &dyn Fn(&ProductData) -> bool) -> Result<Iter, Error>;
 }
 
-pub(in crate::domain) type Iter = IntoIter<ProductData>;
+pub type Iter = IntoIter<ProductData>;
+
+/**
+A product store that can be registered with the `Resolver`.
+
+This trait is implemented for anything that's both a `ProductStore` and a `ProductStoreFilter`,
+so a single registration can serve both.
+*/
+pub trait ProductStoreBackend: ProductStore + ProductStoreFilter + Send + Sync {}
+
+impl<T> ProductStoreBackend for T where T: ProductStore + ProductStoreFilter + Send + Sync {}
 
 /** A test in-memory product store. */
-pub(in crate::domain) struct InMemoryStore(TransactionValueStore<ProductData>);
+pub struct InMemoryStore(TransactionValueStore<ProductData>);
 
 impl ProductStore for InMemoryStore {
     fn get_product(&self, id: ProductId) -> Result<Option<Product>, Error> {
@@ -66,22 +77,28 @@ impl ProductStore for InMemoryStore {
 
 impl ProductStoreFilter for InMemoryStore {
     #[allow(clippy::needless_collect)]
-    fn filter<F>(&self, predicate: F) -> Result<Iter, Error>
-    where
-        F: Fn(&ProductData) -> bool,
-    {
+    fn filter(&self, predicate: &dyn Fn(&ProductData) -> bool) -> Result<Iter, Error> {
         let products: Vec<_> = self.0.get_all(predicate).map(|(_, data)| data).collect();
 
         Ok(products.into_iter())
     }
 }
 
-pub(in crate::domain::products) fn in_memory_store(
-    transaction_store: TransactionStore,
-) -> InMemoryStore {
+/** Create an in-memory product store that tracks transactions in the given transaction store. */
+pub fn in_memory_store(transaction_store: TransactionStore) -> InMemoryStore {
     InMemoryStore(TransactionValueStore::new(transaction_store))
 }
 
+pub fn file_store(
+    transaction_store: TransactionStore,
+    path: impl Into<PathBuf>,
+) -> Result<InMemoryStore, Error> {
+    Ok(InMemoryStore(TransactionValueStore::open(
+        transaction_store,
+        path,
+    )?))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
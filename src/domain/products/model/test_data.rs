@@ -12,7 +12,29 @@ pub fn default_price() -> Currency {
 }
 
 pub fn default_product() -> Product {
-    Product::new(NextProductId::new(), default_title(), default_price()).unwrap()
+    let mut product = Product::new(
+        NextProductId::new(),
+        default_title(),
+        default_price(),
+        SystemClock,
+    )
+    .unwrap();
+
+    product
+        .set_status(ProductStatus::Active, SystemClock)
+        .unwrap();
+
+    product
+}
+
+/** The header of a PNG image with the given dimensions, which is all that's needed to store it. */
+pub fn png(width: u32, height: u32) -> Vec<u8> {
+    let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+    png.extend_from_slice(&width.to_be_bytes());
+    png.extend_from_slice(&height.to_be_bytes());
+    png.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    png
 }
 
 pub struct ProductBuilder {
@@ -37,6 +59,16 @@ impl ProductBuilder {
         self
     }
 
+    pub fn price(mut self, price: Currency) -> Self {
+        self.product.data.price = price;
+        self
+    }
+
+    pub fn status(mut self, status: ProductStatus) -> Self {
+        self.product.data.status = status;
+        self
+    }
+
     pub fn build(self) -> Product {
         self.product
     }
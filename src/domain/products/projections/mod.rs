@@ -0,0 +1,5 @@
+/*! Projections that maintain read models for product queries. */
+
+mod search_index;
+
+pub(in crate::domain) use self::search_index::*;
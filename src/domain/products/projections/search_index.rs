@@ -0,0 +1,421 @@
+/*!
+Contains the `product_search_index` projection.
+
+Products are searched through an in-process inverted index. Text is split into lowercase words,
+which are stemmed so `shirts` and `shirt` are the same term. Each term maps to the products that
+contain it, along with how many times they contain it.
+
+Every word in a search has to match a product for it to be found, either exactly or as the prefix of
+a term in the product. Matches are ranked with BM25, so rarer terms count for more than common ones
+and shorter titles rank above longer ones with the same matches. Prefix matches count for less than exact ones.
+
+The index is kept in memory and isn't transactional, so it's rebuilt from the change feed when the app restarts.
+Products are always reindexed from their current state, so applying the same change twice is harmless.
+*/
+
+use std::{
+    cmp::Ordering,
+    collections::{
+        BTreeMap,
+        HashMap,
+    },
+    sync::{
+        Arc,
+        RwLock,
+    },
+};
+
+use crate::domain::{
+    infra::*,
+    products::*,
+    Error,
+};
+
+/** How quickly repeating a term stops increasing a product's score. */
+const K1: f64 = 1.2;
+/** How much a product's length counts against its score, from `0` to `1`. */
+const B: f64 = 0.75;
+/** How much a prefix match counts compared to an exact match. */
+const PREFIX_WEIGHT: f64 = 0.5;
+/** The shortest word that's matched as a prefix. */
+const MIN_PREFIX_LEN: usize = 2;
+
+/**
+An inverted index of the words in products.
+*/
+#[derive(Clone, Default)]
+pub(in crate::domain) struct SearchIndex(Arc<RwLock<IndexedProducts>>);
+
+#[derive(Default)]
+struct IndexedProducts {
+    /** Each term, and the number of times it appears in each product. */
+    postings: BTreeMap<String, HashMap<ProductId, u32>>,
+    /** The terms in each product, so they can be removed when it's reindexed. */
+    products: HashMap<ProductId, Vec<String>>,
+    /** The total number of terms in all products. */
+    total_len: usize,
+}
+
+/** A product found by a search, and how relevant it is. */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(in crate::domain) struct SearchHit {
+    pub id: ProductId,
+    pub score: f64,
+}
+
+impl SearchIndex {
+    /** Add a product to the index, replacing any text it was indexed with before. */
+    pub(in crate::domain) fn index<'a>(
+        &self,
+        id: ProductId,
+        text: impl IntoIterator<Item = &'a str>,
+    ) {
+        let terms = text
+            .into_iter()
+            .flat_map(tokenize)
+            .map(|word| stem(&word))
+            .collect::<Vec<_>>();
+
+        let mut index = self.0.write().unwrap();
+
+        index.remove(id);
+
+        for term in &terms {
+            *index
+                .postings
+                .entry(term.clone())
+                .or_default()
+                .entry(id)
+                .or_default() += 1;
+        }
+
+        index.total_len += terms.len();
+        index.products.insert(id, terms);
+    }
+
+    /** Remove a product from the index. */
+    pub(in crate::domain) fn remove(&self, id: ProductId) {
+        self.0.write().unwrap().remove(id);
+    }
+
+    /** Find the products that match every word in the query, most relevant first. */
+    pub(in crate::domain) fn search(&self, query: &str) -> Vec<SearchHit> {
+        let words = tokenize(query);
+
+        if words.is_empty() {
+            return vec![];
+        }
+
+        let index = self.0.read().unwrap();
+
+        let mut scores: Option<HashMap<ProductId, f64>> = None;
+
+        for word in words {
+            let word_scores = index.score(&word);
+
+            // Keep only the products that matched every word so far
+            scores = Some(match scores {
+                None => word_scores,
+                Some(scores) => scores
+                    .into_iter()
+                    .filter_map(|(id, score)| {
+                        word_scores
+                            .get(&id)
+                            .map(|word_score| (id, score + word_score))
+                    })
+                    .collect(),
+            });
+        }
+
+        let mut hits = scores
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(id, score)| SearchHit { id, score })
+            .collect::<Vec<_>>();
+
+        // Ties are broken by id so results are stable
+        hits.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(Ordering::Equal)
+                .then(a.id.cmp(&b.id))
+        });
+
+        hits
+    }
+}
+
+impl IndexedProducts {
+    fn remove(&mut self, id: ProductId) {
+        let Some(terms) = self.products.remove(&id) else {
+            return;
+        };
+
+        self.total_len -= terms.len();
+
+        for term in terms {
+            if let Some(postings) = self.postings.get_mut(&term) {
+                postings.remove(&id);
+
+                if postings.is_empty() {
+                    self.postings.remove(&term);
+                }
+            }
+        }
+    }
+
+    /** Score every product that matches a single query word. */
+    fn score(&self, word: &str) -> HashMap<ProductId, f64> {
+        let mut scores = HashMap::<ProductId, f64>::new();
+
+        let stemmed = stem(word);
+
+        if let Some(postings) = self.postings.get(&stemmed) {
+            self.score_term(postings, 1.0, &mut scores);
+        }
+
+        if word.chars().count() >= MIN_PREFIX_LEN {
+            for (term, postings) in self.postings.range(word.to_owned()..) {
+                if !term.starts_with(word) {
+                    break;
+                }
+
+                if *term != stemmed {
+                    self.score_term(postings, PREFIX_WEIGHT, &mut scores);
+                }
+            }
+        }
+
+        scores
+    }
+
+    fn score_term(
+        &self,
+        postings: &HashMap<ProductId, u32>,
+        weight: f64,
+        scores: &mut HashMap<ProductId, f64>,
+    ) {
+        let count = self.products.len() as f64;
+        let avg_len = self.total_len as f64 / count.max(1.0);
+
+        let matched = postings.len() as f64;
+        let idf = ((count - matched + 0.5) / (matched + 0.5) + 1.0).ln();
+
+        for (id, frequency) in postings {
+            let len = self.products.get(id).map(Vec::len).unwrap_or_default() as f64;
+            let frequency = *frequency as f64;
+
+            let score = idf * frequency * (K1 + 1.0)
+                / (frequency + K1 * (1.0 - B + B * len / avg_len.max(1.0)));
+
+            // A product that matches several terms by prefix only counts its best match
+            let best = scores.entry(*id).or_default();
+            *best = best.max(score * weight);
+        }
+    }
+}
+
+/** Split text into lowercase words. */
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect()
+}
+
+/**
+Reduce a word to its stem.
+
+This is a light English stemmer that strips common plural and verb endings.
+It doesn't try to be a full Porter stemmer, it only needs to be consistent between products and searches.
+*/
+fn stem(word: &str) -> String {
+    fn strip<'a>(word: &'a str, suffix: &str, min_len: usize) -> Option<&'a str> {
+        word.strip_suffix(suffix)
+            .filter(|stem| stem.chars().count() >= min_len)
+    }
+
+    // Words like `running` and `stopped` double their last consonant before the ending
+    fn undouble(stem: &str) -> String {
+        let mut chars = stem.chars().rev();
+
+        match (chars.next(), chars.next()) {
+            (Some(a), Some(b)) if a == b && !"aeioulsz".contains(a) => {
+                stem[..stem.len() - a.len_utf8()].to_owned()
+            }
+            _ => stem.to_owned(),
+        }
+    }
+
+    if let Some(stem) = strip(word, "ies", 2) {
+        return format!("{}y", stem);
+    }
+
+    if let Some(stem) = strip(word, "sses", 2) {
+        return format!("{}ss", stem);
+    }
+
+    for suffix in ["ches", "shes", "xes", "zes"] {
+        if let Some(stem) = strip(word, suffix, 2) {
+            return format!("{}{}", stem, &suffix[..suffix.len() - 2]);
+        }
+    }
+
+    if let Some(stem) = strip(word, "ing", 3) {
+        return undouble(stem);
+    }
+
+    if let Some(stem) = strip(word, "ed", 3) {
+        return undouble(stem);
+    }
+
+    if let Some(stem) = strip(word, "ly", 3) {
+        return stem.to_owned();
+    }
+
+    if !word.ends_with("ss") && !word.ends_with("us") && !word.ends_with("is") {
+        if let Some(stem) = strip(word, "s", 3) {
+            return stem.to_owned();
+        }
+    }
+
+    word.to_owned()
+}
+
+/** Default implementation for the `product_search_index` projection. */
+async fn apply(change: Change, store: impl ProductStore, index: SearchIndex) -> Result<(), Error> {
+    let Some(id) = change.id::<ProductData>() else {
+        return Ok(());
+    };
+
+    match store.get_product(id)? {
+        Some(product) => index.index(id, [product.to_data().title.as_str()]),
+        None => index.remove(id),
+    }
+
+    Ok(())
+}
+
+/** Maintain the search index over products. */
+pub(in crate::domain) fn product_search_index_projection() -> Projection {
+    Projection::new(
+        "product_search_index",
+        |resolver: Resolver, change| async move {
+            let store = resolver.product_store();
+            let index = resolver.product_search_index();
+
+            apply(change, store, index).await
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search(index: &SearchIndex, query: &str) -> Vec<ProductId> {
+        index.search(query).into_iter().map(|hit| hit.id).collect()
+    }
+
+    #[test]
+    fn stem_words() {
+        for (word, expected) in [
+            ("shirts", "shirt"),
+            ("dresses", "dress"),
+            ("boxes", "box"),
+            ("berries", "berry"),
+            ("running", "run"),
+            ("stopped", "stop"),
+            ("quickly", "quick"),
+            ("glass", "glass"),
+            ("cactus", "cactus"),
+            ("bus", "bus"),
+        ] {
+            assert_eq!(expected, stem(word), "{}", word);
+        }
+    }
+
+    #[test]
+    fn tokenize_text() {
+        assert_eq!(
+            vec!["a", "red", "t", "shirt", "2"],
+            tokenize("A red T-shirt (2)")
+        );
+    }
+
+    #[test]
+    fn every_word_must_match() {
+        let index = SearchIndex::default();
+
+        let red_shirt = ProductId::new();
+        let blue_shirt = ProductId::new();
+
+        index.index(red_shirt, ["Red shirt"]);
+        index.index(blue_shirt, ["Blue shirts"]);
+
+        let mut shirts = search(&index, "SHIRT");
+        shirts.sort();
+
+        let mut expected = vec![red_shirt, blue_shirt];
+        expected.sort();
+
+        assert_eq!(expected, shirts);
+        assert_eq!(vec![red_shirt], search(&index, "red shirts"));
+        assert!(search(&index, "green shirt").is_empty());
+        assert!(search(&index, "  ").is_empty());
+    }
+
+    #[test]
+    fn prefixes_match_but_rank_lower() {
+        let index = SearchIndex::default();
+
+        let shirt = ProductId::new();
+        let shirtdress = ProductId::new();
+
+        index.index(shirt, ["Shirt"]);
+        index.index(shirtdress, ["Shirtdress"]);
+
+        assert_eq!(vec![shirt, shirtdress], search(&index, "shirt"));
+        assert_eq!(vec![shirtdress], search(&index, "shirtd"));
+
+        // Single letters aren't matched as prefixes
+        assert!(search(&index, "s").is_empty());
+    }
+
+    #[test]
+    fn rarer_and_denser_matches_rank_higher() {
+        let index = SearchIndex::default();
+
+        let short = ProductId::new();
+        let long = ProductId::new();
+        let other = ProductId::new();
+
+        index.index(short, ["Linen shirt"]);
+        index.index(long, ["Linen shirt with long sleeves and pockets"]);
+        index.index(other, ["Cotton shirt"]);
+
+        assert_eq!(vec![short, long], search(&index, "linen shirt"));
+
+        let hits = index.search("linen");
+        assert!(hits[0].score > hits[1].score);
+    }
+
+    #[test]
+    fn reindex_replaces_previous_text() {
+        let index = SearchIndex::default();
+
+        let id = ProductId::new();
+
+        index.index(id, ["Red shirt"]);
+        index.index(id, ["Blue shirt"]);
+
+        assert!(search(&index, "red").is_empty());
+        assert_eq!(vec![id], search(&index, "blue"));
+
+        index.remove(id);
+
+        assert!(search(&index, "blue").is_empty());
+        assert_eq!(0, index.0.read().unwrap().total_len);
+        assert!(index.0.read().unwrap().postings.is_empty());
+    }
+}
@@ -0,0 +1,114 @@
+/*! Contains the `GetLocalizedProductQuery` type. */
+
+use crate::domain::{
+    infra::*,
+    products::*,
+    Error,
+};
+
+/** Input for a `GetLocalizedProductQuery`. */
+#[derive(Serialize, Deserialize)]
+pub struct GetLocalizedProduct {
+    pub id: ProductId,
+    /** The locales the reader prefers, most preferred first. */
+    #[serde(default)]
+    pub locales: Vec<Locale>,
+}
+
+/** A product with its text in the reader's locale. */
+#[derive(Serialize)]
+pub struct LocalizedProduct {
+    pub id: ProductId,
+    /** The locale the text is in, or `None` if the product's own title was used. */
+    pub locale: Option<Locale>,
+    pub title: String,
+    pub description: Option<String>,
+    pub price: Currency,
+}
+
+impl QueryArgs for GetLocalizedProduct {
+    type Output = Result<Option<LocalizedProduct>, Error>;
+}
+
+/** Default implementation for a `GetLocalizedProductQuery`. */
+async fn execute(
+    query: GetLocalizedProduct,
+    store: impl ProductStore,
+    default_locale: &Locale,
+) -> Result<Option<LocalizedProduct>, Error> {
+    let Some(product) = store.get_product(query.id)? else {
+        return Ok(None);
+    };
+
+    let text = product.localize(&Locale::fallback_chain(&query.locales, default_locale));
+    let product = product.into_data();
+
+    Ok(Some(LocalizedProduct {
+        id: product.id,
+        locale: text.locale,
+        title: text.title,
+        description: text.description,
+        price: product.price,
+    }))
+}
+
+impl Resolver {
+    /**
+    Get a product with its text in the first of the reader's locales it has a translation for.
+
+    The app's default locale is tried after the reader's, and then the product's own title.
+    */
+    pub fn get_localized_product_query(&self) -> impl Query<GetLocalizedProduct> {
+        self.query(|resolver, query: GetLocalizedProduct| async move {
+            let store = resolver.product_store();
+            let config = resolver.config();
+
+            execute(query, store, &config.locale.default).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use super::*;
+
+    use crate::domain::products::model::{
+        store::in_memory_store,
+        test_data::ProductBuilder,
+    };
+
+    #[tokio::test]
+    async fn falls_back_to_default_locale() {
+        let locale = |locale: &str| Locale::try_from(locale).unwrap();
+
+        let store = in_memory_store(Default::default());
+        let id = ProductId::new();
+
+        let mut product = ProductBuilder::new().id(id).build();
+        product
+            .set_translation(locale("en"), "A shirt", None, SystemClock)
+            .unwrap();
+        product
+            .set_translation(locale("de"), "Ein Hemd", None, SystemClock)
+            .unwrap();
+
+        store
+            .set_product(&crate::store::Transaction::none(), product)
+            .unwrap();
+
+        for (expected, locales) in [
+            ("Ein Hemd", vec![locale("de-AT")]),
+            ("A shirt", vec![locale("fr")]),
+            ("A shirt", vec![]),
+        ] {
+            let product = execute(GetLocalizedProduct { id, locales }, &store, &locale("en"))
+                .await
+                .unwrap()
+                .unwrap();
+
+            assert_eq!(expected, product.title);
+        }
+    }
+}
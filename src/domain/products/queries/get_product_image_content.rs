@@ -0,0 +1,64 @@
+/*! Contains the `GetProductImageContentQuery` type. */
+
+use crate::domain::{
+    error,
+    infra::*,
+    products::{
+        model::blob::ImageBlobStore,
+        *,
+    },
+    Error,
+};
+
+/** Input for a `GetProductImageContentQuery`. */
+#[derive(Serialize, Deserialize)]
+pub struct GetProductImageContent {
+    pub id: ProductId,
+    pub image_id: ImageId,
+}
+
+/** An image along with its content. */
+pub struct ImageContent {
+    pub image: ImageData,
+    pub content: Vec<u8>,
+}
+
+impl QueryArgs for GetProductImageContent {
+    type Output = Result<Option<ImageContent>, Error>;
+}
+
+/** Default implementation for a `GetProductImageContentQuery`. */
+async fn execute(
+    query: GetProductImageContent,
+    store: impl ProductStore,
+    blob_store: impl ImageBlobStore,
+) -> Result<Option<ImageContent>, Error> {
+    let Some(image) = store
+        .get_product(query.id)?
+        .and_then(|product| product.image(query.image_id).cloned())
+    else {
+        return Ok(None);
+    };
+
+    // Blobs are never removed, so an image without content means the blob store has lost data
+    let Some(content) = blob_store.get(&image.hash)? else {
+        return Err(error::msg(format!(
+            "the content of image `{}` is missing from the blob store",
+            image.hash
+        )));
+    };
+
+    Ok(Some(ImageContent { image, content }))
+}
+
+impl Resolver {
+    /** Get one of a product's images, along with its content. */
+    pub fn get_product_image_content_query(&self) -> impl Query<GetProductImageContent> {
+        self.query(|resolver, query: GetProductImageContent| async move {
+            let store = resolver.product_store();
+            let blob_store = resolver.image_blob_store();
+
+            execute(query, store, blob_store).await
+        })
+    }
+}
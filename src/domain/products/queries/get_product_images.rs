@@ -0,0 +1,52 @@
+/*! Contains the `GetProductImagesQuery` type. */
+
+use crate::domain::{
+    infra::*,
+    products::*,
+    Error,
+};
+
+/** Input for a `GetProductImagesQuery`. */
+#[derive(Serialize, Deserialize)]
+pub struct GetProductImages {
+    pub id: ProductId,
+}
+
+/** A product's images, in the order they're displayed. */
+#[derive(Serialize)]
+pub struct ProductImages {
+    pub id: ProductId,
+    pub images: Vec<ImageData>,
+}
+
+impl QueryArgs for GetProductImages {
+    type Output = Result<Option<ProductImages>, Error>;
+}
+
+/** Default implementation for a `GetProductImagesQuery`. */
+async fn execute(
+    query: GetProductImages,
+    store: impl ProductStore,
+) -> Result<Option<ProductImages>, Error> {
+    let Some(product) = store.get_product(query.id)? else {
+        return Ok(None);
+    };
+
+    let product = product.into_data();
+
+    Ok(Some(ProductImages {
+        id: product.id,
+        images: product.images,
+    }))
+}
+
+impl Resolver {
+    /** Get the metadata for a product's images. */
+    pub fn get_product_images_query(&self) -> impl Query<GetProductImages> {
+        self.query(|resolver, query: GetProductImages| async move {
+            let store = resolver.product_store();
+
+            execute(query, store).await
+        })
+    }
+}
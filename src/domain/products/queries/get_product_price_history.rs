@@ -0,0 +1,56 @@
+/*! Contains the `GetProductPriceHistoryQuery` type. */
+
+use crate::domain::{
+    infra::*,
+    products::*,
+    Error,
+};
+
+/** Input for a `GetProductPriceHistoryQuery`. */
+#[derive(Serialize, Deserialize)]
+pub struct GetProductPriceHistory {
+    pub id: ProductId,
+}
+
+/** The prices a product has had. */
+#[derive(Serialize)]
+pub struct ProductPriceHistory {
+    pub id: ProductId,
+    /** The product's current price. */
+    pub price: Currency,
+    /** Every price the product has had, oldest first. */
+    pub prices: Vec<PriceChange>,
+}
+
+impl QueryArgs for GetProductPriceHistory {
+    type Output = Result<Option<ProductPriceHistory>, Error>;
+}
+
+/** Default implementation for a `GetProductPriceHistoryQuery`. */
+async fn execute(
+    query: GetProductPriceHistory,
+    store: impl ProductStore,
+) -> Result<Option<ProductPriceHistory>, Error> {
+    let Some(product) = store.get_product(query.id)? else {
+        return Ok(None);
+    };
+
+    let product = product.into_data();
+
+    Ok(Some(ProductPriceHistory {
+        id: product.id,
+        price: product.price,
+        prices: product.price_history,
+    }))
+}
+
+impl Resolver {
+    /** Get the prices a product has had and when they took effect. */
+    pub fn get_product_price_history_query(&self) -> impl Query<GetProductPriceHistory> {
+        self.query(|resolver, query: GetProductPriceHistory| async move {
+            let store = resolver.product_store();
+
+            execute(query, store).await
+        })
+    }
+}
@@ -18,6 +18,13 @@ pub struct ProductSummary {
     pub id: ProductId,
     pub title: String,
     pub price: Currency,
+    /**
+    The product's title and description in other locales.
+
+    Summaries are returned in the product's own title, so translations are only used by callers that localize them.
+    */
+    #[serde(skip)]
+    pub translations: Translations,
 }
 
 impl QueryArgs for GetProductSummaries {
@@ -30,12 +37,13 @@ async fn execute(
     store: impl ProductStoreFilter,
 ) -> Result<Vec<ProductSummary>, Error> {
     store
-        .filter(|p| query.ids.iter().any(|id| p.id == *id))?
+        .filter(&|p| query.ids.iter().any(|id| p.id == *id))?
         .map(|p| {
             Ok(ProductSummary {
                 id: p.id,
                 title: p.title,
                 price: p.price,
+                translations: p.translations,
             })
         })
         .collect()
@@ -0,0 +1,54 @@
+/*! Contains the `GetProductVariantsQuery` type. */
+
+use crate::domain::{
+    infra::*,
+    products::*,
+    Error,
+};
+
+/** Input for a `GetProductVariantsQuery`. */
+#[derive(Serialize, Deserialize)]
+pub struct GetProductVariants {
+    pub id: ProductId,
+}
+
+/** The axes a product varies along and its variants. */
+#[derive(Serialize)]
+pub struct ProductVariants {
+    pub id: ProductId,
+    pub options: Vec<ProductOption>,
+    pub variants: Vec<VariantData>,
+}
+
+impl QueryArgs for GetProductVariants {
+    type Output = Result<Option<ProductVariants>, Error>;
+}
+
+/** Default implementation for a `GetProductVariantsQuery`. */
+async fn execute(
+    query: GetProductVariants,
+    store: impl ProductStore,
+) -> Result<Option<ProductVariants>, Error> {
+    let Some(product) = store.get_product(query.id)? else {
+        return Ok(None);
+    };
+
+    let product = product.into_data();
+
+    Ok(Some(ProductVariants {
+        id: product.id,
+        options: product.options,
+        variants: product.variants,
+    }))
+}
+
+impl Resolver {
+    /** Get a product's options and variants. */
+    pub fn get_product_variants_query(&self) -> impl Query<GetProductVariants> {
+        self.query(|resolver, query: GetProductVariants| async move {
+            let store = resolver.product_store();
+
+            execute(query, store).await
+        })
+    }
+}
@@ -0,0 +1,462 @@
+/*! Contains the `GetProductsQuery` type. */
+
+use std::{
+    cmp::Ordering,
+    fmt::Write,
+};
+
+use crate::domain::{
+    catalog::{
+        AttributeFilter,
+        GetProductIdsWithAttributes,
+    },
+    error,
+    infra::*,
+    products::*,
+    Error,
+};
+
+/** What to sort a list of products by. */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProductSort {
+    /** Sort by title, ignoring case. */
+    Title,
+    /** Sort by price in the currency's smallest unit. */
+    Price,
+    /** Sort by when the product was created. */
+    #[default]
+    CreatedAt,
+}
+
+impl<'a> TryFrom<&'a str> for ProductSort {
+    type Error = Error;
+
+    fn try_from(sort: &'a str) -> Result<Self, Self::Error> {
+        match sort {
+            "title" => Ok(ProductSort::Title),
+            "price" => Ok(ProductSort::Price),
+            "created_at" => Ok(ProductSort::CreatedAt),
+            _ => Err(error::bad_input(
+                "product.invalid_sort",
+                format!(
+                    "`{}` isn't a valid sort, expected `title`, `price` or `created_at`",
+                    sort
+                ),
+            )),
+        }
+    }
+}
+
+/**
+An opaque position in a sorted list of products.
+
+The cursor holds the sort key and id of the last product on a page, so the next page starts
+after it even if products are added or changed in between.
+*/
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ProductCursor(String);
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+enum SortKey {
+    Title(String),
+    Price(u64),
+    CreatedAt(Timestamp),
+}
+
+impl SortKey {
+    fn of(sort: ProductSort, product: &ProductData) -> Self {
+        match sort {
+            ProductSort::Title => SortKey::Title(product.title.to_lowercase()),
+            ProductSort::Price => SortKey::Price(product.price.minor_units()),
+            ProductSort::CreatedAt => SortKey::CreatedAt(product.created_at),
+        }
+    }
+
+    fn sort(&self) -> ProductSort {
+        match self {
+            SortKey::Title(_) => ProductSort::Title,
+            SortKey::Price(_) => ProductSort::Price,
+            SortKey::CreatedAt(_) => ProductSort::CreatedAt,
+        }
+    }
+}
+
+impl ProductCursor {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    fn new(key: &(SortKey, ProductId)) -> Self {
+        let json = serde_json::to_vec(key).expect("failed to serialize cursor");
+
+        let mut cursor = String::with_capacity(json.len() * 2);
+        for b in json {
+            let _ = write!(cursor, "{:02x}", b);
+        }
+
+        ProductCursor(cursor)
+    }
+
+    fn decode(&self, sort: ProductSort) -> Result<(SortKey, ProductId), Error> {
+        let invalid = || error::bad_input("product.invalid_cursor", "the cursor is invalid");
+
+        let json = self
+            .0
+            .as_bytes()
+            .chunks(2)
+            .map(|b| {
+                std::str::from_utf8(b)
+                    .ok()
+                    .filter(|b| b.len() == 2)
+                    .and_then(|b| u8::from_str_radix(b, 16).ok())
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(invalid)?;
+
+        let key: (SortKey, ProductId) = serde_json::from_slice(&json).map_err(|_| invalid())?;
+
+        // A cursor only makes sense for the sort it was created with
+        if key.0.sort() != sort {
+            return Err(invalid());
+        }
+
+        Ok(key)
+    }
+}
+
+impl From<String> for ProductCursor {
+    fn from(cursor: String) -> Self {
+        ProductCursor(cursor)
+    }
+}
+
+/** Input for a `GetProductsQuery`. */
+#[derive(Serialize, Deserialize)]
+pub struct GetProducts {
+    /** Only include products with titles containing this text, ignoring case. */
+    pub title: Option<String>,
+    /** Only include products that cost at least this much, in the currency's smallest unit. */
+    pub min_price: Option<u64>,
+    /** Only include products that cost at most this much, in the currency's smallest unit. */
+    pub max_price: Option<u64>,
+    /** Only include products with attribute values matching all of these filters. */
+    pub attributes: Vec<AttributeFilter>,
+    pub sort: ProductSort,
+    pub descending: bool,
+    /** Start after the last product of a previous page. */
+    pub after: Option<ProductCursor>,
+    /** The most products to return. */
+    pub limit: usize,
+}
+
+/** A page of products. */
+#[derive(Serialize)]
+pub struct ProductPage {
+    pub products: Vec<ProductSummary>,
+    /** Where the next page starts, if there is one. */
+    pub next: Option<ProductCursor>,
+    /**
+    Roughly how many products match across all pages.
+
+    Products can change between pages, so this is only an estimate.
+    */
+    pub total_estimate: usize,
+}
+
+impl QueryArgs for GetProducts {
+    type Output = Result<ProductPage, Error>;
+}
+
+/** Default implementation for a `GetProductsQuery`. */
+async fn execute(
+    query: GetProducts,
+    store: impl ProductStoreFilter,
+    attributes_query: impl Query<GetProductIdsWithAttributes>,
+) -> Result<ProductPage, Error> {
+    let after = query
+        .after
+        .as_ref()
+        .map(|after| after.decode(query.sort))
+        .transpose()?;
+
+    let title = query.title.as_ref().map(|title| title.to_lowercase());
+
+    let with_attributes = if query.attributes.is_empty() {
+        None
+    } else {
+        Some(
+            attributes_query
+                .execute(GetProductIdsWithAttributes {
+                    filters: query.attributes.clone(),
+                })
+                .await?,
+        )
+    };
+
+    let mut products = store
+        .filter(&|product| {
+            let price = product.price.minor_units();
+
+            product.status != ProductStatus::Archived
+                && title
+                    .as_ref()
+                    .map(|title| product.title.to_lowercase().contains(title))
+                    .unwrap_or(true)
+                && query.min_price.map(|min| price >= min).unwrap_or(true)
+                && query.max_price.map(|max| price <= max).unwrap_or(true)
+                && with_attributes
+                    .as_ref()
+                    .map(|ids| ids.contains(&product.id))
+                    .unwrap_or(true)
+        })?
+        .map(|product| ((SortKey::of(query.sort, &product), product.id), product))
+        .collect::<Vec<_>>();
+
+    let total_estimate = products.len();
+
+    let order = |a: &(SortKey, ProductId), b: &(SortKey, ProductId)| -> Ordering {
+        if query.descending {
+            b.cmp(a)
+        } else {
+            a.cmp(b)
+        }
+    };
+
+    products.sort_by(|(a, _), (b, _)| order(a, b));
+
+    let mut page = products
+        .into_iter()
+        .filter(|(key, _)| {
+            after
+                .as_ref()
+                .map(|after| order(key, after) == Ordering::Greater)
+                .unwrap_or(true)
+        })
+        .take(query.limit + 1)
+        .collect::<Vec<_>>();
+
+    // One more product than the limit is fetched to tell whether there's another page
+    let next = if page.len() > query.limit {
+        page.truncate(query.limit);
+        page.last().map(|(key, _)| ProductCursor::new(key))
+    } else {
+        None
+    };
+
+    Ok(ProductPage {
+        products: page
+            .into_iter()
+            .map(|(_, product)| ProductSummary {
+                id: product.id,
+                title: product.title,
+                price: product.price,
+                translations: product.translations,
+            })
+            .collect(),
+        next,
+        total_estimate,
+    })
+}
+
+impl Resolver {
+    /**
+    Get a page of products, filtered and sorted.
+
+    Archived products aren't included.
+    */
+    pub fn get_products_query(&self) -> impl Query<GetProducts> {
+        self.query(|resolver, query: GetProducts| async move {
+            let store = resolver.product_store_filter();
+
+            let attributes_query = resolver.get_product_ids_with_attributes_query();
+
+            execute(query, store, attributes_query).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeSet,
+        convert::TryFrom,
+    };
+
+    use super::*;
+
+    use crate::domain::products::model::{
+        store::in_memory_store,
+        test_data::ProductBuilder,
+    };
+
+    fn query(sort: ProductSort, limit: usize) -> GetProducts {
+        GetProducts {
+            title: None,
+            min_price: None,
+            max_price: None,
+            attributes: vec![],
+            sort,
+            descending: false,
+            after: None,
+            limit,
+        }
+    }
+
+    fn store_with(products: &[(&str, u64)]) -> impl ProductStore + ProductStoreFilter {
+        let store = in_memory_store(Default::default());
+
+        for (title, cents) in products {
+            let mut product = ProductBuilder::new().id(ProductId::new()).build();
+
+            product.set_title(*title, SystemClock).unwrap();
+            product
+                .set_price(Currency::usd(*cents), SystemClock)
+                .unwrap();
+
+            store
+                .set_product(&crate::store::Transaction::none(), product)
+                .unwrap();
+        }
+
+        store
+    }
+
+    async fn no_attributes(
+        _: GetProductIdsWithAttributes,
+    ) -> Result<BTreeSet<ProductId>, Error> {
+        Ok(BTreeSet::new())
+    }
+
+    fn titles(page: &ProductPage) -> Vec<&str> {
+        page.products
+            .iter()
+            .map(|product| product.title.as_str())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn pages_follow_on() {
+        let store = store_with(&[("b", 300), ("a", 200), ("d", 100), ("c", 400)]);
+
+        let first = execute(query(ProductSort::Title, 3), &store, no_attributes).await.unwrap();
+
+        assert_eq!(vec!["a", "b", "c"], titles(&first));
+        assert_eq!(4, first.total_estimate);
+
+        let second = execute(
+            GetProducts {
+                after: first.next,
+                ..query(ProductSort::Title, 3)
+            },
+            &store,
+            no_attributes,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(vec!["d"], titles(&second));
+        assert!(second.next.is_none());
+    }
+
+    #[tokio::test]
+    async fn filter_and_sort_by_price() {
+        let store = store_with(&[
+            ("Red shirt", 300),
+            ("Blue shirt", 200),
+            ("Green shirt", 100),
+            ("Red hat", 250),
+        ]);
+
+        let page = execute(
+            GetProducts {
+                title: Some("SHIRT".to_owned()),
+                min_price: Some(150),
+                descending: true,
+                ..query(ProductSort::Price, 10)
+            },
+            &store,
+            no_attributes,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(vec!["Red shirt", "Blue shirt"], titles(&page));
+    }
+
+    #[tokio::test]
+    async fn cursor_must_match_sort() {
+        let store = store_with(&[("a", 100), ("b", 200)]);
+
+        let first = execute(query(ProductSort::Title, 1), &store, no_attributes).await.unwrap();
+
+        for after in [first.next.unwrap(), ProductCursor::from("nope".to_owned())] {
+            let err = execute(
+                GetProducts {
+                    after: Some(after),
+                    ..query(ProductSort::Price, 1)
+                },
+                &store,
+                no_attributes,
+            )
+            .await
+            .map(|_| ())
+            .unwrap_err();
+
+            assert_eq!("product.invalid_cursor", err.code());
+        }
+    }
+
+    #[tokio::test]
+    async fn filter_by_attributes_across_pages() {
+        let store = store_with(&[
+            ("Linen shirt", 300),
+            ("Cotton shirt", 200),
+            ("Linen trousers", 400),
+        ]);
+
+        let linen = store
+            .filter(&|product| product.title.starts_with("Linen"))
+            .unwrap()
+            .map(|product| product.id)
+            .collect::<BTreeSet<_>>();
+
+        let with_attributes = |query: GetProductIdsWithAttributes| {
+            let linen = linen.clone();
+
+            async move {
+                assert_eq!(
+                    vec![AttributeFilter::try_from("material=linen").unwrap()],
+                    query.filters
+                );
+
+                Ok(linen)
+            }
+        };
+
+        let query = || GetProducts {
+            attributes: vec![AttributeFilter::try_from("material=linen").unwrap()],
+            ..query(ProductSort::Price, 1)
+        };
+
+        let first = execute(query(), &store, with_attributes).await.unwrap();
+
+        assert_eq!(vec!["Linen shirt"], titles(&first));
+        assert_eq!(2, first.total_estimate);
+
+        let second = execute(
+            GetProducts {
+                after: first.next,
+                ..query()
+            },
+            &store,
+            with_attributes,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(vec!["Linen trousers"], titles(&second));
+        assert!(second.next.is_none());
+    }
+}
@@ -1,9 +1,23 @@
 /*! Queries for fetching product state. */
 
+mod get_localized_product;
 mod get_product;
+mod get_product_image_content;
+mod get_product_images;
+mod get_product_price_history;
 mod get_product_summaries;
+mod get_product_variants;
+mod get_products;
+mod search_products;
 
 pub use self::{
+    get_localized_product::*,
     get_product::*,
+    get_product_image_content::*,
+    get_product_images::*,
+    get_product_price_history::*,
     get_product_summaries::*,
+    get_product_variants::*,
+    get_products::*,
+    search_products::*,
 };
@@ -0,0 +1,81 @@
+/*! Contains the `SearchProductsQuery` type. */
+
+use crate::domain::{
+    infra::*,
+    products::*,
+    Error,
+};
+
+/** Input for a `SearchProductsQuery`. */
+#[derive(Serialize, Deserialize)]
+pub struct SearchProducts {
+    pub query: String,
+    /** The most products to return. */
+    pub limit: usize,
+}
+
+/** A product found by a search. */
+#[derive(Serialize)]
+pub struct ProductSearchResult {
+    pub id: ProductId,
+    pub title: String,
+    pub price: Currency,
+    /** How relevant the product is to the search. Higher scores are more relevant. */
+    pub score: f64,
+}
+
+impl QueryArgs for SearchProducts {
+    type Output = Result<Vec<ProductSearchResult>, Error>;
+}
+
+/** Default implementation for a `SearchProductsQuery`. */
+async fn execute(
+    query: SearchProducts,
+    index: SearchIndex,
+    products_query: impl Query<GetProductSummaries>,
+) -> Result<Vec<ProductSearchResult>, Error> {
+    let hits = index
+        .search(&query.query)
+        .into_iter()
+        .take(query.limit)
+        .collect::<Vec<_>>();
+
+    let products = products_query
+        .execute(GetProductSummaries {
+            ids: hits.iter().map(|hit| hit.id).collect(),
+        })
+        .await?;
+
+    // The index can be briefly behind the store, so products that have gone are skipped
+    Ok(hits
+        .into_iter()
+        .filter_map(|hit| {
+            products
+                .iter()
+                .find(|product| product.id == hit.id)
+                .map(|product| ProductSearchResult {
+                    id: product.id,
+                    title: product.title.clone(),
+                    price: product.price,
+                    score: hit.score,
+                })
+        })
+        .collect())
+}
+
+impl Resolver {
+    /**
+    Search for products by the words in their title, most relevant first.
+
+    Products are searched through the `product_search_index` projection.
+    */
+    pub fn search_products_query(&self) -> impl Query<SearchProducts> {
+        self.query(|resolver, query: SearchProducts| async move {
+            let index = resolver.product_search_index();
+
+            let products_query = resolver.get_product_summaries_query();
+
+            execute(query, index, products_query).await
+        })
+    }
+}
@@ -2,13 +2,32 @@
 
 use std::sync::Arc;
 
-use crate::domain::{
-    infra::*,
-    products::model::store::{
-        self,
-        InMemoryStore,
-        ProductStore,
-        ProductStoreFilter,
+use crate::{
+    config::StoreBackend,
+    domain::{
+        infra::*,
+        products::{
+            model::{
+                blob::{
+                    self,
+                    ImageBlobStore,
+                },
+                sku::store::{
+                    self as sku_store,
+                    SkuClaimStore,
+                },
+                store::{
+                    self,
+                    ProductStore,
+                    ProductStoreBackend,
+                    ProductStoreFilter,
+                },
+            },
+            ImageData,
+            ProductData,
+            SearchIndex,
+            VariantData,
+        },
     },
 };
 
@@ -19,14 +38,69 @@ The `ProductsResolver` type wraps private implementation details and exposes the
 */
 #[derive(Clone)]
 pub(in crate::domain) struct ProductsResolver {
-    product_store: Register<Arc<InMemoryStore>>,
+    product_store: Register<Arc<dyn ProductStoreBackend>>,
+    product_id: Register<Arc<dyn IdProvider<ProductData> + Send + Sync>>,
+    product_search_index: Register<SearchIndex>,
+    sku_claim_store: Register<Arc<dyn SkuClaimStore + Send + Sync>>,
+    variant_id: Register<Arc<dyn IdProvider<VariantData> + Send + Sync>>,
+    image_blob_store: Register<Arc<dyn ImageBlobStore + Send + Sync>>,
+    image_id: Register<Arc<dyn IdProvider<ImageData> + Send + Sync>>,
 }
 
 impl Default for ProductsResolver {
     fn default() -> Self {
         ProductsResolver {
-            product_store: Register::once(|resolver| {
-                Arc::new(store::in_memory_store(resolver.transaction_store()))
+            product_store: Register::per_tenant(|resolver| match resolver.config().store.backend {
+                StoreBackend::InMemory => {
+                    Arc::new(store::in_memory_store(resolver.transaction_store()))
+                        as Arc<dyn ProductStoreBackend>
+                }
+                StoreBackend::File => Arc::new(
+                    store::file_store(
+                        resolver.transaction_store(),
+                        resolver.tenant_store_file("products"),
+                    )
+                    .expect("failed to open the product store"),
+                ) as Arc<dyn ProductStoreBackend>,
+            }),
+            product_id: Register::once(|_| {
+                Arc::new(NextId::<ProductData>::new())
+                    as Arc<dyn IdProvider<ProductData> + Send + Sync>
+            }),
+            product_search_index: Register::per_tenant(|_| SearchIndex::default()),
+            sku_claim_store: Register::per_tenant(|resolver| {
+                match resolver.config().store.backend {
+                    StoreBackend::InMemory => {
+                        Arc::new(sku_store::in_memory_store(resolver.transaction_store()))
+                            as Arc<dyn SkuClaimStore + Send + Sync>
+                    }
+                    StoreBackend::File => Arc::new(
+                        sku_store::file_store(
+                            resolver.transaction_store(),
+                            resolver.tenant_store_file("sku_claims"),
+                        )
+                        .expect("failed to open the sku_claim store"),
+                    )
+                        as Arc<dyn SkuClaimStore + Send + Sync>,
+                }
+            }),
+            variant_id: Register::once(|_| {
+                Arc::new(NextId::<VariantData>::new())
+                    as Arc<dyn IdProvider<VariantData> + Send + Sync>
+            }),
+            // Each tenant's images are kept in their own directory
+            image_blob_store: Register::per_tenant(|resolver| {
+                let root = resolver
+                    .config()
+                    .store
+                    .path
+                    .join("images")
+                    .join(resolver.tenant().to_string());
+
+                Arc::new(blob::file_blob_store(root)) as Arc<dyn ImageBlobStore + Send + Sync>
+            }),
+            image_id: Register::once(|_| {
+                Arc::new(NextId::<ImageData>::new()) as Arc<dyn IdProvider<ImageData> + Send + Sync>
             }),
         }
     }
@@ -40,4 +114,73 @@ impl Resolver {
     pub(in crate::domain::products) fn product_store_filter(&self) -> impl ProductStoreFilter {
         self.resolve(&self.products_resolver.product_store)
     }
+
+    pub(in crate::domain::products) fn product_search_index(&self) -> SearchIndex {
+        self.resolve(&self.products_resolver.product_search_index)
+    }
+
+    pub(in crate::domain::products) fn sku_claim_store(&self) -> impl SkuClaimStore {
+        self.resolve(&self.products_resolver.sku_claim_store)
+    }
+
+    pub(in crate::domain::products) fn image_blob_store(&self) -> impl ImageBlobStore {
+        self.resolve(&self.products_resolver.image_blob_store)
+    }
+
+    pub fn product_id(&self) -> impl IdProvider<ProductData> {
+        self.journaled_id(self.resolve(&self.products_resolver.product_id))
+    }
+
+    pub fn variant_id(&self) -> impl IdProvider<VariantData> {
+        self.journaled_id(self.resolve(&self.products_resolver.variant_id))
+    }
+
+    pub fn image_id(&self) -> impl IdProvider<ImageData> {
+        self.journaled_id(self.resolve(&self.products_resolver.image_id))
+    }
+}
+
+impl AppBuilder {
+    /** Use a different store for products. */
+    pub fn product_store(mut self, product_store: Register<Arc<dyn ProductStoreBackend>>) -> Self {
+        self.resolver.products_resolver.product_store = product_store;
+        self
+    }
+
+    /** Use a different source of ids for new products. */
+    pub fn product_id(
+        mut self,
+        product_id: Register<Arc<dyn IdProvider<ProductData> + Send + Sync>>,
+    ) -> Self {
+        self.resolver.products_resolver.product_id = product_id;
+        self
+    }
+
+    /** Use a different store for image content. */
+    #[allow(dead_code)]
+    pub(in crate::domain) fn image_blob_store(
+        mut self,
+        image_blob_store: Register<Arc<dyn ImageBlobStore + Send + Sync>>,
+    ) -> Self {
+        self.resolver.products_resolver.image_blob_store = image_blob_store;
+        self
+    }
+
+    /** Use a different source of ids for new product variants. */
+    pub fn variant_id(
+        mut self,
+        variant_id: Register<Arc<dyn IdProvider<VariantData> + Send + Sync>>,
+    ) -> Self {
+        self.resolver.products_resolver.variant_id = variant_id;
+        self
+    }
+
+    /** Use a different source of ids for new product images. */
+    pub fn image_id(
+        mut self,
+        image_id: Register<Arc<dyn IdProvider<ImageData> + Send + Sync>>,
+    ) -> Self {
+        self.resolver.products_resolver.image_id = image_id;
+        self
+    }
 }
@@ -0,0 +1,103 @@
+/*! Contains the `CreatePromotionCommand` type. */
+
+use crate::domain::{
+    error,
+    infra::*,
+    promotions::*,
+    Error,
+};
+
+/** Input for a `CreatePromotionCommand`. */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CreatePromotion {
+    pub id: PromotionId,
+    pub name: String,
+    pub conditions: Vec<PromotionCondition>,
+    pub action: PromotionAction,
+    pub starts_at: Option<Timestamp>,
+    pub ends_at: Option<Timestamp>,
+}
+
+impl CommandArgs for CreatePromotion {
+    type Output = Result<(), Error>;
+}
+
+/** Default implementation for a `CreatePromotionCommand`. */
+async fn execute(
+    command: CreatePromotion,
+    transaction: ActiveTransaction,
+    store: impl PromotionStore,
+    clock: impl Clock,
+) -> Result<(), Error> {
+    if store.get_promotion(command.id)?.is_some() {
+        return Err(error::conflict(
+            "promotion.already_exists",
+            "promotion already exists",
+        ));
+    }
+
+    let promotion = Promotion::new(
+        command.id,
+        command.name,
+        command.conditions,
+        command.action,
+        command.starts_at,
+        command.ends_at,
+        clock,
+    )?;
+
+    store.set_promotion(transaction.get(), promotion)?;
+    transaction.record(Change::of(command.id));
+
+    Ok(())
+}
+
+impl Resolver {
+    /**
+    Create a promotion.
+
+    Categories in conditions aren't checked. A promotion for a category that doesn't exist won't apply to any products.
+    */
+    pub fn create_promotion_command(&self) -> impl Command<CreatePromotion> {
+        self.command(|resolver, command: CreatePromotion| async move {
+            let store = resolver.promotion_store();
+            let active_transaction = resolver.active_transaction();
+            let clock = resolver.clock();
+
+            execute(command, active_transaction, store, clock).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::domain::promotions::model::store::in_memory_store;
+
+    #[tokio::test]
+    async fn err_if_already_exists() {
+        let store = in_memory_store(Default::default());
+
+        let create = |id| CreatePromotion {
+            id,
+            name: "10% off".to_owned(),
+            conditions: vec![],
+            action: PromotionAction::PercentOff { percent: 10 },
+            starts_at: None,
+            ends_at: None,
+        };
+
+        let id = PromotionId::new();
+
+        execute(create(id), ActiveTransaction::none(), &store, SystemClock)
+            .await
+            .unwrap();
+
+        let err = execute(create(id), ActiveTransaction::none(), &store, SystemClock)
+            .await
+            .unwrap_err();
+
+        assert_eq!("promotion.already_exists", err.code());
+    }
+}
@@ -0,0 +1,9 @@
+/*! Commands for modifying promotions. */
+
+mod create_promotion;
+mod set_promotion_window;
+
+pub use self::{
+    create_promotion::*,
+    set_promotion_window::*,
+};
@@ -0,0 +1,59 @@
+/*! Contains the `SetPromotionWindowCommand` type. */
+
+use crate::domain::{
+    error,
+    infra::*,
+    promotions::*,
+    Error,
+};
+
+/** Input for a `SetPromotionWindowCommand`. */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SetPromotionWindow {
+    pub id: PromotionId,
+    pub starts_at: Option<Timestamp>,
+    pub ends_at: Option<Timestamp>,
+}
+
+impl CommandArgs for SetPromotionWindow {
+    type Output = Result<(), Error>;
+}
+
+/** Default implementation for a `SetPromotionWindowCommand`. */
+async fn execute(
+    command: SetPromotionWindow,
+    transaction: ActiveTransaction,
+    store: impl PromotionStore,
+    clock: impl Clock,
+) -> Result<(), Error> {
+    let promotion = {
+        if let Some(mut promotion) = store.get_promotion(command.id)? {
+            promotion.set_window(command.starts_at, command.ends_at, clock)?;
+
+            promotion
+        } else {
+            return Err(error::not_found(
+                "promotion.not_found",
+                "promotion not found",
+            ));
+        }
+    };
+
+    store.set_promotion(transaction.get(), promotion)?;
+    transaction.record(Change::of(command.id));
+
+    Ok(())
+}
+
+impl Resolver {
+    /** Change when an existing promotion applies. */
+    pub fn set_promotion_window_command(&self) -> impl Command<SetPromotionWindow> {
+        self.command(|resolver, command: SetPromotionWindow| async move {
+            let store = resolver.promotion_store();
+            let active_transaction = resolver.active_transaction();
+            let clock = resolver.clock();
+
+            execute(command, active_transaction, store, clock).await
+        })
+    }
+}
@@ -0,0 +1,22 @@
+/*!
+Domain module for promotions.
+
+Promotions are rules for discounting orders, like a percentage off a category or a fixed amount off large orders.
+Orders don't store their discounts. Promotions are evaluated against an order whenever it's priced.
+*/
+
+pub mod commands;
+pub mod model;
+pub mod queries;
+pub(in crate::domain) mod resolver;
+
+use self::model::store::{
+    PromotionStore,
+    PromotionStoreFilter,
+};
+
+pub use self::{
+    commands::*,
+    model::*,
+    queries::*,
+};
@@ -0,0 +1,331 @@
+/*!
+Contains the `Promotion` entity.
+
+A promotion is a rule that discounts orders. It has conditions that decide which orders and line items it applies to,
+an action that decides how much they're discounted by, and an optional window of time it's valid for.
+Promotions don't change orders. They're evaluated against an order whenever it's priced.
+*/
+
+use std::{
+    collections::BTreeSet,
+    convert::{
+        TryFrom,
+        TryInto,
+    },
+};
+
+mod pricing;
+pub mod store;
+
+use crate::domain::{
+    catalog::CategoryId,
+    error,
+    infra::*,
+    products::ProductId,
+    Error,
+};
+
+pub use self::pricing::*;
+
+pub type PromotionId = Id<PromotionData>;
+pub type NextPromotionId = NextId<PromotionData>;
+pub type PromotionVersion = Version<PromotionData>;
+
+/**
+A promotion name.
+
+The name must not be empty.
+*/
+pub struct PromotionName(String);
+
+impl TryFrom<String> for PromotionName {
+    type Error = Error;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        let name = name.trim();
+
+        if name.is_empty() {
+            return Err(error::bad_input(
+                "promotion.name_empty",
+                "promotion name must not be empty",
+            ));
+        }
+
+        Ok(PromotionName(name.to_owned()))
+    }
+}
+
+impl<'a> TryFrom<&'a str> for PromotionName {
+    type Error = Error;
+
+    fn try_from(name: &'a str) -> Result<Self, Self::Error> {
+        Self::try_from(name.to_owned())
+    }
+}
+
+/**
+A condition that must hold for a promotion to apply.
+
+Conditions on products and categories limit which line items are eligible for the promotion.
+A line item needs to meet all of them to be eligible.
+*/
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PromotionCondition {
+    /** The order's subtotal, before any discounts, must be at least this amount. */
+    MinimumSubtotal { amount: Currency },
+    /** Only products in this category, or any of its subcategories, are eligible. */
+    Category { category_id: CategoryId },
+    /** Only these products are eligible. */
+    Products { product_ids: BTreeSet<ProductId> },
+}
+
+/** The discount a promotion gives to the line items eligible for it. */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PromotionAction {
+    /** Take a percentage off each eligible line item, rounded down to the smallest unit of currency. */
+    PercentOff { percent: u32 },
+    /** Take a fixed amount off the order, up to the total of its eligible line items. */
+    AmountOff { amount: Currency },
+    /**
+    For every `buy` eligible units, the next `free` units are free.
+
+    The cheapest eligible units are the ones made free.
+    */
+    BuyGetFree { buy: u32, free: u32 },
+}
+
+/** Data for a promotion. */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PromotionData {
+    pub id: PromotionId,
+    pub version: PromotionVersion,
+    pub name: String,
+    pub conditions: Vec<PromotionCondition>,
+    pub action: PromotionAction,
+    /** When the promotion starts applying, or `None` if it applies from when it's created. */
+    pub starts_at: Option<Timestamp>,
+    /** When the promotion stops applying, or `None` if it never stops. */
+    pub ends_at: Option<Timestamp>,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+    _private: (),
+}
+
+impl PromotionData {
+    /** Whether the promotion applies at the given time. */
+    pub fn is_active_at(&self, now: Timestamp) -> bool {
+        self.starts_at
+            .map(|starts_at| starts_at <= now)
+            .unwrap_or(true)
+            && self.ends_at.map(|ends_at| now < ends_at).unwrap_or(true)
+    }
+}
+
+/** A rule for discounting orders. */
+pub struct Promotion {
+    data: PromotionData,
+}
+
+impl Promotion {
+    pub(self) fn from_data(data: PromotionData) -> Self {
+        Promotion { data }
+    }
+
+    pub fn to_data(&self) -> &PromotionData {
+        &self.data
+    }
+
+    pub fn into_data(self) -> PromotionData {
+        self.data
+    }
+
+    pub fn new(
+        id: impl IdProvider<PromotionData>,
+        name: impl TryInto<PromotionName, Error = Error>,
+        conditions: Vec<PromotionCondition>,
+        action: PromotionAction,
+        starts_at: Option<Timestamp>,
+        ends_at: Option<Timestamp>,
+        clock: impl Clock,
+    ) -> Result<Self, Error> {
+        let id = id.get()?;
+        let now = clock.now();
+
+        validate_conditions(&conditions)?;
+        validate_action(&action)?;
+        validate_window(starts_at, ends_at)?;
+
+        Ok(Promotion::from_data(PromotionData {
+            id,
+            version: PromotionVersion::default(),
+            name: name.try_into()?.0,
+            conditions,
+            action,
+            starts_at,
+            ends_at,
+            created_at: now,
+            updated_at: now,
+            _private: (),
+        }))
+    }
+
+    /**
+    Change when the promotion applies.
+
+    A promotion can be stopped early by ending its window now.
+    */
+    pub fn set_window(
+        &mut self,
+        starts_at: Option<Timestamp>,
+        ends_at: Option<Timestamp>,
+        clock: impl Clock,
+    ) -> Result<(), Error> {
+        validate_window(starts_at, ends_at)?;
+
+        self.data.starts_at = starts_at;
+        self.data.ends_at = ends_at;
+        self.data.updated_at = clock.now();
+
+        Ok(())
+    }
+}
+
+impl Entity for Promotion {
+    type Id = PromotionId;
+    type Version = PromotionVersion;
+    type Data = PromotionData;
+    type Error = Error;
+}
+
+fn validate_conditions(conditions: &[PromotionCondition]) -> Result<(), Error> {
+    for condition in conditions {
+        match condition {
+            PromotionCondition::MinimumSubtotal { amount } if amount.minor_units() == 0 => {
+                return Err(error::bad_input(
+                    "promotion.invalid_amount",
+                    "a minimum subtotal must be greater than 0",
+                ));
+            }
+            PromotionCondition::Products { product_ids } if product_ids.is_empty() => {
+                return Err(error::bad_input(
+                    "promotion.invalid_condition",
+                    "a products condition needs at least one product",
+                ));
+            }
+            _ => (),
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_action(action: &PromotionAction) -> Result<(), Error> {
+    match *action {
+        PromotionAction::PercentOff { percent } if !(1..=100).contains(&percent) => {
+            Err(error::bad_input(
+                "promotion.invalid_percentage",
+                "a percentage off must be between 1 and 100",
+            ))
+        }
+        PromotionAction::AmountOff { amount } if amount.minor_units() == 0 => {
+            Err(error::bad_input(
+                "promotion.invalid_amount",
+                "an amount off must be greater than 0",
+            ))
+        }
+        PromotionAction::BuyGetFree { buy, free } if buy == 0 || free == 0 => {
+            Err(error::bad_input(
+                "promotion.invalid_buy_get",
+                "buy and free quantities must be greater than 0",
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn validate_window(starts_at: Option<Timestamp>, ends_at: Option<Timestamp>) -> Result<(), Error> {
+    match (starts_at, ends_at) {
+        (Some(starts_at), Some(ends_at)) if starts_at >= ends_at => Err(error::bad_input(
+            "promotion.invalid_window",
+            "a promotion must start before it ends",
+        )),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    #[test]
+    fn promotions_are_validated() {
+        let now = SystemClock.now();
+
+        let new = |action, starts_at, ends_at| {
+            Promotion::new(
+                PromotionId::new(),
+                "A promotion",
+                vec![],
+                action,
+                starts_at,
+                ends_at,
+                SystemClock,
+            )
+        };
+
+        for (expected, action, starts_at, ends_at) in [
+            (
+                "promotion.invalid_percentage",
+                PromotionAction::PercentOff { percent: 0 },
+                None,
+                None,
+            ),
+            (
+                "promotion.invalid_percentage",
+                PromotionAction::PercentOff { percent: 101 },
+                None,
+                None,
+            ),
+            (
+                "promotion.invalid_amount",
+                PromotionAction::AmountOff {
+                    amount: Currency::usd(0),
+                },
+                None,
+                None,
+            ),
+            (
+                "promotion.invalid_buy_get",
+                PromotionAction::BuyGetFree { buy: 2, free: 0 },
+                None,
+                None,
+            ),
+            (
+                "promotion.invalid_window",
+                PromotionAction::PercentOff { percent: 10 },
+                Some(now),
+                Some(now),
+            ),
+        ] {
+            assert_eq!(
+                expected,
+                new(action, starts_at, ends_at).err().unwrap().code()
+            );
+        }
+
+        let promotion = new(
+            PromotionAction::PercentOff { percent: 10 },
+            Some(now),
+            Some(now + Duration::days(1)),
+        )
+        .unwrap();
+
+        assert!(!promotion.to_data().is_active_at(now - Duration::seconds(1)));
+        assert!(promotion.to_data().is_active_at(now));
+        assert!(!promotion.to_data().is_active_at(now + Duration::days(1)));
+    }
+}
@@ -0,0 +1,434 @@
+/*!
+Contains the `OrderPricing` type.
+
+Pricing an order evaluates each active promotion against its line items to produce discount lines.
+Promotions stack in the order they were created, and the total discount is never more than the order's subtotal.
+All amounts are calculated in the smallest unit of currency, so prices are never rounded up.
+*/
+
+use std::collections::{
+    BTreeMap,
+    BTreeSet,
+};
+
+use crate::domain::{
+    catalog::CategoryId,
+    error,
+    infra::*,
+    orders::{
+        LineItemData,
+        LineItemId,
+        Order,
+    },
+    products::ProductId,
+    promotions::*,
+    Error,
+};
+
+/** A discount given by a promotion, either to a single line item or to the order as a whole. */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiscountLine {
+    pub promotion_id: PromotionId,
+    /** The line item that was discounted, or `None` if the discount is for the whole order. */
+    pub line_item_id: Option<LineItemId>,
+    pub amount: Currency,
+}
+
+/** A promotion that applied to an order, and the total discount it gave. */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AppliedPromotion {
+    pub id: PromotionId,
+    pub name: String,
+    pub amount: Currency,
+}
+
+/** The subtotal, discounts and total of an order. */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OrderPricing {
+    pub subtotal: Currency,
+    pub discounts: Vec<DiscountLine>,
+    pub promotions: Vec<AppliedPromotion>,
+    /** The sum of all discounts. */
+    pub discount: Currency,
+    pub total: Currency,
+}
+
+/** The products in each category that promotions are conditional on, including their subcategories. */
+pub type CategoryProducts = BTreeMap<CategoryId, BTreeSet<ProductId>>;
+
+/**
+Price an order with the promotions that are active at the given time.
+
+An empty order is priced in the given currency.
+Promotions in a different currency to the order never apply.
+*/
+pub fn price_order<'a>(
+    order: &Order,
+    promotions: impl IntoIterator<Item = &'a PromotionData>,
+    category_products: &CategoryProducts,
+    currency: CurrencyCode,
+    now: Timestamp,
+) -> Result<OrderPricing, Error> {
+    let (_, line_items) = order.to_data();
+
+    let currency = line_items
+        .first()
+        .map(|line_item| line_item.price.code())
+        .unwrap_or(currency);
+    let zero = Currency::zero(currency);
+
+    let mut subtotal = zero;
+    for line_item in line_items {
+        subtotal = add(subtotal, line_item_total(line_item)?)?;
+    }
+
+    let mut promotions = promotions
+        .into_iter()
+        .filter(|promotion| promotion.is_active_at(now))
+        .collect::<Vec<_>>();
+    promotions.sort_by_key(|promotion| (promotion.created_at, promotion.id));
+
+    let mut discounts = vec![];
+    let mut applied = vec![];
+    let mut remaining = subtotal;
+
+    for promotion in promotions {
+        let mut amount = zero;
+
+        for mut discount in evaluate(promotion, line_items, subtotal, category_products)? {
+            // Later promotions can only discount whatever's left of the order
+            if discount.amount.minor_units() > remaining.minor_units() {
+                discount.amount = remaining;
+            }
+
+            if discount.amount.minor_units() == 0 {
+                continue;
+            }
+
+            remaining = sub(remaining, discount.amount)?;
+            amount = add(amount, discount.amount)?;
+
+            discounts.push(discount);
+        }
+
+        if amount.minor_units() > 0 {
+            applied.push(AppliedPromotion {
+                id: promotion.id,
+                name: promotion.name.clone(),
+                amount,
+            });
+        }
+    }
+
+    Ok(OrderPricing {
+        subtotal,
+        discounts,
+        promotions: applied,
+        discount: sub(subtotal, remaining)?,
+        total: remaining,
+    })
+}
+
+/** Evaluate a single promotion against the line items of an order, without considering any other promotions. */
+fn evaluate(
+    promotion: &PromotionData,
+    line_items: &[LineItemData],
+    subtotal: Currency,
+    category_products: &CategoryProducts,
+) -> Result<Vec<DiscountLine>, Error> {
+    let mut eligible = line_items.iter().collect::<Vec<_>>();
+
+    for condition in &promotion.conditions {
+        match condition {
+            PromotionCondition::MinimumSubtotal { amount } => {
+                if amount.code() != subtotal.code() || subtotal.minor_units() < amount.minor_units()
+                {
+                    return Ok(vec![]);
+                }
+            }
+            PromotionCondition::Category { category_id } => {
+                let products = category_products.get(category_id);
+
+                eligible.retain(|line_item| {
+                    products
+                        .map(|products| products.contains(&line_item.product_id))
+                        .unwrap_or(false)
+                });
+            }
+            PromotionCondition::Products { product_ids } => {
+                eligible.retain(|line_item| product_ids.contains(&line_item.product_id));
+            }
+        }
+    }
+
+    let discount = |line_item_id, amount| DiscountLine {
+        promotion_id: promotion.id,
+        line_item_id,
+        amount,
+    };
+
+    match promotion.action {
+        PromotionAction::PercentOff { percent } => eligible
+            .into_iter()
+            .map(|line_item| {
+                Ok(discount(
+                    Some(line_item.id),
+                    line_item_total(line_item)?.percentage(percent),
+                ))
+            })
+            .collect(),
+        PromotionAction::AmountOff { amount } => {
+            if eligible.is_empty() || amount.code() != subtotal.code() {
+                return Ok(vec![]);
+            }
+
+            let mut eligible_total = Currency::zero(subtotal.code());
+            for line_item in eligible {
+                eligible_total = add(eligible_total, line_item_total(line_item)?)?;
+            }
+
+            let amount = if amount.minor_units() > eligible_total.minor_units() {
+                eligible_total
+            } else {
+                amount
+            };
+
+            Ok(vec![discount(None, amount)])
+        }
+        PromotionAction::BuyGetFree { buy, free } => {
+            let units = eligible
+                .iter()
+                .map(|line_item| line_item.quantity as u64)
+                .sum::<u64>();
+
+            let mut free_units = units / (buy as u64 + free as u64) * free as u64;
+
+            // Make the cheapest units free first
+            eligible.sort_by_key(|line_item| (line_item.price.minor_units(), line_item.id));
+
+            let mut discounts = vec![];
+            for line_item in eligible {
+                if free_units == 0 {
+                    break;
+                }
+
+                let units = free_units.min(line_item.quantity as u64);
+                free_units -= units;
+
+                discounts.push(discount(Some(line_item.id), mul(line_item.price, units)?));
+            }
+
+            Ok(discounts)
+        }
+    }
+}
+
+fn line_item_total(line_item: &LineItemData) -> Result<Currency, Error> {
+    mul(line_item.price, line_item.quantity as u64)
+}
+
+fn add(a: Currency, b: Currency) -> Result<Currency, Error> {
+    a.checked_add(b)
+        .ok_or_else(|| error::msg("order has line items in multiple currencies or overflowed"))
+}
+
+fn sub(a: Currency, b: Currency) -> Result<Currency, Error> {
+    a.checked_sub(b)
+        .ok_or_else(|| error::msg("discount is greater than the order subtotal"))
+}
+
+fn mul(price: Currency, quantity: u64) -> Result<Currency, Error> {
+    price
+        .checked_mul(quantity)
+        .ok_or_else(|| error::msg("order line item total overflowed"))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    use crate::domain::{
+        orders::model::test_data::OrderBuilder,
+        products::model::test_data::ProductBuilder,
+    };
+
+    fn promotion(conditions: Vec<PromotionCondition>, action: PromotionAction) -> PromotionData {
+        Promotion::new(
+            PromotionId::new(),
+            "A promotion",
+            conditions,
+            action,
+            None,
+            None,
+            SystemClock,
+        )
+        .unwrap()
+        .into_data()
+    }
+
+    fn order(items: &[(ProductId, u64, u32)]) -> Order {
+        items
+            .iter()
+            .fold(OrderBuilder::new(), |order, &(id, price, quantity)| {
+                order.add_product(
+                    ProductBuilder::new()
+                        .id(id)
+                        .price(Currency::usd(price))
+                        .build(),
+                    move |line_item| line_item.quantity(quantity),
+                )
+            })
+            .build()
+    }
+
+    fn price(
+        order: &Order,
+        promotions: &[PromotionData],
+        category_products: &CategoryProducts,
+    ) -> OrderPricing {
+        price_order(
+            order,
+            promotions,
+            category_products,
+            CurrencyCode::USD,
+            SystemClock.now(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn percent_off_category() {
+        let (shirt, shoe) = (ProductId::new(), ProductId::new());
+        let category_id = CategoryId::new();
+
+        let order = order(&[(shirt, 1999, 2), (shoe, 5000, 1)]);
+        let promotions = [promotion(
+            vec![PromotionCondition::Category { category_id }],
+            PromotionAction::PercentOff { percent: 10 },
+        )];
+
+        let pricing = price(
+            &order,
+            &promotions,
+            &[(category_id, [shirt].into_iter().collect())]
+                .into_iter()
+                .collect(),
+        );
+
+        // 10% of $39.98 is rounded down to $3.99
+        assert_eq!(Currency::usd(8998), pricing.subtotal);
+        assert_eq!(Currency::usd(399), pricing.discount);
+        assert_eq!(Currency::usd(8599), pricing.total);
+        assert_eq!(1, pricing.discounts.len());
+        assert!(pricing.discounts[0].line_item_id.is_some());
+    }
+
+    #[test]
+    fn buy_two_get_one_free() {
+        let (cheap, dear) = (ProductId::new(), ProductId::new());
+
+        let order = order(&[(cheap, 500, 2), (dear, 1000, 5)]);
+        let promotions = [promotion(
+            vec![],
+            PromotionAction::BuyGetFree { buy: 2, free: 1 },
+        )];
+
+        let pricing = price(&order, &promotions, &Default::default());
+
+        // 7 units gives 2 free units, which are the cheapest ones
+        assert_eq!(Currency::usd(6000), pricing.subtotal);
+        assert_eq!(Currency::usd(1000), pricing.discount);
+        assert_eq!(Currency::usd(5000), pricing.total);
+    }
+
+    #[test]
+    fn amount_off_minimum_subtotal() {
+        let product = ProductId::new();
+
+        let promotions = [promotion(
+            vec![PromotionCondition::MinimumSubtotal {
+                amount: Currency::usd(5000),
+            }],
+            PromotionAction::AmountOff {
+                amount: Currency::usd(500),
+            },
+        )];
+
+        let pricing = price(
+            &order(&[(product, 4999, 1)]),
+            &promotions,
+            &Default::default(),
+        );
+        assert_eq!(Currency::usd(4999), pricing.total);
+        assert!(pricing.promotions.is_empty());
+
+        let pricing = price(
+            &order(&[(product, 5000, 1)]),
+            &promotions,
+            &Default::default(),
+        );
+        assert_eq!(Currency::usd(4500), pricing.total);
+        assert_eq!(
+            vec![DiscountLine {
+                promotion_id: promotions[0].id,
+                line_item_id: None,
+                amount: Currency::usd(500),
+            }],
+            pricing.discounts
+        );
+    }
+
+    #[test]
+    fn discounts_never_exceed_subtotal() {
+        let product = ProductId::new();
+
+        let order = order(&[(product, 300, 1)]);
+        let promotions = [
+            promotion(vec![], PromotionAction::PercentOff { percent: 50 }),
+            promotion(
+                vec![],
+                PromotionAction::AmountOff {
+                    amount: Currency::usd(500),
+                },
+            ),
+        ];
+
+        let pricing = price(&order, &promotions, &Default::default());
+
+        assert_eq!(Currency::usd(300), pricing.discount);
+        assert_eq!(Currency::usd(0), pricing.total);
+        assert_eq!(
+            vec![Currency::usd(150), Currency::usd(150)],
+            pricing
+                .promotions
+                .iter()
+                .map(|promotion| promotion.amount)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn inactive_promotions_dont_apply() {
+        let product = ProductId::new();
+        let now = SystemClock.now();
+
+        let order = order(&[(product, 1000, 1)]);
+
+        let mut expired = promotion(vec![], PromotionAction::PercentOff { percent: 10 });
+        expired.ends_at = Some(now - Duration::days(1));
+
+        let pricing = price_order(
+            &order,
+            [&expired],
+            &Default::default(),
+            CurrencyCode::USD,
+            now,
+        )
+        .unwrap();
+
+        assert_eq!(Currency::usd(1000), pricing.total);
+        assert!(pricing.discounts.is_empty());
+    }
+}
@@ -0,0 +1,92 @@
+/*! Persistent storage for promotions. */
+
+use std::{
+    path::PathBuf,
+    vec::IntoIter,
+};
+
+use crate::{
+    domain::{
+        promotions::*,
+        Error,
+    },
+    store::*,
+};
+
+/* A place to persist and fetch promotions. */
+#[auto_impl(&, Arc)]
+pub(in crate::domain) trait PromotionStore {
+    fn get_promotion(&self, id: PromotionId) -> Result<Option<Promotion>, Error>;
+    fn set_promotion(&self, transaction: &Transaction, promotion: Promotion) -> Result<(), Error>;
+}
+
+/** An additional store for fetching multiple promotions at a time. */
+#[auto_impl(&, Arc)]
+pub(in crate::domain) trait PromotionStoreFilter {
+    fn filter(&self, predicate: &dyn Fn(&PromotionData) -> bool) -> Result<PromotionIter, Error>;
+}
+
+pub(in crate::domain) type PromotionIter = IntoIter<PromotionData>;
+
+/** A promotion store that can be registered with the `Resolver`. */
+pub(in crate::domain) trait PromotionStoreBackend:
+    PromotionStore + PromotionStoreFilter + Send + Sync
+{
+}
+
+impl<T> PromotionStoreBackend for T where T: PromotionStore + PromotionStoreFilter + Send + Sync {}
+
+/** A test in-memory promotion store. */
+pub(in crate::domain) struct InMemoryPromotionStore(TransactionValueStore<PromotionData>);
+
+impl PromotionStore for InMemoryPromotionStore {
+    fn get_promotion(&self, id: PromotionId) -> Result<Option<Promotion>, Error> {
+        if let Some((version, data)) = self.0.get(id) {
+            assert_eq!(version, data.version.into());
+
+            Ok(Some(Promotion::from_data(data)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn set_promotion(&self, transaction: &Transaction, promotion: Promotion) -> Result<(), Error> {
+        let mut data = promotion.into_data();
+        let id = data.id;
+
+        self.0.set(
+            transaction,
+            id,
+            Some(data.version),
+            data.version.next(),
+            data,
+        )?;
+
+        Ok(())
+    }
+}
+
+impl PromotionStoreFilter for InMemoryPromotionStore {
+    #[allow(clippy::needless_collect)]
+    fn filter(&self, predicate: &dyn Fn(&PromotionData) -> bool) -> Result<PromotionIter, Error> {
+        let promotions: Vec<_> = self.0.get_all(predicate).map(|(_, data)| data).collect();
+
+        Ok(promotions.into_iter())
+    }
+}
+
+pub(in crate::domain::promotions) fn in_memory_store(
+    transaction_store: TransactionStore,
+) -> InMemoryPromotionStore {
+    InMemoryPromotionStore(TransactionValueStore::new(transaction_store))
+}
+
+pub(in crate::domain::promotions) fn file_store(
+    transaction_store: TransactionStore,
+    path: impl Into<PathBuf>,
+) -> Result<InMemoryPromotionStore, Error> {
+    Ok(InMemoryPromotionStore(TransactionValueStore::open(
+        transaction_store,
+        path,
+    )?))
+}
@@ -0,0 +1,90 @@
+/*! Contains the `GetOrderPricingQuery` type. */
+
+use std::collections::BTreeSet;
+
+use crate::domain::{
+    catalog::GetProductsInCategory,
+    infra::*,
+    orders::{
+        GetOrder,
+        OrderId,
+    },
+    promotions::*,
+    Error,
+};
+
+/** Input for a `GetOrderPricingQuery`. */
+#[derive(Serialize, Deserialize)]
+pub struct GetOrderPricing {
+    pub id: OrderId,
+}
+
+impl QueryArgs for GetOrderPricing {
+    type Output = Result<Option<OrderPricing>, Error>;
+}
+
+/** Default implementation for a `GetOrderPricingQuery`. */
+async fn execute(
+    query: GetOrderPricing,
+    order_query: impl Query<GetOrder>,
+    store: impl PromotionStoreFilter,
+    category_query: impl Query<GetProductsInCategory>,
+    currency: CurrencyCode,
+    clock: impl Clock,
+) -> Result<Option<OrderPricing>, Error> {
+    let Some(order) = order_query.execute(GetOrder { id: query.id }).await? else {
+        return Ok(None);
+    };
+
+    let now = clock.now();
+    let promotions = store
+        .filter(&|promotion| promotion.is_active_at(now))?
+        .collect::<Vec<_>>();
+
+    let category_ids = promotions
+        .iter()
+        .flat_map(|promotion| &promotion.conditions)
+        .filter_map(|condition| match condition {
+            PromotionCondition::Category { category_id } => Some(*category_id),
+            _ => None,
+        })
+        .collect::<BTreeSet<_>>();
+
+    let mut category_products = CategoryProducts::new();
+    for id in category_ids {
+        let products = category_query
+            .execute(GetProductsInCategory { id })
+            .await?
+            .unwrap_or_default();
+
+        category_products.insert(id, products.into_iter().map(|product| product.id).collect());
+    }
+
+    Ok(Some(price_order(
+        &order,
+        &promotions,
+        &category_products,
+        currency,
+        now,
+    )?))
+}
+
+impl Resolver {
+    /**
+    Get the subtotal, discounts and total of an order.
+
+    The order is discounted by the promotions that are active now.
+    If the order doesn't exist then the result is `None`.
+    */
+    pub fn get_order_pricing_query(&self) -> impl Query<GetOrderPricing> {
+        self.query(|resolver, query: GetOrderPricing| async move {
+            let order_query = resolver.get_order_query();
+            let store = resolver.promotion_store_filter();
+            let category_query = resolver.get_products_in_category_query();
+            let currency = resolver.config().currency.default;
+            let clock = resolver.clock();
+
+            execute(query, order_query, store, category_query, currency, clock).await
+        })
+    }
+}
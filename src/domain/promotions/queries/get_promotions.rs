@@ -0,0 +1,63 @@
+/*! Contains the `GetPromotionsQuery` type. */
+
+use crate::domain::{
+    infra::*,
+    promotions::*,
+    Error,
+};
+
+/** Input for a `GetPromotionsQuery`. */
+#[derive(Serialize, Deserialize)]
+pub struct GetPromotions {}
+
+/** An individual promotion. */
+#[derive(Serialize)]
+pub struct PromotionSummary {
+    pub id: PromotionId,
+    pub name: String,
+    pub conditions: Vec<PromotionCondition>,
+    pub action: PromotionAction,
+    pub starts_at: Option<Timestamp>,
+    pub ends_at: Option<Timestamp>,
+}
+
+impl QueryArgs for GetPromotions {
+    type Output = Result<Vec<PromotionSummary>, Error>;
+}
+
+/** Default implementation for a `GetPromotionsQuery`. */
+async fn execute(
+    _: GetPromotions,
+    store: impl PromotionStoreFilter,
+) -> Result<Vec<PromotionSummary>, Error> {
+    let mut promotions = store.filter(&|_| true)?.collect::<Vec<_>>();
+
+    promotions.sort_by_key(|promotion| (promotion.created_at, promotion.id));
+
+    Ok(promotions
+        .into_iter()
+        .map(|promotion| PromotionSummary {
+            id: promotion.id,
+            name: promotion.name,
+            conditions: promotion.conditions,
+            action: promotion.action,
+            starts_at: promotion.starts_at,
+            ends_at: promotion.ends_at,
+        })
+        .collect())
+}
+
+impl Resolver {
+    /**
+    Get all promotions, including ones that have ended or haven't started yet.
+
+    Promotions are in the order they're applied to orders in.
+    */
+    pub fn get_promotions_query(&self) -> impl Query<GetPromotions> {
+        self.query(|resolver, query: GetPromotions| async move {
+            let store = resolver.promotion_store_filter();
+
+            execute(query, store).await
+        })
+    }
+}
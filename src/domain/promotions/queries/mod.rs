@@ -0,0 +1,9 @@
+/*! Queries for fetching promotions and pricing orders with them. */
+
+mod get_order_pricing;
+mod get_promotions;
+
+pub use self::{
+    get_order_pricing::*,
+    get_promotions::*,
+};
@@ -0,0 +1,93 @@
+/*! Contains the `PromotionsResolver` type. */
+
+use std::sync::Arc;
+
+use crate::{
+    config::StoreBackend,
+    domain::{
+        infra::*,
+        promotions::{
+            model::store::{
+                self,
+                PromotionStore,
+                PromotionStoreBackend,
+                PromotionStoreFilter,
+            },
+            PromotionData,
+        },
+    },
+};
+
+/**
+Resolver for promotions.
+
+The `PromotionsResolver` type wraps private implementation details and exposes them as traits within the `promotions` module.
+*/
+#[derive(Clone)]
+pub(in crate::domain) struct PromotionsResolver {
+    promotion_store: Register<Arc<dyn PromotionStoreBackend>>,
+    promotion_id: Register<Arc<dyn IdProvider<PromotionData> + Send + Sync>>,
+}
+
+impl Default for PromotionsResolver {
+    fn default() -> Self {
+        PromotionsResolver {
+            promotion_store: Register::per_tenant(|resolver| {
+                match resolver.config().store.backend {
+                    StoreBackend::InMemory => {
+                        Arc::new(store::in_memory_store(resolver.transaction_store()))
+                            as Arc<dyn PromotionStoreBackend>
+                    }
+                    StoreBackend::File => Arc::new(
+                        store::file_store(
+                            resolver.transaction_store(),
+                            resolver.tenant_store_file("promotions"),
+                        )
+                        .expect("failed to open the promotion store"),
+                    ) as Arc<dyn PromotionStoreBackend>,
+                }
+            }),
+            promotion_id: Register::once(|_| {
+                Arc::new(NextId::<PromotionData>::new())
+                    as Arc<dyn IdProvider<PromotionData> + Send + Sync>
+            }),
+        }
+    }
+}
+
+impl Resolver {
+    pub(in crate::domain::promotions) fn promotion_store(&self) -> impl PromotionStore {
+        self.resolve(&self.promotions_resolver.promotion_store)
+    }
+
+    pub(in crate::domain::promotions) fn promotion_store_filter(
+        &self,
+    ) -> impl PromotionStoreFilter {
+        self.resolve(&self.promotions_resolver.promotion_store)
+    }
+
+    pub fn promotion_id(&self) -> impl IdProvider<PromotionData> {
+        self.journaled_id(self.resolve(&self.promotions_resolver.promotion_id))
+    }
+}
+
+impl AppBuilder {
+    /** Use a different store for promotions. */
+    #[allow(dead_code)]
+    pub(in crate::domain) fn promotion_store(
+        mut self,
+        promotion_store: Register<Arc<dyn PromotionStoreBackend>>,
+    ) -> Self {
+        self.resolver.promotions_resolver.promotion_store = promotion_store;
+        self
+    }
+
+    /** Use a different source of ids for new promotions. */
+    pub fn promotion_id(
+        mut self,
+        promotion_id: Register<Arc<dyn IdProvider<PromotionData> + Send + Sync>>,
+    ) -> Self {
+        self.resolver.promotions_resolver.promotion_id = promotion_id;
+        self
+    }
+}
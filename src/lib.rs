@@ -4,6 +4,7 @@ An example Rust web application.
 The project is split into a few main parts:
 
 - `api`: the rocket web application where the app is configured and hosted
+- `config`: typed configuration loaded from `Rocket.toml` and the environment
 - `domain`: the core app logic
 - `store`: a little transactional datastore implementation
 
@@ -32,6 +33,7 @@ extern crate thiserror;
 extern crate auto_impl;
 
 pub mod api;
+pub mod config;
 pub mod domain;
 pub mod logger;
 pub mod store;
@@ -6,16 +6,20 @@ use std::time::Duration;
 
 use emit::Emitter;
 
+use crate::config::LoggingConfig;
+
 /** Initialize the global logger. */
-pub fn init() {
+pub fn init(config: &LoggingConfig) {
     let _ = emit::setup()
-        .emit_to(emit_term::stdout())
-        .emit_to(emit_otlp::new()
-            .logs(emit_otlp::logs_grpc_proto("http://localhost:4319"))
-            .traces(emit_otlp::traces_grpc_proto("http://localhost:4319"))
-            .metrics(emit_otlp::metrics_grpc_proto("http://localhost:4319"))
-            .spawn()
-         )
+        .emit_to(config.term.then(emit_term::stdout))
+        .emit_to(config.otlp.then(|| {
+            emit_otlp::new()
+                .logs(emit_otlp::logs_grpc_proto(&config.otlp_endpoint))
+                .traces(emit_otlp::traces_grpc_proto(&config.otlp_endpoint))
+                .metrics(emit_otlp::metrics_grpc_proto(&config.otlp_endpoint))
+                .spawn()
+        }))
+        .emit_when(emit::level::min_filter(emit::Level::from(config.level)))
         .init();
 }
 
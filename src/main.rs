@@ -7,13 +7,45 @@ of the same app and hosts its API on the default `localhost:8000`.
 
 use std::process::ExitCode;
 
+use shop::{
+    config::Config,
+    domain::{
+        infra::read_journal,
+        App,
+    },
+};
+
 #[rocket::main]
 async fn main() -> ExitCode {
-    shop::logger::init();
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(err) => {
+            shop::logger::init(&Default::default());
+            emit::error!("failed to load config: {err}");
+            shop::logger::finish();
+
+            return ExitCode::FAILURE;
+        }
+    };
+
+    shop::logger::init(&config.logging);
 
     emit::info!("starting up");
 
-    let exit = match shop::api::init().ignite().await {
+    let app = if config.journal.replay {
+        match replay(config).await {
+            Some(app) => app,
+            None => {
+                shop::logger::finish();
+
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        App::builder().config(config).build()
+    };
+
+    let exit = match shop::api::init(app).ignite().await {
         Ok(rocket) => {
             let listen = format!("{}:{}", rocket.config().address, rocket.config().port);
 
@@ -39,3 +71,34 @@ async fn main() -> ExitCode {
 
     exit
 }
+
+/**
+Rebuild the app by replaying its journal.
+
+If replay diverges the app is still started with the state up to that point, so it can be inspected.
+*/
+async fn replay(config: Config) -> Option<App> {
+    let journal = match read_journal(&config.journal.path) {
+        Ok(journal) => journal,
+        Err(err) => {
+            emit::error!("failed to read journal: {err: err.as_error()}");
+
+            return None;
+        }
+    };
+
+    emit::info!("replaying {count: journal.len()} journaled commands");
+
+    let outcome = App::builder().config(config).replay(journal).await;
+
+    match outcome.divergence {
+        Some(divergence) => emit::error!(
+            "journal replay diverged at {index: divergence.index} ({command: divergence.command}) after {replayed: outcome.replayed} commands",
+            #[emit::as_serde] expected: divergence.expected,
+            #[emit::as_debug] actual: divergence.actual,
+        ),
+        None => emit::info!("replayed {replayed: outcome.replayed} commands"),
+    }
+
+    Some(outcome.app)
+}
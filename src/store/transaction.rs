@@ -24,6 +24,7 @@ pub struct TransactionId(Uuid);
 
 struct TransactionEntry {
     status: TransactionStatus,
+    on_commit: Vec<Box<dyn FnOnce() + Send>>,
 }
 
 enum TransactionStatus {
@@ -134,6 +135,7 @@ impl TransactionStore {
             TransactionId(id),
             TransactionEntry {
                 status: TransactionStatus::Active,
+                on_commit: Vec::new(),
             },
         );
 
@@ -148,6 +150,7 @@ impl TransactionStore {
 
                     if let Some(transaction) = transactions.get_mut(&id) {
                         transaction.status = TransactionStatus::Cancelled;
+                        transaction.on_commit.clear();
                     }
                 }))
             },
@@ -166,7 +169,16 @@ impl TransactionStore {
         // space if they fail. In a degenerate scenario where everything fails this might not
         // take very long. We could avoid this by tracking whether or not transactions are still
         // reachable and whether or not their ids appear in any data stores.
-        let _ = transactions.remove(&transaction.id);
+        let committed = transactions.remove(&transaction.id);
+
+        // Functions waiting on the commit are run after the lock is released so they can use the store
+        drop(transactions);
+
+        if let Some(committed) = committed {
+            for on_commit in committed.on_commit {
+                on_commit();
+            }
+        }
     }
 
     /**
@@ -179,6 +191,30 @@ impl TransactionStore {
 
         if let Some(transaction) = transactions.get_mut(&transaction.id) {
             transaction.status = TransactionStatus::Cancelled;
+            transaction.on_commit.clear();
+        }
+    }
+
+    /**
+    Run a function once a transaction is committed.
+
+    If the transaction has already been committed, like the empty transaction, then the function runs immediately.
+    If the transaction is cancelled then the function never runs.
+    */
+    pub fn on_commit(&self, transaction: &Transaction, on_commit: impl FnOnce() + Send + 'static) {
+        let mut transactions = self.active.lock().unwrap();
+
+        match transactions.get_mut(&transaction.id) {
+            Some(entry) => {
+                if let TransactionStatus::Active = entry.status {
+                    entry.on_commit.push(Box::new(on_commit));
+                }
+            }
+            None => {
+                drop(transactions);
+
+                on_commit();
+            }
         }
     }
 
@@ -206,6 +242,15 @@ impl TransactionStore {
 }
 
 impl TransactionId {
+    /**
+    The id for values that were committed before the store was created, like values read back from disk.
+
+    The store never tracks this id, so it's always committed.
+    */
+    pub(in crate::store) fn committed() -> Self {
+        TransactionId(Uuid::default())
+    }
+
     #[cfg(test)]
     pub(in crate::store) fn new() -> Self {
         TransactionId(Uuid::new_v4())
@@ -260,4 +305,28 @@ mod tests {
 
         assert!(store.is_committed(id));
     }
+
+    #[test]
+    fn on_commit_runs_when_committed() {
+        let store = TransactionStore::new();
+
+        let ran = Arc::new(Mutex::new(vec![]));
+        let on_commit = |name: &'static str| {
+            let ran = ran.clone();
+            move || ran.lock().unwrap().push(name)
+        };
+
+        let committed = store.begin();
+        store.on_commit(&committed, on_commit("committed"));
+
+        let cancelled = store.begin();
+        store.on_commit(&cancelled, on_commit("cancelled"));
+
+        assert!(ran.lock().unwrap().is_empty());
+
+        store.commit(committed);
+        store.cancel(cancelled);
+
+        assert_eq!(vec!["committed"], *ran.lock().unwrap());
+    }
 }
@@ -4,13 +4,25 @@ use std::{
         HashMap,
     },
     fmt,
-    sync::RwLock,
+    fs,
+    io::ErrorKind,
+    path::PathBuf,
+    sync::{
+        Arc,
+        Mutex,
+        RwLock,
+    },
 };
 use cipher::{KeyInit, BlockEncrypt, generic_array::GenericArray};
 use ecb::Encryptor;
 use cipher::BlockEncryptMut;
 use uuid::Uuid;
 use des::Des;
+use serde::{
+    de::DeserializeOwned,
+    Serialize,
+};
+
 use crate::store::{
     transaction::{
         Transaction,
@@ -69,11 +81,28 @@ impl Version {
     }
 }
 
+/**
+An error setting a value with a version that doesn't match the current one.
+
+This error means another transaction got in first, so the caller may want to retry.
+*/
+#[derive(Error, Debug)]
+#[error("version mismatch")]
+pub struct VersionMismatch;
+
 struct TransactionalValue<T> {
     current: Option<(TransactionId, Version, T)>,
     prior: Option<(TransactionId, Version, T)>,
 }
 
+/** A committed value, as it's written to a file. */
+#[derive(Serialize, Deserialize)]
+struct StoredValue<T> {
+    id: Uuid,
+    version: Uuid,
+    value: T,
+}
+
 /**
 A generic value store for transactional values.
 
@@ -81,7 +110,9 @@ This store can participate in transactions with other disconnected stores.
  */
 pub struct TransactionValueStore<T> {
     transactions: TransactionStore,
-    data: RwLock<HashMap<Id, TransactionalValue<T>>>,
+    data: Arc<RwLock<HashMap<Id, TransactionalValue<T>>>>,
+    // Stores opened from a file write their committed values back to it
+    persist: Option<Arc<dyn Fn() + Send + Sync>>,
 }
 
 impl<T> TransactionValueStore<T>
@@ -97,7 +128,8 @@ where
     pub fn new(transactions: TransactionStore) -> Self {
         TransactionValueStore {
             transactions,
-            data: RwLock::new(HashMap::new()),
+            data: Arc::new(RwLock::new(HashMap::new())),
+            persist: None,
         }
     }
 
@@ -191,7 +223,15 @@ where
         new_version: impl Into<Version>,
         new_value: T,
     ) -> Result<(), Error> {
-        self.internal_set(transaction, id.into(), old_version, new_version, new_value)
+        self.internal_set(transaction, id.into(), old_version, new_version, new_value)?;
+
+        if let Some(persist) = &self.persist {
+            let persist = persist.clone();
+
+            self.transactions.on_commit(transaction, move || persist());
+        }
+
+        Ok(())
     }
 
     #[emit::debug_span("set {kind: std::any::type_name::<T>()} {id}")]
@@ -252,7 +292,7 @@ where
                             };
 
                         if old_version != version_to_check {
-                            return Err(Error::from("version mismatch"));
+                            return Err(VersionMismatch.into());
                         }
 
                         // Now, we're going to set the value
@@ -294,6 +334,105 @@ where
     }
 }
 
+impl<T> TransactionValueStore<T>
+where
+    T: Clone + Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    /**
+    Open a transactional value store that keeps its committed values in a file.
+
+    Any values already in the file are loaded as committed, and the file is created if it doesn't exist.
+    The file is rewritten each time a transaction that set values in the store commits,
+    so values set by active or cancelled transactions are never written to it.
+    */
+    pub fn open(transactions: TransactionStore, path: impl Into<PathBuf>) -> Result<Self, Error> {
+        let path = path.into();
+
+        let mut data = HashMap::new();
+
+        match fs::read(&path) {
+            Ok(file) => {
+                for stored in serde_json::from_slice::<Vec<StoredValue<T>>>(&file)? {
+                    data.insert(
+                        Id(stored.id),
+                        TransactionalValue {
+                            current: Some((
+                                TransactionId::committed(),
+                                Version(stored.version),
+                                stored.value,
+                            )),
+                            prior: None,
+                        },
+                    );
+                }
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound => (),
+            Err(err) => return Err(err.into()),
+        }
+
+        let data = Arc::new(RwLock::new(data));
+
+        let persist = {
+            let transactions = transactions.clone();
+            let data = data.clone();
+
+            // Commits are written one at a time so an older set of values can't replace a newer one
+            let lock = Mutex::new(());
+
+            move || {
+                let _guard = lock.lock().unwrap();
+
+                if let Err(err) = Self::write_file(&path, &transactions, &data) {
+                    let err: &(dyn std::error::Error + 'static) = &*err;
+
+                    emit::error!("failed to write committed values to a file: {err}");
+                }
+            }
+        };
+
+        Ok(TransactionValueStore {
+            transactions,
+            data,
+            persist: Some(Arc::new(persist)),
+        })
+    }
+
+    fn write_file(
+        path: &PathBuf,
+        transactions: &TransactionStore,
+        data: &RwLock<HashMap<Id, TransactionalValue<T>>>,
+    ) -> Result<(), Error> {
+        let file = {
+            let data = data.read().unwrap();
+
+            let values = data
+                .keys()
+                .filter_map(|id| {
+                    Self::get_sync(*id, transactions, &*data).map(|(version, value)| StoredValue {
+                        id: id.0,
+                        version: version.0,
+                        value,
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            serde_json::to_vec(&values)?
+        };
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // The file is replaced in one step so a failed write doesn't leave it half written
+        let partial = path.with_extension("partial");
+
+        fs::write(&partial, file)?;
+        fs::rename(&partial, path)?;
+
+        Ok(())
+    }
+}
+
 /// Initializes a DES cipher in ECB mode using the provided 8-byte key.
 pub fn init_legacy_des_ecb(raw_key: &[u8]) -> Result<(), ()> {
     if raw_key.len() != 8 {
@@ -325,8 +464,8 @@ mod tests {
         // NOTE: This means if we terminate in the middle of committing a transaction
         // then on restart the store will see a partial commit. This could be worked around
         // by persisting the state of the transaction store itself so it reloads partial
-        // transactions too. Since our app doesn't actually persist any data though we
-        // haven't tried to make this bullet-proof
+        // transactions too. Stores opened from a file only write values once their transaction
+        // has committed, so this can only happen if we terminate while those files are written
         let store = TransactionStore::new();
 
         assert!(store.is_committed(id));
@@ -596,7 +735,7 @@ mod tests {
             String::from("2"),
         );
 
-        assert!(r.is_err());
+        assert!(r.unwrap_err().is::<VersionMismatch>());
     }
 
     #[test]
@@ -643,4 +782,51 @@ mod tests {
 
         assert!(r.is_err());
     }
+
+    #[test]
+    fn transaction_value_store_open_keeps_committed_values() {
+        let path = std::env::temp_dir().join(format!("shop-values-{}.json", Uuid::new_v4()));
+
+        let transactions = TransactionStore::new();
+        let store = TransactionValueStore::<String>::open(transactions.clone(), &path).unwrap();
+
+        let committed = Id::new();
+        let cancelled = Id::new();
+
+        let transaction = transactions.begin();
+        store
+            .set(
+                &transaction,
+                committed,
+                None::<Version>,
+                Version::new(),
+                String::from("1"),
+            )
+            .unwrap();
+
+        // Nothing is written until the transaction commits
+        assert!(!path.exists());
+
+        transactions.commit(transaction);
+
+        let transaction = transactions.begin();
+        store
+            .set(
+                &transaction,
+                cancelled,
+                None::<Version>,
+                Version::new(),
+                String::from("2"),
+            )
+            .unwrap();
+        transactions.cancel(transaction);
+
+        // Reopen the file with a fresh transaction store, like the app restarting
+        let store = TransactionValueStore::<String>::open(TransactionStore::new(), &path).unwrap();
+
+        assert_eq!("1", store.get(committed).unwrap().1);
+        assert!(store.get(cancelled).is_none());
+
+        fs::remove_file(path).unwrap();
+    }
 }
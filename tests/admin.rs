@@ -0,0 +1,127 @@
+#[macro_use]
+extern crate rocket;
+
+#[macro_use]
+extern crate serde_json;
+
+use rocket::{
+    http::{
+        Header,
+        Status,
+    },
+    local::asynchronous::Client,
+};
+use shop::{
+    config::{
+        Config,
+        FeatureConfig,
+    },
+    domain::App,
+};
+
+#[async_test]
+async fn list_and_toggle_features() {
+    let mut config = Config::default();
+    config
+        .features
+        .insert("new_pricing".to_owned(), FeatureConfig::Toggle(false));
+    config.admin.token = Some("admin-token".to_owned());
+
+    let app = Client::untracked(shop::api::init(App::builder().config(config).build()))
+        .await
+        .expect("invalid app");
+
+    let put = app
+        .put("/admin/features/new_pricing")
+        .header(admin_token("admin-token"))
+        .json(&json!({
+            "rollout": {
+                "percentage": 25
+            }
+        }))
+        .dispatch()
+        .await;
+
+    assert_eq!(Status::Ok, put.status());
+
+    let get = app
+        .get("/admin/features")
+        .header(admin_token("admin-token"))
+        .dispatch()
+        .await;
+
+    assert_eq!(Status::Ok, get.status());
+    let flags: serde_json::Value =
+        serde_json::from_str(&get.into_string().await.expect("missing body"))
+            .expect("invalid value");
+
+    assert_eq!(
+        json!([{ "name": "new_pricing", "rollout": { "percentage": 25 } }]),
+        flags
+    );
+
+    let put = app
+        .put("/admin/features/new_pricing")
+        .header(admin_token("admin-token"))
+        .json(&json!({
+            "rollout": {
+                "percentage": 101
+            }
+        }))
+        .dispatch()
+        .await;
+
+    assert_eq!(Status::BadRequest, put.status());
+}
+
+#[async_test]
+async fn features_need_admin_token() {
+    let mut config = Config::default();
+    config.admin.token = Some("admin-token".to_owned());
+    config.tenants.path_prefix = true;
+    config.tenants.known.insert("shop-a".to_owned());
+
+    let app = Client::untracked(shop::api::init(App::builder().config(config).build()))
+        .await
+        .expect("invalid app");
+
+    let put = app
+        .put("/admin/features/new_pricing")
+        .json(&json!({ "rollout": { "percentage": 25 } }))
+        .dispatch()
+        .await;
+    assert_eq!(Status::Unauthorized, put.status());
+
+    let reload = app
+        .post("/admin/features/reload")
+        .header(admin_token("not-the-admin-token"))
+        .dispatch()
+        .await;
+    assert_eq!(Status::Unauthorized, reload.status());
+
+    let put = app
+        .put("/tenants/shop-a/admin/features/new_pricing")
+        .header(admin_token("admin-token"))
+        .json(&json!({ "rollout": { "percentage": 25 } }))
+        .dispatch()
+        .await;
+    assert_eq!(Status::NotFound, put.status());
+}
+
+#[async_test]
+async fn features_are_disabled_without_admin_token() {
+    let app = Client::untracked(shop::api::init(App::new()))
+        .await
+        .expect("invalid app");
+
+    let get = app
+        .get("/admin/features")
+        .header(admin_token(""))
+        .dispatch()
+        .await;
+    assert_eq!(Status::NotFound, get.status());
+}
+
+fn admin_token(token: &str) -> Header<'static> {
+    Header::new("Authorization", format!("Bearer {}", token))
+}
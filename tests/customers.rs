@@ -8,10 +8,11 @@ use rocket::{
     http::Status,
     local::asynchronous::Client,
 };
+use shop::domain::App;
 
 #[async_test]
 async fn set_get() {
-    let app = Client::untracked(shop::api::init())
+    let app = Client::untracked(shop::api::init(App::new()))
         .await
         .expect("invalid app");
 
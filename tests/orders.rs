@@ -8,10 +8,11 @@ use rocket::{
     http::Status,
     local::asynchronous::Client,
 };
+use shop::domain::App;
 
 #[async_test]
 async fn set_get() {
-    let app = Client::untracked(shop::api::init())
+    let app = Client::untracked(shop::api::init(App::new()))
         .await
         .expect("invalid app");
 
@@ -33,6 +34,15 @@ async fn set_get() {
             .expect("invalid value")
     };
 
+    set_product_status(&app, &product_id, "active").await;
+
+    app.post(format!("/inventory/{}/receive", product_id))
+        .json(&json!({
+            "quantity": 10
+        }))
+        .dispatch()
+        .await;
+
     let customer_id: String = {
         let get = app.put("/customers").json(&json!({})).dispatch().await;
 
@@ -72,3 +82,519 @@ async fn set_get() {
             .len()
     );
 }
+
+async fn set_product_status(app: &Client, product_id: &str, status: &str) {
+    let put = app
+        .put(format!("/products/{}/status", product_id))
+        .json(&json!({ "status": status }))
+        .dispatch()
+        .await;
+
+    assert_eq!(Status::Ok, put.status());
+}
+
+async fn create_order(app: &Client) -> String {
+    let customer_id: String = {
+        let put = app.put("/customers").json(&json!({})).dispatch().await;
+
+        serde_json::from_str(&put.into_string().await.expect("missing body"))
+            .expect("invalid value")
+    };
+
+    let put = app
+        .put("/orders")
+        .json(&json!({ "customer": customer_id }))
+        .dispatch()
+        .await;
+
+    serde_json::from_str(&put.into_string().await.expect("missing body")).expect("invalid value")
+}
+
+async fn get_stock(app: &Client, product_id: &str) -> serde_json::Value {
+    let get = app
+        .get(format!("/inventory/{}", product_id))
+        .dispatch()
+        .await;
+
+    assert_eq!(Status::Ok, get.status());
+
+    serde_json::from_str(&get.into_string().await.expect("missing body")).expect("invalid value")
+}
+
+#[async_test]
+async fn reserve_and_release_stock() {
+    let app = Client::untracked(shop::api::init(App::new()))
+        .await
+        .expect("invalid app");
+
+    let product_id: String = {
+        let put = app
+            .put("/products")
+            .json(&json!({
+                "title": "A new product",
+                "price": {
+                    "usd": {
+                        "cents": 123
+                    }
+                }
+            }))
+            .dispatch()
+            .await;
+
+        serde_json::from_str(&put.into_string().await.expect("missing body"))
+            .expect("invalid value")
+    };
+
+    set_product_status(&app, &product_id, "active").await;
+
+    let receive = app
+        .post(format!("/inventory/{}/receive", product_id))
+        .json(&json!({
+            "quantity": 5
+        }))
+        .dispatch()
+        .await;
+
+    assert_eq!(Status::Ok, receive.status());
+
+    let first_order = create_order(&app).await;
+    let second_order = create_order(&app).await;
+
+    let add = |order_id: String, quantity: u32| {
+        app.post(format!("/orders/{}/products/{}", order_id, product_id))
+            .json(&json!({ "quantity": quantity }))
+            .dispatch()
+    };
+
+    assert_eq!(Status::Ok, add(first_order.clone(), 4).await.status());
+
+    // Only 1 unit is left to reserve
+    assert_eq!(
+        Status::Conflict,
+        add(second_order.clone(), 2).await.status()
+    );
+
+    let stock = get_stock(&app, &product_id).await;
+    assert_eq!(4, stock["reserved"]);
+    assert_eq!(1, stock["available"]);
+
+    // Lowering the quantity releases the difference
+    assert_eq!(Status::Ok, add(first_order.clone(), 3).await.status());
+    assert_eq!(2, get_stock(&app, &product_id).await["available"]);
+
+    // Abandoning the order releases everything it reserved
+    let abandon = app
+        .post(format!("/orders/{}/abandon", first_order))
+        .dispatch()
+        .await;
+
+    assert_eq!(Status::Ok, abandon.status());
+    assert_eq!(5, get_stock(&app, &product_id).await["available"]);
+
+    assert_eq!(Status::Conflict, add(first_order, 1).await.status());
+    assert_eq!(Status::Ok, add(second_order, 2).await.status());
+}
+
+#[async_test]
+async fn order_product_variants() {
+    let app = Client::untracked(shop::api::init(App::new()))
+        .await
+        .expect("invalid app");
+
+    let create_product = || async {
+        let put = app
+            .put("/products")
+            .json(&json!({
+                "title": "A shirt",
+                "price": {
+                    "usd": {
+                        "cents": 2000
+                    }
+                }
+            }))
+            .dispatch()
+            .await;
+
+        let product_id: String =
+            serde_json::from_str(&put.into_string().await.expect("missing body"))
+                .expect("invalid value");
+
+        let options = app
+            .put(format!("/products/{}/options", product_id))
+            .json(&json!({
+                "options": [{ "name": "size", "values": ["S", "M"] }]
+            }))
+            .dispatch()
+            .await;
+
+        assert_eq!(Status::Ok, options.status());
+
+        set_product_status(&app, &product_id, "active").await;
+
+        product_id
+    };
+
+    let product_id = create_product().await;
+    let other_product_id = create_product().await;
+
+    let add_variant = |product_id: String, sku: &'static str, size: &'static str| {
+        app.put(format!("/products/{}/variants", product_id))
+            .json(&json!({
+                "sku": sku,
+                "options": { "size": size },
+                "price": { "usd": { "cents": 2500 } }
+            }))
+            .dispatch()
+    };
+
+    let put = add_variant(product_id.clone(), "SHIRT-S", "S").await;
+    assert_eq!(Status::Created, put.status());
+    let variant_id: String = serde_json::from_str(&put.into_string().await.expect("missing body"))
+        .expect("invalid value");
+
+    // SKUs are unique across all products
+    assert_eq!(
+        Status::Conflict,
+        add_variant(other_product_id, "shirt-s", "S").await.status()
+    );
+
+    let receive = app
+        .post(format!("/inventory/{}/receive", product_id))
+        .json(&json!({ "quantity": 5, "variant": variant_id }))
+        .dispatch()
+        .await;
+
+    assert_eq!(Status::Ok, receive.status());
+
+    let order_id = create_order(&app).await;
+
+    // Products with variants are ordered by variant
+    let add = app
+        .post(format!("/orders/{}/products/{}", order_id, product_id))
+        .json(&json!({ "quantity": 2 }))
+        .dispatch()
+        .await;
+
+    assert_eq!(Status::BadRequest, add.status());
+
+    let add = app
+        .post(format!("/orders/{}/products/{}", order_id, product_id))
+        .json(&json!({ "quantity": 2, "variant": variant_id }))
+        .dispatch()
+        .await;
+
+    assert_eq!(Status::Ok, add.status());
+
+    let stock = get_stock(&app, &format!("{}?variant={}", product_id, variant_id)).await;
+
+    assert_eq!(2, stock["reserved"]);
+    assert_eq!(3, stock["available"]);
+}
+
+#[async_test]
+async fn only_active_products_are_orderable() {
+    let app = Client::untracked(shop::api::init(App::new()))
+        .await
+        .expect("invalid app");
+
+    let product_id: String = {
+        let put = app
+            .put("/products")
+            .json(&json!({
+                "title": "A new product",
+                "price": {
+                    "usd": {
+                        "cents": 123
+                    }
+                }
+            }))
+            .dispatch()
+            .await;
+
+        serde_json::from_str(&put.into_string().await.expect("missing body"))
+            .expect("invalid value")
+    };
+
+    let order_id = create_order(&app).await;
+
+    let add = || {
+        app.post(format!("/orders/{}/products/{}", order_id, product_id))
+            .json(&json!({ "quantity": 1 }))
+            .dispatch()
+    };
+
+    // New products are drafts
+    assert_eq!(Status::Conflict, add().await.status());
+
+    set_product_status(&app, &product_id, "active").await;
+    set_product_status(&app, &product_id, "discontinued").await;
+
+    assert_eq!(Status::Conflict, add().await.status());
+
+    // Discontinued products can't be made active again
+    let put = app
+        .put(format!("/products/{}/status", product_id))
+        .json(&json!({ "status": "active" }))
+        .dispatch()
+        .await;
+
+    assert_eq!(Status::Conflict, put.status());
+}
+
+#[async_test]
+async fn promotions_discount_orders() {
+    let app = Client::untracked(shop::api::init(App::new()))
+        .await
+        .expect("invalid app");
+
+    let product_id: String = {
+        let put = app
+            .put("/products")
+            .json(&json!({
+                "title": "A new product",
+                "price": {
+                    "usd": {
+                        "cents": 3000
+                    }
+                }
+            }))
+            .dispatch()
+            .await;
+
+        serde_json::from_str(&put.into_string().await.expect("missing body"))
+            .expect("invalid value")
+    };
+
+    set_product_status(&app, &product_id, "active").await;
+
+    app.post(format!("/inventory/{}/receive", product_id))
+        .json(&json!({ "quantity": 10 }))
+        .dispatch()
+        .await;
+
+    let order_id = create_order(&app).await;
+
+    let add = app
+        .post(format!("/orders/{}/products/{}", order_id, product_id))
+        .json(&json!({ "quantity": 2 }))
+        .dispatch()
+        .await;
+
+    assert_eq!(Status::Ok, add.status());
+
+    let create_promotion =
+        |promotion: serde_json::Value| app.put("/promotions").json(&promotion).dispatch();
+
+    let put = create_promotion(json!({
+        "name": "$5 off orders over $50",
+        "conditions": [{ "type": "minimum_subtotal", "amount": { "usd": { "cents": 5000 } } }],
+        "action": { "type": "amount_off", "amount": { "usd": { "cents": 500 } } }
+    }))
+    .await;
+
+    assert_eq!(Status::Created, put.status());
+
+    let put = create_promotion(json!({
+        "name": "10% off",
+        "conditions": [{ "type": "products", "product_ids": [product_id] }],
+        "action": { "type": "percent_off", "percent": 10 }
+    }))
+    .await;
+
+    assert_eq!(Status::Created, put.status());
+    let percent_off: String = serde_json::from_str(&put.into_string().await.expect("missing body"))
+        .expect("invalid value");
+
+    let put = create_promotion(json!({
+        "name": "Nothing off",
+        "action": { "type": "percent_off", "percent": 0 }
+    }))
+    .await;
+
+    assert_eq!(Status::BadRequest, put.status());
+
+    let get_pricing = || async {
+        let get = app
+            .get(format!("/orders/{}/pricing", order_id))
+            .dispatch()
+            .await;
+
+        assert_eq!(Status::Ok, get.status());
+
+        serde_json::from_str::<serde_json::Value>(&get.into_string().await.expect("missing body"))
+            .expect("invalid value")
+    };
+
+    let pricing = get_pricing().await;
+
+    assert_eq!(6000, pricing["subtotal"]["usd"]["cents"]);
+    assert_eq!(1100, pricing["discount"]["usd"]["cents"]);
+    assert_eq!(4900, pricing["total"]["usd"]["cents"]);
+    assert_eq!(
+        2,
+        pricing["promotions"]
+            .as_array()
+            .expect("invalid pricing")
+            .len()
+    );
+
+    // Ending a promotion stops it from applying
+    let put = app
+        .put(format!("/promotions/{}/window", percent_off))
+        .json(&json!({ "ends_at": "2000-01-01T00:00:00Z" }))
+        .dispatch()
+        .await;
+
+    assert_eq!(Status::Ok, put.status());
+    assert_eq!(5500, get_pricing().await["total"]["usd"]["cents"]);
+}
+
+#[async_test]
+async fn bundles_reserve_their_components() {
+    let app = Client::untracked(shop::api::init(App::new()))
+        .await
+        .expect("invalid app");
+
+    let create_product = |cents: u64, stock: u32| {
+        let app = &app;
+
+        async move {
+            let put = app
+                .put("/products")
+                .json(&json!({
+                    "title": "A product",
+                    "price": { "usd": { "cents": cents } }
+                }))
+                .dispatch()
+                .await;
+
+            let product_id: String =
+                serde_json::from_str(&put.into_string().await.expect("missing body"))
+                    .expect("invalid value");
+
+            set_product_status(app, &product_id, "active").await;
+
+            if stock > 0 {
+                app.post(format!("/inventory/{}/receive", product_id))
+                    .json(&json!({ "quantity": stock }))
+                    .dispatch()
+                    .await;
+            }
+
+            product_id
+        }
+    };
+
+    let shirt = create_product(1000, 10).await;
+    let socks = create_product(500, 3).await;
+    let kit = create_product(1200, 0).await;
+
+    let put = app
+        .put(format!("/products/{}/bundle", kit))
+        .json(&json!({
+            "components": [
+                { "product_id": shirt, "quantity": 1 },
+                { "product_id": socks, "quantity": 2 }
+            ]
+        }))
+        .dispatch()
+        .await;
+
+    assert_eq!(Status::Ok, put.status());
+
+    let get_bundle = || async {
+        let get = app
+            .get(format!("/products/{}/bundle", kit))
+            .dispatch()
+            .await;
+
+        assert_eq!(Status::Ok, get.status());
+
+        serde_json::from_str::<serde_json::Value>(&get.into_string().await.expect("missing body"))
+            .expect("invalid value")
+    };
+
+    assert_eq!(true, get_bundle().await["orderable"]);
+
+    let order_id = create_order(&app).await;
+
+    let add = |product_id: String, quantity: u32| {
+        app.post(format!("/orders/{}/products/{}", order_id, product_id))
+            .json(&json!({ "quantity": quantity }))
+            .dispatch()
+    };
+
+    assert_eq!(Status::Ok, add(kit.clone(), 1).await.status());
+
+    assert_eq!(1, get_stock(&app, &shirt).await["reserved"]);
+    assert_eq!(2, get_stock(&app, &socks).await["reserved"]);
+
+    // Only 3 socks are on hand
+    assert_eq!(Status::Conflict, add(kit.clone(), 2).await.status());
+
+    // Socks ordered on their own are reserved along with the ones in the kit
+    assert_eq!(Status::Ok, add(socks.clone(), 1).await.status());
+    assert_eq!(3, get_stock(&app, &socks).await["reserved"]);
+
+    let get = app
+        .get(format!("/orders/{}/components", order_id))
+        .dispatch()
+        .await;
+
+    assert_eq!(Status::Ok, get.status());
+    let components: serde_json::Value =
+        serde_json::from_str(&get.into_string().await.expect("missing body"))
+            .expect("invalid value");
+
+    assert_eq!(3, components.as_array().expect("invalid components").len());
+
+    // The kit is priced as a single line item
+    let get = app
+        .get(format!("/orders/{}/pricing", order_id))
+        .dispatch()
+        .await;
+
+    let pricing: serde_json::Value =
+        serde_json::from_str(&get.into_string().await.expect("missing body"))
+            .expect("invalid value");
+
+    assert_eq!(1700, pricing["subtotal"]["usd"]["cents"]);
+
+    // Deactivating a component makes the bundle unorderable
+    set_product_status(&app, &shirt, "discontinued").await;
+
+    assert_eq!(false, get_bundle().await["orderable"]);
+    assert_eq!(Status::Conflict, add(kit.clone(), 2).await.status());
+
+    // Bundles already in the order can keep their quantity
+    assert_eq!(Status::Ok, add(kit.clone(), 1).await.status());
+
+    // Bundles can change after they're ordered, including products that are already in the order
+    let hat = create_product(300, 2).await;
+    assert_eq!(Status::Ok, add(hat.clone(), 1).await.status());
+
+    for bundle in [&kit, &hat] {
+        let put = app
+            .put(format!("/products/{}/bundle", bundle))
+            .json(&json!({
+                "components": [
+                    { "product_id": socks, "quantity": 1 }
+                ]
+            }))
+            .dispatch()
+            .await;
+
+        assert_eq!(Status::Ok, put.status());
+    }
+
+    // Everything the order reserved is released, not what its bundles are now made of
+    let abandon = app
+        .post(format!("/orders/{}/abandon", order_id))
+        .dispatch()
+        .await;
+
+    assert_eq!(Status::Ok, abandon.status());
+    assert_eq!(10, get_stock(&app, &shirt).await["available"]);
+    assert_eq!(3, get_stock(&app, &socks).await["available"]);
+    assert_eq!(2, get_stock(&app, &hat).await["available"]);
+}
@@ -4,14 +4,50 @@ extern crate rocket;
 #[macro_use]
 extern crate serde_json;
 
+use std::sync::{
+    Arc,
+    Mutex,
+};
+
 use rocket::{
-    http::Status,
+    http::{
+        ContentType,
+        Header,
+        Status,
+    },
     local::asynchronous::Client,
 };
+use shop::{
+    config::Config,
+    domain::{
+        infra::{
+            IdProvider,
+            Register,
+        },
+        products::{
+            model::store::{
+                self,
+                Iter,
+                ProductStore,
+                ProductStoreBackend,
+                ProductStoreFilter,
+            },
+            Product,
+            ProductData,
+            ProductId,
+        },
+        App,
+        Error,
+    },
+    store::{
+        Transaction,
+        TransactionStore,
+    },
+};
 
 #[async_test]
 async fn set_get() {
-    let app = Client::untracked(shop::api::init())
+    let app = Client::untracked(shop::api::init(App::new()))
         .await
         .expect("invalid app");
 
@@ -44,3 +80,921 @@ async fn set_get() {
         product.as_object().expect("invalid product")["title"]
     );
 }
+
+#[async_test]
+async fn create_with_injected_id() {
+    let id = ProductId::new();
+
+    let app = App::builder()
+        .product_id(Register::once(move |_| {
+            Arc::new(id) as Arc<dyn IdProvider<ProductData> + Send + Sync>
+        }))
+        .build();
+
+    let app = Client::untracked(shop::api::init(app))
+        .await
+        .expect("invalid app");
+
+    let put = app
+        .put("/products")
+        .json(&json!({
+            "title": "A new product",
+            "price": {
+                "usd": {
+                    "cents": 123
+                }
+            }
+        }))
+        .dispatch()
+        .await;
+
+    assert_eq!(Status::Created, put.status());
+    let created: ProductId = serde_json::from_str(&put.into_string().await.expect("missing body"))
+        .expect("invalid value");
+
+    assert_eq!(id, created);
+}
+
+/** A product store that records the products written to it. */
+struct RecordingProductStore {
+    inner: store::InMemoryStore,
+    written: Arc<Mutex<Vec<ProductId>>>,
+}
+
+impl ProductStore for RecordingProductStore {
+    fn get_product(&self, id: ProductId) -> Result<Option<Product>, Error> {
+        self.inner.get_product(id)
+    }
+
+    fn set_product(&self, transaction: &Transaction, product: Product) -> Result<(), Error> {
+        self.written.lock().unwrap().push(product.to_data().id);
+
+        self.inner.set_product(transaction, product)
+    }
+}
+
+impl ProductStoreFilter for RecordingProductStore {
+    fn filter(&self, predicate: &dyn Fn(&ProductData) -> bool) -> Result<Iter, Error> {
+        self.inner.filter(predicate)
+    }
+}
+
+#[async_test]
+async fn create_with_injected_store() {
+    let transactions = TransactionStore::new();
+    let written = Arc::new(Mutex::new(Vec::new()));
+
+    let product_store = Arc::new(RecordingProductStore {
+        inner: store::in_memory_store(transactions.clone()),
+        written: written.clone(),
+    }) as Arc<dyn ProductStoreBackend>;
+
+    let app = App::builder()
+        .transaction_store(Register::once(move |_| transactions.clone()))
+        .product_store(Register::once(move |_| product_store.clone()))
+        .build();
+
+    let app = Client::untracked(shop::api::init(app))
+        .await
+        .expect("invalid app");
+
+    let id = create_product(&app, "/products", "localhost").await;
+
+    let get = app.get("/products").dispatch().await;
+    assert_eq!(Status::Ok, get.status());
+    let page: serde_json::Value =
+        serde_json::from_str(&get.into_string().await.expect("missing body"))
+            .expect("invalid value");
+    assert_eq!(id, page["products"][0]["id"]);
+
+    let id: ProductId = serde_json::from_value(json!(id)).expect("invalid id");
+    assert_eq!(vec![id], *written.lock().unwrap());
+}
+
+async fn create_product(app: &Client, path: &str, host: &str) -> String {
+    let put = app
+        .put(path.to_owned())
+        .header(Header::new("Host", host.to_owned()))
+        .json(&json!({
+            "title": "A new product",
+            "price": {
+                "usd": {
+                    "cents": 123
+                }
+            }
+        }))
+        .dispatch()
+        .await;
+
+    assert_eq!(Status::Created, put.status());
+    serde_json::from_str(&put.into_string().await.expect("missing body")).expect("invalid value")
+}
+
+#[async_test]
+async fn tenants_are_isolated_by_path() {
+    let mut config = Config::default();
+    config.tenants.path_prefix = true;
+    config.tenants.known.insert("shop-a".to_owned());
+    config.tenants.known.insert("shop-b".to_owned());
+
+    let app = Client::untracked(shop::api::init(App::builder().config(config).build()))
+        .await
+        .expect("invalid app");
+
+    let id = create_product(&app, "/tenants/shop-a/products", "localhost").await;
+
+    let get = app
+        .get(format!("/tenants/shop-a/products/{}", id))
+        .dispatch()
+        .await;
+    assert_ne!(Status::NotFound, get.status());
+
+    for other in [
+        "/tenants/shop-b/products",
+        "/products",
+        "/tenants/Not_A_Tenant/products",
+        "/tenants/not-a-known-tenant/products",
+    ] {
+        let get = app.get(format!("{}/{}", other, id)).dispatch().await;
+        assert_eq!(Status::NotFound, get.status(), "{}", other);
+    }
+}
+
+#[async_test]
+async fn tenant_path_prefix_is_disabled_by_default() {
+    let mut config = Config::default();
+    config.tenants.known.insert("shop-a".to_owned());
+
+    let app = Client::untracked(shop::api::init(App::builder().config(config).build()))
+        .await
+        .expect("invalid app");
+
+    let put = app
+        .put("/tenants/shop-a/products")
+        .json(&json!({
+            "title": "A new product",
+            "price": {
+                "usd": {
+                    "cents": 123
+                }
+            }
+        }))
+        .dispatch()
+        .await;
+    assert_eq!(Status::NotFound, put.status());
+}
+
+#[async_test]
+async fn tenants_are_isolated_by_host() {
+    let mut config = Config::default();
+    config.tenants.path_prefix = true;
+    config
+        .tenants
+        .hosts
+        .insert("shop-a.example.com".to_owned(), "shop-a".to_owned());
+    config.tenants.known.insert("shop-b".to_owned());
+
+    let app = Client::untracked(shop::api::init(App::builder().config(config).build()))
+        .await
+        .expect("invalid app");
+
+    let id = create_product(&app, "/products", "shop-a.example.com").await;
+
+    let get = app
+        .get(format!("/tenants/shop-a/products/{}", id))
+        .dispatch()
+        .await;
+    assert_ne!(Status::NotFound, get.status());
+
+    let get = app
+        .get(format!("/products/{}", id))
+        .header(Header::new("Host", "shop-b.example.com"))
+        .dispatch()
+        .await;
+    assert_eq!(Status::NotFound, get.status());
+
+    // A path prefix can't override the tenant configured for a host
+    let other = create_product(&app, "/tenants/shop-b/products", "localhost").await;
+
+    let get = app
+        .get(format!("/tenants/shop-b/products/{}", other))
+        .header(Header::new("Host", "shop-a.example.com"))
+        .dispatch()
+        .await;
+    assert_eq!(Status::NotFound, get.status());
+}
+
+#[async_test]
+async fn set_price_and_get_history() {
+    let app = Client::untracked(shop::api::init(App::new()))
+        .await
+        .expect("invalid app");
+
+    let id = create_product(&app, "/products", "localhost").await;
+
+    let put = app
+        .put(format!("/products/{}/price", id))
+        .json(&json!({
+            "price": {
+                "usd": {
+                    "cents": 456
+                }
+            }
+        }))
+        .dispatch()
+        .await;
+
+    assert_eq!(Status::Ok, put.status());
+
+    let put = app
+        .put(format!("/products/{}/price", id))
+        .json(&json!({
+            "price": {
+                "usd": {
+                    "cents": 0
+                }
+            }
+        }))
+        .dispatch()
+        .await;
+
+    assert_eq!(Status::BadRequest, put.status());
+
+    let get = app.get(format!("/products/{}/prices", id)).dispatch().await;
+
+    assert_eq!(Status::Ok, get.status());
+    let history: serde_json::Value =
+        serde_json::from_str(&get.into_string().await.expect("missing body"))
+            .expect("invalid value");
+
+    let prices = history["prices"]
+        .as_array()
+        .expect("invalid prices")
+        .iter()
+        .map(|price| {
+            price["price"]["usd"]["cents"]
+                .as_u64()
+                .expect("invalid price")
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(vec![123, 456], prices);
+}
+
+async fn create_category(app: &Client, name: &str, parent: Option<&str>) -> String {
+    let put = app
+        .put("/categories")
+        .json(&json!({
+            "name": name,
+            "parent": parent
+        }))
+        .dispatch()
+        .await;
+
+    assert_eq!(Status::Created, put.status());
+    serde_json::from_str(&put.into_string().await.expect("missing body")).expect("invalid value")
+}
+
+async fn get_product_ids(app: &Client, path: String) -> Vec<String> {
+    let get = app.get(path).dispatch().await;
+
+    assert_eq!(Status::Ok, get.status());
+    let products: serde_json::Value =
+        serde_json::from_str(&get.into_string().await.expect("missing body"))
+            .expect("invalid value");
+
+    products
+        .as_array()
+        .expect("invalid products")
+        .iter()
+        .map(|product| product["id"].as_str().expect("invalid id").to_owned())
+        .collect()
+}
+
+#[async_test]
+async fn categories_and_tags() {
+    let app = Client::untracked(shop::api::init(App::new()))
+        .await
+        .expect("invalid app");
+
+    let clothing = create_category(&app, "Clothing", None).await;
+    let shirts = create_category(&app, "Shirts", Some(&clothing)).await;
+
+    let shirt = create_product(&app, "/products", "localhost").await;
+    let hat = create_product(&app, "/products", "localhost").await;
+
+    for (id, category, tags) in [
+        (&shirt, &shirts, vec!["Sale", "summer"]),
+        (&hat, &clothing, vec!["sale"]),
+    ] {
+        let put = app
+            .put(format!("/products/{}/categories", id))
+            .json(&json!({ "categories": [category] }))
+            .dispatch()
+            .await;
+        assert_eq!(Status::Ok, put.status());
+
+        let put = app
+            .put(format!("/products/{}/tags", id))
+            .json(&json!({ "tags": tags }))
+            .dispatch()
+            .await;
+        assert_eq!(Status::Ok, put.status());
+    }
+
+    // Products in subcategories are in their parent categories too
+    let mut in_clothing = get_product_ids(&app, format!("/categories/{}/products", clothing)).await;
+    in_clothing.sort();
+
+    let mut expected = vec![shirt.clone(), hat.clone()];
+    expected.sort();
+
+    assert_eq!(expected, in_clothing);
+    assert_eq!(
+        vec![shirt.clone()],
+        get_product_ids(&app, format!("/categories/{}/products", shirts)).await
+    );
+
+    assert_eq!(
+        vec![shirt.clone()],
+        get_product_ids(&app, "/products/tagged?tag=SALE&tag=summer".to_owned()).await
+    );
+
+    // A category can't be moved beneath one of its own subcategories
+    let put = app
+        .put(format!("/categories/{}/parent", clothing))
+        .json(&json!({ "parent": shirts }))
+        .dispatch()
+        .await;
+    assert_eq!(Status::Conflict, put.status());
+
+    let put = app
+        .put(format!("/categories/{}/parent", shirts))
+        .json(&json!({ "parent": null }))
+        .dispatch()
+        .await;
+    assert_eq!(Status::Ok, put.status());
+
+    assert_eq!(
+        vec![hat],
+        get_product_ids(&app, format!("/categories/{}/products", clothing)).await
+    );
+}
+
+#[async_test]
+async fn attributes_are_validated_and_filterable() {
+    let app = Client::untracked(shop::api::init(App::new()))
+        .await
+        .expect("invalid app");
+
+    let clothing = create_category(&app, "Clothing", None).await;
+    let shirts = create_category(&app, "Shirts", Some(&clothing)).await;
+
+    for (category, attributes) in [
+        (
+            &clothing,
+            json!([{
+                "name": "material",
+                "type": "enum",
+                "values": ["cotton", "wool"],
+                "required": true
+            }]),
+        ),
+        (
+            &shirts,
+            json!([{ "name": "weight", "type": "number", "unit": "kg" }]),
+        ),
+    ] {
+        let put = app
+            .put(format!("/categories/{}/attributes", category))
+            .json(&json!({ "attributes": attributes }))
+            .dispatch()
+            .await;
+        assert_eq!(Status::Ok, put.status());
+    }
+
+    let light = create_product(&app, "/products", "localhost").await;
+    let heavy = create_product(&app, "/products", "localhost").await;
+
+    // Shirts require a material, so products need one to be put in the category
+    let put = app
+        .put(format!("/products/{}/categories", light))
+        .json(&json!({ "categories": [shirts] }))
+        .dispatch()
+        .await;
+    assert_eq!(Status::BadRequest, put.status());
+
+    for (id, attributes) in [
+        (&light, json!({ "material": "cotton", "weight": 0.2 })),
+        (&heavy, json!({ "material": "wool", "weight": 0.8 })),
+    ] {
+        let put = app
+            .put(format!("/products/{}/categories", id))
+            .json(&json!({ "categories": [shirts], "attributes": attributes }))
+            .dispatch()
+            .await;
+        assert_eq!(Status::Ok, put.status());
+    }
+
+    // Values are checked against the attributes of the product's categories and their parents
+    for invalid in [
+        json!({ "weight": 0.2 }),
+        json!({ "material": "silk" }),
+        json!({ "material": "cotton", "colour": "red" }),
+    ] {
+        let put = app
+            .put(format!("/products/{}/attributes", light))
+            .json(&json!({ "attributes": invalid }))
+            .dispatch()
+            .await;
+        assert_eq!(Status::BadRequest, put.status());
+    }
+
+    let get = app
+        .get(format!("/products/{}/attributes", light))
+        .dispatch()
+        .await;
+    assert_eq!(Status::Ok, get.status());
+
+    let attributes: serde_json::Value =
+        serde_json::from_str(&get.into_string().await.expect("missing body"))
+            .expect("invalid value");
+
+    assert_eq!(
+        2,
+        attributes["schema"]
+            .as_array()
+            .expect("invalid schema")
+            .len()
+    );
+    assert_eq!("cotton", attributes["values"]["material"]);
+
+    // Attributes can't be changed in a way that the values of products already in the category don't match
+    let put = app
+        .put(format!("/categories/{}/attributes", clothing))
+        .json(&json!({ "attributes": [{
+            "name": "material",
+            "type": "enum",
+            "values": ["wool"],
+            "required": true
+        }] }))
+        .dispatch()
+        .await;
+    assert_eq!(Status::Conflict, put.status());
+
+    let get = app
+        .get(format!("/products/{}/attributes", ProductId::new()))
+        .dispatch()
+        .await;
+    assert_eq!(Status::NotFound, get.status());
+
+    // Attribute filters combine with the other filters and the cursor of the product listing
+    let mut listed = vec![];
+    let mut next = Some("/products?attribute=weight%3E%3D0.1&sort=price&limit=1".to_owned());
+
+    while let Some(path) = next {
+        assert!(path.contains("attribute=weight%3E%3D0.1"), "{}", path);
+
+        let get = app.get(path).dispatch().await;
+
+        assert_eq!(Status::Ok, get.status());
+        let page: serde_json::Value =
+            serde_json::from_str(&get.into_string().await.expect("missing body"))
+                .expect("invalid value");
+
+        assert_eq!(2, page["total_estimate"]);
+
+        for product in page["products"].as_array().expect("invalid products") {
+            listed.push(product["id"].as_str().expect("invalid id").to_owned());
+        }
+
+        next = page["next"].as_str().map(|next| next.to_owned());
+    }
+
+    listed.sort();
+    let mut both = vec![light.clone(), heavy.clone()];
+    both.sort();
+    assert_eq!(both, listed);
+
+    let get = app
+        .get("/products?attribute=material%3Dcotton&attribute=weight%3C%3D0.5")
+        .dispatch()
+        .await;
+
+    assert_eq!(Status::Ok, get.status());
+    let page: serde_json::Value =
+        serde_json::from_str(&get.into_string().await.expect("missing body"))
+            .expect("invalid value");
+
+    assert_eq!(light, page["products"][0]["id"]);
+    assert_eq!(1, page["total_estimate"]);
+
+    let get = app
+        .get("/products?attribute=weight%3E%3Dheavy")
+        .dispatch()
+        .await;
+    assert_eq!(Status::BadRequest, get.status());
+}
+
+#[async_test]
+async fn search_by_title() {
+    let app = Client::untracked(shop::api::init(App::new()))
+        .await
+        .expect("invalid app");
+
+    let linen = create_product(&app, "/products", "localhost").await;
+    let post = app
+        .post(format!("/products/{}/title/Linen%20shirt", linen))
+        .dispatch()
+        .await;
+    assert_eq!(Status::Ok, post.status());
+
+    let cotton = create_product(&app, "/products", "localhost").await;
+    let post = app
+        .post(format!(
+            "/products/{}/title/Cotton%20shirts%20with%20long%20sleeves",
+            cotton
+        ))
+        .dispatch()
+        .await;
+    assert_eq!(Status::Ok, post.status());
+
+    assert_eq!(
+        vec![linen.clone(), cotton.clone()],
+        get_product_ids(&app, "/products/search?q=SHIRT".to_owned()).await
+    );
+    assert_eq!(
+        vec![cotton],
+        get_product_ids(&app, "/products/search?q=cott%20shirt".to_owned()).await
+    );
+
+    // Products are reindexed when their title changes
+    assert!(get_product_ids(&app, "/products/search?q=new".to_owned())
+        .await
+        .is_empty());
+    assert_eq!(
+        vec![linen],
+        get_product_ids(&app, "/products/search?q=linen&limit=1".to_owned()).await
+    );
+}
+
+#[async_test]
+async fn list_in_pages() {
+    let app = Client::untracked(shop::api::init(App::new()))
+        .await
+        .expect("invalid app");
+
+    let mut created = vec![];
+    for title in ["Blue shirt", "Red shirt", "Green shirt", "Red hat"] {
+        let id = create_product(&app, "/products", "localhost").await;
+
+        let post = app
+            .post(format!(
+                "/products/{}/title/{}",
+                id,
+                title.replace(' ', "%20")
+            ))
+            .dispatch()
+            .await;
+        assert_eq!(Status::Ok, post.status());
+
+        created.push(id);
+    }
+
+    let mut listed = vec![];
+    let mut next = Some("/products?title=SHIRT&sort=title&limit=2".to_owned());
+
+    while let Some(path) = next {
+        let get = app.get(path).dispatch().await;
+
+        assert_eq!(Status::Ok, get.status());
+        let page: serde_json::Value =
+            serde_json::from_str(&get.into_string().await.expect("missing body"))
+                .expect("invalid value");
+
+        assert_eq!(3, page["total_estimate"]);
+
+        for product in page["products"].as_array().expect("invalid products") {
+            listed.push(product["id"].as_str().expect("invalid id").to_owned());
+        }
+
+        next = page["next"].as_str().map(|next| next.to_owned());
+    }
+
+    // Products are sorted by title, and the hat doesn't match
+    assert_eq!(
+        vec![created[0].clone(), created[2].clone(), created[1].clone()],
+        listed
+    );
+
+    let get = app.get("/products?sort=size").dispatch().await;
+    assert_eq!(Status::BadRequest, get.status());
+}
+
+#[async_test]
+async fn list_in_pages_for_tenant() {
+    let mut config = Config::default();
+    config.tenants.path_prefix = true;
+    config.tenants.known.insert("shop-a".to_owned());
+
+    let app = Client::untracked(shop::api::init(App::builder().config(config).build()))
+        .await
+        .expect("invalid app");
+
+    let mut created = vec![];
+    for _ in 0..3 {
+        created.push(create_product(&app, "/tenants/shop-a/products", "localhost").await);
+    }
+
+    // Products in the default tenant aren't listed
+    create_product(&app, "/products", "localhost").await;
+
+    let mut listed = vec![];
+    let mut next = Some("/tenants/shop-a/products?limit=2".to_owned());
+
+    while let Some(path) = next {
+        assert!(path.starts_with("/tenants/shop-a/products?"), "{}", path);
+
+        let get = app.get(path).dispatch().await;
+
+        assert_eq!(Status::Ok, get.status());
+        let page: serde_json::Value =
+            serde_json::from_str(&get.into_string().await.expect("missing body"))
+                .expect("invalid value");
+
+        for product in page["products"].as_array().expect("invalid products") {
+            listed.push(product["id"].as_str().expect("invalid id").to_owned());
+        }
+
+        next = page["next"].as_str().map(|next| next.to_owned());
+    }
+
+    listed.sort();
+    created.sort();
+    assert_eq!(created, listed);
+}
+
+#[async_test]
+async fn import_csv_and_json_lines() {
+    let app = Client::untracked(shop::api::init(App::new()))
+        .await
+        .expect("invalid app");
+
+    let existing = create_product(&app, "/products", "localhost").await;
+
+    let csv = format!(
+        "id,title,price\n\
+        {},Imported shirt,250\n\
+        ,Imported hat,100\n\
+        ,,100\n\
+        ,Imported scarf,lots\n",
+        existing
+    );
+
+    let post = app
+        .post("/products/import")
+        .header(ContentType::CSV)
+        .body(csv)
+        .dispatch()
+        .await;
+
+    assert_eq!(Status::Ok, post.status());
+    let report: serde_json::Value =
+        serde_json::from_str(&post.into_string().await.expect("missing body"))
+            .expect("invalid value");
+
+    assert_eq!(1, report["created"]);
+    assert_eq!(1, report["updated"]);
+    assert_eq!(2, report["rejected"]);
+    assert_eq!(
+        vec!["updated", "created", "rejected", "rejected"],
+        report["rows"]
+            .as_array()
+            .expect("invalid rows")
+            .iter()
+            .map(|row| row["status"].as_str().expect("invalid status"))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!("product.title_empty", report["rows"][2]["code"]);
+    assert_eq!(4, report["rows"][2]["line"]);
+
+    let json_lines = r#"{"title":"Imported socks","price":{"usd":{"cents":50}}}
+{"title":"Imported gloves","price":{"usd":{"cents":0}}}
+"#;
+
+    let post = app
+        .post("/products/import?chunk_size=1")
+        .header(ContentType::new("application", "x-ndjson"))
+        .body(json_lines)
+        .dispatch()
+        .await;
+
+    assert_eq!(Status::Ok, post.status());
+    let report: serde_json::Value =
+        serde_json::from_str(&post.into_string().await.expect("missing body"))
+            .expect("invalid value");
+
+    assert_eq!(1, report["created"]);
+    assert_eq!(1, report["rejected"]);
+
+    let get = app
+        .get("/products?title=imported&sort=title")
+        .dispatch()
+        .await;
+
+    assert_eq!(Status::Ok, get.status());
+    let page: serde_json::Value =
+        serde_json::from_str(&get.into_string().await.expect("missing body"))
+            .expect("invalid value");
+
+    assert_eq!(
+        vec!["Imported hat", "Imported shirt", "Imported socks"],
+        page["products"]
+            .as_array()
+            .expect("invalid products")
+            .iter()
+            .map(|product| product["title"].as_str().expect("invalid title"))
+            .collect::<Vec<_>>()
+    );
+}
+
+#[async_test]
+async fn set_and_remove_translations() {
+    let app = Client::untracked(shop::api::init(App::new()))
+        .await
+        .expect("invalid app");
+
+    let id = create_product(&app, "/products", "localhost").await;
+
+    let put = app
+        .put(format!("/products/{}/translations/fr_ca", id))
+        .json(&json!({
+            "title": "Un produit",
+            "description": "Un produit importé"
+        }))
+        .dispatch()
+        .await;
+    assert_eq!(Status::Ok, put.status());
+
+    let put = app
+        .put(format!("/products/{}/translations/fr", id))
+        .json(&json!({ "title": "" }))
+        .dispatch()
+        .await;
+    assert_eq!(Status::BadRequest, put.status());
+
+    let delete = app
+        .delete(format!("/products/{}/translations/fr-CA", id))
+        .dispatch()
+        .await;
+    assert_eq!(Status::Ok, delete.status());
+
+    let delete = app
+        .delete(format!("/products/{}/translations/fr-CA", id))
+        .dispatch()
+        .await;
+    assert_eq!(Status::NotFound, delete.status());
+}
+
+fn png(width: u32, height: u32) -> Vec<u8> {
+    let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+    png.extend_from_slice(&width.to_be_bytes());
+    png.extend_from_slice(&height.to_be_bytes());
+    png.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    png
+}
+
+async fn upload_image(app: &Client, id: &str, image: &[u8], alt_text: &str) -> Status {
+    let mut body = Vec::new();
+    body.extend_from_slice(
+        b"--BOUNDARY\r\n\
+        Content-Disposition: form-data; name=\"image\"; filename=\"image.png\"\r\n\
+        Content-Type: image/png\r\n\r\n",
+    );
+    body.extend_from_slice(image);
+    body.extend_from_slice(
+        format!(
+            "\r\n--BOUNDARY\r\n\
+            Content-Disposition: form-data; name=\"alt_text\"\r\n\r\n\
+            {}\r\n--BOUNDARY--\r\n",
+            alt_text
+        )
+        .as_bytes(),
+    );
+
+    app.post(format!("/products/{}/images", id))
+        .header(ContentType::new("multipart", "form-data").with_params(("boundary", "BOUNDARY")))
+        .body(body)
+        .dispatch()
+        .await
+        .status()
+}
+
+#[async_test]
+async fn upload_and_serve_images() {
+    let root = std::env::temp_dir().join(format!("shop-images-{}", ProductId::new()));
+
+    let mut config = Config::default();
+    config.store.path = root.clone();
+
+    let app = Client::untracked(shop::api::init(App::builder().config(config).build()))
+        .await
+        .expect("invalid app");
+
+    let large = png(640, 480);
+    let small = png(64, 48);
+
+    // Nothing is stored for products that don't exist
+    assert_eq!(
+        Status::NotFound,
+        upload_image(&app, &ProductId::new().to_string(), &large, "Nothing").await
+    );
+    assert!(!root.join("images").exists());
+
+    let id = create_product(&app, "/products", "localhost").await;
+
+    assert_eq!(
+        Status::Created,
+        upload_image(&app, &id, &large, "A large shirt").await
+    );
+    assert_eq!(
+        Status::Created,
+        upload_image(&app, &id, &small, "A small shirt").await
+    );
+
+    // Only images can be uploaded, and only once per product
+    assert_eq!(
+        Status::BadRequest,
+        upload_image(&app, &id, b"not an image", "Nothing").await
+    );
+    assert_eq!(
+        Status::Conflict,
+        upload_image(&app, &id, &large, "A large shirt again").await
+    );
+
+    let get = app.get(format!("/products/{}/images", id)).dispatch().await;
+    assert_eq!(Status::Ok, get.status());
+
+    let images: serde_json::Value =
+        serde_json::from_str(&get.into_string().await.expect("missing body"))
+            .expect("invalid value");
+    let images = images["images"].as_array().expect("invalid images").clone();
+
+    assert_eq!(2, images.len());
+    assert_eq!(640, images[0]["width"]);
+    assert_eq!("image/png", images[0]["mime_type"]);
+    assert_eq!("A small shirt", images[1]["alt_text"]);
+
+    let ids = images
+        .iter()
+        .map(|image| image["id"].as_str().expect("invalid id").to_owned())
+        .collect::<Vec<_>>();
+
+    let put = app
+        .put(format!("/products/{}/images/order", id))
+        .json(&json!({ "images": [ids[1], ids[0]] }))
+        .dispatch()
+        .await;
+    assert_eq!(Status::Ok, put.status());
+
+    let get = app
+        .get(format!("/products/{}/images/{}", id, ids[1]))
+        .dispatch()
+        .await;
+    assert_eq!(Status::Ok, get.status());
+    assert_eq!(Some(ContentType::PNG), get.content_type());
+    assert!(get
+        .headers()
+        .get_one("Cache-Control")
+        .expect("missing cache control")
+        .contains("immutable"));
+
+    let etag = get
+        .headers()
+        .get_one("ETag")
+        .expect("missing etag")
+        .to_owned();
+    assert_eq!(Some(small), get.into_bytes().await);
+
+    let get = app
+        .get(format!("/products/{}/images/{}", id, ids[1]))
+        .header(Header::new("If-None-Match", etag))
+        .dispatch()
+        .await;
+    assert_eq!(Status::NotModified, get.status());
+
+    let delete = app
+        .delete(format!("/products/{}/images/{}", id, ids[1]))
+        .dispatch()
+        .await;
+    assert_eq!(Status::Ok, delete.status());
+
+    let get = app
+        .get(format!("/products/{}/images/{}", id, ids[1]))
+        .dispatch()
+        .await;
+    assert_eq!(Status::NotFound, get.status());
+
+    std::fs::remove_dir_all(root).expect("failed to remove images");
+}